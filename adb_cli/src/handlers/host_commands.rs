@@ -1,4 +1,4 @@
-use adb_client::{ADBServer, DeviceShort, MDNSBackend, Result, WaitForDeviceState};
+use adb_client::{ADBHostServer, ADBServer, DeviceShort, MDNSBackend, Result, WaitForDeviceState};
 
 use crate::models::{HostCommand, MdnsCommand, ServerCommand};
 
@@ -76,6 +76,17 @@ pub fn handle_host_commands(server_command: ServerCommand<HostCommand>) -> Resul
             log::info!("waiting for device to be connected...");
             adb_server.wait_for_device(WaitForDeviceState::Device, transport)?;
         }
+        HostCommand::Server {
+            no_usb,
+            path_to_private_key,
+        } => {
+            let mut host_server = match path_to_private_key {
+                Some(pk) => ADBHostServer::new_with_custom_private_key(server_command.address, pk)?,
+                None => ADBHostServer::new(server_command.address)?,
+            };
+            host_server.set_usb_scan(!no_usb);
+            host_server.run()?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,53 @@
+use std::fs::File;
+use std::path::Path;
+
+use adb_client::{ADBDeviceExt, InstrumentationOptions, TestStatus};
+use anyhow::{Result, bail};
+
+/// Run instrumentation with `options`, printing tests as they finish and writing a JUnit report into `junit` if given.
+pub fn instrument(
+    device: &mut dyn ADBDeviceExt,
+    options: InstrumentationOptions,
+    junit: Option<&Path>,
+) -> Result<()> {
+    let report = device.run_instrumentation(&options, &mut |test| {
+        println!(
+            "{:<10} {}#{} ({:.3}s)",
+            format!("{:?}", test.status),
+            test.class,
+            test.name,
+            test.duration.as_secs_f64()
+        );
+        if let Some(stack) = test
+            .stack
+            .as_ref()
+            .filter(|_| matches!(test.status, TestStatus::Failed | TestStatus::Error))
+        {
+            println!("{stack}");
+        }
+    })?;
+
+    if let Some(junit) = junit {
+        let suite = options.runner.split('/').next().unwrap_or(&options.runner);
+        report.write_junit_xml(suite, &mut File::create(junit)?)?;
+        log::info!("JUnit report written to {}", junit.display());
+    }
+
+    let failures = report
+        .tests
+        .iter()
+        .filter(|test| matches!(test.status, TestStatus::Failed | TestStatus::Error))
+        .count();
+    log::info!(
+        "{} tests run in {:.3}s, {failures} failed",
+        report.tests.len(),
+        report.duration.as_secs_f64()
+    );
+    if let Some(error) = &report.error {
+        bail!("instrumentation did not complete: {error}");
+    }
+    if failures > 0 {
+        bail!("{failures} tests failed");
+    }
+    Ok(())
+}
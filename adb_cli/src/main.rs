@@ -4,11 +4,17 @@
 mod adb_termios;
 
 mod handlers;
+mod instrument;
 mod models;
+mod progress;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod terminal;
+mod transfer;
 mod utils;
 
 use adb_client::{
-    ADBDeviceExt, ADBServer, ADBServerDevice, ADBTcpDevice, ADBUSBDevice, MDNSDiscoveryService,
+    ADBDeviceExt, ADBKeyStore, ADBRsaKey, ADBServer, ADBServerDevice, ADBTcpDevice, ADBUSBDevice,
+    DirSyncOptions, MDNSDiscoveryService, TerminalSize, UninstallOptions, get_default_adb_key_path,
 };
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -19,11 +25,14 @@ use clap::Parser;
 use handlers::{handle_emulator_commands, handle_host_commands, handle_local_commands};
 use models::{DeviceCommands, LocalCommand, MainCommand, Opts};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
 use std::path::Path;
 use utils::setup_logger;
 
+/// Terminal type requested when `TERM` is not set.
+const DEFAULT_TERM: &str = "xterm-256color";
+/// Terminal size used when it cannot be determined.
+const DEFAULT_TERMINAL_SIZE: TerminalSize = TerminalSize { rows: 24, cols: 80 };
+
 fn main() -> Result<()> {
     // This depends on `clap`
     let opts = Opts::parse();
@@ -82,6 +91,11 @@ fn main() -> Result<()> {
             let device = ADBTcpDevice::new(tcp_command.address)?;
             (device.boxed(), tcp_command.commands)
         }
+        MainCommand::Pair { address, code } => {
+            let guid = ADBTcpDevice::pair(address, &code)?;
+            log::info!("Paired with device {guid}");
+            return Ok(());
+        }
         MainCommand::Mdns => {
             let mut service = MDNSDiscoveryService::new()?;
 
@@ -99,61 +113,147 @@ fn main() -> Result<()> {
 
             return Ok(service.shutdown()?);
         }
+        MainCommand::Keygen { path } => {
+            let key = ADBRsaKey::new_random()?;
+            key.save(&path)?;
+            log::info!(
+                "Generated key {} with fingerprint {}",
+                path.display(),
+                key.fingerprint()?
+            );
+            return Ok(());
+        }
+        MainCommand::Keys {
+            path_to_private_key,
+        } => {
+            let path = match path_to_private_key {
+                Some(path) => path,
+                None => get_default_adb_key_path()?,
+            };
+            let keys = ADBKeyStore::load(&path)?;
+            for (index, key) in keys.keys().iter().enumerate() {
+                let kind = if index == 0 { "user" } else { "vendor" };
+                println!("{kind}\t{}", key.fingerprint()?);
+            }
+            return Ok(());
+        }
     };
 
     match commands {
         DeviceCommands::Shell { commands } => {
             if commands.is_empty() {
+                let term = std::env::var("TERM").unwrap_or_else(|_| DEFAULT_TERM.to_string());
+
                 // Need to duplicate some code here as ADBTermios [Drop] implementation resets terminal state.
                 // Using a scope here would call drop() too early..
                 #[cfg(any(target_os = "linux", target_os = "macos"))]
-                {
+                let exit_code = {
                     let mut adb_termios = ADBTermios::new(std::io::stdin())?;
                     adb_termios.set_adb_termios()?;
-                    device.shell(&mut std::io::stdin(), Box::new(std::io::stdout()))?;
-                }
+                    let size = terminal::current_terminal_size().unwrap_or(DEFAULT_TERMINAL_SIZE);
+                    let shell = device.pty_shell(
+                        &term,
+                        size,
+                        Box::new(std::io::stdin()),
+                        Box::new(std::io::stdout()),
+                    )?;
+                    terminal::forward_terminal_resizes(shell.resizer())?;
+                    shell.wait()?
+                };
 
                 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-                {
-                    device.shell(&mut std::io::stdin(), Box::new(std::io::stdout()))?;
+                let exit_code = device
+                    .pty_shell(
+                        &term,
+                        DEFAULT_TERMINAL_SIZE,
+                        Box::new(std::io::stdin()),
+                        Box::new(std::io::stdout()),
+                    )?
+                    .wait()?;
+
+                if let Some(exit_code) = exit_code.filter(|code| *code != 0) {
+                    std::process::exit(exit_code.into());
                 }
             } else {
                 let commands: Vec<&str> = commands.iter().map(|v| v.as_str()).collect();
-                device.shell_command(&commands, &mut std::io::stdout())?;
+                let exit_code = device.shell_command_with_status(
+                    &commands,
+                    &mut std::io::stdout(),
+                    &mut std::io::stderr(),
+                )?;
+                // Forward exit code of command, as `adb` does
+                if let Some(exit_code) = exit_code.filter(|code| *code != 0) {
+                    std::process::exit(exit_code.into());
+                }
             }
         }
         DeviceCommands::Pull {
             source,
             destination,
-        } => {
-            let mut output = File::create(Path::new(&destination))?;
-            device.pull(&source, &mut output)?;
-            log::info!("Downloaded {source} as {destination}");
-        }
+        } => transfer::pull(device.as_mut(), &source, Path::new(&destination))?,
         DeviceCommands::Stat { path } => {
             let stat_response = device.stat(&path)?;
             println!("{stat_response}");
         }
+        DeviceCommands::List { path } => {
+            for entry in device.list_dir(&path)? {
+                println!("{entry}");
+            }
+        }
         DeviceCommands::Reboot { reboot_type } => {
             log::info!("Reboots device in mode {reboot_type:?}");
             device.reboot(reboot_type.into())?
         }
         DeviceCommands::Push { filename, path } => {
-            let mut input = File::open(Path::new(&filename))?;
-            device.push(&mut input, &path)?;
-            log::info!("Uploaded {filename} to {path}");
+            transfer::push(device.as_mut(), Path::new(&filename), &path)?
         }
-        DeviceCommands::Run { package, activity } => {
-            let output = device.run_activity(&package, &activity)?;
-            std::io::stdout().write_all(&output)?;
+        DeviceCommands::Sync {
+            source,
+            destination,
+            delete,
+            checksum,
+            dry_run,
+        } => {
+            let options = DirSyncOptions {
+                compare_hash: checksum,
+                delete_extraneous: delete,
+                dry_run,
+            };
+            transfer::sync(device.as_mut(), &source, &destination, options)?
         }
-        DeviceCommands::Install { path } => {
-            log::info!("Starting installation of APK {}...", path.display());
-            device.install(&path)?;
+        DeviceCommands::Run { package, activity } => {
+            // Activity name is relative to package
+            let component = format!("{package}/.{activity}");
+            device.shell_command(&["am", "start", "-n", &component], &mut std::io::stdout())?;
         }
-        DeviceCommands::Uninstall { package } => {
+        DeviceCommands::Install { paths, options } => match paths.as_slice() {
+            [path] if path.extension().is_some_and(|extension| extension == "apk") => {
+                log::info!("Starting installation of APK {}...", path.display());
+                progress::with_progress_bar(&path.display().to_string(), |monitor| {
+                    device.install_with_options(path, &options.into(), monitor)
+                })?;
+            }
+            // Split APKs have to be installed together, in a single install session
+            _ => {
+                log::info!("Starting installation of {} files...", paths.len());
+                let paths = paths
+                    .iter()
+                    .map(|path| path as &dyn AsRef<Path>)
+                    .collect::<Vec<_>>();
+                device.install_multiple_with_options(&paths, &options.into())?;
+                log::info!("Package successfully installed");
+            }
+        },
+        DeviceCommands::Uninstall {
+            package,
+            keep_data,
+            user,
+        } => {
             log::info!("Uninstalling the package {package}...");
-            device.uninstall(&package)?;
+            device.uninstall_with_options(&package, &UninstallOptions { keep_data, user })?;
+        }
+        DeviceCommands::Instrument { options, junit } => {
+            instrument::instrument(device.as_mut(), options.into(), junit.as_deref())?
         }
         DeviceCommands::Framebuffer { path } => {
             device.framebuffer(&path)?;
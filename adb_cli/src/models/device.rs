@@ -2,18 +2,36 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use super::RebootTypeCommand;
+use super::{InstallCommandOptions, InstrumentCommandOptions, RebootTypeCommand};
 
 #[derive(Parser, Debug)]
 pub enum DeviceCommands {
     /// Spawn an interactive shell or run a list of commands on the device
     Shell { commands: Vec<String> },
-    /// Pull a file from device
+    /// Pull a file or directory from device
     Pull { source: String, destination: String },
-    /// Push a file on device
+    /// Push a file or directory on device
     Push { filename: String, path: String },
+    /// Push only files of a local directory which changed since last sync, as `adb sync` does
+    Sync {
+        /// Local directory
+        source: PathBuf,
+        /// Directory on device, made a copy of local one
+        destination: String,
+        /// Delete files on device which do not exist locally
+        #[clap(long = "delete")]
+        delete: bool,
+        /// Compare contents of files having same size, instead of their modification times
+        #[clap(long = "checksum")]
+        checksum: bool,
+        /// Only list changes, without making them
+        #[clap(short = 'n', long = "dry-run")]
+        dry_run: bool,
+    },
     /// Stat a file on device
     Stat { path: String },
+    /// List a directory on device
+    List { path: String },
     /// Run an activity on device specified by the intent
     Run {
         /// The package whose activity is to be invoked
@@ -28,15 +46,32 @@ pub enum DeviceCommands {
         #[clap(subcommand)]
         reboot_type: RebootTypeCommand,
     },
-    /// Install an APK on device
+    /// Install an APK on device, or several ones as a single package
     Install {
-        /// Path to APK file. Extension must be ".apk"
-        path: PathBuf,
+        /// Paths to APK files, or to ".apks" and ".xapk" archives of split APKs
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+        #[clap(flatten)]
+        options: InstallCommandOptions,
     },
     /// Uninstall a package from the device
     Uninstall {
         /// Name of the package to uninstall
         package: String,
+        /// Keep data and cache directories of package
+        #[clap(short = 'k', long = "keep-data")]
+        keep_data: bool,
+        /// Only uninstall package for this user id
+        #[clap(long = "user")]
+        user: Option<u32>,
+    },
+    /// Run instrumentation tests on device, printing their results as they finish
+    Instrument {
+        #[clap(flatten)]
+        options: InstrumentCommandOptions,
+        /// Write results as a JUnit XML report into this file
+        #[clap(long = "junit")]
+        junit: Option<PathBuf>,
     },
     /// Dump framebuffer of device
     Framebuffer {
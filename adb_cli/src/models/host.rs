@@ -1,4 +1,5 @@
 use std::net::SocketAddrV4;
+use std::path::PathBuf;
 
 use adb_client::{RustADBError, WaitForDeviceTransport};
 use clap::Parser;
@@ -41,6 +42,15 @@ pub enum HostCommand {
         #[clap(short = 't', long = "transport", value_parser = parse_wait_for_device_device_transport)]
         transport: Option<WaitForDeviceTransport>,
     },
+    /// Run a standalone ADB server listening on given address
+    Server {
+        /// Do not automatically detect USB devices
+        #[clap(long = "no-usb")]
+        no_usb: bool,
+        /// Path to a custom private key to use for authentication
+        #[clap(short = 'k', long = "private-key")]
+        path_to_private_key: Option<PathBuf>,
+    },
 }
 
 #[derive(Parser, Debug)]
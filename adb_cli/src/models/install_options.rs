@@ -0,0 +1,52 @@
+use adb_client::{InstallLocation, InstallOptions};
+use clap::{Args, ValueEnum};
+
+#[derive(Args, Debug)]
+pub struct InstallCommandOptions {
+    /// Replace an already installed application, keeping its data
+    #[clap(short = 'r', long = "replace")]
+    replace: bool,
+    /// Allow installing an older version
+    #[clap(short = 'd', long = "downgrade")]
+    downgrade: bool,
+    /// Grant all runtime permissions requested by application
+    #[clap(short = 'g', long = "grant")]
+    grant: bool,
+    /// Allow installing test packages
+    #[clap(short = 't', long = "test")]
+    test: bool,
+    /// Only install application for this user id
+    #[clap(long = "user")]
+    user: Option<u32>,
+    /// Install as an instant app
+    #[clap(long = "instant")]
+    instant: bool,
+    /// Where application gets installed
+    #[clap(long = "install-location", value_enum)]
+    install_location: Option<InstallLocationCommand>,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum InstallLocationCommand {
+    Auto,
+    Internal,
+    External,
+}
+
+impl From<InstallCommandOptions> for InstallOptions {
+    fn from(value: InstallCommandOptions) -> Self {
+        InstallOptions {
+            replace: value.replace,
+            allow_downgrade: value.downgrade,
+            grant_permissions: value.grant,
+            allow_test: value.test,
+            user: value.user,
+            instant: value.instant,
+            install_location: value.install_location.map(|location| match location {
+                InstallLocationCommand::Auto => InstallLocation::Auto,
+                InstallLocationCommand::Internal => InstallLocation::InternalOnly,
+                InstallLocationCommand::External => InstallLocation::PreferExternal,
+            }),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use adb_client::InstrumentationOptions;
+use clap::Args;
+
+fn parse_runner_arg(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected key=value, got {arg}"))
+}
+
+#[derive(Args, Debug)]
+pub struct InstrumentCommandOptions {
+    /// Instrumentation to run, e.g. "com.example.test/androidx.test.runner.AndroidJUnitRunner"
+    runner: String,
+    /// Only run this test class, or test method given as "class#method"
+    #[clap(short = 'c', long = "class")]
+    classes: Vec<String>,
+    /// Only run tests of this Java package
+    #[clap(long = "package")]
+    package: Option<String>,
+    /// Only run tests having this annotation
+    #[clap(long = "annotation")]
+    annotation: Option<String>,
+    /// Skip tests having this annotation
+    #[clap(long = "not-annotation")]
+    not_annotation: Option<String>,
+    /// Amount of shards tests are split into
+    #[clap(long = "num-shards", requires = "shard_index")]
+    num_shards: Option<u32>,
+    /// Index of shard to run, starting from 0
+    #[clap(long = "shard-index", requires = "num_shards")]
+    shard_index: Option<u32>,
+    /// Other argument given to runner, as "key=value"
+    #[clap(short = 'e', long = "arg", value_parser = parse_runner_arg)]
+    args: Vec<(String, String)>,
+    /// Run instrumentation as this user id
+    #[clap(long = "user")]
+    user: Option<u32>,
+}
+
+impl From<InstrumentCommandOptions> for InstrumentationOptions {
+    fn from(value: InstrumentCommandOptions) -> Self {
+        InstrumentationOptions {
+            runner: value.runner,
+            classes: value.classes,
+            package: value.package,
+            annotation: value.annotation,
+            not_annotation: value.not_annotation,
+            shard: value.shard_index.zip(value.num_shards),
+            args: value.args,
+            user: value.user,
+        }
+    }
+}
@@ -1,6 +1,8 @@
 mod device;
 mod emu;
 mod host;
+mod install_options;
+mod instrument_options;
 mod local;
 mod opts;
 mod reboot_type;
@@ -10,6 +12,8 @@ mod usb;
 pub use device::DeviceCommands;
 pub use emu::{EmuCommand, EmulatorCommand};
 pub use host::{HostCommand, MdnsCommand};
+pub use install_options::InstallCommandOptions;
+pub use instrument_options::InstrumentCommandOptions;
 pub use local::{LocalCommand, LocalDeviceCommand};
 pub use opts::{MainCommand, Opts, ServerCommand};
 pub use reboot_type::RebootTypeCommand;
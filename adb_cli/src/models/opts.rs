@@ -1,4 +1,5 @@
-use std::net::SocketAddrV4;
+use std::net::{SocketAddr, SocketAddrV4};
+use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
@@ -25,8 +26,18 @@ pub enum MainCommand {
     Usb(UsbCommand),
     /// TCP device related commands
     Tcp(TcpCommand),
+    /// Pair device over WI-FI with a given code, without any ADB server
+    Pair { address: SocketAddr, code: String },
     /// MDNS discovery related commands
     Mdns,
+    /// Generate a new private key at given path, along with its public key
+    Keygen { path: PathBuf },
+    /// List fingerprints of keys used for authentication, user key first, then vendor keys from ADB_VENDOR_KEYS
+    Keys {
+        /// Path to a custom private key, generated if it does not exist
+        #[clap(short = 'k', long = "private-key")]
+        path_to_private_key: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -0,0 +1,33 @@
+use adb_client::TransferMonitor;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+
+const BAR_TEMPLATE: &str = "{prefix} [{bar:30}] {bytes}/{total_bytes} {msg} ETA {eta}";
+const SPINNER_TEMPLATE: &str = "{prefix} {spinner} {bytes} {msg}";
+
+/// Run `transfer` while displaying its progress on stderr, labelled `label`.
+pub fn with_progress_bar<T>(
+    label: &str,
+    transfer: impl FnOnce(&mut TransferMonitor) -> adb_client::Result<T>,
+) -> adb_client::Result<T> {
+    let bar = ProgressBar::no_length().with_prefix(label.to_string());
+    bar.set_style(style(SPINNER_TEMPLATE));
+
+    let mut monitor = TransferMonitor::new().on_progress(|progress| {
+        if let Some(total) = progress.total.filter(|_| bar.length().is_none()) {
+            bar.set_length(total);
+            bar.set_style(style(BAR_TEMPLATE));
+        }
+        bar.set_position(progress.bytes_done);
+        bar.set_message(format!("{}/s", HumanBytes(progress.throughput() as u64)));
+    });
+
+    let result = transfer(&mut monitor);
+    bar.finish_and_clear();
+    result
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ")
+}
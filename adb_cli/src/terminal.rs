@@ -0,0 +1,30 @@
+#![cfg(any(target_os = "linux", target_os = "macos"))]
+
+use adb_client::{ShellResizer, TerminalSize};
+use signal_hook::{consts::SIGWINCH, iterator::Signals};
+use terminal_size::{Height, Width};
+
+use crate::Result;
+
+/// Size of the terminal attached to stdout, if any.
+pub fn current_terminal_size() -> Option<TerminalSize> {
+    terminal_size::terminal_size().map(|(Width(cols), Height(rows))| TerminalSize::new(rows, cols))
+}
+
+/// Propagate terminal size changes, notified by `SIGWINCH`, to device shell until it exits.
+pub fn forward_terminal_resizes(resizer: ShellResizer) -> Result<()> {
+    let mut signals = Signals::new([SIGWINCH])?;
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            let Some(size) = current_terminal_size() else {
+                continue;
+            };
+            if let Err(e) = resizer.resize(size) {
+                log::debug!("cannot send terminal size to device: {e}");
+                return;
+            }
+        }
+    });
+
+    Ok(())
+}
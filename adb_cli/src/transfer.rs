@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::path::Path;
+
+use adb_client::{ADBDeviceExt, AdbFileType, DirSyncOptions, FileTransfer};
+use anyhow::{Result, bail};
+
+use crate::progress::with_progress_bar;
+
+/// Push local file or directory `source` to `destination`, or into it if it is an existing directory, as `adb push` does.
+pub fn push(device: &mut dyn ADBDeviceExt, source: &Path, destination: &str) -> Result<()> {
+    let destination = match (is_remote_dir(device, destination)?, source.file_name()) {
+        (true, Some(name)) => format!(
+            "{}/{}",
+            destination.trim_end_matches('/'),
+            name.to_string_lossy()
+        ),
+        _ => destination.to_string(),
+    };
+
+    if source.is_dir() {
+        let transfers = device.push_dir(&source, &destination)?;
+        return report(&transfers);
+    }
+
+    with_progress_bar(&destination, |monitor| {
+        device.push_file_with_monitor(&source, &destination, monitor)
+    })?;
+    log::info!("Uploaded {} to {destination}", source.display());
+    Ok(())
+}
+
+/// Pull file or directory `source` to local `destination`, or into it if it is an existing directory, as `adb pull` does.
+pub fn pull(device: &mut dyn ADBDeviceExt, source: &str, destination: &Path) -> Result<()> {
+    let mut destination = destination.to_path_buf();
+    if let Some(name) = Path::new(source)
+        .file_name()
+        .filter(|_| destination.is_dir())
+    {
+        destination.push(name);
+    }
+
+    if is_remote_dir(device, source)? {
+        let transfers = device.pull_dir(source, &destination)?;
+        return report(&transfers);
+    }
+
+    let mut output = File::create(&destination)?;
+    with_progress_bar(source, |monitor| {
+        device.pull_with_monitor(&source, &mut output, monitor)
+    })?;
+    log::info!("Downloaded {source} as {}", destination.display());
+    Ok(())
+}
+
+/// Make `destination` on device a copy of local directory `source`, printing changes made.
+pub fn sync(
+    device: &mut dyn ADBDeviceExt,
+    source: &Path,
+    destination: &str,
+    options: DirSyncOptions,
+) -> Result<()> {
+    let report = device.sync_dir(&source, destination, options)?;
+    for change in &report.changes {
+        match &change.result {
+            Ok(()) => println!("{:<10} {}", change.kind, change.remote_path),
+            Err(e) => log::error!("{}: {e}", change.remote_path),
+        }
+    }
+
+    let failures = report.failures().count();
+    log::info!(
+        "{} changes{}, {} files up to date",
+        report.changes.len() - failures,
+        if options.dry_run { " to make" } else { " made" },
+        report.unchanged
+    );
+    if failures > 0 {
+        bail!("{failures} changes could not be made");
+    }
+    Ok(())
+}
+
+/// Whether `path` is a directory on device, following symbolic links.
+fn is_remote_dir(device: &mut dyn ADBDeviceExt, path: &str) -> Result<bool> {
+    // Devices only follow links of paths ending with a `/`, which cannot be stat'ed unless they are directories
+    let stat = device.stat(&format!("{}/", path.trim_end_matches('/')))?;
+    Ok(stat.file_type() == AdbFileType::Directory)
+}
+
+/// Log outcome of each file of a directory transfer, failing if any of them could not be transferred.
+fn report(transfers: &[FileTransfer]) -> Result<()> {
+    let mut size = 0;
+    let mut failures = 0;
+    for transfer in transfers {
+        match &transfer.result {
+            Ok(()) => {
+                log::debug!(
+                    "{} <-> {}",
+                    transfer.local_path.display(),
+                    transfer.remote_path
+                );
+                size += transfer.size;
+            }
+            Err(e) => {
+                log::error!("{}: {e}", transfer.remote_path);
+                failures += 1;
+            }
+        }
+    }
+
+    log::info!(
+        "{} files transferred ({size} bytes)",
+        transfers.len() - failures
+    );
+    if failures > 0 {
+        bail!("{failures} files could not be transferred");
+    }
+    Ok(())
+}
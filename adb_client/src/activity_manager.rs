@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use crate::models::{ActivityStartResult, BroadcastResult, Intent};
+use crate::package_manager::run_package_command;
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// Start activity described by `intent`, waiting for it to be drawn if `wait` is set. See [`ADBDeviceExt::start_activity`].
+pub(crate) fn start_activity<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    intent: &Intent,
+    wait: bool,
+) -> Result<ActivityStartResult> {
+    let output = run_am_command(device, &start_command(wait), intent)?;
+    Ok(parse_activity_start(&output))
+}
+
+/// `am` subcommand starting an activity, waiting for it to be drawn if `wait` is set.
+pub(crate) fn start_command(wait: bool) -> Vec<&'static str> {
+    match wait {
+        true => vec!["start", "-W"],
+        false => vec!["start"],
+    }
+}
+
+/// Parse output of `am start`, which reports outcome of waited starts.
+pub(crate) fn parse_activity_start(output: &str) -> ActivityStartResult {
+    // Waited starts report their outcome as `Key: value` lines, e.g. `TotalTime: 523`
+    let mut result = ActivityStartResult::default();
+    for (key, value) in output.lines().filter_map(|line| line.split_once(": ")) {
+        let value = value.trim();
+        let millis = || value.parse().ok().map(Duration::from_millis);
+        match key.trim() {
+            "Status" => result.status = Some(value.to_string()),
+            "Activity" => result.activity = Some(value.to_string()),
+            "LaunchState" => result.launch_state = Some(value.to_string()),
+            "ThisTime" => result.this_time = millis(),
+            "TotalTime" => result.total_time = millis(),
+            "WaitTime" => result.wait_time = millis(),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Start service described by `intent`. See [`ADBDeviceExt::start_service`].
+pub(crate) fn start_service<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    intent: &Intent,
+) -> Result<()> {
+    run_am_command(device, &["startservice"], intent)?;
+    Ok(())
+}
+
+/// Send broadcast described by `intent`, and wait for its receivers. See [`ADBDeviceExt::send_broadcast`].
+pub(crate) fn send_broadcast<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    intent: &Intent,
+) -> Result<BroadcastResult> {
+    let output = run_am_command(device, &["broadcast"], intent)?;
+
+    // Output ends with `Broadcast completed: result=0, data="..."`
+    let completed = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Broadcast completed: "))
+        .ok_or_else(|| RustADBError::ADBRequestFailed(output.trim().to_string()))?;
+    let code = completed
+        .strip_prefix("result=")
+        .and_then(|result| result.split(',').next())
+        .and_then(|code| code.trim().parse().ok())
+        .unwrap_or_default();
+    let data = completed
+        .split_once("data=\"")
+        .and_then(|(_, data)| data.rsplit_once('"'))
+        .map(|(data, _)| data.to_string());
+    Ok(BroadcastResult { code, data })
+}
+
+/// Run `am` with `command` on `intent`, failing with error reported by activity manager if any.
+fn run_am_command<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    command: &[&str],
+    intent: &Intent,
+) -> Result<String> {
+    let args = intent.args();
+    check_am_output(run_package_command(device, &am_command(command, &args))?)
+}
+
+/// Command line running `am` with `command` on an intent described by `args`.
+pub(crate) fn am_command<'a>(command: &[&'a str], args: &'a [String]) -> Vec<&'a str> {
+    let mut am_command = vec!["am"];
+    am_command.extend(command);
+    am_command.extend(args.iter().map(String::as_str));
+    am_command
+}
+
+/// Check `output` of `am`, failing with error reported by activity manager if any.
+pub(crate) fn check_am_output(output: String) -> Result<String> {
+    // Activity manager exits successfully on most errors, only reporting them with an `Error: ...` line
+    match output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Error: "))
+        .next_back()
+    {
+        Some(error) => Err(RustADBError::ADBRequestFailed(error.to_string())),
+        None => Ok(output),
+    }
+}
+
+#[test]
+fn test_activity_manager() {
+    use crate::FakeADBDevice;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response(
+        "am start -W -n 'com.example.app/.MainActivity' --ez 'debug' 'true'",
+        b"Starting: Intent { cmp=com.example.app/.MainActivity (has extras) }\n\
+          Status: ok\n\
+          LaunchState: COLD\n\
+          Activity: com.example.app/.MainActivity\n\
+          TotalTime: 523\n\
+          WaitTime: 530\n\
+          Complete\n",
+    );
+    fake_device.add_shell_response(
+        "am start -n 'com.example.app/.Missing'",
+        b"Starting: Intent { cmp=com.example.app/.Missing }\n\
+          Error type 3\n\
+          Error: Activity class {com.example.app/com.example.app.Missing} does not exist.\n",
+    );
+    fake_device.add_shell_response(
+        "am startservice -n 'com.example.app/.SyncService'",
+        b"Starting service: Intent { cmp=com.example.app/.SyncService }\n",
+    );
+    fake_device.add_shell_response(
+        "am broadcast -p 'com.example.app' -a 'com.example.app.PING'",
+        b"Broadcasting: Intent { act=com.example.app.PING pkg=com.example.app }\n\
+          Broadcast completed: result=-1, data=\"pong\"\n",
+    );
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let intent = Intent::new()
+        .with_component("com.example.app", ".MainActivity")
+        .with_extra("debug", true);
+    let result = device
+        .start_activity_and_wait(&intent)
+        .expect("cannot start activity");
+    assert_eq!(result.status.as_deref(), Some("ok"));
+    assert_eq!(result.launch_state.as_deref(), Some("COLD"));
+    assert_eq!(result.total_time, Some(Duration::from_millis(523)));
+    assert_eq!(result.wait_time, Some(Duration::from_millis(530)));
+
+    let intent = Intent::new().with_component("com.example.app", ".Missing");
+    assert!(matches!(
+        device.start_activity(&intent),
+        Err(RustADBError::ADBRequestFailed(error)) if error.ends_with("does not exist.")
+    ));
+
+    let intent = Intent::new().with_component("com.example.app", ".SyncService");
+    device.start_service(&intent).expect("cannot start service");
+
+    let intent = Intent::new()
+        .with_package("com.example.app")
+        .with_action("com.example.app.PING");
+    assert_eq!(
+        device.send_broadcast(&intent).ok(),
+        Some(BroadcastResult {
+            code: -1,
+            data: Some("pong".to_string())
+        })
+    );
+}
@@ -1,33 +1,237 @@
+use std::fs::File;
 use std::io::{Cursor, Read, Write};
 use std::path::Path;
+use std::time::Duration;
 
 use image::{ImageBuffer, ImageFormat, Rgba};
 
-use crate::models::AdbStatResponse;
-use crate::{RebootType, Result};
+use crate::models::{
+    ActivityStartResult, AdbDirEntry, AdbFileType, AdbStatResponse, AppOpMode, BroadcastResult,
+    DeviceProperties, DirSyncOptions, DirSyncReport, FileTransfer, InstalledPackage,
+    InstrumentationOptions, InstrumentationReport, Intent, PackageDetails, PackageFilter,
+    PushOptions, TestResult,
+};
+use crate::utils::check_extension_is_apk;
+use crate::{
+    ADBPtyShell, InstallOptions, RebootType, Result, TerminalSize, TransferMonitor,
+    UninstallOptions,
+};
+use crate::{
+    activity_manager, boot, dir_sync, dir_transfer, instrumentation, package_manager, permissions,
+    properties,
+};
 
 /// Trait representing all features available on both [`crate::ADBServerDevice`] and [`crate::ADBUSBDevice`]
 pub trait ADBDeviceExt {
     /// Runs command in a shell on the device, and write its output and error streams into output.
     fn shell_command(&mut self, command: &[&str], output: &mut dyn Write) -> Result<()>;
 
+    /// Runs command in a shell on the device, writing its output and error streams into `stdout` and `stderr`. Returns its exit code.
+    ///
+    /// Exit code is only known when device supports shell protocol v2 (`shell_v2` feature). Otherwise, `None` is returned
+    /// and both streams are written into `stdout`.
+    fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        let _ = stderr;
+        self.shell_command(command, stdout)?;
+        Ok(None)
+    }
+
     /// Starts an interactive shell session on the device.
     /// Input data is read from reader and write to writer.
-    fn shell(&mut self, reader: &mut dyn Read, writer: Box<(dyn Write + Send)>) -> Result<()>;
+    fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()>;
+
+    /// Starts an interactive shell session on the device, in a pseudo-terminal of type `term` (e.g. `xterm-256color`) sized `size`.
+    /// Input data is read from reader and write to writer by background threads.
+    ///
+    /// Returned [`ADBPtyShell`] propagates terminal size changes and reports exit code of shell. Both require shell protocol v2 (`shell_v2` feature):
+    /// otherwise, size changes are ignored and no exit code is reported.
+    fn pty_shell(
+        &mut self,
+        term: &str,
+        size: TerminalSize,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<ADBPtyShell>;
 
     /// Display the stat information for a remote file
     fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse>;
 
+    /// List entries of remote directory `path`, except `.` and `..`.
+    ///
+    /// Sizes and modification times are truncated to 32 bits unless device supports listing with sync protocol v2 (`ls_v2` feature).
+    fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>>;
+
     /// Pull the remote file pointed to by `source` and write its contents into `output`
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()>;
 
-    /// Push `stream` to `path` on the device.
-    fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()>;
+    /// Pull the remote file pointed to by `source` into `output`, reporting progress to `monitor` which may cancel it.
+    fn pull_with_monitor(
+        &mut self,
+        source: &dyn AsRef<str>,
+        output: &mut dyn Write,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        // Size of links is the one of their target path, not of the file they point to
+        let total = match self.stat(source.as_ref())? {
+            stat if stat.file_type() == AdbFileType::Symlink => None,
+            stat => Some(u64::from(stat.file_size)),
+        };
+        let result = self.pull(source, &mut monitor.writer(output, total));
+        monitor.finish(result)
+    }
+
+    /// Push `stream` to `path` on the device, with default [`PushOptions`].
+    fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()> {
+        self.push_with_options(stream, path, PushOptions::default())
+    }
+
+    /// Push `stream` to `path` on the device, giving it mode and modification time from `options`.
+    ///
+    /// Missing parent directories of `path` are created.
+    fn push_with_options(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        options: PushOptions,
+    ) -> Result<()>;
+
+    /// Push `stream` to `path` on the device like [`ADBDeviceExt::push_with_options`], reporting progress to `monitor` which may cancel it.
+    ///
+    /// Size of `stream` being unknown, reported progress has no total.
+    fn push_with_monitor(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        options: PushOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        let result = self.push_with_options(&mut monitor.reader(stream, None), path, options);
+        monitor.finish(result)
+    }
+
+    /// Push local file `source` to `path` on the device, keeping its mode and modification time.
+    fn push_file(&mut self, source: &dyn AsRef<Path>, path: &dyn AsRef<str>) -> Result<()> {
+        self.push_file_with_monitor(source, path, &mut TransferMonitor::new())
+    }
+
+    /// Push local file `source` to `path` on the device like [`ADBDeviceExt::push_file`], reporting progress to `monitor` which may cancel it.
+    fn push_file_with_monitor(
+        &mut self,
+        source: &dyn AsRef<Path>,
+        path: &dyn AsRef<str>,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        let file = File::open(source.as_ref())?;
+        let metadata = file.metadata()?;
+        let options = PushOptions::from_metadata(&metadata);
+        let result = self.push_with_options(
+            &mut monitor.reader(file, Some(metadata.len())),
+            path,
+            options,
+        );
+        monitor.finish(result)
+    }
+
+    /// Push local directory `source` and everything it contains to `destination` on the device, which becomes a copy of it.
+    ///
+    /// Modes and modification times of files are kept, symbolic links are recreated as links and empty directories are created.
+    /// Transfer goes on when a file cannot be pushed: outcome of each one is returned.
+    fn push_dir(
+        &mut self,
+        source: &dyn AsRef<Path>,
+        destination: &str,
+    ) -> Result<Vec<FileTransfer>> {
+        dir_transfer::push_dir(self, source.as_ref(), destination)
+    }
+
+    /// Pull remote directory `source` and everything it contains into local `destination`, which becomes a copy of it.
+    ///
+    /// Modes and modification times of files are kept and empty directories are created. Symbolic links are followed,
+    /// file they point to being pulled in their place. Transfer goes on when a file cannot be pulled: outcome of each one is returned.
+    fn pull_dir(
+        &mut self,
+        source: &str,
+        destination: &dyn AsRef<Path>,
+    ) -> Result<Vec<FileTransfer>> {
+        dir_transfer::pull_dir(self, source, destination.as_ref())
+    }
+
+    /// Make remote directory `destination` a copy of local directory `source`, only pushing files which changed, as `adb sync` does.
+    ///
+    /// Files are compared by size and modification time, or by contents with [`DirSyncOptions::compare_hash`]. Outcome of each change
+    /// is returned, changes being only reported with [`DirSyncOptions::dry_run`].
+    fn sync_dir(
+        &mut self,
+        source: &dyn AsRef<Path>,
+        destination: &str,
+        options: DirSyncOptions,
+    ) -> Result<DirSyncReport> {
+        dir_sync::sync_dir(self, source.as_ref(), destination, options)
+    }
+
+    /// Get all system properties of device, as listed by `getprop`, e.g. to read its SDK level or ABIs.
+    fn properties(&mut self) -> Result<DeviceProperties> {
+        properties::properties(self)
+    }
+
+    /// Get value of system property `name`, `None` if it is not set.
+    fn property(&mut self, name: &str) -> Result<Option<String>> {
+        properties::property(self, name)
+    }
+
+    /// Set system property `name` to `value`, as `setprop` does, checking that device did set it.
+    fn set_property(&mut self, name: &str, value: &str) -> Result<()> {
+        properties::set_property(self, name, value)
+    }
 
     /// Reboot the device using given reboot type
     fn reboot(&mut self, reboot_type: RebootType) -> Result<()>;
 
+    /// Reboot the device using given reboot type, then wait for it to be fully booted again, failing after `timeout`.
+    ///
+    /// Device is first waited for to actually go down, as it still reports being booted until then. See [`ADBDeviceExt::wait_for_boot`].
+    fn reboot_and_wait(&mut self, reboot_type: RebootType, timeout: Duration) -> Result<()> {
+        boot::reboot_and_wait(self, reboot_type, timeout)
+    }
+
+    /// Wait for device to be fully booted, failing after `timeout`.
+    ///
+    /// Device has to come back, have `sys.boot_completed` and `dev.bootcomplete` set, and its package manager has to answer requests.
+    /// Timeouts are reported as [`crate::RustADBError::BootTimeout`], along with last [`crate::BootStage`] reached.
+    ///
+    /// Right after a [`ADBDeviceExt::reboot`], device may still report being booted: use [`ADBDeviceExt::reboot_and_wait`] instead.
+    fn wait_for_boot(&mut self, timeout: Duration) -> Result<()> {
+        self.wait_for_boot_since(None, timeout)
+    }
+
+    /// Wait for device to be fully booted in a boot other than `previous_boot`, as returned by [`ADBDeviceExt::boot_id`],
+    /// failing after `timeout`. See [`ADBDeviceExt::wait_for_boot`].
+    ///
+    /// Devices connected directly or through a server get reconnected when they cannot be reached.
+    fn wait_for_boot_since(
+        &mut self,
+        previous_boot: Option<&str>,
+        timeout: Duration,
+    ) -> Result<()> {
+        boot::poll_boot(self, previous_boot, timeout)
+    }
+
+    /// Get identifier of current boot of device, changing each time it boots, or `None` if device does not expose it.
+    fn boot_id(&mut self) -> Result<Option<String>> {
+        boot::boot_id(self)
+    }
+
     /// Run `activity` from `package` on device. Return the command output.
+    ///
+    /// Activity name is relative to package, see [`ADBDeviceExt::start_activity`] to start any activity.
+    #[deprecated(
+        note = "use `start_activity` with an `Intent`, which reports errors and starts activities outside of package namespace"
+    )]
     fn run_activity(&mut self, package: &str, activity: &str) -> Result<Vec<u8>> {
         let mut output = Vec::new();
         self.shell_command(
@@ -38,11 +242,172 @@ pub trait ADBDeviceExt {
         Ok(output)
     }
 
+    /// Start activity described by `intent`, failing with error reported by activity manager if it cannot be started.
+    fn start_activity(&mut self, intent: &Intent) -> Result<()> {
+        activity_manager::start_activity(self, intent, false)?;
+        Ok(())
+    }
+
+    /// Start activity described by `intent` and wait for it to be drawn, as `am start -W` does, returning its launch timings.
+    fn start_activity_and_wait(&mut self, intent: &Intent) -> Result<ActivityStartResult> {
+        activity_manager::start_activity(self, intent, true)
+    }
+
+    /// Start service described by `intent`.
+    fn start_service(&mut self, intent: &Intent) -> Result<()> {
+        activity_manager::start_service(self, intent)
+    }
+
+    /// Send broadcast described by `intent`, returning result set by its receivers once they all got it.
+    fn send_broadcast(&mut self, intent: &Intent) -> Result<BroadcastResult> {
+        activity_manager::send_broadcast(self, intent)
+    }
+
+    /// Run instrumentation tests with `options`, as `am instrument -w -r` does, calling `on_test` each time a test finishes.
+    ///
+    /// Instrumentation failing or crashing is not an error: it is reported in returned report, along with all tests which were run.
+    fn run_instrumentation(
+        &mut self,
+        options: &InstrumentationOptions,
+        on_test: &mut dyn FnMut(&TestResult),
+    ) -> Result<InstrumentationReport> {
+        instrumentation::run_instrumentation(self, options, on_test)
+    }
+
     /// Install an APK pointed to by `apk_path` on device.
-    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()>;
+    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
+        self.install_with_monitor(apk_path, &mut TransferMonitor::new())
+    }
+
+    /// Install an APK pointed to by `apk_path` on device, reporting upload progress to `monitor` which may cancel it.
+    fn install_with_monitor(
+        &mut self,
+        apk_path: &dyn AsRef<Path>,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        self.install_with_options(apk_path, &InstallOptions::default(), monitor)
+    }
+
+    /// Install an APK pointed to by `apk_path` on device with given `options`, reporting upload progress to `monitor` which may cancel it.
+    ///
+    /// Package manager refusing APK is reported as [`crate::RustADBError::InstallFailed`], along with its `INSTALL_FAILED_*` reason.
+    fn install_with_options(
+        &mut self,
+        apk_path: &dyn AsRef<Path>,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        check_extension_is_apk(apk_path)?;
+        let mut apk_file = File::open(apk_path)?;
+        let size = apk_file.metadata()?.len();
+        self.install_from_reader(&mut apk_file, size, options, monitor)?;
+
+        log::info!(
+            "APK file {} successfully installed",
+            apk_path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Install an APK of `size` bytes read from `reader` on device with given `options`, reporting upload progress to `monitor` which may cancel it.
+    ///
+    /// Contents are checked to start like an APK, failing with [`crate::RustADBError::InvalidApk`] otherwise.
+    fn install_from_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()>;
+
+    /// Install APKs pointed to by `apk_paths` on device as a single package, e.g. a base APK and its splits.
+    ///
+    /// Paths may also point to `.apks` or `.xapk` archives, whose APKs get installed. Everything is written into a package manager
+    /// install session, committed once all APKs have been written or abandoned on failure, leaving device untouched.
+    fn install_multiple(&mut self, apk_paths: &[&dyn AsRef<Path>]) -> Result<()> {
+        self.install_multiple_with_options(apk_paths, &InstallOptions::default())
+    }
+
+    /// Install APKs pointed to by `apk_paths` on device as a single package like [`ADBDeviceExt::install_multiple`], with given `options`.
+    fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &InstallOptions,
+    ) -> Result<()>;
 
     /// Uninstall the package `package` from device.
-    fn uninstall(&mut self, package: &str) -> Result<()>;
+    fn uninstall(&mut self, package: &str) -> Result<()> {
+        self.uninstall_with_options(package, &UninstallOptions::default())
+    }
+
+    /// Uninstall the package `package` from device with given `options`, e.g. keeping its data.
+    fn uninstall_with_options(&mut self, package: &str, options: &UninstallOptions) -> Result<()>;
+
+    /// List packages installed on device matching `filter`, along with their APK path, version code, installer and uid.
+    fn list_packages(&mut self, filter: &PackageFilter) -> Result<Vec<InstalledPackage>> {
+        package_manager::list_packages(self, filter)
+    }
+
+    /// Get details of installed package `package`, as reported by `dumpsys package`.
+    ///
+    /// Fails with [`crate::RustADBError::PackageNotFound`] if package is not installed.
+    fn package_details(&mut self, package: &str) -> Result<PackageDetails> {
+        package_manager::package_details(self, package)
+    }
+
+    /// Enable package `package`, e.g. after it has been disabled with [`ADBDeviceExt::disable_package`].
+    fn enable_package(&mut self, package: &str) -> Result<()> {
+        package_manager::set_package_enabled(self, package, true)
+    }
+
+    /// Disable package `package` for current user, as `pm disable-user` does.
+    fn disable_package(&mut self, package: &str) -> Result<()> {
+        package_manager::set_package_enabled(self, package, false)
+    }
+
+    /// Delete all data of package `package`, as `pm clear` does.
+    fn clear_package_data(&mut self, package: &str) -> Result<()> {
+        package_manager::clear_package_data(self, package)
+    }
+
+    /// Stop everything running for package `package`, as `am force-stop` does.
+    fn force_stop_package(&mut self, package: &str) -> Result<()> {
+        package_manager::force_stop_package(self, package)
+    }
+
+    /// Grant runtime permission `permission` to package `package`, e.g. `android.permission.CAMERA`.
+    ///
+    /// Refusals are reported as [`crate::RustADBError::PermissionChangeFailed`], along with their reason.
+    fn grant_permission(&mut self, package: &str, permission: &str) -> Result<()> {
+        permissions::grant_permission(self, package, permission)
+    }
+
+    /// Revoke runtime permission `permission` from package `package`.
+    fn revoke_permission(&mut self, package: &str, permission: &str) -> Result<()> {
+        permissions::revoke_permission(self, package, permission)
+    }
+
+    /// Reset runtime permissions of package `package` to their initial state, as `pm reset-permissions -p` does.
+    fn reset_permissions(&mut self, package: &str) -> Result<()> {
+        permissions::reset_permissions(self, package)
+    }
+
+    /// Grant all runtime permissions requested in manifest of package `package`, e.g. to set up a test build.
+    ///
+    /// Permissions which are not runtime ones are skipped. Return permissions which got granted.
+    fn grant_requested_permissions(&mut self, package: &str) -> Result<Vec<String>> {
+        permissions::grant_requested_permissions(self, package)
+    }
+
+    /// Get mode of app op `op` for package `package`, e.g. `CAMERA` or `android:run_in_background`.
+    fn app_op(&mut self, package: &str, op: &str) -> Result<AppOpMode> {
+        permissions::app_op(self, package, op)
+    }
+
+    /// Set mode of app op `op` for package `package`.
+    fn set_app_op(&mut self, package: &str, op: &str, mode: AppOpMode) -> Result<()> {
+        permissions::set_app_op(self, package, op, mode)
+    }
 
     /// Inner method requesting framebuffer from an Android device
     fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>>;
@@ -0,0 +1,60 @@
+use crate::activity_manager::{am_command, check_am_output, parse_activity_start, start_command};
+use crate::models::{ActivityStartResult, Intent};
+use crate::package_manager::check_package_command;
+use crate::{AsyncADBDeviceExt, Result};
+
+/// Start activity described by `intent`, waiting for it to be drawn if `wait` is set. See [`AsyncADBDeviceExt::start_activity`].
+pub(crate) async fn start_activity<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    intent: &Intent,
+    wait: bool,
+) -> Result<ActivityStartResult> {
+    let args = intent.args();
+    let command = am_command(&start_command(wait), &args);
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let status = device
+        .shell_command_with_status(&command, &mut stdout, &mut stderr)
+        .await?;
+    let output = check_am_output(check_package_command(status, &stdout, &stderr)?)?;
+    Ok(parse_activity_start(&output))
+}
+
+#[tokio::test]
+async fn test_async_start_activity() {
+    use crate::{FakeADBDevice, RustADBError};
+    use std::time::Duration;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response(
+        "am start -W -n 'com.example.app/.MainActivity'",
+        b"Starting: Intent { cmp=com.example.app/.MainActivity }\n\
+          Status: ok\n\
+          LaunchState: COLD\n\
+          Activity: com.example.app/.MainActivity\n\
+          TotalTime: 523\n\
+          WaitTime: 530\n\
+          Complete\n",
+    );
+    fake_device.add_shell_response(
+        "am start -n 'com.example.app/.Missing'",
+        b"Starting: Intent { cmp=com.example.app/.Missing }\n\
+          Error type 3\n\
+          Error: Activity class {com.example.app/com.example.app.Missing} does not exist.\n",
+    );
+    let mut device = crate::fake_device::connected_async_tcp_device(&fake_device).await;
+
+    let intent = Intent::new().with_component("com.example.app", ".MainActivity");
+    let result = device
+        .start_activity_and_wait(&intent)
+        .await
+        .expect("cannot start activity");
+    assert_eq!(result.status.as_deref(), Some("ok"));
+    assert_eq!(result.total_time, Some(Duration::from_millis(523)));
+
+    let intent = Intent::new().with_component("com.example.app", ".Missing");
+    assert!(matches!(
+        device.start_activity(&intent).await,
+        Err(RustADBError::ADBRequestFailed(error)) if error.ends_with("does not exist.")
+    ));
+}
@@ -0,0 +1,252 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use image::{ImageBuffer, ImageFormat, Rgba};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{activity_manager, boot, dir_transfer};
+use crate::models::{
+    ActivityStartResult, AdbDirEntry, AdbStatResponse, FileTransfer, Intent, PushOptions,
+};
+use crate::utils::check_extension_is_apk;
+use crate::{InstallOptions, RebootType, Result, UninstallOptions};
+
+/// Asynchronous counterpart of [`crate::ADBDeviceExt`], implemented by [`crate::AsyncADBServerDevice`], [`crate::AsyncADBTcpDevice`] and [`crate::AsyncADBUSBDevice`].
+#[async_trait]
+pub trait AsyncADBDeviceExt: Send {
+    /// Runs command in a shell on the device, and write its output and error streams into output.
+    async fn shell_command(
+        &mut self,
+        command: &[&str],
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()>;
+
+    /// Runs command in a shell on the device, writing its output and error streams into `stdout` and `stderr`. Returns its exit code.
+    ///
+    /// Exit code is only known when device supports shell protocol v2 (`shell_v2` feature). Otherwise, `None` is returned
+    /// and both streams are written into `stdout`.
+    async fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<Option<u8>> {
+        let _ = stderr;
+        self.shell_command(command, stdout).await?;
+        Ok(None)
+    }
+
+    /// Starts an interactive shell session on the device.
+    /// Input data is read from reader and write to writer.
+    async fn shell(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<()>;
+
+    /// Display the stat information for a remote file
+    async fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse>;
+
+    /// List entries of remote directory `path`, except `.` and `..`.
+    ///
+    /// Sizes and modification times are truncated to 32 bits unless device supports listing with sync protocol v2 (`ls_v2` feature).
+    async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>>;
+
+    /// Pull the remote file pointed to by `source` and write its contents into `output`
+    async fn pull(
+        &mut self,
+        source: &(dyn AsRef<str> + Sync),
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()>;
+
+    /// Push `stream` to `path` on the device, with default [`PushOptions`].
+    async fn push(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &(dyn AsRef<str> + Sync),
+    ) -> Result<()> {
+        self.push_with_options(stream, path, PushOptions::default())
+            .await
+    }
+
+    /// Push `stream` to `path` on the device, giving it mode and modification time from `options`.
+    async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &(dyn AsRef<str> + Sync),
+        options: PushOptions,
+    ) -> Result<()>;
+
+    /// Push local directory `source` and everything it contains to `destination` on the device, which becomes a copy of it.
+    ///
+    /// Modes and modification times of files are kept, symbolic links are recreated as links and empty directories are created.
+    /// Transfer goes on when a file cannot be pushed: outcome of each one is returned.
+    async fn push_dir(
+        &mut self,
+        source: &(dyn AsRef<Path> + Sync),
+        destination: &str,
+    ) -> Result<Vec<FileTransfer>> {
+        dir_transfer::push_dir(self, source.as_ref(), destination).await
+    }
+
+    /// Pull remote directory `source` and everything it contains into local `destination`, which becomes a copy of it.
+    ///
+    /// Modes and modification times of files are kept and empty directories are created. Symbolic links are followed,
+    /// file they point to being pulled in their place. Transfer goes on when a file cannot be pulled: outcome of each one is returned.
+    async fn pull_dir(
+        &mut self,
+        source: &str,
+        destination: &(dyn AsRef<Path> + Sync),
+    ) -> Result<Vec<FileTransfer>> {
+        dir_transfer::pull_dir(self, source, destination.as_ref()).await
+    }
+
+    /// Reboot the device using given reboot type
+    async fn reboot(&mut self, reboot_type: RebootType) -> Result<()>;
+
+    /// Reboot the device using given reboot type, then wait for it to be fully booted again, failing after `timeout`.
+    ///
+    /// Device is first waited for to actually go down, as it still reports being booted until then. See [`AsyncADBDeviceExt::wait_for_boot`].
+    async fn reboot_and_wait(&mut self, reboot_type: RebootType, timeout: Duration) -> Result<()> {
+        boot::reboot_and_wait(self, reboot_type, timeout).await
+    }
+
+    /// Wait for device to be fully booted, failing after `timeout`.
+    ///
+    /// Device has to come back, have `sys.boot_completed` and `dev.bootcomplete` set, and its package manager has to answer requests.
+    /// Timeouts are reported as [`crate::RustADBError::BootTimeout`], along with last [`crate::BootStage`] reached.
+    ///
+    /// Right after a [`AsyncADBDeviceExt::reboot`], device may still report being booted: use [`AsyncADBDeviceExt::reboot_and_wait`] instead.
+    async fn wait_for_boot(&mut self, timeout: Duration) -> Result<()> {
+        self.wait_for_boot_since(None, timeout).await
+    }
+
+    /// Wait for device to be fully booted in a boot other than `previous_boot`, as returned by [`AsyncADBDeviceExt::boot_id`],
+    /// failing after `timeout`. See [`AsyncADBDeviceExt::wait_for_boot`].
+    ///
+    /// Devices are not reconnected: a device connected directly has to be connected again once it rebooted.
+    async fn wait_for_boot_since(
+        &mut self,
+        previous_boot: Option<&str>,
+        timeout: Duration,
+    ) -> Result<()> {
+        boot::wait_for_boot(self, previous_boot, timeout).await
+    }
+
+    /// Get identifier of current boot of device, changing each time it boots, or `None` if device does not expose it.
+    async fn boot_id(&mut self) -> Result<Option<String>> {
+        boot::boot_id(self).await
+    }
+
+    /// Run `activity` from `package` on device. Return the command output.
+    ///
+    /// Activity name is relative to package, see [`AsyncADBDeviceExt::start_activity`] to start any activity.
+    #[deprecated(
+        note = "use `start_activity` with an `Intent`, which reports errors and starts activities outside of package namespace"
+    )]
+    async fn run_activity(&mut self, package: &str, activity: &str) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.shell_command(
+            &["am", "start", &format!("{package}/{package}.{activity}")],
+            &mut output,
+        )
+        .await?;
+
+        Ok(output)
+    }
+
+    /// Start activity described by `intent`, failing with error reported by activity manager if it cannot be started.
+    async fn start_activity(&mut self, intent: &Intent) -> Result<()> {
+        activity_manager::start_activity(self, intent, false).await?;
+        Ok(())
+    }
+
+    /// Start activity described by `intent` and wait for it to be drawn, as `am start -W` does, returning its launch timings.
+    async fn start_activity_and_wait(&mut self, intent: &Intent) -> Result<ActivityStartResult> {
+        activity_manager::start_activity(self, intent, true).await
+    }
+
+    /// Install an APK pointed to by `apk_path` on device.
+    async fn install(&mut self, apk_path: &(dyn AsRef<Path> + Sync)) -> Result<()> {
+        self.install_with_options(apk_path, &InstallOptions::default())
+            .await
+    }
+
+    /// Install an APK pointed to by `apk_path` on device with given `options`.
+    ///
+    /// Package manager refusing APK is reported as [`crate::RustADBError::InstallFailed`], along with its `INSTALL_FAILED_*` reason.
+    async fn install_with_options(
+        &mut self,
+        apk_path: &(dyn AsRef<Path> + Sync),
+        options: &InstallOptions,
+    ) -> Result<()> {
+        check_extension_is_apk(apk_path)?;
+        let mut apk_file = tokio::fs::File::open(apk_path).await?;
+        let size = apk_file.metadata().await?.len();
+        self.install_from_reader(&mut apk_file, size, options)
+            .await?;
+
+        log::info!(
+            "APK file {} successfully installed",
+            apk_path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Install an APK of `size` bytes read from `reader` on device with given `options`.
+    ///
+    /// Contents are checked to start like an APK, failing with [`crate::RustADBError::InvalidApk`] otherwise.
+    async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()>;
+
+    /// Uninstall the package `package` from device.
+    async fn uninstall(&mut self, package: &str) -> Result<()> {
+        self.uninstall_with_options(package, &UninstallOptions::default())
+            .await
+    }
+
+    /// Uninstall the package `package` from device with given `options`, e.g. keeping its data.
+    async fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &UninstallOptions,
+    ) -> Result<()>;
+
+    /// Inner method requesting framebuffer from an Android device
+    async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>>;
+
+    /// Dump framebuffer of this device into given path
+    async fn framebuffer(&mut self, path: &(dyn AsRef<Path> + Sync)) -> Result<()> {
+        let img = self.framebuffer_inner().await?;
+        let path = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || img.save(path)).await??;
+
+        Ok(())
+    }
+
+    /// Dump framebuffer of this device and return corresponding bytes.
+    ///
+    /// Output data format is currently only `PNG`.
+    async fn framebuffer_bytes(&mut self) -> Result<Vec<u8>> {
+        let img = self.framebuffer_inner().await?;
+        let mut vec = Cursor::new(Vec::new());
+        img.write_to(&mut vec, ImageFormat::Png)?;
+
+        Ok(vec.into_inner())
+    }
+
+    /// Return a boxed instance representing this trait
+    fn boxed(self) -> Box<dyn AsyncADBDeviceExt>
+    where
+        Self: Sized,
+        Self: 'static,
+    {
+        Box::new(self)
+    }
+}
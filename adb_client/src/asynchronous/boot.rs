@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::boot::{BOOT_ID_PATH, POLL_INTERVAL, boot_checks, parse_boot_id};
+use crate::models::{BootStage, RebootType};
+use crate::{AsyncADBDeviceExt, Result, RustADBError};
+
+/// Wait for device to be fully booted in a boot other than `previous_boot`. See [`AsyncADBDeviceExt::wait_for_boot_since`].
+pub(crate) async fn wait_for_boot<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    previous_boot: Option<&str>,
+    timeout: Duration,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    let mut stage = BootStage::Disconnected;
+    loop {
+        // A device which stops answering must not block past deadline
+        stage = match tokio::time::timeout_at(deadline, boot_stage(device, previous_boot)).await {
+            Ok(Ok(stage)) => stage,
+            Ok(Err(e)) => {
+                log::debug!("device cannot be reached: {e}");
+                BootStage::Disconnected
+            }
+            Err(_) => return Err(RustADBError::BootTimeout(stage)),
+        };
+        log::debug!("device reached boot stage: {stage}");
+        if stage == BootStage::Ready {
+            return Ok(());
+        }
+
+        let left = deadline.saturating_duration_since(Instant::now());
+        tokio::time::sleep(POLL_INTERVAL.min(left)).await;
+        if left <= POLL_INTERVAL {
+            return Err(RustADBError::BootTimeout(stage));
+        }
+    }
+}
+
+/// Reboot device, then wait for it to be fully booted again. See [`AsyncADBDeviceExt::reboot_and_wait`].
+pub(crate) async fn reboot_and_wait<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    reboot_type: RebootType,
+    timeout: Duration,
+) -> Result<()> {
+    // Device keeps reporting itself as booted until it actually goes down
+    let previous_boot = boot_id(device).await?;
+    device.reboot(reboot_type).await?;
+    device
+        .wait_for_boot_since(previous_boot.as_deref(), timeout)
+        .await
+}
+
+/// Identifier of current boot of device, `None` if device does not expose it.
+pub(crate) async fn boot_id<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+) -> Result<Option<String>> {
+    let mut output = Vec::new();
+    device
+        .shell_command_with_status(&["cat", BOOT_ID_PATH], &mut output, &mut tokio::io::sink())
+        .await?;
+    Ok(parse_boot_id(&output))
+}
+
+/// Last boot stage reached by device, which did not go down yet if it still runs boot `previous_boot`.
+async fn boot_stage<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    previous_boot: Option<&str>,
+) -> Result<BootStage> {
+    if previous_boot.is_some() && boot_id(device).await?.as_deref() == previous_boot {
+        return Ok(BootStage::Connected);
+    }
+
+    for check in boot_checks() {
+        let mut output = Vec::new();
+        device
+            .shell_command_with_status(&check.command(), &mut output, &mut tokio::io::sink())
+            .await?;
+        if !check.passed(&output) {
+            return Ok(check.stage);
+        }
+    }
+
+    Ok(BootStage::Ready)
+}
+
+#[tokio::test]
+async fn test_async_wait_for_boot() {
+    use crate::FakeADBDevice;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop 'sys.boot_completed'", b"1\n");
+    fake_device.add_shell_response("getprop 'dev.bootcomplete'", b"1\n");
+    fake_device.add_shell_response("service check package", b"Service package: found\n");
+    fake_device.add_shell_response(
+        "pm path android",
+        b"package:/system/framework/framework-res.apk\n",
+    );
+    let mut device = crate::fake_device::connected_async_tcp_device(&fake_device).await;
+    device
+        .wait_for_boot(Duration::from_secs(5))
+        .await
+        .expect("device is not booted");
+
+    // Package manager is not up yet
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop 'sys.boot_completed'", b"1\n");
+    fake_device.add_shell_response("getprop 'dev.bootcomplete'", b"1\n");
+    fake_device.add_shell_response("service check package", b"Service package: not found\n");
+    let mut device = crate::fake_device::connected_async_tcp_device(&fake_device).await;
+    assert!(matches!(
+        device.wait_for_boot(Duration::from_millis(600)).await,
+        Err(RustADBError::BootTimeout(BootStage::DeviceBooted))
+    ));
+
+    // Device answering too slowly does not hold caller past timeout
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_fault(crate::FakeFault::Delay(Duration::from_secs(2)));
+    let mut device = crate::fake_device::connected_async_tcp_device(&fake_device).await;
+    let start = Instant::now();
+    assert!(matches!(
+        device.wait_for_boot(Duration::from_millis(500)).await,
+        Err(RustADBError::BootTimeout(_))
+    ));
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
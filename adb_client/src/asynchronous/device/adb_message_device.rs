@@ -0,0 +1,323 @@
+use rand::Rng;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    AdbStatResponse, AsyncADBMessageTransport, Result, RustADBError,
+    constants::BUFFER_SIZE,
+    device::{
+        ADBDeviceBanner, ADBKeyStore, ADBTransportMessage, MessageCommand, MessageSubcommand,
+        adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN},
+    },
+    models::SyncDataDecoder,
+};
+
+/// Time device gets to acknowledge closing a session.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Initial `CNXN` message sent to the device.
+pub(crate) fn cnxn_message() -> ADBTransportMessage {
+    ADBTransportMessage::new(
+        MessageCommand::Cnxn,
+        0x01000000,
+        1048576,
+        format!("host::{}\0", env!("CARGO_PKG_NAME")).as_bytes(),
+    )
+}
+
+/// Asynchronous counterpart of the generic message device, reachable over an [`AsyncADBMessageTransport`].
+/// Structure is totally agnostic over which transport is truly used.
+#[derive(Debug)]
+pub struct AsyncADBMessageDevice<T: AsyncADBMessageTransport> {
+    transport: T,
+    local_id: Option<u32>,
+    remote_id: Option<u32>,
+    banner: Option<ADBDeviceBanner>,
+}
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    /// Instantiate a new [`AsyncADBMessageDevice`]
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            local_id: None,
+            remote_id: None,
+            banner: None,
+        }
+    }
+
+    pub(crate) fn get_transport(&mut self) -> &T {
+        &self.transport
+    }
+
+    pub(crate) fn get_transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Whether device advertised `feature` in its banner when connecting.
+    pub(crate) fn has_feature(&self, feature: &str) -> bool {
+        self.banner
+            .as_ref()
+            .is_some_and(|banner| banner.has_feature(feature))
+    }
+
+    /// Store device banner received in given `CNXN` message
+    pub(crate) fn set_banner(&mut self, cnxn_message: &ADBTransportMessage) {
+        match ADBDeviceBanner::try_from(cnxn_message.payload().as_slice()) {
+            Ok(banner) => self.banner = Some(banner),
+            Err(e) => log::warn!("cannot parse device banner: {e}"),
+        }
+    }
+
+    /// Handle the authentication flow with the device, trying each key of `keys` before sending the public key of the user key.
+    pub(crate) async fn authenticate(
+        &mut self,
+        keys: &ADBKeyStore,
+        auth_message: ADBTransportMessage,
+    ) -> Result<()> {
+        let mut auth_message = auth_message;
+        for key in keys.keys() {
+            // At this point, we should have received an AUTH message with arg0 == 1
+            match auth_message.header().arg0() {
+                AUTH_TOKEN => {}
+                v => {
+                    return Err(RustADBError::ADBRequestFailed(format!(
+                        "Received AUTH message with type != 1 ({v})"
+                    )));
+                }
+            }
+
+            let sign = key.sign(auth_message.into_payload())?;
+            let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_SIGNATURE, 0, &sign);
+            self.transport.write_message(message).await?;
+
+            // Device answers with a new token when it does not know the key
+            let received_response = self.transport.read_message().await?;
+            if received_response.header().command() == MessageCommand::Cnxn {
+                self.set_banner(&received_response);
+                log::info!(
+                    "Authentication OK with key {}, device info {}",
+                    key.fingerprint()?,
+                    String::from_utf8(received_response.into_payload())?
+                );
+                return Ok(());
+            }
+            received_response.assert_command(MessageCommand::Auth)?;
+            auth_message = received_response;
+        }
+
+        let user_key = keys.user_key();
+        log::info!(
+            "Device does not know any key, sending public key with fingerprint {}",
+            user_key.fingerprint()?
+        );
+        let mut pubkey = user_key.android_pubkey_encode()?.into_bytes();
+        pubkey.push(b'\0');
+
+        let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_RSAPUBLICKEY, 0, &pubkey);
+
+        self.transport.write_message(message).await?;
+
+        let response = self
+            .transport
+            .read_message_with_timeout(Duration::from_secs(10))
+            .await?;
+        response.assert_command(MessageCommand::Cnxn)?;
+        self.set_banner(&response);
+
+        log::info!(
+            "Authentication OK, device info {}",
+            String::from_utf8(response.into_payload())?
+        );
+
+        Ok(())
+    }
+
+    /// Receive a message and acknowledge it by replying with an `OKAY` command
+    pub(crate) async fn recv_and_reply_okay(&mut self) -> Result<ADBTransportMessage> {
+        let message = self.read_session_message().await?;
+        let (local_id, remote_id) = (self.get_local_id()?, self.get_remote_id()?);
+        self.transport
+            .write_message(ADBTransportMessage::new(
+                MessageCommand::Okay,
+                local_id,
+                remote_id,
+                &[],
+            ))
+            .await?;
+        Ok(message)
+    }
+
+    /// Expect a message with an `OKAY` command after sending a message.
+    pub(crate) async fn send_and_expect_okay(
+        &mut self,
+        message: ADBTransportMessage,
+    ) -> Result<ADBTransportMessage> {
+        self.transport.write_message(message).await?;
+
+        let message = self.read_session_message().await?;
+        message.assert_command(MessageCommand::Okay)?;
+        Ok(message)
+    }
+
+    /// Send a `WRTE` message carrying `data` on the current session and wait for its acknowledgement.
+    pub(crate) async fn write_and_expect_okay(&mut self, data: &[u8]) -> Result<()> {
+        let message = ADBTransportMessage::new(
+            MessageCommand::Write,
+            self.get_local_id()?,
+            self.get_remote_id()?,
+            data,
+        );
+        self.send_and_expect_okay(message).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn recv_file(
+        &mut self,
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        let mut decoder = SyncDataDecoder::default();
+        while !decoder.is_done() {
+            decoder.push(self.recv_and_reply_okay().await?.payload());
+            while let Some(data) = decoder.next_data()? {
+                output.write_all(&data).await?;
+            }
+        }
+        output.flush().await?;
+        Ok(())
+    }
+
+    pub(crate) async fn push_file(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        mtime: u32,
+    ) -> Result<()> {
+        let mut buffer = vec![0; BUFFER_SIZE];
+
+        loop {
+            match reader.read(&mut buffer).await? {
+                0 => {
+                    self.write_and_expect_okay(&MessageSubcommand::Done.encode(mtime, &[]))
+                        .await?;
+
+                    // Command should end with a Write => Okay
+                    let received = self.read_session_message().await?;
+                    return match received.header().command() {
+                        MessageCommand::Write => Ok(()),
+                        c => Err(RustADBError::ADBRequestFailed(format!(
+                            "Wrong command received {c}"
+                        ))),
+                    };
+                }
+                size => {
+                    let packet = MessageSubcommand::Data.encode(size as u32, &buffer[..size]);
+                    self.write_and_expect_okay(&packet).await?;
+                }
+            }
+        }
+    }
+
+    pub(crate) async fn begin_synchronization(&mut self) -> Result<()> {
+        self.open_session(b"sync:\0").await?;
+        Ok(())
+    }
+
+    pub(crate) async fn stat_with_explicit_ids(
+        &mut self,
+        remote_path: &str,
+    ) -> Result<AdbStatResponse> {
+        let stat_buffer = MessageSubcommand::Stat.encode(remote_path.len() as u32, &[]);
+        self.write_and_expect_okay(&stat_buffer).await?;
+        self.write_and_expect_okay(remote_path.as_bytes()).await?;
+        let response = self.read_session_message().await?;
+        AdbStatResponse::decode(response.payload())
+    }
+
+    pub(crate) async fn end_transaction(&mut self) -> Result<()> {
+        self.write_and_expect_okay(&MessageSubcommand::Quit.encode(0, &[]))
+            .await?;
+        let _discard_close = self.read_session_message().await?;
+        Ok(())
+    }
+
+    /// Close current session, waiting for device to acknowledge it.
+    pub(crate) async fn close_session(&mut self) -> Result<()> {
+        let local_id = self.get_local_id()?;
+        self.transport
+            .write_message(ADBTransportMessage::new(
+                MessageCommand::Clse,
+                local_id,
+                self.get_remote_id()?,
+                &[],
+            ))
+            .await?;
+
+        loop {
+            let message = self
+                .transport
+                .read_message_with_timeout(CLOSE_TIMEOUT)
+                .await?;
+            let header = message.header();
+            if header.command() == MessageCommand::Clse && header.arg1() == local_id {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Outcome of a transfer made in current session, closing this session if transfer failed.
+    pub(crate) async fn close_session_on_error<V: Send>(&mut self, result: Result<V>) -> Result<V> {
+        if result.is_err() {
+            if let Err(e) = self.close_session().await {
+                log::debug!("cannot close session of failed transfer: {e}");
+            }
+        }
+        result
+    }
+
+    pub(crate) async fn open_session(&mut self, data: &[u8]) -> Result<ADBTransportMessage> {
+        let local_id: u32 = rand::rng().random();
+
+        let message = ADBTransportMessage::new(
+            MessageCommand::Open,
+            local_id, // Our 'local-id'
+            0,
+            data,
+        );
+        self.get_transport_mut().write_message(message).await?;
+
+        self.local_id = Some(local_id);
+        let response = self.read_session_message().await?;
+        self.remote_id = Some(response.header().arg0());
+
+        Ok(response)
+    }
+
+    /// Read next message sent on current session.
+    ///
+    /// Messages of previous sessions which were not consumed (e.g. late acknowledgements) are skipped.
+    pub(crate) async fn read_session_message(&mut self) -> Result<ADBTransportMessage> {
+        let local_id = self.get_local_id()?;
+        loop {
+            let message = self.transport.read_message().await?;
+            if message.header().arg1() == local_id {
+                return Ok(message);
+            }
+            log::debug!(
+                "ignoring {} message of a previous session",
+                message.header().command()
+            );
+        }
+    }
+
+    pub(crate) fn get_local_id(&self) -> Result<u32> {
+        self.local_id.ok_or(RustADBError::ADBRequestFailed(
+            "connection not opened, no local_id".into(),
+        ))
+    }
+
+    pub(crate) fn get_remote_id(&self) -> Result<u32> {
+        self.remote_id.ok_or(RustADBError::ADBRequestFailed(
+            "connection not opened, no remote_id".into(),
+        ))
+    }
+}
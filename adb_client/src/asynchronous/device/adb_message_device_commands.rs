@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use image::{ImageBuffer, Rgba};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    AsyncADBDeviceExt, AsyncADBMessageTransport, InstallOptions, RebootType, Result,
+    UninstallOptions,
+    models::{AdbDirEntry, AdbStatResponse, PushOptions},
+};
+
+use super::AsyncADBMessageDevice;
+
+#[async_trait]
+impl<T: AsyncADBMessageTransport> AsyncADBDeviceExt for AsyncADBMessageDevice<T> {
+    async fn shell_command(
+        &mut self,
+        command: &[&str],
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.shell_command(command, output).await
+    }
+
+    async fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<Option<u8>> {
+        self.shell_command_with_status(command, stdout, stderr)
+            .await
+    }
+
+    async fn shell(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<()> {
+        self.shell(reader, writer).await
+    }
+
+    async fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
+        self.stat(remote_path).await
+    }
+
+    async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        self.list_dir(path).await
+    }
+
+    async fn pull(
+        &mut self,
+        source: &(dyn AsRef<str> + Sync),
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.pull(source.as_ref(), output).await
+    }
+
+    async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &(dyn AsRef<str> + Sync),
+        options: PushOptions,
+    ) -> Result<()> {
+        self.push_with_options(stream, path.as_ref(), options).await
+    }
+
+    async fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.reboot(reboot_type).await
+    }
+
+    async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.install_from_reader(reader, size, options).await
+    }
+
+    async fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.uninstall_with_options(package, options).await
+    }
+
+    async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.framebuffer_inner().await
+    }
+}
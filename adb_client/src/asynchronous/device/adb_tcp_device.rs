@@ -0,0 +1,327 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use image::{ImageBuffer, Rgba};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{AsyncADBMessageDevice, cnxn_message};
+use crate::device::{ADBKeyStore, ADBTransportMessage, MessageCommand, get_default_adb_key_path};
+use crate::{
+    AdbDirEntry, AdbStatResponse, AsyncADBDeviceExt, AsyncADBMessageTransport, AsyncADBTransport,
+    AsyncTcpTransport, InstallOptions, RebootType, Result, RustADBError, UninstallOptions,
+};
+
+/// Asynchronous counterpart of [`crate::ADBTcpDevice`], representing a device reached and available over TCP.
+#[derive(Debug)]
+pub struct AsyncADBTcpDevice {
+    keys: ADBKeyStore,
+    inner: AsyncADBMessageDevice<AsyncTcpTransport>,
+}
+
+impl AsyncADBTcpDevice {
+    /// Instantiate a new [`AsyncADBTcpDevice`]
+    pub async fn new(address: SocketAddr) -> Result<Self> {
+        Self::new_with_custom_private_key(address, get_default_adb_key_path()?).await
+    }
+
+    /// Instantiate a new [`AsyncADBTcpDevice`] using a custom private key path
+    pub async fn new_with_custom_private_key(
+        address: SocketAddr,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        let transport =
+            AsyncTcpTransport::new_with_custom_private_key(address, private_key_path.clone())?;
+        Self::new_from_transport_inner(transport, private_key_path).await
+    }
+
+    /// Instantiate a new [`AsyncADBTcpDevice`] from an [`AsyncTcpTransport`] and an optional private key path.
+    pub async fn new_from_transport(
+        transport: AsyncTcpTransport,
+        private_key_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let private_key_path = match private_key_path {
+            Some(private_key_path) => private_key_path,
+            None => get_default_adb_key_path()?,
+        };
+
+        Self::new_from_transport_inner(transport, private_key_path).await
+    }
+
+    async fn new_from_transport_inner(
+        transport: AsyncTcpTransport,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        let keys = ADBKeyStore::load(&private_key_path)?;
+
+        let mut s = Self {
+            keys,
+            inner: AsyncADBMessageDevice::new(transport),
+        };
+
+        s.connect().await?;
+
+        Ok(s)
+    }
+
+    /// Send initial connect
+    pub async fn connect(&mut self) -> Result<()> {
+        self.get_transport_mut().connect().await?;
+        self.get_transport_mut()
+            .write_message(cnxn_message())
+            .await?;
+
+        let message = self.get_transport_mut().read_message().await?;
+
+        // Check if client is requesting a secure connection and upgrade it if necessary
+        match message.header().command() {
+            MessageCommand::Stls => {
+                self.get_transport_mut()
+                    .write_message(ADBTransportMessage::new(MessageCommand::Stls, 1, 0, &[]))
+                    .await?;
+                self.get_transport_mut().upgrade_connection().await?;
+                log::debug!("Connection successfully upgraded from TCP to TLS");
+
+                // After TLS upgrade, send CNXN again and check for authentication
+                self.get_transport_mut()
+                    .write_message(cnxn_message())
+                    .await?;
+                let message = self.get_transport_mut().read_message().await?;
+
+                // After TLS, device might require authentication or accept connection
+                match message.header().command() {
+                    MessageCommand::Cnxn => {
+                        log::debug!("Secure connection established without authentication");
+                        self.inner.set_banner(&message);
+                        Ok(())
+                    }
+                    MessageCommand::Auth => self.inner.authenticate(&self.keys, message).await,
+                    _ => Err(RustADBError::WrongResponseReceived(
+                        "Expected CNXN or AUTH command after TLS upgrade".to_string(),
+                        message.header().command().to_string(),
+                    )),
+                }
+            }
+            MessageCommand::Cnxn => {
+                log::debug!("Unencrypted connection established without authentication");
+                self.inner.set_banner(&message);
+                Ok(())
+            }
+            MessageCommand::Auth => {
+                log::debug!("Authentication required");
+                self.inner.authenticate(&self.keys, message).await
+            }
+            _ => Err(RustADBError::WrongResponseReceived(
+                "Expected CNXN, AUTH, or STLS command".to_string(),
+                message.header().command().to_string(),
+            )),
+        }
+    }
+
+    #[inline]
+    fn get_transport_mut(&mut self) -> &mut AsyncTcpTransport {
+        self.inner.get_transport_mut()
+    }
+}
+
+#[async_trait]
+impl AsyncADBDeviceExt for AsyncADBTcpDevice {
+    #[inline]
+    async fn shell_command(
+        &mut self,
+        command: &[&str],
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.inner.shell_command(command, output).await
+    }
+
+    #[inline]
+    async fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<Option<u8>> {
+        self.inner
+            .shell_command_with_status(command, stdout, stderr)
+            .await
+    }
+
+    #[inline]
+    async fn shell(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<()> {
+        self.inner.shell(reader, writer).await
+    }
+
+    #[inline]
+    async fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
+        self.inner.stat(remote_path).await
+    }
+
+    #[inline]
+    async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        self.inner.list_dir(path).await
+    }
+
+    #[inline]
+    async fn pull(
+        &mut self,
+        source: &(dyn AsRef<str> + Sync),
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.inner.pull(source.as_ref(), output).await
+    }
+
+    #[inline]
+    async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &(dyn AsRef<str> + Sync),
+        options: crate::PushOptions,
+    ) -> Result<()> {
+        self.inner
+            .push_with_options(stream, path.as_ref(), options)
+            .await
+    }
+
+    #[inline]
+    async fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.inner.reboot(reboot_type).await
+    }
+
+    #[inline]
+    async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.inner.install_from_reader(reader, size, options).await
+    }
+
+    #[inline]
+    async fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.inner.uninstall_with_options(package, options).await
+    }
+
+    #[inline]
+    async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.inner.framebuffer_inner().await
+    }
+}
+
+impl Drop for AsyncADBTcpDevice {
+    fn drop(&mut self) {
+        // Best effort here, connection can only be closed if a runtime is still available
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let mut transport = self.get_transport_mut().clone();
+            handle.spawn(async move { transport.disconnect().await });
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_async_tcp_device() {
+    use crate::{AdbFileType, FakeADBDevice, InstallFailureReason};
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop ro.build.version.sdk", b"35\n");
+    fake_device.add_shell_response_with_status(
+        "ls /data",
+        b"",
+        b"ls: /data: Permission denied\n",
+        1,
+    );
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+    let mut device = crate::fake_device::connected_async_tcp_device(&fake_device).await;
+
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.build.version.sdk"], &mut output)
+        .await
+        .expect("cannot run shell command");
+    assert_eq!(output, b"35\n");
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let exit_code = device
+        .shell_command_with_status(&["ls", "/data"], &mut stdout, &mut stderr)
+        .await
+        .expect("cannot run shell command");
+    assert_eq!(exit_code, Some(1));
+    assert!(stdout.is_empty());
+    assert_eq!(stderr, b"ls: /data: Permission denied\n");
+
+    let contents = vec![42; 200_000];
+    device
+        .push(&mut contents.as_slice(), &"/sdcard/data.bin")
+        .await
+        .expect("cannot push file");
+    assert_eq!(
+        fake_device
+            .file("/sdcard/data.bin")
+            .expect("cannot get file"),
+        Some(contents)
+    );
+
+    let stat = device
+        .stat("/sdcard/data.bin")
+        .await
+        .expect("cannot stat file");
+    assert_eq!(stat.file_size, 200_000);
+    assert_eq!(stat.file_type(), AdbFileType::File);
+
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/hello.txt", &mut pulled)
+        .await
+        .expect("cannot pull file");
+    assert_eq!(pulled, b"hello");
+
+    let mut entries = device.list_dir("/sdcard").await.expect("cannot list directory");
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.file_size))
+            .collect::<Vec<_>>(),
+        [("data.bin", 200_000), ("hello.txt", 5)]
+    );
+
+    let apk = b"PK\x03\x04app";
+    device
+        .install_from_reader(&mut apk.as_slice(), apk.len() as u64, &InstallOptions::default())
+        .await
+        .expect("cannot install APK");
+    let apk = b"PK\x03\x04testOnly";
+    assert!(matches!(
+        device
+            .install_from_reader(&mut apk.as_slice(), apk.len() as u64, &InstallOptions::default())
+            .await,
+        Err(RustADBError::InstallFailed(InstallFailureReason::TestOnly, _))
+    ));
+    // Files not looking like an APK are not sent to device
+    let text = b"not an APK";
+    assert!(
+        device
+            .install_from_reader(&mut text.as_slice(), text.len() as u64, &InstallOptions::default())
+            .await
+            .is_err()
+    );
+
+    // Device remains usable after failed installations
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.build.version.sdk"], &mut output)
+        .await
+        .expect("cannot run shell command");
+    assert_eq!(output, b"35\n");
+}
@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use image::{ImageBuffer, Rgba};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{AsyncADBMessageDevice, cnxn_message};
+use crate::device::{ADBKeyStore, MessageCommand, get_default_adb_key_path, search_adb_devices};
+use crate::{
+    AdbDirEntry, AdbStatResponse, AsyncADBDeviceExt, AsyncADBMessageTransport, AsyncADBTransport,
+    AsyncUSBTransport, InstallOptions, RebootType, Result, RustADBError, UninstallOptions,
+};
+
+/// Asynchronous counterpart of [`crate::ADBUSBDevice`], representing a device reached and available over USB.
+#[derive(Debug)]
+pub struct AsyncADBUSBDevice {
+    keys: ADBKeyStore,
+    inner: AsyncADBMessageDevice<AsyncUSBTransport>,
+}
+
+impl AsyncADBUSBDevice {
+    /// Instantiate a new [`AsyncADBUSBDevice`]
+    pub async fn new(vendor_id: u16, product_id: u16) -> Result<Self> {
+        Self::new_with_custom_private_key(vendor_id, product_id, get_default_adb_key_path()?).await
+    }
+
+    /// Instantiate a new [`AsyncADBUSBDevice`] using a custom private key path
+    pub async fn new_with_custom_private_key(
+        vendor_id: u16,
+        product_id: u16,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        let transport = AsyncUSBTransport::new(vendor_id, product_id)?;
+        Self::new_from_transport_inner(transport, private_key_path).await
+    }
+
+    /// Instantiate a new [`AsyncADBUSBDevice`] from an [`AsyncUSBTransport`] and an optional private key path.
+    pub async fn new_from_transport(
+        transport: AsyncUSBTransport,
+        private_key_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let private_key_path = match private_key_path {
+            Some(private_key_path) => private_key_path,
+            None => get_default_adb_key_path()?,
+        };
+
+        Self::new_from_transport_inner(transport, private_key_path).await
+    }
+
+    async fn new_from_transport_inner(
+        transport: AsyncUSBTransport,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        let keys = ADBKeyStore::load(&private_key_path)?;
+
+        let mut s = Self {
+            keys,
+            inner: AsyncADBMessageDevice::new(transport),
+        };
+
+        s.connect().await?;
+
+        Ok(s)
+    }
+
+    /// autodetect connected ADB devices and establish a connection with the first device found
+    pub async fn autodetect() -> Result<Self> {
+        Self::autodetect_with_custom_private_key(get_default_adb_key_path()?).await
+    }
+
+    /// autodetect connected ADB devices and establish a connection with the first device found using a custom private key path
+    pub async fn autodetect_with_custom_private_key(private_key_path: PathBuf) -> Result<Self> {
+        match tokio::task::spawn_blocking(search_adb_devices).await?? {
+            Some((vendor_id, product_id)) => {
+                Self::new_with_custom_private_key(vendor_id, product_id, private_key_path).await
+            }
+            _ => Err(RustADBError::DeviceNotFound(
+                "cannot find USB devices matching the signature of an ADB device".into(),
+            )),
+        }
+    }
+
+    /// Send initial connect
+    pub async fn connect(&mut self) -> Result<()> {
+        self.get_transport_mut().connect().await?;
+        self.get_transport_mut()
+            .write_message(cnxn_message())
+            .await?;
+
+        let message = self.get_transport_mut().read_message().await?;
+
+        // If the device returned CNXN instead of AUTH it does not require authentication,
+        // so we can skip the auth steps.
+        if message.header().command() == MessageCommand::Cnxn {
+            self.inner.set_banner(&message);
+            return Ok(());
+        }
+        message.assert_command(MessageCommand::Auth)?;
+
+        self.inner.authenticate(&self.keys, message).await
+    }
+
+    #[inline]
+    fn get_transport_mut(&mut self) -> &mut AsyncUSBTransport {
+        self.inner.get_transport_mut()
+    }
+}
+
+#[async_trait]
+impl AsyncADBDeviceExt for AsyncADBUSBDevice {
+    #[inline]
+    async fn shell_command(
+        &mut self,
+        command: &[&str],
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.inner.shell_command(command, output).await
+    }
+
+    #[inline]
+    async fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<Option<u8>> {
+        self.inner
+            .shell_command_with_status(command, stdout, stderr)
+            .await
+    }
+
+    #[inline]
+    async fn shell(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<()> {
+        self.inner.shell(reader, writer).await
+    }
+
+    #[inline]
+    async fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
+        self.inner.stat(remote_path).await
+    }
+
+    #[inline]
+    async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        self.inner.list_dir(path).await
+    }
+
+    #[inline]
+    async fn pull(
+        &mut self,
+        source: &(dyn AsRef<str> + Sync),
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.inner.pull(source.as_ref(), output).await
+    }
+
+    #[inline]
+    async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &(dyn AsRef<str> + Sync),
+        options: crate::PushOptions,
+    ) -> Result<()> {
+        self.inner
+            .push_with_options(stream, path.as_ref(), options)
+            .await
+    }
+
+    #[inline]
+    async fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.inner.reboot(reboot_type).await
+    }
+
+    #[inline]
+    async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.inner.install_from_reader(reader, size, options).await
+    }
+
+    #[inline]
+    async fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.inner.uninstall_with_options(package, options).await
+    }
+
+    #[inline]
+    async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.inner.framebuffer_inner().await
+    }
+}
+
+impl Drop for AsyncADBUSBDevice {
+    fn drop(&mut self) {
+        // Best effort here, connection can only be closed if a runtime is still available
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let mut transport = self.get_transport_mut().clone();
+            handle.spawn(async move { transport.disconnect().await });
+        }
+    }
+}
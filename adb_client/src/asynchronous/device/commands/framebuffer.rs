@@ -0,0 +1,72 @@
+use std::io::{Cursor, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use image::{ImageBuffer, Rgba};
+
+use crate::{
+    AsyncADBMessageTransport, Result, RustADBError,
+    asynchronous::device::AsyncADBMessageDevice,
+    device::MessageCommand,
+    models::{FrameBufferInfoV1, FrameBufferInfoV2},
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.open_session(b"framebuffer:\0").await?;
+
+        let response = self.recv_and_reply_okay().await?;
+
+        let mut payload_cursor = Cursor::new(response.payload());
+
+        let version = payload_cursor.read_u32::<LittleEndian>()?;
+
+        let (width, height, size) = match version {
+            // RGBA_8888
+            1 => {
+                let mut buf = [0u8; std::mem::size_of::<FrameBufferInfoV1>()];
+                payload_cursor.read_exact(&mut buf)?;
+                let framebuffer_info: FrameBufferInfoV1 = buf.try_into()?;
+                (
+                    framebuffer_info.width,
+                    framebuffer_info.height,
+                    framebuffer_info.size,
+                )
+            }
+            // RGBX_8888
+            2 => {
+                let mut buf = [0u8; std::mem::size_of::<FrameBufferInfoV2>()];
+                payload_cursor.read_exact(&mut buf)?;
+                let framebuffer_info: FrameBufferInfoV2 = buf.try_into()?;
+                (
+                    framebuffer_info.width,
+                    framebuffer_info.height,
+                    framebuffer_info.size,
+                )
+            }
+            v => return Err(RustADBError::UnimplementedFramebufferImageVersion(v)),
+        };
+
+        let mut framebuffer_data = Vec::new();
+        payload_cursor.read_to_end(&mut framebuffer_data)?;
+
+        while framebuffer_data.len() as u32 != size {
+            let response = self.recv_and_reply_okay().await?;
+
+            framebuffer_data.extend_from_slice(&response.into_payload());
+
+            log::debug!(
+                "received framebuffer data. new size {}",
+                framebuffer_data.len()
+            );
+        }
+
+        let img = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_vec(width, height, framebuffer_data)
+            .ok_or_else(|| RustADBError::FramebufferConversionError)?;
+
+        self.read_session_message()
+            .await
+            .and_then(|message| message.assert_command(MessageCommand::Clse))?;
+
+        Ok(img)
+    }
+}
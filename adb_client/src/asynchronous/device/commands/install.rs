@@ -0,0 +1,53 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    AsyncADBMessageTransport, InstallOptions, Result,
+    asynchronous::device::AsyncADBMessageDevice,
+    constants::BUFFER_SIZE,
+    utils::{check_apk_signature, check_apk_size, check_package_manager_response},
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        let mut magic = [0; 4];
+        let magic = check_apk_signature(reader.read_exact(&mut magic).await.map(|_| magic))?;
+
+        self.open_session(format!("{}\0", options.install_service(size)).as_bytes())
+            .await?;
+
+        let copied = self.write_apk((&magic[..]).chain(reader), size).await;
+        // Package manager would otherwise keep waiting for the rest of the APK
+        self.close_session_on_error(copied).await?;
+
+        // Session is closed by device once response is sent, which must be consumed before next request
+        let mut response = Vec::new();
+        while let Some(data) = self.read_shell_data().await? {
+            response.extend_from_slice(&data);
+        }
+
+        check_package_manager_response(&response)
+    }
+
+    /// Write an APK declared to be `size` bytes long from `apk` to current session.
+    async fn write_apk(&mut self, apk: impl AsyncRead + Unpin + Send, size: u64) -> Result<()> {
+        // Package manager expects exactly `size` bytes
+        let mut apk = apk.take(size);
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut copied = 0;
+        loop {
+            let amount_read = apk.read(&mut buffer).await?;
+            if amount_read == 0 {
+                break;
+            }
+            self.write_and_expect_okay(&buffer[..amount_read]).await?;
+            copied += amount_read as u64;
+        }
+
+        check_apk_size(size, copied)
+    }
+}
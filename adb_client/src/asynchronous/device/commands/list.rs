@@ -0,0 +1,30 @@
+use crate::{
+    AsyncADBMessageTransport, Result, asynchronous::device::AsyncADBMessageDevice,
+    device::MessageSubcommand, models::AdbDirEntry,
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        let list_v2 = self.has_feature("ls_v2");
+        self.begin_synchronization().await?;
+
+        let list_buffer = match list_v2 {
+            true => MessageSubcommand::List2,
+            false => MessageSubcommand::List,
+        }
+        .encode(path.len() as u32, path.as_bytes());
+        self.write_and_expect_okay(&list_buffer).await?;
+
+        // Listing may be split across messages
+        let mut listing = Vec::new();
+        let entries = loop {
+            listing.extend_from_slice(self.recv_and_reply_okay().await?.payload());
+            if let Some(entries) = AdbDirEntry::decode_all(&listing, list_v2)? {
+                break entries;
+            }
+        };
+        self.end_transaction().await?;
+
+        Ok(entries)
+    }
+}
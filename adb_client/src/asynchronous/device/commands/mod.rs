@@ -0,0 +1,9 @@
+mod framebuffer;
+mod install;
+mod list;
+mod pull;
+mod push;
+mod reboot;
+mod shell;
+mod stat;
+mod uninstall;
@@ -0,0 +1,43 @@
+use tokio::io::AsyncWrite;
+
+use crate::{
+    AsyncADBMessageTransport, Result, RustADBError,
+    asynchronous::device::AsyncADBMessageDevice,
+    device::{ADBTransportMessage, MessageCommand, MessageSubcommand},
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn pull(
+        &mut self,
+        source: &str,
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.begin_synchronization().await?;
+
+        let adb_stat_response = self.stat_with_explicit_ids(source).await?;
+
+        if adb_stat_response.file_perm == 0 {
+            return Err(RustADBError::UnknownResponseType(
+                "mode is 0: source file does not exist".to_string(),
+            ));
+        }
+
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+
+        self.get_transport_mut()
+            .write_message_with_timeout(
+                ADBTransportMessage::new(MessageCommand::Okay, local_id, remote_id, &[]),
+                std::time::Duration::from_secs(4),
+            )
+            .await?;
+
+        let recv_buffer = MessageSubcommand::Recv.encode(source.len() as u32, &[]);
+        self.write_and_expect_okay(&recv_buffer).await?;
+        self.write_and_expect_okay(source.as_bytes()).await?;
+
+        self.recv_file(output).await?;
+        self.end_transaction().await?;
+        Ok(())
+    }
+}
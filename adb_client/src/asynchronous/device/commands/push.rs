@@ -0,0 +1,30 @@
+use tokio::io::AsyncRead;
+
+use crate::{
+    AsyncADBMessageTransport, Result, asynchronous::device::AsyncADBMessageDevice,
+    device::MessageSubcommand, models::PushOptions,
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &str,
+        options: PushOptions,
+    ) -> Result<()> {
+        self.begin_synchronization().await?;
+
+        let path_header = format!("{path},{}", options.mode);
+
+        let send_buffer =
+            MessageSubcommand::Send.encode(path_header.len() as u32, path_header.as_bytes());
+
+        self.write_and_expect_okay(&send_buffer).await?;
+
+        self.push_file(stream, options.mtime_or_now()).await?;
+
+        self.end_transaction().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{
+    AsyncADBMessageTransport, RebootType, Result, asynchronous::device::AsyncADBMessageDevice,
+    device::MessageCommand,
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.open_session(format!("reboot:{reboot_type}\0").as_bytes())
+            .await?;
+
+        self.read_session_message()
+            .await
+            .and_then(|message| message.assert_command(MessageCommand::Okay))
+    }
+}
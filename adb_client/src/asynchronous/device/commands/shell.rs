@@ -0,0 +1,183 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    AsyncADBMessageTransport, Result, RustADBError,
+    asynchronous::device::AsyncADBMessageDevice,
+    asynchronous::shell_v2_output::AsyncShellV2Output,
+    constants::BUFFER_SIZE,
+    device::{ADBTransportMessage, MessageCommand},
+    models::{ShellPacketId, encode_shell_packet},
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    /// Runs 'command' in a shell on the device, and write its output and error streams into output.
+    pub(crate) async fn shell_command(
+        &mut self,
+        command: &[&str],
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.open_shell_session(&format!("shell:{}", command.join(" ")))
+            .await?;
+
+        while let Some(data) = self.read_shell_data().await? {
+            output.write_all(&data).await?;
+        }
+        output.flush().await?;
+
+        Ok(())
+    }
+
+    /// Runs 'command' in a shell on the device, writing its output and error streams into `stdout` and `stderr`.
+    ///
+    /// Returns exit code of command if device supports shell protocol v2, else both streams are written into `stdout`.
+    pub(crate) async fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<Option<u8>> {
+        if !self.has_feature("shell_v2") {
+            log::debug!("device does not support shell v2, exit code will not be available");
+            self.shell_command(command, stdout).await?;
+            return Ok(None);
+        }
+
+        self.open_shell_session(&format!("shell,v2,raw:{}", command.join(" ")))
+            .await?;
+
+        // Command does not get any input
+        let close_stdin = ADBTransportMessage::new(
+            MessageCommand::Write,
+            self.get_local_id()?,
+            self.get_remote_id()?,
+            &encode_shell_packet(ShellPacketId::CloseStdin, &[]),
+        );
+        self.get_transport_mut().write_message(close_stdin).await?;
+
+        let mut output = AsyncShellV2Output::new(stdout, stderr);
+        while let Some(data) = self.read_shell_data().await? {
+            output.write(&data).await?;
+        }
+
+        output.finish().await
+    }
+
+    async fn open_shell_session(&mut self, service: &str) -> Result<()> {
+        let response = self.open_session(format!("{service}\0").as_bytes()).await?;
+
+        if response.header().command() != MessageCommand::Okay {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "wrong command {}",
+                response.header().command()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Next data sent by device on current session, or `None` once device closed it.
+    pub(crate) async fn read_shell_data(&mut self) -> Result<Option<Vec<u8>>> {
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+
+        loop {
+            let response = self.read_session_message().await?;
+
+            match response.header().command() {
+                MessageCommand::Write => {
+                    // Send OKAY acknowledgment for more data
+                    let ack =
+                        ADBTransportMessage::new(MessageCommand::Okay, local_id, remote_id, &[]);
+                    self.get_transport_mut().write_message(ack).await?;
+
+                    return Ok(Some(response.into_payload()));
+                }
+                MessageCommand::Okay => continue,
+                MessageCommand::Clse => {
+                    // Ignore Close messages targeting another session
+                    if response.header().arg1() == local_id && response.header().arg0() == remote_id
+                    {
+                        let close_msg = ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            local_id,
+                            remote_id,
+                            &[],
+                        );
+                        self.get_transport_mut().write_message(close_msg).await?;
+                        return Ok(None);
+                    }
+                }
+                _ => {
+                    return Err(RustADBError::ADBRequestFailed(format!(
+                        "unexpected command: {}",
+                        response.header().command()
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Starts an interactive shell session on the device.
+    /// Input data is read from [reader] and write to [writer].
+    pub(crate) async fn shell(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        mut writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<()> {
+        self.open_session(b"shell:\0").await?;
+
+        let mut transport = self.get_transport().clone();
+
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+
+        // Reading task, reads response from adbd until the session gets closed
+        let mut reading_task = tokio::spawn(async move {
+            loop {
+                let message = transport.read_message().await?;
+
+                match message.header().command() {
+                    MessageCommand::Write => {
+                        // Acknowledge for more data
+                        let response = ADBTransportMessage::new(
+                            MessageCommand::Okay,
+                            local_id,
+                            remote_id,
+                            &[],
+                        );
+                        transport.write_message(response).await?;
+
+                        writer.write_all(&message.into_payload()).await?;
+                        writer.flush().await?;
+                    }
+                    MessageCommand::Okay => continue,
+                    MessageCommand::Clse => return Ok(()),
+                    _ => return Err(RustADBError::ADBShellNotSupported),
+                }
+            }
+        });
+
+        // Read from given reader (that could be stdin e.g), and write content to device adbd
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            tokio::select! {
+                result = &mut reading_task => return result?,
+                amount_read = reader.read(&mut buffer) => {
+                    let amount_read = amount_read?;
+                    if amount_read == 0 {
+                        reading_task.abort();
+                        return Ok(());
+                    }
+
+                    let message = ADBTransportMessage::new(
+                        MessageCommand::Write,
+                        local_id,
+                        remote_id,
+                        &buffer[..amount_read],
+                    );
+                    self.get_transport_mut().write_message(message).await?;
+                }
+            }
+        }
+    }
+}
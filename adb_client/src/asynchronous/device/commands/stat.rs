@@ -0,0 +1,12 @@
+use crate::{
+    AdbStatResponse, AsyncADBMessageTransport, Result, asynchronous::device::AsyncADBMessageDevice,
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
+        self.begin_synchronization().await?;
+        let adb_stat_response = self.stat_with_explicit_ids(remote_path).await?;
+        self.end_transaction().await?;
+        Ok(adb_stat_response)
+    }
+}
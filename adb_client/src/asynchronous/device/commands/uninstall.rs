@@ -0,0 +1,24 @@
+use crate::{
+    AsyncADBMessageTransport, Result, UninstallOptions,
+    asynchronous::device::AsyncADBMessageDevice, utils::check_package_manager_response,
+};
+
+impl<T: AsyncADBMessageTransport> AsyncADBMessageDevice<T> {
+    pub(crate) async fn uninstall_with_options(
+        &mut self,
+        package_name: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.open_session(format!("{}\0", options.uninstall_service(package_name)).as_bytes())
+            .await?;
+
+        let mut response = Vec::new();
+        while let Some(data) = self.read_shell_data().await? {
+            response.extend_from_slice(&data);
+        }
+
+        check_package_manager_response(&response)?;
+        log::info!("Package {package_name} successfully uninstalled");
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+mod adb_message_device;
+mod adb_message_device_commands;
+mod adb_tcp_device;
+mod adb_usb_device;
+mod commands;
+
+pub(crate) use adb_message_device::{AsyncADBMessageDevice, cnxn_message};
+pub use adb_tcp_device::AsyncADBTcpDevice;
+pub use adb_usb_device::AsyncADBUSBDevice;
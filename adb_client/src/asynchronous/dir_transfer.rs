@@ -0,0 +1,247 @@
+use std::path::{Path, PathBuf};
+
+use crate::dir_transfer::{
+    LocalEntry, apply_entry_metadata, check_remote_command, local_entries, local_push_options,
+};
+use crate::models::{AdbDirEntry, AdbFileType, FileTransfer};
+use crate::utils::shell_quote;
+use crate::{AsyncADBDeviceExt, Result, RustADBError};
+
+/// Remote directory being pulled: its path, local path it is pulled into, and its entries left to pull.
+type PendingDir = (String, PathBuf, std::vec::IntoIter<AdbDirEntry>);
+
+/// Push local directory `source` to `destination` on device. See [`AsyncADBDeviceExt::push_dir`].
+pub(crate) async fn push_dir<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &Path,
+    destination: &str,
+) -> Result<Vec<FileTransfer>> {
+    if !tokio::fs::metadata(source).await?.is_dir() {
+        return Err(RustADBError::NotADirectory(source.display().to_string()));
+    }
+
+    let source = source.to_path_buf();
+    let destination = destination.trim_end_matches('/').to_string();
+    let entries =
+        tokio::task::spawn_blocking(move || local_entries(&source, &destination)).await??;
+
+    let mut transfers = Vec::new();
+    for entry in entries {
+        let result = match entry.file_type {
+            // Directories are created by device along with files they contain, empty ones have to be created explicitly
+            AdbFileType::Directory => make_remote_dir(device, &entry.remote_path).await,
+            _ => push_local_file(device, &entry).await,
+        };
+        transfers.push(entry.into_transfer(result));
+    }
+    Ok(transfers)
+}
+
+/// Pull directory `source` from device into local `destination`. See [`AsyncADBDeviceExt::pull_dir`].
+pub(crate) async fn pull_dir<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &str,
+    destination: &Path,
+) -> Result<Vec<FileTransfer>> {
+    let source = source.trim_end_matches('/');
+    // Devices stat files without following symbolic links, unless their path ends with a `/`
+    if device.stat(&format!("{source}/")).await?.file_type() != AdbFileType::Directory {
+        return Err(RustADBError::NotADirectory(source.to_string()));
+    }
+
+    let mut transfers = Vec::new();
+    let mut pending = vec![
+        open_dir(
+            device,
+            source.to_string(),
+            destination.to_path_buf(),
+            &mut transfers,
+        )
+        .await?,
+    ];
+
+    // Directories are walked depth first, as entries are sorted
+    while let Some((source, destination, entries)) = pending.last_mut() {
+        let Some(entry) = entries.next() else {
+            pending.pop();
+            continue;
+        };
+        let remote_path = format!("{source}/{}", entry.name);
+        let local_path = destination.join(&entry.name);
+
+        let result = match entry.file_type {
+            AdbFileType::Directory => {
+                let dir = open_dir(device, remote_path, local_path, &mut transfers).await?;
+                pending.push(dir);
+                continue;
+            }
+            AdbFileType::File | AdbFileType::Symlink => {
+                pull_file(device, &remote_path, &local_path, &entry).await
+            }
+            _ => {
+                log::warn!("skipping special file {remote_path}");
+                continue;
+            }
+        };
+
+        transfers.push(FileTransfer {
+            local_path,
+            remote_path,
+            file_type: entry.file_type,
+            size: entry.file_size,
+            result,
+        });
+    }
+
+    Ok(transfers)
+}
+
+/// Create local directory `destination` and list entries of remote directory `source` to pull into it.
+async fn open_dir<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: String,
+    destination: PathBuf,
+    transfers: &mut Vec<FileTransfer>,
+) -> Result<PendingDir> {
+    tokio::fs::create_dir_all(&destination).await?;
+
+    let mut entries = device.list_dir(&source).await?;
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if entries.is_empty() {
+        transfers.push(FileTransfer {
+            local_path: destination.clone(),
+            remote_path: source.clone(),
+            file_type: AdbFileType::Directory,
+            size: 0,
+            result: Ok(()),
+        });
+    }
+
+    Ok((source, destination, entries.into_iter()))
+}
+
+/// Pull file `source` into `destination`, giving it mode and modification time of `entry`.
+async fn pull_file<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &str,
+    destination: &Path,
+    entry: &AdbDirEntry,
+) -> Result<()> {
+    let mut file = tokio::fs::File::create(destination).await?;
+    if let Err(e) = device.pull(&source, &mut file).await {
+        drop(file);
+        let _ = tokio::fs::remove_file(destination).await;
+        return Err(e);
+    }
+
+    apply_entry_metadata(&file.into_std().await, entry)
+}
+
+/// Push local file or symbolic link of `entry`, keeping its mode and modification time.
+async fn push_local_file<D: AsyncADBDeviceExt + ?Sized>(
+    device: &mut D,
+    entry: &LocalEntry,
+) -> Result<()> {
+    let options = local_push_options(&entry.metadata);
+    if entry.metadata.is_symlink() {
+        let target = tokio::fs::read_link(&entry.local_path).await?;
+        let target = target.to_string_lossy();
+        return device
+            .push_with_options(&mut target.as_bytes(), &entry.remote_path, options)
+            .await;
+    }
+
+    let mut file = tokio::fs::File::open(&entry.local_path).await?;
+    device
+        .push_with_options(&mut file, &entry.remote_path, options)
+        .await
+}
+
+/// Create directory `path` on device, along with its missing parents.
+async fn make_remote_dir<D: AsyncADBDeviceExt + ?Sized>(device: &mut D, path: &str) -> Result<()> {
+    let mut stderr = Vec::new();
+    let status = device
+        .shell_command_with_status(
+            &["mkdir", "-p", &shell_quote(path)],
+            &mut tokio::io::sink(),
+            &mut stderr,
+        )
+        .await?;
+    check_remote_command(status, &stderr)
+}
+
+#[tokio::test]
+async fn test_async_push_pull_dir() {
+    use crate::FakeADBDevice;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let private_key_path = crate::fake_device::write_test_private_key("async_dir_transfer");
+    let directory = private_key_path.parent().expect("no parent directory");
+    let source = directory.join("source");
+    std::fs::create_dir_all(source.join("bin")).expect("cannot create directory");
+    std::fs::create_dir_all(source.join("empty")).expect("cannot create directory");
+    std::fs::write(source.join("bin/tool"), b"#!/bin/sh\n").expect("cannot write file");
+    std::fs::set_permissions(
+        source.join("bin/tool"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .expect("cannot set permissions");
+    std::fs::write(source.join("data.txt"), b"data").expect("cannot write file");
+    std::fs::File::options()
+        .write(true)
+        .open(source.join("data.txt"))
+        .and_then(|data| data.set_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000)))
+        .expect("cannot set modification time");
+
+    let fake_device = FakeADBDevice::new();
+    let mut device = crate::fake_device::connected_async_tcp_device(&fake_device).await;
+
+    let transfers = device
+        .push_dir(&source, "/data/local/tmp/tree/")
+        .await
+        .expect("cannot push directory");
+    assert!(
+        transfers.iter().all(|transfer| transfer.result.is_ok()),
+        "{transfers:?}"
+    );
+    assert_eq!(
+        transfers
+            .iter()
+            .map(|transfer| (transfer.remote_path.as_str(), transfer.file_type))
+            .collect::<Vec<_>>(),
+        [
+            ("/data/local/tmp/tree/bin/tool", AdbFileType::File),
+            ("/data/local/tmp/tree/data.txt", AdbFileType::File),
+            ("/data/local/tmp/tree/empty", AdbFileType::Directory),
+        ]
+    );
+
+    let destination = directory.join("destination");
+    let transfers = device
+        .pull_dir("/data/local/tmp/tree", &destination)
+        .await
+        .expect("cannot pull directory");
+    assert!(
+        transfers.iter().all(|transfer| transfer.result.is_ok()),
+        "{transfers:?}"
+    );
+    assert_eq!(transfers.len(), 3);
+
+    let tool = std::fs::metadata(destination.join("bin/tool")).expect("cannot stat file");
+    assert_eq!(tool.permissions().mode(), 0o100755);
+    let data = std::fs::metadata(destination.join("data.txt")).expect("cannot stat file");
+    assert_eq!(
+        data.modified().expect("no modification time"),
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    );
+    assert!(destination.join("empty").is_dir());
+
+    assert!(matches!(
+        device
+            .pull_dir("/data/local/tmp/tree/data.txt", &destination)
+            .await,
+        Err(RustADBError::NotADirectory(_))
+    ));
+}
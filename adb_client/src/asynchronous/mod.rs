@@ -0,0 +1,15 @@
+mod activity_manager;
+mod adb_device_ext;
+mod boot;
+mod device;
+mod dir_transfer;
+mod server;
+mod server_device;
+mod shell_v2_output;
+mod transports;
+
+pub use adb_device_ext::AsyncADBDeviceExt;
+pub use device::{AsyncADBTcpDevice, AsyncADBUSBDevice};
+pub use server::AsyncADBServer;
+pub use server_device::AsyncADBServerDevice;
+pub use transports::*;
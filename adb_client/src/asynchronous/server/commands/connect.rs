@@ -0,0 +1,19 @@
+use crate::{AsyncADBServer, Result, RustADBError, models::AdbServerCommand};
+use std::net::SocketAddrV4;
+
+impl AsyncADBServer {
+    /// Connect device over tcp with address and port
+    pub async fn connect_device(&mut self, address: SocketAddrV4) -> Result<()> {
+        let response = self
+            .connect()
+            .await?
+            .proxy_connection(AdbServerCommand::Connect(address), true)
+            .await?;
+
+        match String::from_utf8(response) {
+            Ok(s) if s.starts_with("connected to") => Ok(()),
+            Ok(s) => Err(RustADBError::ADBRequestFailed(s)),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    AsyncADBServer, AsyncADBServerDevice, DeviceLong, DeviceShort, Result, RustADBError,
+    models::AdbServerCommand,
+};
+
+impl AsyncADBServer {
+    /// Gets a list of connected devices.
+    pub async fn devices(&mut self) -> Result<Vec<DeviceShort>> {
+        let devices = self
+            .connect()
+            .await?
+            .proxy_connection(AdbServerCommand::Devices, true)
+            .await?;
+
+        let mut vec_devices: Vec<DeviceShort> = vec![];
+        for device in devices.split(|x| x.eq(&b'\n')) {
+            if device.is_empty() {
+                break;
+            }
+
+            vec_devices.push(DeviceShort::try_from(device.to_vec())?);
+        }
+
+        Ok(vec_devices)
+    }
+
+    /// Gets an extended list of connected devices including the device paths in the state.
+    pub async fn devices_long(&mut self) -> Result<Vec<DeviceLong>> {
+        let devices_long = self
+            .connect()
+            .await?
+            .proxy_connection(AdbServerCommand::DevicesLong, true)
+            .await?;
+
+        let mut vec_devices: Vec<DeviceLong> = vec![];
+        for device in devices_long.split(|x| x.eq(&b'\n')) {
+            if device.is_empty() {
+                break;
+            }
+
+            vec_devices.push(DeviceLong::try_from(device)?);
+        }
+
+        Ok(vec_devices)
+    }
+
+    /// Get a device, assuming that only this device is connected.
+    pub async fn get_device(&mut self) -> Result<AsyncADBServerDevice> {
+        let mut devices = self.devices().await?.into_iter();
+        match devices.next() {
+            Some(device) => match devices.next() {
+                Some(_) => Err(RustADBError::DeviceNotFound(
+                    "too many devices connected".to_string(),
+                )),
+                None => Ok(AsyncADBServerDevice::new(
+                    device.identifier,
+                    self.socket_addr,
+                )),
+            },
+            None => Err(RustADBError::DeviceNotFound(
+                "no device connected".to_string(),
+            )),
+        }
+    }
+
+    /// Get a device matching the given name, if existing.
+    /// - There is no device connected => Error
+    /// - There is a single device connected => Ok
+    /// - There are more than 1 device connected => Error
+    pub async fn get_device_by_name(&mut self, name: &str) -> Result<AsyncADBServerDevice> {
+        let nb_devices = self
+            .devices()
+            .await?
+            .into_iter()
+            .filter(|d| d.identifier.as_str() == name)
+            .count();
+        if nb_devices != 1 {
+            Err(RustADBError::DeviceNotFound(format!(
+                "could not find device {name}"
+            )))
+        } else {
+            Ok(AsyncADBServerDevice::new(
+                name.to_string(),
+                self.socket_addr,
+            ))
+        }
+    }
+
+    /// Tracks new devices showing up.
+    pub async fn track_devices(
+        &mut self,
+        callback: impl Fn(DeviceShort) -> Result<()> + Send,
+    ) -> Result<()> {
+        self.connect()
+            .await?
+            .send_adb_request(AdbServerCommand::TrackDevices)
+            .await?;
+
+        loop {
+            let length = self.get_transport()?.get_hex_body_length().await?;
+
+            if length > 0 {
+                let mut body = vec![
+                    0;
+                    length
+                        .try_into()
+                        .map_err(|_| RustADBError::ConversionError)?
+                ];
+                self.get_transport()?
+                    .get_raw_connection()?
+                    .read_exact(&mut body)
+                    .await?;
+
+                for device in body.split(|x| x.eq(&b'\n')) {
+                    if device.is_empty() {
+                        break;
+                    }
+                    callback(DeviceShort::try_from(device.to_vec())?)?;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use crate::{AsyncADBServer, Result, models::AdbServerCommand};
+
+impl AsyncADBServer {
+    /// Asks the ADB server to quit immediately.
+    pub async fn kill(&mut self) -> Result<()> {
+        self.connect()
+            .await?
+            .proxy_connection(AdbServerCommand::Kill, false)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,10 @@
+mod connect;
+mod devices;
+mod disconnect;
+mod kill;
+mod mdns;
+mod pair;
+mod reconnect;
+mod server_status;
+mod version;
+mod wait_for_device;
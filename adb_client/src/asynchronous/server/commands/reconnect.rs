@@ -0,0 +1,12 @@
+use crate::{AsyncADBServer, Result, models::AdbServerCommand};
+
+impl AsyncADBServer {
+    /// Reconnect the device
+    pub async fn reconnect_offline(&mut self) -> Result<()> {
+        self.connect()
+            .await?
+            .proxy_connection(AdbServerCommand::ReconnectOffline, false)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,14 @@
+use crate::{AsyncADBServer, Result, ServerStatus, models::AdbServerCommand};
+
+impl AsyncADBServer {
+    /// Check ADB server status
+    pub async fn server_status(&mut self) -> Result<ServerStatus> {
+        let status = self
+            .connect()
+            .await?
+            .proxy_connection(AdbServerCommand::ServerStatus, true)
+            .await?;
+
+        ServerStatus::try_from(status)
+    }
+}
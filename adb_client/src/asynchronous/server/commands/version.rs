@@ -0,0 +1,14 @@
+use crate::{AdbVersion, AsyncADBServer, Result, models::AdbServerCommand};
+
+impl AsyncADBServer {
+    /// Gets server's internal version number.
+    pub async fn version(&mut self) -> Result<AdbVersion> {
+        let version = self
+            .connect()
+            .await?
+            .proxy_connection(AdbServerCommand::Version, true)
+            .await?;
+
+        AdbVersion::try_from(version)
+    }
+}
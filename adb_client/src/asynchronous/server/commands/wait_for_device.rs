@@ -0,0 +1,22 @@
+use crate::{
+    AsyncADBServer, Result, WaitForDeviceState, WaitForDeviceTransport, models::AdbServerCommand,
+};
+
+impl AsyncADBServer {
+    /// Wait for a device in a given state to be connected
+    pub async fn wait_for_device(
+        &mut self,
+        state: WaitForDeviceState,
+        transport: Option<WaitForDeviceTransport>,
+    ) -> Result<()> {
+        let transport = transport.unwrap_or_default();
+
+        self.connect()
+            .await?
+            .send_adb_request(AdbServerCommand::WaitForDevice(state, transport, None))
+            .await?;
+
+        // Server should respond with an "OKAY" response
+        self.get_transport()?.read_adb_response().await
+    }
+}
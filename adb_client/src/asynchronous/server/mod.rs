@@ -0,0 +1,4 @@
+mod adb_server;
+mod commands;
+
+pub use adb_server::AsyncADBServer;
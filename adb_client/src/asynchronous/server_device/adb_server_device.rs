@@ -0,0 +1,137 @@
+use std::net::SocketAddrV4;
+
+use crate::{AsyncADBTransport, AsyncTCPServerTransport, Result, models::AdbServerCommand};
+
+/// Asynchronous counterpart of [`crate::ADBServerDevice`], representing a device connected to the ADB server.
+#[derive(Debug)]
+pub struct AsyncADBServerDevice {
+    /// Unique device identifier.
+    pub identifier: Option<String>,
+    /// Internal [AsyncTCPServerTransport]
+    pub(crate) transport: AsyncTCPServerTransport,
+}
+
+impl AsyncADBServerDevice {
+    /// Instantiates a new [AsyncADBServerDevice], knowing its ADB identifier (as returned by `adb devices` command).
+    pub fn new(identifier: String, server_addr: Option<SocketAddrV4>) -> Self {
+        let transport = AsyncTCPServerTransport::new_or_default(server_addr);
+
+        Self {
+            identifier: Some(identifier),
+            transport,
+        }
+    }
+
+    /// Instantiates a new [AsyncADBServerDevice], assuming only one is currently connected.
+    pub fn autodetect(server_addr: Option<SocketAddrV4>) -> Self {
+        let transport = AsyncTCPServerTransport::new_or_default(server_addr);
+
+        Self {
+            identifier: None,
+            transport,
+        }
+    }
+
+    /// Connect to underlying transport
+    pub(crate) async fn connect(&mut self) -> Result<&mut AsyncTCPServerTransport> {
+        self.transport.connect().await?;
+
+        Ok(&mut self.transport)
+    }
+
+    /// Set device connection to use serial transport
+    pub(crate) async fn set_serial_transport(&mut self) -> Result<()> {
+        let identifier = self.identifier.clone();
+        let transport = self.connect().await?;
+        if let Some(serial) = identifier {
+            transport
+                .send_adb_request(AdbServerCommand::TransportSerial(serial))
+                .await?;
+        } else {
+            transport
+                .send_adb_request(AdbServerCommand::TransportAny)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_async_server_device() {
+    use crate::{AsyncADBDeviceExt, FakeADBDevice, FakeADBServer, InstallOptions};
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop ro.build.version.sdk", b"35\n");
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+    let fake_server = FakeADBServer::new();
+    fake_server
+        .add_device("emulator-5554", &fake_device)
+        .expect("cannot add device");
+    let address = fake_server.listen().expect("cannot listen");
+
+    let mut device = AsyncADBServerDevice::new("emulator-5554".to_string(), Some(address));
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.build.version.sdk"], &mut output)
+        .await
+        .expect("cannot run shell command");
+    assert_eq!(output, b"35\n");
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let exit_code = device
+        .shell_command_with_status(&["false"], &mut stdout, &mut stderr)
+        .await
+        .expect("cannot run shell command");
+    assert_eq!(exit_code, Some(127));
+    assert_eq!(
+        stderr,
+        b"/system/bin/sh: false: inaccessible or not found\n"
+    );
+
+    device
+        .push(&mut b"world".as_slice(), "/sdcard/world.txt")
+        .await
+        .expect("cannot push file");
+    assert_eq!(
+        fake_device
+            .file("/sdcard/world.txt")
+            .expect("cannot get file"),
+        Some(b"world".to_vec())
+    );
+
+    let mut pulled = Vec::new();
+    device
+        .pull("/sdcard/hello.txt", &mut pulled)
+        .await
+        .expect("cannot pull file");
+    assert_eq!(pulled, b"hello");
+
+    let mut entries = device
+        .list_dir("/sdcard")
+        .await
+        .expect("cannot list directory");
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>(),
+        ["hello.txt", "world.txt"]
+    );
+
+    let apk = b"PK\x03\x04app";
+    device
+        .install_from_reader(
+            &mut apk.as_slice(),
+            apk.len() as u64,
+            &InstallOptions::default(),
+        )
+        .await
+        .expect("cannot install APK");
+
+    let requests = fake_server.requests().expect("cannot get requests");
+    assert!(requests.contains(&"host:transport:emulator-5554".to_string()));
+}
@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use image::{ImageBuffer, Rgba};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    AsyncADBDeviceExt, InstallOptions, RebootType, Result, RustADBError, UninstallOptions,
+    asynchronous::shell_v2_output::AsyncShellV2Output,
+    constants::BUFFER_SIZE,
+    models::{
+        AdbDirEntry, AdbServerCommand, AdbStatResponse, HostFeatures, PushOptions, ShellPacketId,
+        encode_shell_packet,
+    },
+};
+
+use super::AsyncADBServerDevice;
+
+impl AsyncADBServerDevice {
+    async fn check_shell_support(&mut self) -> Result<()> {
+        let supported_features = self.host_features().await?;
+        if !supported_features.contains(&HostFeatures::ShellV2)
+            && !supported_features.contains(&HostFeatures::Cmd)
+        {
+            return Err(RustADBError::ADBShellNotSupported);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncADBDeviceExt for AsyncADBServerDevice {
+    async fn shell_command(
+        &mut self,
+        command: &[&str],
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.check_shell_support().await?;
+
+        self.set_serial_transport().await?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::ShellCommand(command.join(" ")))
+            .await?;
+
+        tokio::io::copy(self.transport.get_raw_connection()?, output).await?;
+        output.flush().await?;
+
+        Ok(())
+    }
+
+    async fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<Option<u8>> {
+        if !self.host_features().await?.contains(&HostFeatures::ShellV2) {
+            log::debug!("device does not support shell v2, exit code will not be available");
+            AsyncADBDeviceExt::shell_command(self, command, stdout).await?;
+            return Ok(None);
+        }
+
+        self.set_serial_transport().await?;
+        self.transport
+            .send_adb_request(AdbServerCommand::ShellV2Command(command.join(" ")))
+            .await?;
+
+        let connection = self.transport.get_raw_connection()?;
+        // Command does not get any input
+        connection
+            .write_all(&encode_shell_packet(ShellPacketId::CloseStdin, &[]))
+            .await?;
+
+        let mut output = AsyncShellV2Output::new(stdout, stderr);
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            match connection.read(&mut buffer).await? {
+                0 => return output.finish().await,
+                size => output.write(&buffer[..size]).await?,
+            }
+        }
+    }
+
+    async fn shell(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        mut writer: Box<dyn AsyncWrite + Unpin + Send>,
+    ) -> Result<()> {
+        self.check_shell_support().await?;
+
+        self.set_serial_transport().await?;
+        self.transport
+            .send_adb_request(AdbServerCommand::Shell)
+            .await?;
+
+        let (mut read_stream, mut write_stream) =
+            self.transport.take_raw_connection()?.into_split();
+
+        // Reading task, reads response from adb-server
+        let mut reading_task = tokio::spawn(async move {
+            let mut buffer = vec![0; BUFFER_SIZE];
+            loop {
+                match read_stream.read(&mut buffer).await? {
+                    0 => return Ok::<(), RustADBError>(()),
+                    size => {
+                        writer.write_all(&buffer[..size]).await?;
+                        writer.flush().await?;
+                    }
+                }
+            }
+        });
+
+        // Read from given reader (that could be stdin e.g), and write content to server socket
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            tokio::select! {
+                result = &mut reading_task => return result?,
+                amount_read = reader.read(&mut buffer) => {
+                    match amount_read? {
+                        0 => {
+                            reading_task.abort();
+                            return Ok(());
+                        }
+                        size => write_stream.write_all(&buffer[..size]).await?,
+                    }
+                }
+            }
+        }
+    }
+
+    async fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
+        self.stat(remote_path).await
+    }
+
+    async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        self.list_dir(path).await
+    }
+
+    async fn pull(
+        &mut self,
+        source: &(dyn AsRef<str> + Sync),
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.pull(source.as_ref(), output).await
+    }
+
+    async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &(dyn AsRef<str> + Sync),
+        options: PushOptions,
+    ) -> Result<()> {
+        self.push_with_options(stream, path.as_ref(), options).await
+    }
+
+    async fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.reboot(reboot_type).await
+    }
+
+    async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.install_from_reader(reader, size, options).await
+    }
+
+    async fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.uninstall_with_options(package, options).await
+    }
+
+    async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.framebuffer_inner().await
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{AsyncADBServerDevice, Result, models::AdbServerCommand};
+
+impl AsyncADBServerDevice {
+    /// Forward socket connection
+    pub async fn forward(&mut self, remote: String, local: String) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::Forward(remote, local), false)
+            .await
+            .map(|_| ())
+    }
+
+    /// Remove all previously applied forward rules
+    pub async fn forward_remove_all(&mut self) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::ForwardRemoveAll, false)
+            .await
+            .map(|_| ())
+    }
+}
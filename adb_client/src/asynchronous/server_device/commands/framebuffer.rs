@@ -0,0 +1,54 @@
+use image::{ImageBuffer, Rgba};
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    AsyncADBServerDevice, Result, RustADBError,
+    models::{AdbServerCommand, FrameBufferInfoV1, FrameBufferInfoV2},
+};
+
+impl AsyncADBServerDevice {
+    /// Inner method requesting framebuffer from Android device
+    pub(crate) async fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::FrameBuffer)
+            .await?;
+
+        let raw_connection = self.transport.get_raw_connection()?;
+
+        let version = raw_connection.read_u32_le().await?;
+
+        let (width, height, size) = match version {
+            // RGBA_8888
+            1 => {
+                let mut buf = [0u8; std::mem::size_of::<FrameBufferInfoV1>()];
+                raw_connection.read_exact(&mut buf).await?;
+                let framebuffer_info: FrameBufferInfoV1 = buf.try_into()?;
+                (
+                    framebuffer_info.width,
+                    framebuffer_info.height,
+                    framebuffer_info.size,
+                )
+            }
+            // RGBX_8888
+            2 => {
+                let mut buf = [0u8; std::mem::size_of::<FrameBufferInfoV2>()];
+                raw_connection.read_exact(&mut buf).await?;
+                let framebuffer_info: FrameBufferInfoV2 = buf.try_into()?;
+                (
+                    framebuffer_info.width,
+                    framebuffer_info.height,
+                    framebuffer_info.size,
+                )
+            }
+            v => return Err(RustADBError::UnimplementedFramebufferImageVersion(v)),
+        };
+
+        let mut data = vec![0_u8; size.try_into().map_err(|_| RustADBError::ConversionError)?];
+        raw_connection.read_exact(&mut data).await?;
+
+        ImageBuffer::<Rgba<u8>, Vec<u8>>::from_vec(width, height, data)
+            .ok_or_else(|| RustADBError::FramebufferConversionError)
+    }
+}
@@ -0,0 +1,21 @@
+use crate::{
+    AsyncADBServerDevice, Result,
+    models::{AdbServerCommand, HostFeatures},
+};
+
+impl AsyncADBServerDevice {
+    /// Lists available ADB server features.
+    pub async fn host_features(&mut self) -> Result<Vec<HostFeatures>> {
+        self.set_serial_transport().await?;
+
+        let features = self
+            .transport
+            .proxy_connection(AdbServerCommand::HostFeatures, true)
+            .await?;
+
+        Ok(features
+            .split(|x| x.eq(&b','))
+            .filter_map(|v| HostFeatures::try_from(v).ok())
+            .collect())
+    }
+}
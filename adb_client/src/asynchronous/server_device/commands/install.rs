@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+    AsyncADBDeviceExt, AsyncADBServerDevice, InstallOptions, Result,
+    models::AdbServerCommand,
+    utils::{check_apk_signature, check_apk_size, check_package_manager_response},
+};
+
+impl AsyncADBServerDevice {
+    /// Install an APK on device
+    pub async fn install(&mut self, apk_path: &(dyn AsRef<Path> + Sync)) -> Result<()> {
+        AsyncADBDeviceExt::install(self, apk_path).await
+    }
+
+    /// Install an APK on device with given `options`
+    pub async fn install_with_options(
+        &mut self,
+        apk_path: &(dyn AsRef<Path> + Sync),
+        options: &InstallOptions,
+    ) -> Result<()> {
+        AsyncADBDeviceExt::install_with_options(self, apk_path, options).await
+    }
+
+    /// Install an APK of `size` bytes read from `reader` on device with given `options`
+    pub async fn install_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        let mut magic = [0; 4];
+        let magic = check_apk_signature(reader.read_exact(&mut magic).await.map(|_| magic))?;
+
+        self.set_serial_transport().await?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::Install(size, options.clone()))
+            .await?;
+
+        let raw_connection = self.transport.get_raw_connection()?;
+
+        // Package manager expects exactly `size` bytes
+        let mut apk = (&magic[..]).chain(reader).take(size);
+        let copied = tokio::io::copy(&mut apk, raw_connection).await?;
+        check_apk_size(size, copied)?;
+        raw_connection.flush().await?;
+
+        let mut data = [0; 1024];
+        let read_amount = raw_connection.read(&mut data).await?;
+
+        check_package_manager_response(&data[0..read_amount])
+    }
+}
@@ -0,0 +1,47 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+    AsyncADBServerDevice, Result,
+    constants::BUFFER_SIZE,
+    models::{AdbDirEntry, AdbServerCommand, HostFeatures, SyncCommand},
+};
+
+impl AsyncADBServerDevice {
+    /// Lists files in path on the device.
+    pub async fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        let list_v2 = self.host_features().await?.contains(&HostFeatures::LsV2);
+        self.set_serial_transport().await?;
+
+        // Set device in SYNC mode
+        self.transport
+            .send_adb_request(AdbServerCommand::Sync)
+            .await?;
+
+        // Send a list command, using sync protocol v2 when device supports it
+        match list_v2 {
+            true => self.transport.send_sync_request(SyncCommand::List2).await?,
+            false => self.transport.send_sync_request(SyncCommand::List).await?,
+        }
+
+        // 4 bytes of command name is already sent by send_sync_request
+        let connection = self.transport.get_raw_connection()?;
+        let mut buffer = Vec::with_capacity(4 + path.len());
+        buffer.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(path.as_bytes());
+        connection.write_all(&buffer).await?;
+
+        // Device then sends an entry per file, and "DONE" once listing is complete
+        let mut listing = Vec::new();
+        let mut data = vec![0; BUFFER_SIZE];
+        loop {
+            if let Some(entries) = AdbDirEntry::decode_all(&listing, list_v2)? {
+                return Ok(entries);
+            }
+
+            match connection.read(&mut data).await? {
+                0 => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                size => listing.extend_from_slice(&data[..size]),
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+mod forward;
+mod framebuffer;
+mod host_features;
+mod install;
+mod list;
+mod reboot;
+mod reconnect;
+mod recv;
+mod reverse;
+mod send;
+mod stat;
+mod tcpip;
+mod transport;
+mod uninstall;
+mod usb;
@@ -0,0 +1,16 @@
+use crate::{
+    AsyncADBServerDevice, Result,
+    models::{AdbServerCommand, RebootType},
+};
+
+impl AsyncADBServerDevice {
+    /// Reboots the device
+    pub async fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::Reboot(reboot_type), false)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,13 @@
+use crate::{AsyncADBServerDevice, Result, models::AdbServerCommand};
+
+impl AsyncADBServerDevice {
+    /// Reconnect device
+    pub async fn reconnect(&mut self) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::Reconnect, false)
+            .await
+            .map(|_| ())
+    }
+}
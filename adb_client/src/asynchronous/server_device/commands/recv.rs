@@ -0,0 +1,62 @@
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    AsyncADBServerDevice, Result,
+    constants::BUFFER_SIZE,
+    models::{AdbServerCommand, SyncCommand, SyncDataDecoder},
+};
+
+impl AsyncADBServerDevice {
+    /// Receives path to stream from the device.
+    pub async fn pull(
+        &mut self,
+        path: &str,
+        stream: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        // Set device in SYNC mode
+        self.transport
+            .send_adb_request(AdbServerCommand::Sync)
+            .await?;
+
+        // Send a recv command
+        self.transport.send_sync_request(SyncCommand::Recv).await?;
+
+        self.handle_recv_command(path, stream).await
+    }
+
+    async fn handle_recv_command(
+        &mut self,
+        from: &str,
+        output: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<()> {
+        let raw_connection = self.transport.get_raw_connection()?;
+
+        let mut buffer = Vec::with_capacity(4 + from.len());
+        buffer.extend_from_slice(&(from.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(from.as_bytes());
+        raw_connection.write_all(&buffer).await?;
+
+        let mut decoder = SyncDataDecoder::default();
+        let mut data = vec![0_u8; BUFFER_SIZE];
+        while !decoder.is_done() {
+            while let Some(chunk) = decoder.next_data()? {
+                output.write_all(&chunk).await?;
+            }
+            if decoder.is_done() {
+                break;
+            }
+
+            match raw_connection.read(&mut data).await? {
+                0 => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                size => decoder.push(&data[..size]),
+            }
+        }
+
+        output.flush().await?;
+
+        // Connection should've been left in SYNC mode by now
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{AsyncADBServerDevice, Result, models::AdbServerCommand};
+
+impl AsyncADBServerDevice {
+    /// Reverse socket connection
+    pub async fn reverse(&mut self, remote: String, local: String) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::Reverse(remote, local), false)
+            .await
+            .map(|_| ())
+    }
+
+    /// Remove all reverse rules
+    pub async fn reverse_remove_all(&mut self) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::ReverseRemoveAll, false)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,106 @@
+use std::str::{self, FromStr};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+    AsyncADBServerDevice, Result, RustADBError,
+    constants::BUFFER_SIZE,
+    models::{AdbRequestStatus, AdbServerCommand, PushOptions, SyncCommand},
+};
+
+impl AsyncADBServerDevice {
+    /// Send stream to path on the device, with default [`PushOptions`].
+    pub async fn push(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &str,
+    ) -> Result<()> {
+        self.push_with_options(stream, path, PushOptions::default())
+            .await
+    }
+
+    /// Send stream to path on the device, creating it with mode and modification time from `options`.
+    pub async fn push_with_options(
+        &mut self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+        path: &str,
+        options: PushOptions,
+    ) -> Result<()> {
+        log::info!("Sending data to {path}");
+        self.set_serial_transport().await?;
+
+        // Set device in SYNC mode
+        self.transport
+            .send_adb_request(AdbServerCommand::Sync)
+            .await?;
+
+        // Send a send command
+        self.transport.send_sync_request(SyncCommand::Send).await?;
+
+        self.handle_send_command(stream, path, options).await
+    }
+
+    async fn handle_send_command(
+        &mut self,
+        input: &mut (dyn AsyncRead + Unpin + Send),
+        to: &str,
+        options: PushOptions,
+    ) -> Result<()> {
+        // Append the permission flags to the filename
+        let to = format!("{to},{}", options.mode);
+
+        let raw_connection = self.transport.get_raw_connection()?;
+
+        // The name of the command is already sent by send_sync_request
+        let mut buffer = Vec::with_capacity(4 + to.len());
+        buffer.extend_from_slice(&(to.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(to.as_bytes());
+        raw_connection.write_all(&buffer).await?;
+
+        // 8 = "DATA".len() + sizeof(u32)
+        let mut buffer = vec![0_u8; 8 + BUFFER_SIZE];
+        loop {
+            let amount_read = input.read(&mut buffer[8..]).await?;
+            if amount_read == 0 {
+                break;
+            }
+            buffer[..4].copy_from_slice(b"DATA");
+            buffer[4..8].copy_from_slice(&(amount_read as u32).to_le_bytes());
+            raw_connection.write_all(&buffer[..8 + amount_read]).await?;
+        }
+
+        // Copy is finished, we can now notify as finished
+        // Have to send DONE + file mtime
+        let mut done_buffer = Vec::with_capacity(8);
+        done_buffer.extend_from_slice(b"DONE");
+        done_buffer.extend_from_slice(&options.mtime_or_now().to_le_bytes());
+        raw_connection.write_all(&done_buffer).await?;
+
+        // We expect 'OKAY' response from this
+        let mut request_status = [0; 4];
+        raw_connection.read_exact(&mut request_status).await?;
+
+        match AdbRequestStatus::from_str(str::from_utf8(&request_status)?)? {
+            AdbRequestStatus::Fail => {
+                // We can keep reading to get further details
+                let length = self.transport.get_body_length().await?;
+
+                let mut body = vec![
+                    0;
+                    length
+                        .try_into()
+                        .map_err(|_| RustADBError::ConversionError)?
+                ];
+                if length > 0 {
+                    self.transport
+                        .get_raw_connection()?
+                        .read_exact(&mut body)
+                        .await?;
+                }
+
+                Err(RustADBError::ADBRequestFailed(String::from_utf8(body)?))
+            }
+            AdbRequestStatus::Okay => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+    AsyncADBServerDevice, Result,
+    models::{AdbServerCommand, AdbStatResponse, STAT_RESPONSE_SIZE, SyncCommand},
+};
+
+impl AsyncADBServerDevice {
+    async fn handle_stat_command(&mut self, path: &str) -> Result<AdbStatResponse> {
+        // 4 bytes of command name is already sent by send_sync_request
+        let mut buffer = Vec::with_capacity(4 + path.len());
+        buffer.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(path.as_bytes());
+        self.transport
+            .get_raw_connection()?
+            .write_all(&buffer)
+            .await?;
+
+        // Reads returned status code from ADB server
+        let mut response = [0_u8; STAT_RESPONSE_SIZE];
+        let connection = self.transport.get_raw_connection()?;
+        connection.read_exact(&mut response[..4]).await?;
+        // Any other response is reported without waiting for more data
+        if response.starts_with(b"STAT") {
+            connection.read_exact(&mut response[4..]).await?;
+        }
+
+        AdbStatResponse::decode(&response)
+    }
+
+    /// Stat file given as path on the device.
+    pub async fn stat(&mut self, path: &str) -> Result<AdbStatResponse> {
+        self.set_serial_transport().await?;
+
+        // Set device in SYNC mode
+        self.transport
+            .send_adb_request(AdbServerCommand::Sync)
+            .await?;
+
+        // Send a "Stat" command
+        self.transport.send_sync_request(SyncCommand::Stat).await?;
+
+        self.handle_stat_command(path).await
+    }
+}
@@ -0,0 +1,13 @@
+use crate::{AsyncADBServerDevice, Result, models::AdbServerCommand};
+
+impl AsyncADBServerDevice {
+    /// Set adb daemon to tcp/ip mode
+    pub async fn tcpip(&mut self, port: u16) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .proxy_connection(AdbServerCommand::TcpIp(port), false)
+            .await
+            .map(|_| ())
+    }
+}
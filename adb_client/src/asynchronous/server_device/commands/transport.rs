@@ -0,0 +1,12 @@
+use crate::{AsyncADBServerDevice, Result, models::AdbServerCommand};
+
+impl AsyncADBServerDevice {
+    /// Asks ADB server to switch the connection to either the device or emulator connect to/running on the host. Will fail if there is more than one such device/emulator available.
+    pub async fn transport_any(&mut self) -> Result<()> {
+        self.connect()
+            .await?
+            .proxy_connection(AdbServerCommand::TransportAny, false)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,37 @@
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    AsyncADBServerDevice, Result, UninstallOptions, models::AdbServerCommand,
+    utils::check_package_manager_response,
+};
+
+impl AsyncADBServerDevice {
+    /// Uninstall a package from device
+    pub async fn uninstall(&mut self, package_name: &str) -> Result<()> {
+        self.uninstall_with_options(package_name, &UninstallOptions::default())
+            .await
+    }
+
+    /// Uninstall a package from device with given `options`, e.g. keeping its data.
+    pub async fn uninstall_with_options(
+        &mut self,
+        package_name: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.set_serial_transport().await?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::Uninstall(
+                package_name.to_string(),
+                options.clone(),
+            ))
+            .await?;
+
+        let mut data = [0; 1024];
+        let read_amount = self.transport.get_raw_connection()?.read(&mut data).await?;
+
+        check_package_manager_response(&data[0..read_amount])?;
+        log::info!("Package {package_name} successfully uninstalled");
+        Ok(())
+    }
+}
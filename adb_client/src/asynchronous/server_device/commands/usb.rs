@@ -0,0 +1,12 @@
+use crate::{AsyncADBServerDevice, Result, models::AdbServerCommand};
+
+impl AsyncADBServerDevice {
+    /// Set adb daemon to usb mode
+    pub async fn usb(&mut self) -> Result<()> {
+        self.set_serial_transport().await?;
+        self.transport
+            .proxy_connection(AdbServerCommand::Usb, false)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,5 @@
+mod adb_server_device;
+mod adb_server_device_commands;
+mod commands;
+
+pub use adb_server_device::AsyncADBServerDevice;
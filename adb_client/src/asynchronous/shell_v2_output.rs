@@ -0,0 +1,47 @@
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::Result;
+use crate::models::{ShellOutput, ShellPacketDecoder};
+
+/// Asynchronous counterpart of [`crate::models::ShellV2Output`], splitting shell protocol v2 packets into `stdout` and `stderr`.
+pub(crate) struct AsyncShellV2Output<'a> {
+    decoder: ShellPacketDecoder,
+    stdout: &'a mut (dyn AsyncWrite + Unpin + Send),
+    stderr: &'a mut (dyn AsyncWrite + Unpin + Send),
+    exit_code: Option<u8>,
+}
+
+impl<'a> AsyncShellV2Output<'a> {
+    pub(crate) fn new(
+        stdout: &'a mut (dyn AsyncWrite + Unpin + Send),
+        stderr: &'a mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Self {
+        Self {
+            decoder: ShellPacketDecoder::default(),
+            stdout,
+            stderr,
+            exit_code: None,
+        }
+    }
+
+    /// Write packets received in `data`, which may be split anywhere.
+    pub(crate) async fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.decoder.push(data);
+        while let Some(output) = self.decoder.next_output()? {
+            match output {
+                ShellOutput::Stdout(data) => self.stdout.write_all(&data).await?,
+                ShellOutput::Stderr(data) => self.stderr.write_all(&data).await?,
+                ShellOutput::Exit(code) => self.exit_code = code,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flush both streams, returning exit code of command once received.
+    pub(crate) async fn finish(self) -> Result<Option<u8>> {
+        self.stdout.flush().await?;
+        self.stderr.flush().await?;
+        Ok(self.exit_code)
+    }
+}
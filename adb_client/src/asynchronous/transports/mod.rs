@@ -0,0 +1,9 @@
+mod tcp_server_transport;
+mod tcp_transport;
+mod traits;
+mod usb_transport;
+
+pub use tcp_server_transport::AsyncTCPServerTransport;
+pub use tcp_transport::AsyncTcpTransport;
+pub use traits::{AsyncADBMessageTransport, AsyncADBTransport};
+pub use usb_transport::AsyncUSBTransport;
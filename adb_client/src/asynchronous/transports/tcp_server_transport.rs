@@ -0,0 +1,191 @@
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use byteorder::{ByteOrder, LittleEndian};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::AsyncADBTransport;
+use crate::models::{
+    AdbRequestStatus, AdbServerCommand, SyncCommand, decode_hex_length, encode_hex_framed,
+};
+use crate::{Result, RustADBError};
+
+const DEFAULT_SERVER_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+const DEFAULT_SERVER_PORT: u16 = 5037;
+
+/// Asynchronous server transport running on top on TCP
+#[derive(Debug)]
+pub struct AsyncTCPServerTransport {
+    socket_addr: SocketAddrV4,
+    tcp_stream: Option<TcpStream>,
+}
+
+impl Default for AsyncTCPServerTransport {
+    fn default() -> Self {
+        Self::new(SocketAddrV4::new(DEFAULT_SERVER_IP, DEFAULT_SERVER_PORT))
+    }
+}
+
+impl AsyncTCPServerTransport {
+    /// Instantiates a new instance of [AsyncTCPServerTransport]
+    pub fn new(socket_addr: SocketAddrV4) -> Self {
+        Self {
+            socket_addr,
+            tcp_stream: None,
+        }
+    }
+
+    /// Instantiate a new instance of [AsyncTCPServerTransport] using given address, or default if not specified.
+    pub fn new_or_default(socket_addr: Option<SocketAddrV4>) -> Self {
+        match socket_addr {
+            Some(s) => Self::new(s),
+            None => Self::default(),
+        }
+    }
+
+    /// Get underlying [SocketAddrV4]
+    pub fn get_socketaddr(&self) -> SocketAddrV4 {
+        self.socket_addr
+    }
+
+    pub(crate) async fn proxy_connection(
+        &mut self,
+        adb_command: AdbServerCommand,
+        with_response: bool,
+    ) -> Result<Vec<u8>> {
+        self.send_adb_request(adb_command).await?;
+
+        if with_response {
+            let length = self.get_hex_body_length().await?;
+            let mut body = vec![
+                0;
+                length
+                    .try_into()
+                    .map_err(|_| RustADBError::ConversionError)?
+            ];
+            if length > 0 {
+                self.get_raw_connection()?.read_exact(&mut body).await?;
+            }
+
+            Ok(body)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub(crate) fn get_raw_connection(&mut self) -> Result<&mut TcpStream> {
+        self.tcp_stream
+            .as_mut()
+            .ok_or(RustADBError::IOError(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "not connected",
+            )))
+    }
+
+    /// Take ownership of the underlying connection, leaving this transport disconnected.
+    pub(crate) fn take_raw_connection(&mut self) -> Result<TcpStream> {
+        self.tcp_stream
+            .take()
+            .ok_or(RustADBError::IOError(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "not connected",
+            )))
+    }
+
+    /// Gets the body length from hexadecimal value
+    pub(crate) async fn get_hex_body_length(&mut self) -> Result<u32> {
+        decode_hex_length(self.read_body_length().await?)
+    }
+
+    /// Send the given [SyncCommand] to ADB server, and checks that the request has been taken in consideration.
+    pub(crate) async fn send_sync_request(&mut self, command: SyncCommand) -> Result<()> {
+        // First 4 bytes are the name of the command we want to send
+        // (e.g. "SEND", "RECV", "STAT", "LIST")
+        Ok(self
+            .get_raw_connection()?
+            .write_all(command.to_string().as_bytes())
+            .await?)
+    }
+
+    /// Gets the body length from a LittleEndian value
+    pub(crate) async fn get_body_length(&mut self) -> Result<u32> {
+        let length_buffer = self.read_body_length().await?;
+        Ok(LittleEndian::read_u32(&length_buffer))
+    }
+
+    /// Read 4 bytes representing body length
+    async fn read_body_length(&mut self) -> Result<[u8; 4]> {
+        let mut length_buffer = [0; 4];
+        self.get_raw_connection()?
+            .read_exact(&mut length_buffer)
+            .await?;
+
+        Ok(length_buffer)
+    }
+
+    /// Send the given [AdbServerCommand] to ADB server, and checks that the request has been taken in consideration.
+    /// If an error occurred, a [RustADBError] is returned with the response error string.
+    pub(crate) async fn send_adb_request(&mut self, command: AdbServerCommand) -> Result<()> {
+        let adb_request = encode_hex_framed(command.to_string().as_bytes());
+
+        self.get_raw_connection()?.write_all(&adb_request).await?;
+
+        self.read_adb_response().await
+    }
+
+    /// Read a response from ADB server
+    pub(crate) async fn read_adb_response(&mut self) -> Result<()> {
+        // Reads returned status code from ADB server
+        let mut request_status = [0; 4];
+        self.get_raw_connection()?
+            .read_exact(&mut request_status)
+            .await?;
+
+        match AdbRequestStatus::from_str(std::str::from_utf8(request_status.as_ref())?)? {
+            AdbRequestStatus::Fail => {
+                // We can keep reading to get further details
+                let length = self.get_hex_body_length().await?;
+
+                let mut body = vec![
+                    0;
+                    length
+                        .try_into()
+                        .map_err(|_| RustADBError::ConversionError)?
+                ];
+                if length > 0 {
+                    self.get_raw_connection()?.read_exact(&mut body).await?;
+                }
+
+                Err(RustADBError::ADBRequestFailed(String::from_utf8(body)?))
+            }
+            AdbRequestStatus::Okay => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncADBTransport for AsyncTCPServerTransport {
+    async fn disconnect(&mut self) -> Result<()> {
+        if let Some(conn) = &mut self.tcp_stream {
+            conn.shutdown().await?;
+            log::trace!("Disconnected from {}", conn.peer_addr()?);
+        }
+
+        Ok(())
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        if let Some(mut previous) = self.tcp_stream.take() {
+            // Ignoring underlying error, we will recreate a new connection
+            let _ = previous.shutdown().await;
+        }
+        let tcp_stream = TcpStream::connect(self.socket_addr).await?;
+        tcp_stream.set_nodelay(true)?;
+        self.tcp_stream = Some(tcp_stream);
+        log::trace!("Successfully connected to {}", self.socket_addr);
+
+        Ok(())
+    }
+}
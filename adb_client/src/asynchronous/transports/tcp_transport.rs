@@ -0,0 +1,341 @@
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf, ReadHalf, WriteHalf},
+    net::TcpStream,
+    sync::Mutex,
+};
+use tokio_rustls::{TlsConnector, client::TlsStream};
+
+use super::{AsyncADBMessageTransport, AsyncADBTransport};
+use crate::{
+    Result, RustADBError,
+    device::{
+        ADBTransportMessage, ADBTransportMessageDecoder, MessageCommand, get_default_adb_key_path,
+    },
+    transports::{KnownDevices, tls_client_config},
+};
+
+#[derive(Debug)]
+enum AsyncConnection {
+    Tcp(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for AsyncConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            AsyncConnection::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_read(cx, buf),
+            AsyncConnection::Tls(tls_stream) => Pin::new(tls_stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for AsyncConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            AsyncConnection::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_write(cx, buf),
+            AsyncConnection::Tls(tls_stream) => Pin::new(tls_stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            AsyncConnection::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_flush(cx),
+            AsyncConnection::Tls(tls_stream) => Pin::new(tls_stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            AsyncConnection::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_shutdown(cx),
+            AsyncConnection::Tls(tls_stream) => Pin::new(tls_stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Asynchronous transport running on TCP, optionally upgraded to TLS.
+///
+/// Read and write halves are locked independently, so one task can wait for incoming messages while another one is writing.
+#[derive(Clone, Debug)]
+pub struct AsyncTcpTransport {
+    address: SocketAddr,
+    reader: Arc<Mutex<Option<AsyncMessageReader>>>,
+    writer: Arc<Mutex<Option<WriteHalf<AsyncConnection>>>>,
+    private_key_path: PathBuf,
+    known_devices: Option<KnownDevices>,
+    device_identifier: Option<String>,
+    key_log: bool,
+}
+
+fn not_connected() -> RustADBError {
+    RustADBError::IOError(std::io::Error::new(
+        std::io::ErrorKind::NotConnected,
+        "not connected",
+    ))
+}
+
+fn timed_out() -> RustADBError {
+    RustADBError::IOError(std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        "operation timed out",
+    ))
+}
+
+/// Read half of a connection, along with data received but not yet consumed as a whole message.
+#[derive(Debug)]
+struct AsyncMessageReader {
+    connection: ReadHalf<AsyncConnection>,
+    decoder: ADBTransportMessageDecoder,
+}
+
+impl AsyncMessageReader {
+    fn new(connection: ReadHalf<AsyncConnection>) -> Self {
+        Self {
+            connection,
+            decoder: ADBTransportMessageDecoder::default(),
+        }
+    }
+
+    /// Read a whole [`ADBTransportMessage`], checking its integrity.
+    ///
+    /// This is cancel safe: data received before cancellation is kept and returned as part of next message.
+    async fn read_message(&mut self) -> Result<ADBTransportMessage> {
+        let mut data = [0; 4096];
+        loop {
+            if let Some(message) = self.decoder.next_message()? {
+                return Ok(message);
+            }
+
+            match self.connection.read(&mut data).await? {
+                0 => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                size => self.decoder.push(&data[..size]),
+            }
+        }
+    }
+}
+
+/// Write a whole [`ADBTransportMessage`] to `writer`.
+pub(crate) async fn write_message_to<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: ADBTransportMessage,
+) -> Result<()> {
+    writer.write_all(&message.header().as_bytes()?).await?;
+    let payload = message.into_payload();
+    if !payload.is_empty() {
+        writer.write_all(&payload).await?;
+    }
+    writer.flush().await?;
+
+    Ok(())
+}
+
+impl AsyncTcpTransport {
+    /// Instantiate a new [`AsyncTcpTransport`]
+    pub fn new(address: SocketAddr) -> Result<Self> {
+        Self::new_with_custom_private_key(address, get_default_adb_key_path()?)
+    }
+
+    /// Instantiate a new [`AsyncTcpTransport`] using a given private key
+    ///
+    /// TLS certificates of devices are pinned in a [`KnownDevices`] store located next to private key.
+    pub fn new_with_custom_private_key(
+        address: SocketAddr,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        Ok(Self {
+            address,
+            reader: Arc::new(Mutex::new(None)),
+            writer: Arc::new(Mutex::new(None)),
+            known_devices: Some(KnownDevices::next_to_private_key(&private_key_path)),
+            private_key_path,
+            device_identifier: None,
+            key_log: false,
+        })
+    }
+
+    /// Pin TLS certificates of devices in given store, or accept any certificate if `None`.
+    pub fn with_known_devices(mut self, known_devices: Option<KnownDevices>) -> Self {
+        self.known_devices = known_devices;
+        self
+    }
+
+    /// Identify device by `identifier` (e.g. its adb GUID) in [`KnownDevices`] store, instead of its address.
+    pub fn with_device_identifier<S: Into<String>>(mut self, identifier: S) -> Self {
+        self.device_identifier = Some(identifier.into());
+        self
+    }
+
+    /// Write TLS session secrets to file named by `SSLKEYLOGFILE` environment variable, to inspect traffic. Disabled by default.
+    pub fn with_key_log(mut self, enabled: bool) -> Self {
+        self.key_log = enabled;
+        self
+    }
+
+    pub(crate) async fn upgrade_connection(&mut self) -> Result<()> {
+        {
+            let mut reader_lock = self.reader.lock().await;
+            let mut writer_lock = self.writer.lock().await;
+
+            let (Some(reader), Some(writer)) = (reader_lock.take(), writer_lock.take()) else {
+                return Err(RustADBError::UpgradeError(
+                    "cannot upgrade a non-existing connection...".into(),
+                ));
+            };
+
+            let tcp_stream = match reader.connection.unsplit(writer) {
+                AsyncConnection::Tcp(tcp_stream) => tcp_stream,
+                AsyncConnection::Tls(_) => {
+                    return Err(RustADBError::UpgradeError(
+                        "cannot upgrade a TLS connection...".into(),
+                    ));
+                }
+            };
+
+            let connector = TlsConnector::from(Arc::new(tls_client_config(
+                &self.private_key_path,
+                self.key_log,
+            )?));
+            let tls_stream = connector
+                .connect(self.address.ip().into(), tcp_stream)
+                .await?;
+
+            if let Some(known_devices) = &self.known_devices {
+                let identifier = self
+                    .device_identifier
+                    .clone()
+                    .unwrap_or_else(|| self.address.to_string());
+                known_devices.verify(&identifier, tls_stream.get_ref().1.peer_certificates())?;
+            }
+
+            // Update current connection state to now use TLS protocol
+            let (reader, writer) = tokio::io::split(AsyncConnection::Tls(Box::new(tls_stream)));
+            *reader_lock = Some(AsyncMessageReader::new(reader));
+            *writer_lock = Some(writer);
+        }
+
+        let message = self.read_message().await?;
+        match message.header().command() {
+            MessageCommand::Cnxn => {
+                let device_infos = String::from_utf8(message.into_payload())?;
+                log::debug!("received device info: {device_infos}");
+                Ok(())
+            }
+            c => Err(RustADBError::ADBRequestFailed(format!(
+                "Wrong command received {c}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncADBTransport for AsyncTcpTransport {
+    async fn connect(&mut self) -> Result<()> {
+        let stream = TcpStream::connect(self.address).await?;
+        let (reader, writer) = tokio::io::split(AsyncConnection::Tcp(stream));
+        *self.reader.lock().await = Some(AsyncMessageReader::new(reader));
+        *self.writer.lock().await = Some(writer);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        log::debug!("disconnecting...");
+        if let Some(mut writer) = self.writer.lock().await.take() {
+            let _ = writer.shutdown().await;
+        }
+        self.reader.lock().await.take();
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncADBMessageTransport for AsyncTcpTransport {
+    async fn read_message_with_timeout(
+        &mut self,
+        read_timeout: Duration,
+    ) -> Result<ADBTransportMessage> {
+        let mut reader_lock = self.reader.lock().await;
+        let reader = reader_lock.as_mut().ok_or_else(not_connected)?;
+
+        tokio::time::timeout(read_timeout, reader.read_message())
+            .await
+            .map_err(|_| timed_out())?
+    }
+
+    async fn write_message_with_timeout(
+        &mut self,
+        message: ADBTransportMessage,
+        write_timeout: Duration,
+    ) -> Result<()> {
+        let mut writer_lock = self.writer.lock().await;
+        let writer = writer_lock.as_mut().ok_or_else(not_connected)?;
+
+        match tokio::time::timeout(write_timeout, write_message_to(writer, message)).await {
+            Ok(result) => result,
+            Err(_) => {
+                // Message may have been partially written, stream cannot be used anymore
+                writer_lock.take();
+                Err(timed_out())
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_read_message_timeout_keeps_partial_message() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("cannot bind listener");
+    let mut transport = AsyncTcpTransport::new_with_custom_private_key(
+        listener.local_addr().expect("cannot get address"),
+        crate::fake_device::write_test_private_key("async_read_timeout"),
+    )
+    .expect("cannot create transport");
+    transport.connect().await.expect("cannot connect");
+    let (mut device, _) = listener.accept().await.expect("cannot accept");
+
+    let mut data = Vec::new();
+    let message = ADBTransportMessage::new(MessageCommand::Write, 1, 2, b"hello");
+    write_message_to(&mut data, message)
+        .await
+        .expect("cannot encode message");
+
+    // Read times out in the middle of message header
+    device
+        .write_all(&data[..10])
+        .await
+        .expect("cannot write message");
+    assert!(
+        transport
+            .read_message_with_timeout(Duration::from_millis(100))
+            .await
+            .is_err()
+    );
+
+    // Bytes received before timeout are not lost
+    device
+        .write_all(&data[10..])
+        .await
+        .expect("cannot write message");
+    let message = transport.read_message().await.expect("cannot read message");
+    assert_eq!(message.header().command(), MessageCommand::Write);
+    assert_eq!(message.header().arg0(), 1);
+    assert_eq!(message.payload(), b"hello");
+}
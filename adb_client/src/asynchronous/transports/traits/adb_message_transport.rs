@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::AsyncADBTransport;
+use crate::{Result, device::ADBTransportMessage};
+
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(u64::MAX);
+const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Asynchronous counterpart of [`crate::ADBMessageTransport`].
+#[async_trait]
+pub trait AsyncADBMessageTransport: AsyncADBTransport + Clone + Sync + 'static {
+    /// Read a message using given timeout on the underlying transport
+    ///
+    /// On [`crate::AsyncTcpTransport`], a timed out or cancelled read keeps data already received, so next read returns the pending message whole.
+    async fn read_message_with_timeout(
+        &mut self,
+        read_timeout: Duration,
+    ) -> Result<ADBTransportMessage>;
+
+    /// Read data to underlying connection, using default timeout
+    async fn read_message(&mut self) -> Result<ADBTransportMessage> {
+        self.read_message_with_timeout(DEFAULT_READ_TIMEOUT).await
+    }
+
+    /// Write a message using given timeout on the underlying transport
+    async fn write_message_with_timeout(
+        &mut self,
+        message: ADBTransportMessage,
+        write_timeout: Duration,
+    ) -> Result<()>;
+
+    /// Write data to underlying connection, using default timeout
+    async fn write_message(&mut self, message: ADBTransportMessage) -> Result<()> {
+        self.write_message_with_timeout(message, DEFAULT_WRITE_TIMEOUT)
+            .await
+    }
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+
+use crate::Result;
+
+/// Asynchronous counterpart of [`crate::ADBTransport`].
+#[async_trait]
+pub trait AsyncADBTransport: Send {
+    /// Initializes the connection to this transport.
+    async fn connect(&mut self) -> Result<()>;
+
+    /// Shuts down the connection to this transport.
+    async fn disconnect(&mut self) -> Result<()>;
+}
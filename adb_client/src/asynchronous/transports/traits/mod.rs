@@ -0,0 +1,5 @@
+mod adb_message_transport;
+mod adb_transport;
+
+pub use adb_message_transport::AsyncADBMessageTransport;
+pub use adb_transport::AsyncADBTransport;
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rusb::GlobalContext;
+
+use super::{AsyncADBMessageTransport, AsyncADBTransport};
+use crate::{ADBMessageTransport, ADBTransport, Result, USBTransport, device::ADBTransportMessage};
+
+/// Asynchronous transport running on USB.
+///
+/// `libusb` only offers blocking bulk transfers, so every operation is run on tokio's blocking thread pool.
+#[derive(Clone, Debug)]
+pub struct AsyncUSBTransport {
+    inner: USBTransport,
+}
+
+impl AsyncUSBTransport {
+    /// Instantiate a new [`AsyncUSBTransport`].
+    /// Only the first device with given vendor_id and product_id is returned.
+    pub fn new(vendor_id: u16, product_id: u16) -> Result<Self> {
+        Ok(Self::from(USBTransport::new(vendor_id, product_id)?))
+    }
+
+    /// Instantiate a new [`AsyncUSBTransport`] from a [`rusb::Device`].
+    pub fn new_from_device(rusb_device: rusb::Device<GlobalContext>) -> Self {
+        Self::from(USBTransport::new_from_device(rusb_device))
+    }
+}
+
+impl From<USBTransport> for AsyncUSBTransport {
+    fn from(inner: USBTransport) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl AsyncADBTransport for AsyncUSBTransport {
+    async fn connect(&mut self) -> Result<()> {
+        let mut inner = self.inner.clone();
+        self.inner = tokio::task::spawn_blocking(move || -> Result<USBTransport> {
+            inner.connect()?;
+            Ok(inner)
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        let mut inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || inner.disconnect()).await?
+    }
+}
+
+#[async_trait]
+impl AsyncADBMessageTransport for AsyncUSBTransport {
+    async fn read_message_with_timeout(
+        &mut self,
+        read_timeout: Duration,
+    ) -> Result<ADBTransportMessage> {
+        let mut inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || inner.read_message_with_timeout(read_timeout)).await?
+    }
+
+    async fn write_message_with_timeout(
+        &mut self,
+        message: ADBTransportMessage,
+        write_timeout: Duration,
+    ) -> Result<()> {
+        let mut inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            inner.write_message_with_timeout(message, write_timeout)
+        })
+        .await?
+    }
+}
@@ -0,0 +1,257 @@
+use std::time::{Duration, Instant};
+
+use crate::models::{BootStage, RebootType};
+use crate::properties::parse_property_value;
+use crate::utils::shell_quote;
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// Delay between two checks of boot stage reached by device.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// File holding a random identifier generated by kernel at each boot.
+pub(crate) const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// Check of boot progress: a shell command, and whether its output tells that device went further than `stage`.
+pub(crate) struct BootCheck {
+    command: Vec<String>,
+    passed: fn(&str) -> bool,
+    /// Stage reached by device when check does not pass
+    pub(crate) stage: BootStage,
+}
+
+impl BootCheck {
+    pub(crate) fn command(&self) -> Vec<&str> {
+        self.command.iter().map(String::as_str).collect()
+    }
+
+    pub(crate) fn passed(&self, output: &[u8]) -> bool {
+        (self.passed)(&String::from_utf8_lossy(output))
+    }
+}
+
+/// Checks made in order on a device which did not go down, until one does not pass. Device is ready once they all pass.
+pub(crate) fn boot_checks() -> [BootCheck; 4] {
+    fn property_is_set(output: &str) -> bool {
+        parse_property_value(output).as_deref() == Some("1")
+    }
+
+    [
+        BootCheck {
+            command: vec!["getprop".into(), shell_quote("sys.boot_completed")],
+            passed: property_is_set,
+            stage: BootStage::Connected,
+        },
+        BootCheck {
+            command: vec!["getprop".into(), shell_quote("dev.bootcomplete")],
+            passed: property_is_set,
+            stage: BootStage::SystemBooted,
+        },
+        // Package manager registers itself as a service, then answers once it scanned packages
+        BootCheck {
+            command: vec!["service".into(), "check".into(), "package".into()],
+            passed: |output| output.contains(": found"),
+            stage: BootStage::DeviceBooted,
+        },
+        BootCheck {
+            command: vec!["pm".into(), "path".into(), "android".into()],
+            passed: |output| output.starts_with("package:"),
+            stage: BootStage::DeviceBooted,
+        },
+    ]
+}
+
+/// Parse boot identifier read from [`BOOT_ID_PATH`], `None` if device does not expose it.
+pub(crate) fn parse_boot_id(output: &[u8]) -> Option<String> {
+    // Without shell v2, errors are written to stdout
+    let boot_id = String::from_utf8_lossy(output).trim().to_string();
+    let is_uuid = boot_id.len() == 36 && boot_id.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    is_uuid.then_some(boot_id)
+}
+
+/// Device which can be reconnected and have its reads bounded while waiting for it to boot.
+pub(crate) trait BootTarget: ADBDeviceExt {
+    /// Get back to device after losing its connection, e.g. because it rebooted.
+    fn reconnect(&mut self) -> Result<()>;
+
+    /// Make reads from device fail after `deadline`, or wait as long as needed if it is `None`.
+    fn set_read_deadline(&mut self, deadline: Option<Instant>);
+}
+
+/// Wait for device to be fully booted in a boot other than `previous_boot`, reconnecting to it when it cannot be reached.
+/// See [`ADBDeviceExt::wait_for_boot_since`].
+pub(crate) fn wait_for_boot<D: BootTarget + ?Sized>(
+    device: &mut D,
+    previous_boot: Option<&str>,
+    timeout: Duration,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    // A device which stops answering must not block past deadline
+    device.set_read_deadline(Some(deadline));
+    let result = wait_until_ready(device, previous_boot, deadline, D::reconnect);
+    device.set_read_deadline(None);
+
+    result
+}
+
+/// Wait for device to be fully booted in a boot other than `previous_boot`, for devices which cannot be reconnected.
+pub(crate) fn poll_boot<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    previous_boot: Option<&str>,
+    timeout: Duration,
+) -> Result<()> {
+    wait_until_ready(device, previous_boot, Instant::now() + timeout, |_| Ok(()))
+}
+
+fn wait_until_ready<D, F>(
+    device: &mut D,
+    previous_boot: Option<&str>,
+    deadline: Instant,
+    mut reconnect: F,
+) -> Result<()>
+where
+    D: ADBDeviceExt + ?Sized,
+    F: FnMut(&mut D) -> Result<()>,
+{
+    loop {
+        let stage = match boot_stage(device, previous_boot) {
+            Ok(stage) => stage,
+            Err(e) => {
+                log::debug!("device cannot be reached: {e}");
+                match reconnect(device) {
+                    Ok(()) => boot_stage(device, previous_boot).unwrap_or(BootStage::Disconnected),
+                    Err(_) => BootStage::Disconnected,
+                }
+            }
+        };
+        log::debug!("device reached boot stage: {stage}");
+        if stage == BootStage::Ready {
+            return Ok(());
+        }
+
+        // Reads fail once deadline passed, so no check is made then
+        let left = deadline.saturating_duration_since(Instant::now());
+        std::thread::sleep(POLL_INTERVAL.min(left));
+        if left <= POLL_INTERVAL {
+            return Err(RustADBError::BootTimeout(stage));
+        }
+    }
+}
+
+/// Reboot device, then wait for it to be fully booted again. See [`ADBDeviceExt::reboot_and_wait`].
+pub(crate) fn reboot_and_wait<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    reboot_type: RebootType,
+    timeout: Duration,
+) -> Result<()> {
+    // Device keeps reporting itself as booted until it actually goes down
+    let previous_boot = boot_id(device)?;
+    device.reboot(reboot_type)?;
+    device.wait_for_boot_since(previous_boot.as_deref(), timeout)
+}
+
+/// Identifier of current boot of device, `None` if device does not expose it.
+pub(crate) fn boot_id<D: ADBDeviceExt + ?Sized>(device: &mut D) -> Result<Option<String>> {
+    let mut output = Vec::new();
+    device.shell_command_with_status(&["cat", BOOT_ID_PATH], &mut output, &mut std::io::sink())?;
+    Ok(parse_boot_id(&output))
+}
+
+/// Last boot stage reached by device, which did not go down yet if it still runs boot `previous_boot`.
+fn boot_stage<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    previous_boot: Option<&str>,
+) -> Result<BootStage> {
+    if previous_boot.is_some() && boot_id(device)?.as_deref() == previous_boot {
+        return Ok(BootStage::Connected);
+    }
+    for check in boot_checks() {
+        let mut output = Vec::new();
+        device.shell_command_with_status(&check.command(), &mut output, &mut std::io::sink())?;
+        if !check.passed(&output) {
+            return Ok(check.stage);
+        }
+    }
+
+    Ok(BootStage::Ready)
+}
+
+#[test]
+fn test_wait_for_boot() {
+    use crate::{ADBServerDevice, FakeADBDevice, FakeADBServer, FakeFault, RebootType};
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop 'sys.boot_completed'", b"1\n");
+    fake_device.add_shell_response("getprop 'dev.bootcomplete'", b"1\n");
+    fake_device.add_shell_response("service check package", b"Service package: found\n");
+    fake_device.add_shell_response(
+        "pm path android",
+        b"package:/system/framework/framework-res.apk\n",
+    );
+    let mut booting_device = fake_device.clone();
+    booting_device.add_fault(FakeFault::BootDelay(Duration::from_secs(1)));
+    let mut device = crate::fake_device::connected_tcp_device(&booting_device);
+
+    // Connection is lost while rebooting, and device gets reconnected once it is back
+    let start = Instant::now();
+    device
+        .reboot_and_wait(RebootType::System, Duration::from_secs(10))
+        .expect("device did not reboot");
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    // Server tells once device it lost is back
+    let fake_server = FakeADBServer::new();
+    fake_server
+        .add_device("emulator-5554", &fake_device)
+        .expect("cannot add device");
+    let address = fake_server.listen().expect("cannot listen");
+    let mut device = ADBServerDevice::new("emulator-5554".to_string(), Some(address));
+    let restart = std::thread::spawn({
+        let fake_server = fake_server.clone();
+        move || {
+            std::thread::sleep(Duration::from_millis(300));
+            fake_server.remove_device("emulator-5554")?;
+            std::thread::sleep(Duration::from_millis(500));
+            fake_server.add_device("emulator-5554", &fake_device)
+        }
+    });
+    device
+        .reboot_and_wait(RebootType::System, Duration::from_secs(10))
+        .expect("device did not reboot");
+    restart
+        .join()
+        .expect("cannot restart device")
+        .expect("cannot add device");
+    let requests = fake_server.requests().expect("cannot get requests");
+    assert!(requests.contains(&"host-serial:emulator-5554:wait-for-any-device".to_string()));
+
+    // Refused reboots are reported without waiting
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_fault(FakeFault::RefuseService("reboot:".to_string()));
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+    assert!(
+        device
+            .reboot_and_wait(RebootType::System, Duration::from_secs(10))
+            .is_err()
+    );
+
+    // Device answering too slowly does not hold caller past timeout
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_fault(FakeFault::Delay(Duration::from_secs(2)));
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+    let start = Instant::now();
+    assert!(matches!(
+        device.wait_for_boot(Duration::from_millis(500)),
+        Err(RustADBError::BootTimeout(_))
+    ));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // Package manager is not up yet
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop 'sys.boot_completed'", b"1\n");
+    fake_device.add_shell_response("getprop 'dev.bootcomplete'", b"1\n");
+    fake_device.add_shell_response("service check package", b"Service package: not found\n");
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+    assert!(matches!(
+        device.wait_for_boot(Duration::from_millis(600)),
+        Err(RustADBError::BootTimeout(BootStage::DeviceBooted))
+    ));
+}
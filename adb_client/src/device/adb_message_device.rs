@@ -1,10 +1,17 @@
-use byteorder::{LittleEndian, ReadBytesExt};
 use rand::Rng;
-use std::io::{Cursor, Read, Seek};
+use std::time::{Duration, Instant};
 
+use crate::models::{SyncCompression, SyncDataDecoder};
+use crate::utils::time_left;
 use crate::{ADBMessageTransport, AdbStatResponse, Result, RustADBError, constants::BUFFER_SIZE};
 
-use super::{ADBTransportMessage, MessageCommand, models::MessageSubcommand};
+use super::adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN};
+use super::{
+    ADBDeviceBanner, ADBKeyStore, ADBTransportMessage, MessageCommand, models::MessageSubcommand,
+};
+
+/// Time device gets to acknowledge closing a session.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Generic structure representing an ADB device reachable over an [`ADBMessageTransport`].
 /// Structure is totally agnostic over which transport is truly used.
@@ -13,6 +20,11 @@ pub struct ADBMessageDevice<T: ADBMessageTransport> {
     transport: T,
     local_id: Option<u32>,
     remote_id: Option<u32>,
+    banner: Option<ADBDeviceBanner>,
+    /// Time after which reading messages fails, if any
+    read_deadline: Option<Instant>,
+    /// Compression requested for file transfers, negotiated with device if `None`
+    sync_compression: Option<SyncCompression>,
 }
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
@@ -22,6 +34,9 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             transport,
             local_id: None,
             remote_id: None,
+            banner: None,
+            read_deadline: None,
+            sync_compression: None,
         }
     }
 
@@ -33,9 +48,117 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         &mut self.transport
     }
 
+    pub(crate) fn get_banner(&self) -> Option<&ADBDeviceBanner> {
+        self.banner.as_ref()
+    }
+
+    /// Bound reads of messages by `deadline`, or wait for messages as long as needed if it is `None`.
+    pub(crate) fn set_read_deadline(&mut self, deadline: Option<Instant>) {
+        self.read_deadline = deadline;
+    }
+
+    /// Read a message from device, failing if it does not come before read deadline.
+    pub(crate) fn read_message(&mut self) -> Result<ADBTransportMessage> {
+        match self.read_deadline {
+            Some(deadline) => {
+                let timeout = time_left(deadline)?;
+                self.transport.read_message_with_timeout(timeout)
+            }
+            None => self.transport.read_message(),
+        }
+    }
+
+    /// Compress contents of pushed and pulled files with `compression` when device supports it, negotiating it if `None`.
+    /// See [`crate::ADBServerDevice::set_sync_compression`].
+    pub fn set_sync_compression(&mut self, compression: Option<SyncCompression>) {
+        self.sync_compression = compression;
+    }
+
+    /// Compression to use for file transfers, or `None` if device only supports legacy sync requests.
+    pub(crate) fn sync_compression(&self) -> Option<SyncCompression> {
+        SyncCompression::negotiate(self.sync_compression, |feature| {
+            self.get_banner()
+                .is_some_and(|banner| banner.has_feature(feature))
+        })
+    }
+
+    /// Store device banner received in given `CNXN` message
+    pub(crate) fn set_banner(&mut self, cnxn_message: &ADBTransportMessage) {
+        match ADBDeviceBanner::try_from(cnxn_message.payload().as_slice()) {
+            Ok(banner) => self.banner = Some(banner),
+            Err(e) => log::warn!("cannot parse device banner: {e}"),
+        }
+    }
+
+    /// Handle the authentication flow with the device, trying each key of `keys` before sending the public key of the user key.
+    pub(crate) fn authenticate(
+        &mut self,
+        keys: &ADBKeyStore,
+        auth_message: ADBTransportMessage,
+    ) -> Result<()> {
+        let mut auth_message = auth_message;
+        for key in keys.keys() {
+            // At this point, we should have received an AUTH message with arg0 == 1
+            match auth_message.header().arg0() {
+                AUTH_TOKEN => {}
+                v => {
+                    return Err(RustADBError::ADBRequestFailed(format!(
+                        "Received AUTH message with type != 1 ({v})"
+                    )));
+                }
+            }
+
+            let sign = key.sign(auth_message.into_payload())?;
+            let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_SIGNATURE, 0, &sign);
+            self.transport.write_message(message)?;
+
+            // Device answers with a new token when it does not know the key
+            let received_response = self.read_message()?;
+            if received_response.header().command() == MessageCommand::Cnxn {
+                self.set_banner(&received_response);
+                log::info!(
+                    "Authentication OK with key {}, device info {}",
+                    key.fingerprint()?,
+                    String::from_utf8(received_response.into_payload())?
+                );
+                return Ok(());
+            }
+            received_response.assert_command(MessageCommand::Auth)?;
+            auth_message = received_response;
+        }
+
+        let user_key = keys.user_key();
+        log::info!(
+            "Device does not know any key, sending public key with fingerprint {}",
+            user_key.fingerprint()?
+        );
+        let mut pubkey = user_key.android_pubkey_encode()?.into_bytes();
+        pubkey.push(b'\0');
+
+        let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_RSAPUBLICKEY, 0, &pubkey);
+
+        self.transport.write_message(message)?;
+
+        let response = self
+            .transport
+            .read_message_with_timeout(Duration::from_secs(10))
+            .and_then(|message| {
+                message.assert_command(MessageCommand::Cnxn)?;
+                Ok(message)
+            })?;
+        self.set_banner(&response);
+
+        log::info!(
+            "Authentication OK, device info {}",
+            String::from_utf8(response.into_payload())?
+        );
+
+        Ok(())
+    }
+
     /// Receive a message and acknowledge it by replying with an `OKAY` command
     pub(crate) fn recv_and_reply_okay(&mut self) -> Result<ADBTransportMessage> {
-        let message = self.transport.read_message()?;
+        let message = self.read_message()?;
         self.transport.write_message(ADBTransportMessage::new(
             MessageCommand::Okay,
             self.get_local_id()?,
@@ -52,7 +175,7 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     ) -> Result<ADBTransportMessage> {
         self.transport.write_message(message)?;
 
-        self.transport.read_message().and_then(|message| {
+        self.read_message().and_then(|message| {
             message.assert_command(MessageCommand::Okay)?;
             Ok(message)
         })
@@ -62,34 +185,11 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         &mut self,
         mut output: W,
     ) -> std::result::Result<(), RustADBError> {
-        let mut len: Option<u64> = None;
-        loop {
-            let payload = self.recv_and_reply_okay()?.into_payload();
-            let mut rdr = Cursor::new(&payload);
-            while rdr.position() != payload.len() as u64 {
-                match len.take() {
-                    Some(0) | None => {
-                        rdr.seek_relative(4)?;
-                        len.replace(rdr.read_u32::<LittleEndian>()? as u64);
-                    }
-                    Some(length) => {
-                        let remaining_bytes = payload.len() as u64 - rdr.position();
-                        if length < remaining_bytes {
-                            std::io::copy(&mut rdr.by_ref().take(length), &mut output)?;
-                        } else {
-                            std::io::copy(&mut rdr.take(remaining_bytes), &mut output)?;
-                            len.replace(length - remaining_bytes);
-                            // this payload is now exhausted
-                            break;
-                        }
-                    }
-                }
-            }
-            if Cursor::new(&payload[(payload.len() - 8)..(payload.len() - 4)])
-                .read_u32::<LittleEndian>()?
-                == MessageSubcommand::Done as u32
-            {
-                break;
+        let mut decoder = SyncDataDecoder::default();
+        while !decoder.is_done() {
+            decoder.push(self.recv_and_reply_okay()?.payload());
+            while let Some(data) = decoder.next_data()? {
+                output.write_all(&data)?;
             }
         }
         Ok(())
@@ -100,74 +200,29 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         local_id: u32,
         remote_id: u32,
         mut reader: R,
+        mtime: u32,
     ) -> std::result::Result<(), RustADBError> {
         let mut buffer = [0; BUFFER_SIZE];
-        let amount_read = reader.read(&mut buffer)?;
-        let subcommand_data = MessageSubcommand::Data.with_arg(amount_read as u32);
-
-        let mut serialized_message =
-            bincode::serialize(&subcommand_data).map_err(|_e| RustADBError::ConversionError)?;
-        serialized_message.append(&mut buffer[..amount_read].to_vec());
-
-        let message = ADBTransportMessage::new(
-            MessageCommand::Write,
-            local_id,
-            remote_id,
-            &serialized_message,
-        );
-
-        self.send_and_expect_okay(message)?;
-
         loop {
-            let mut buffer = [0; BUFFER_SIZE];
-
-            match reader.read(&mut buffer) {
-                Ok(0) => {
-                    // Currently file mtime is not forwarded
-                    let subcommand_data = MessageSubcommand::Done.with_arg(0);
-
-                    let serialized_message = bincode::serialize(&subcommand_data)
-                        .map_err(|_e| RustADBError::ConversionError)?;
-
-                    let message = ADBTransportMessage::new(
-                        MessageCommand::Write,
-                        local_id,
-                        remote_id,
-                        &serialized_message,
-                    );
-
-                    self.send_and_expect_okay(message)?;
-
-                    // Command should end with a Write => Okay
-                    let received = self.transport.read_message()?;
-                    match received.header().command() {
-                        MessageCommand::Write => return Ok(()),
-                        c => {
-                            return Err(RustADBError::ADBRequestFailed(format!(
-                                "Wrong command received {c}"
-                            )));
-                        }
-                    }
-                }
-                Ok(size) => {
-                    let subcommand_data = MessageSubcommand::Data.with_arg(size as u32);
-
-                    let mut serialized_message = bincode::serialize(&subcommand_data)
-                        .map_err(|_e| RustADBError::ConversionError)?;
-                    serialized_message.append(&mut buffer[..size].to_vec());
-
-                    let message = ADBTransportMessage::new(
-                        MessageCommand::Write,
-                        local_id,
-                        remote_id,
-                        &serialized_message,
-                    );
-
-                    self.send_and_expect_okay(message)?;
-                }
-                Err(e) => {
-                    return Err(RustADBError::IOError(e));
-                }
+            let packet = match reader.read(&mut buffer)? {
+                0 => MessageSubcommand::Done.encode(mtime, &[]),
+                size => MessageSubcommand::Data.encode(size as u32, &buffer[..size]),
+            };
+            let done = packet.starts_with(b"DONE");
+
+            let message =
+                ADBTransportMessage::new(MessageCommand::Write, local_id, remote_id, &packet);
+            self.send_and_expect_okay(message)?;
+
+            if done {
+                // Command should end with a Write => Okay
+                let received = self.read_message()?;
+                return match received.header().command() {
+                    MessageCommand::Write => Ok(()),
+                    c => Err(RustADBError::ADBRequestFailed(format!(
+                        "Wrong command received {c}"
+                    ))),
+                };
             }
         }
     }
@@ -178,12 +233,11 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     }
 
     pub(crate) fn stat_with_explicit_ids(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
-        let stat_buffer = MessageSubcommand::Stat.with_arg(remote_path.len() as u32);
         let message = ADBTransportMessage::new(
             MessageCommand::Write,
             self.get_local_id()?,
             self.get_remote_id()?,
-            &bincode::serialize(&stat_buffer).map_err(|_e| RustADBError::ConversionError)?,
+            &MessageSubcommand::Stat.encode(remote_path.len() as u32, &[]),
         );
         self.send_and_expect_okay(message)?;
         self.send_and_expect_okay(ADBTransportMessage::new(
@@ -192,37 +246,75 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             self.get_remote_id()?,
             remote_path.as_bytes(),
         ))?;
-        let response = self.transport.read_message()?;
-        // Skip first 4 bytes as this is the literal "STAT".
-        // Interesting part starts right after
-        bincode::deserialize(&response.into_payload()[4..])
-            .map_err(|_e| RustADBError::ConversionError)
+        let response = self.read_message()?;
+        AdbStatResponse::decode(response.payload())
     }
 
     pub(crate) fn end_transaction(&mut self) -> Result<()> {
-        let quit_buffer = MessageSubcommand::Quit.with_arg(0u32);
         self.send_and_expect_okay(ADBTransportMessage::new(
             MessageCommand::Write,
             self.get_local_id()?,
             self.get_remote_id()?,
-            &bincode::serialize(&quit_buffer).map_err(|_e| RustADBError::ConversionError)?,
+            &MessageSubcommand::Quit.encode(0, &[]),
         ))?;
-        let _discard_close = self.transport.read_message()?;
+        let _discard_close = self.read_message()?;
         Ok(())
     }
 
+    /// Close current session, consuming messages device still sends on it until it acknowledges closing.
+    ///
+    /// Used when a transfer stops midway, so device does not keep waiting for the rest of it.
+    pub(crate) fn close_session(&mut self) -> Result<()> {
+        let local_id = self.get_local_id()?;
+        self.transport.write_message(ADBTransportMessage::new(
+            MessageCommand::Clse,
+            local_id,
+            self.get_remote_id()?,
+            &[],
+        ))?;
+
+        loop {
+            let message = self.transport.read_message_with_timeout(CLOSE_TIMEOUT)?;
+            let header = message.header();
+            if header.command() == MessageCommand::Clse && header.arg1() == local_id {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Outcome of a transfer made in current session, closing this session if transfer failed.
+    pub(crate) fn close_session_on_error<V>(&mut self, result: Result<V>) -> Result<V> {
+        if result.is_err() {
+            if let Err(e) = self.close_session() {
+                log::debug!("cannot close session of failed transfer: {e}");
+            }
+        }
+        result
+    }
+
     pub(crate) fn open_session(&mut self, data: &[u8]) -> Result<ADBTransportMessage> {
         let mut rng = rand::rng();
+        let local_id = rng.random();
 
         let message = ADBTransportMessage::new(
             MessageCommand::Open,
-            rng.random(), // Our 'local-id'
+            local_id, // Our 'local-id'
             0,
             data,
         );
         self.get_transport_mut().write_message(message)?;
 
-        let response = self.get_transport_mut().read_message()?;
+        // Messages of previous sessions may still be pending, e.g. acknowledgements of their last writes
+        let response = loop {
+            let response = self.read_message()?;
+            if response.header().arg1() == local_id {
+                break response;
+            }
+            log::debug!(
+                "ignoring {} message of a previous session",
+                response.header().command()
+            );
+        };
 
         self.local_id = Some(response.header().arg1());
         self.remote_id = Some(response.header().arg0());
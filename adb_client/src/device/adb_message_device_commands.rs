@@ -1,7 +1,13 @@
-use crate::{ADBDeviceExt, ADBMessageTransport, RebootType, Result, models::AdbStatResponse};
+use crate::{
+    ADBDeviceExt, ADBMessageTransport, ADBPtyShell, InstallOptions, RebootType, Result,
+    RustADBError, TerminalSize, TransferMonitor, UninstallOptions,
+    boot::{self, BootTarget},
+    models::{AdbDirEntry, AdbStatResponse, PushOptions},
+};
 use std::{
     io::{Read, Write},
     path::Path,
+    time::{Duration, Instant},
 };
 
 use super::ADBMessageDevice;
@@ -11,35 +17,96 @@ impl<T: ADBMessageTransport> ADBDeviceExt for ADBMessageDevice<T> {
         self.shell_command(command, output)
     }
 
-    fn shell(&mut self, reader: &mut dyn Read, writer: Box<(dyn Write + Send)>) -> Result<()> {
+    fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        self.shell_command_with_status(command, stdout, stderr)
+    }
+
+    fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
         self.shell(reader, writer)
     }
 
+    fn pty_shell(
+        &mut self,
+        term: &str,
+        size: TerminalSize,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<ADBPtyShell> {
+        self.pty_shell(term, size, reader, writer)
+    }
+
     fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
         self.stat(remote_path)
     }
 
+    fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        self.list_dir(path)
+    }
+
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
         self.pull(source, output)
     }
 
-    fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()> {
-        self.push(stream, path)
+    fn push_with_options(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        options: PushOptions,
+    ) -> Result<()> {
+        self.push_with_options(stream, path, options)
     }
 
     fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
         self.reboot(reboot_type)
     }
 
-    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
-        self.install(apk_path)
+    fn wait_for_boot_since(
+        &mut self,
+        previous_boot: Option<&str>,
+        timeout: Duration,
+    ) -> Result<()> {
+        boot::wait_for_boot(self, previous_boot, timeout)
+    }
+
+    fn install_from_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        self.install_from_reader(reader, size, options, monitor)
+    }
+
+    fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.install_multiple_with_options(apk_paths, options)
     }
 
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.uninstall(package)
+    fn uninstall_with_options(&mut self, package: &str, options: &UninstallOptions) -> Result<()> {
+        self.uninstall_with_options(package, options)
     }
 
     fn framebuffer_inner(&mut self) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
         self.framebuffer_inner()
     }
 }
+
+impl<T: ADBMessageTransport> BootTarget for ADBMessageDevice<T> {
+    fn reconnect(&mut self) -> Result<()> {
+        // Transport is not known to support connecting again
+        Err(RustADBError::ReconnectUnsupported)
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) {
+        self.set_read_deadline(deadline);
+    }
+}
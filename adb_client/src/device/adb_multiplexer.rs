@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::models::{ShellPacketId, ShellV2Output, encode_shell_packet};
+use crate::{ADBMessageTransport, Result, RustADBError};
+
+use super::adb_stream::{ADBStream, StreamSenders};
+use super::{ADBDeviceBanner, ADBTransportMessage, MessageCommand};
+
+/// Streams known by the multiplexer, indexed by their local id.
+pub(crate) type StreamRegistry = Arc<Mutex<HashMap<u32, StreamSenders>>>;
+
+/// Streams waiting for an answer to their `OPEN` message, indexed by their local id.
+type PendingOpens = Arc<Mutex<HashMap<u32, (Sender<Result<u32>>, StreamSenders)>>>;
+
+/// Multiplexes many independent streams over a single, already authenticated, [`ADBMessageTransport`].
+///
+/// A background thread owns the reading side of the transport, and routes every incoming `OKAY`, `WRTE` and `CLSE` message to the stream it belongs to.
+/// Streams can be opened, used and closed concurrently from any thread.
+#[derive(Debug)]
+pub struct ADBMultiplexer<T: ADBMessageTransport> {
+    transport: T,
+    streams: StreamRegistry,
+    pending_opens: PendingOpens,
+    next_local_id: AtomicU32,
+    alive: Arc<AtomicBool>,
+    banner: Option<ADBDeviceBanner>,
+}
+
+impl<T: ADBMessageTransport> ADBMultiplexer<T> {
+    /// Instantiate a new [`ADBMultiplexer`] from a connected and authenticated transport.
+    pub fn new(transport: T) -> Self {
+        let streams: StreamRegistry = Arc::default();
+        let pending_opens: PendingOpens = Arc::default();
+        let alive = Arc::new(AtomicBool::new(true));
+
+        let reader = MessageRouter {
+            transport: transport.clone(),
+            streams: streams.clone(),
+            pending_opens: pending_opens.clone(),
+            alive: alive.clone(),
+        };
+        std::thread::spawn(move || reader.run());
+
+        Self {
+            transport,
+            streams,
+            pending_opens,
+            next_local_id: AtomicU32::new(1),
+            alive,
+            banner: None,
+        }
+    }
+
+    pub(crate) fn with_banner(mut self, banner: Option<ADBDeviceBanner>) -> Self {
+        self.banner = banner;
+        self
+    }
+
+    /// Get banner advertised by device when connecting, if any.
+    pub fn banner(&self) -> Option<&ADBDeviceBanner> {
+        self.banner.as_ref()
+    }
+
+    /// Whether underlying connection is still usable.
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Open a new stream to given `service` (e.g. `shell:ls`, `sync:`, `logcat:`...).
+    pub fn open_stream(&self, service: &str) -> Result<ADBStream<T>> {
+        if !self.is_alive() {
+            return Err(RustADBError::IOError(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "multiplexer connection is closed",
+            )));
+        }
+
+        let local_id = self.next_local_id.fetch_add(1, Ordering::SeqCst);
+        let (senders, receivers) = StreamSenders::channels();
+        let (open_sender, open_receiver) = mpsc::channel();
+        self.pending_opens
+            .lock()?
+            .insert(local_id, (open_sender, senders));
+
+        let mut transport = self.transport.clone();
+        let message = ADBTransportMessage::new(
+            MessageCommand::Open,
+            local_id,
+            0,
+            format!("{service}\0").as_bytes(),
+        );
+        if let Err(e) = transport.write_message(message) {
+            self.pending_opens.lock()?.remove(&local_id);
+            return Err(e);
+        }
+
+        let remote_id = open_receiver.recv().map_err(|_| {
+            RustADBError::IOError(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "connection closed while opening stream",
+            ))
+        })??;
+
+        log::debug!("opened stream {local_id} <-> {remote_id} for service {service}");
+
+        Ok(ADBStream::new(
+            transport,
+            local_id,
+            remote_id,
+            self.streams.clone(),
+            receivers,
+        ))
+    }
+
+    /// Runs `command` in a shell on the device on a dedicated stream, and write its output into `output`.
+    pub fn shell_command(&self, command: &[&str], output: &mut dyn Write) -> Result<()> {
+        let mut stream = self.open_stream(&format!("shell:{}", command.join(" ")))?;
+        std::io::copy(&mut stream, output)?;
+        Ok(())
+    }
+
+    /// Runs `command` in a shell on the device on a dedicated stream, writing its output and error streams into `stdout` and `stderr`.
+    ///
+    /// Returns exit code of command if device supports shell protocol v2, else both streams are written into `stdout`.
+    pub fn shell_command_with_status(
+        &self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        if !self
+            .banner
+            .as_ref()
+            .is_some_and(|banner| banner.has_feature("shell_v2"))
+        {
+            self.shell_command(command, stdout)?;
+            return Ok(None);
+        }
+
+        let mut stream = self.open_stream(&format!("shell,v2,raw:{}", command.join(" ")))?;
+        // Command does not get any input
+        stream.write_all(&encode_shell_packet(ShellPacketId::CloseStdin, &[]))?;
+
+        let mut output = ShellV2Output::new(stdout, stderr);
+        std::io::copy(&mut stream, &mut output)?;
+
+        Ok(output.exit_code())
+    }
+
+    /// Number of streams currently open.
+    pub fn open_streams(&self) -> Result<usize> {
+        Ok(self.streams.lock()?.len())
+    }
+}
+
+impl<T: ADBMessageTransport> Drop for ADBMultiplexer<T> {
+    fn drop(&mut self) {
+        // Best effort here, reading thread will stop once transport gets closed
+        let _ = self.transport.disconnect();
+    }
+}
+
+/// Reading side of a multiplexer, running in its own thread.
+struct MessageRouter<T: ADBMessageTransport> {
+    transport: T,
+    streams: StreamRegistry,
+    pending_opens: PendingOpens,
+    alive: Arc<AtomicBool>,
+}
+
+impl<T: ADBMessageTransport> MessageRouter<T> {
+    fn run(mut self) {
+        loop {
+            let message = match self.transport.read_message() {
+                Ok(message) => message,
+                Err(e) => {
+                    log::debug!("multiplexer connection closed: {e}");
+                    break;
+                }
+            };
+
+            if let Err(e) = self.route(message) {
+                log::error!("error while routing message: {e}");
+                break;
+            }
+        }
+
+        self.alive.store(false, Ordering::SeqCst);
+        // Dropping every sender wakes up all readers, writers and pending opens
+        if let Ok(mut pending_opens) = self.pending_opens.lock() {
+            pending_opens.clear();
+        }
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.clear();
+        }
+    }
+
+    fn route(&mut self, message: ADBTransportMessage) -> Result<()> {
+        let remote_id = message.header().arg0();
+        let local_id = message.header().arg1();
+
+        match message.header().command() {
+            MessageCommand::Okay => {
+                if let Some((open_sender, senders)) = self.pending_opens.lock()?.remove(&local_id) {
+                    self.streams.lock()?.insert(local_id, senders);
+                    let _ = open_sender.send(Ok(remote_id));
+                } else if let Some(senders) = self.streams.lock()?.get(&local_id) {
+                    let _ = senders.ack.send(());
+                } else {
+                    log::debug!("received OKAY for unknown stream {local_id}");
+                }
+            }
+            MessageCommand::Write => {
+                let streams = self.streams.lock()?;
+                match streams.get(&local_id) {
+                    Some(senders) => {
+                        let _ = senders.data.send(message.into_payload());
+                    }
+                    None => {
+                        drop(streams);
+                        log::debug!("received WRTE for unknown stream {local_id}, closing it");
+                        self.transport.write_message(ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            local_id,
+                            remote_id,
+                            &[],
+                        ))?;
+                    }
+                }
+            }
+            MessageCommand::Clse => {
+                if let Some((open_sender, _)) = self.pending_opens.lock()?.remove(&local_id) {
+                    let _ = open_sender.send(Err(RustADBError::ADBRequestFailed(
+                        "device refused to open stream".into(),
+                    )));
+                } else if self.streams.lock()?.remove(&local_id).is_some() {
+                    // Stream was still opened on our side, acknowledge closing
+                    self.transport.write_message(ADBTransportMessage::new(
+                        MessageCommand::Clse,
+                        local_id,
+                        remote_id,
+                        &[],
+                    ))?;
+                }
+            }
+            c => log::debug!("ignoring unexpected {c} message on multiplexed connection"),
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: ADBMessageTransport> std::fmt::Debug for MessageRouter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageRouter").finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn test_multiplexer() {
+    use crate::{ADBTransport, FakeADBDevice};
+    use std::io::Read;
+    use std::time::{Duration, Instant};
+
+    let mut fake_device = FakeADBDevice::new();
+    for i in 0..4 {
+        fake_device.add_shell_response(&format!("getprop test.{i}"), format!("{i}\n").as_bytes());
+    }
+    let multiplexer = ADBMultiplexer::new(fake_device.transport());
+
+    // Streams opened from many threads at once each get their own output
+    std::thread::scope(|scope| {
+        let multiplexer = &multiplexer;
+        let commands = (0..4)
+            .map(|i| {
+                scope.spawn(move || {
+                    let mut output = Vec::new();
+                    multiplexer
+                        .shell_command(&["getprop", &format!("test.{i}")], &mut output)
+                        .map(|()| output)
+                })
+            })
+            .collect::<Vec<_>>();
+        for (i, command) in commands.into_iter().enumerate() {
+            let output = command
+                .join()
+                .expect("command thread panicked")
+                .expect("cannot run command");
+            assert_eq!(output, format!("{i}\n").as_bytes());
+        }
+    });
+
+    // Device closing a stream leaves other ones open
+    let mut echo = multiplexer
+        .open_stream("shell:")
+        .expect("cannot open stream");
+    let mut finished = multiplexer
+        .open_stream("shell:getprop test.0")
+        .expect("cannot open stream");
+    let mut output = String::new();
+    finished
+        .read_to_string(&mut output)
+        .expect("cannot read stream");
+    assert_eq!(output, "0\n");
+    assert_eq!(multiplexer.open_streams().expect("cannot count streams"), 1);
+    echo.write_all(b"still open").expect("cannot write stream");
+    let mut echoed = [0; 10];
+    echo.read_exact(&mut echoed).expect("cannot read stream");
+    assert_eq!(&echoed, b"still open");
+
+    // Reader thread stops once connection is closed, ending streams still open
+    multiplexer
+        .transport
+        .clone()
+        .disconnect()
+        .expect("cannot disconnect");
+    let start = Instant::now();
+    while multiplexer.is_alive() {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "reader thread did not stop"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(multiplexer.open_streams().expect("cannot count streams"), 0);
+    assert!(
+        echo.read_to_end(&mut Vec::new())
+            .is_ok_and(|read| read == 0)
+    );
+    assert!(multiplexer.open_stream("shell:").is_err());
+}
@@ -0,0 +1,222 @@
+use std::io::{ErrorKind, Read, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::ADBMessageTransport;
+use crate::constants::BUFFER_SIZE;
+
+use super::adb_multiplexer::StreamRegistry;
+use super::{ADBTransportMessage, MessageCommand};
+
+/// Channels used by the multiplexer to forward messages to a stream.
+#[derive(Debug)]
+pub(crate) struct StreamSenders {
+    pub(crate) data: Sender<Vec<u8>>,
+    pub(crate) ack: Sender<()>,
+}
+
+/// Receiving ends of [`StreamSenders`].
+#[derive(Debug)]
+pub(crate) struct StreamReceivers {
+    data: Receiver<Vec<u8>>,
+    ack: Receiver<()>,
+}
+
+impl StreamSenders {
+    pub(crate) fn channels() -> (StreamSenders, StreamReceivers) {
+        let (data_sender, data_receiver) = mpsc::channel();
+        let (ack_sender, ack_receiver) = mpsc::channel();
+        (
+            StreamSenders {
+                data: data_sender,
+                ack: ack_sender,
+            },
+            StreamReceivers {
+                data: data_receiver,
+                ack: ack_receiver,
+            },
+        )
+    }
+}
+
+/// State shared between both halves of a stream. Stream gets closed when last half is dropped.
+#[derive(Debug)]
+struct StreamInner<T: ADBMessageTransport> {
+    transport: Mutex<T>,
+    local_id: u32,
+    remote_id: u32,
+    registry: StreamRegistry,
+}
+
+impl<T: ADBMessageTransport> StreamInner<T> {
+    fn send(&self, command: MessageCommand, data: &[u8]) -> std::io::Result<()> {
+        let message = ADBTransportMessage::new(command, self.local_id, self.remote_id, data);
+        self.transport
+            .lock()
+            .map_err(|_| std::io::Error::other("poisoned transport lock"))?
+            .write_message(message)
+            .map_err(std::io::Error::other)
+    }
+
+    fn close(&self) -> std::io::Result<()> {
+        // Only notify device if it did not already close this stream
+        let still_open = self
+            .registry
+            .lock()
+            .map(|mut streams| streams.remove(&self.local_id).is_some())
+            .unwrap_or(false);
+
+        if still_open {
+            self.send(MessageCommand::Clse, &[])?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: ADBMessageTransport> Drop for StreamInner<T> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// A single stream opened through an [`crate::ADBMultiplexer`], bound to one service on the device.
+///
+/// Implements [`Read`] and [`Write`]. Reading returns EOF once device closes the stream. Stream is closed when dropped.
+#[derive(Debug)]
+pub struct ADBStream<T: ADBMessageTransport> {
+    reader: ADBStreamReader<T>,
+    writer: ADBStreamWriter<T>,
+}
+
+/// Reading half of an [`ADBStream`].
+#[derive(Debug)]
+pub struct ADBStreamReader<T: ADBMessageTransport> {
+    inner: Arc<StreamInner<T>>,
+    data: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+    offset: usize,
+}
+
+/// Writing half of an [`ADBStream`].
+#[derive(Debug)]
+pub struct ADBStreamWriter<T: ADBMessageTransport> {
+    inner: Arc<StreamInner<T>>,
+    ack: Receiver<()>,
+}
+
+impl<T: ADBMessageTransport> ADBStream<T> {
+    pub(crate) fn new(
+        transport: T,
+        local_id: u32,
+        remote_id: u32,
+        registry: StreamRegistry,
+        receivers: StreamReceivers,
+    ) -> Self {
+        let inner = Arc::new(StreamInner {
+            transport: Mutex::new(transport),
+            local_id,
+            remote_id,
+            registry,
+        });
+
+        Self {
+            reader: ADBStreamReader {
+                inner: inner.clone(),
+                data: receivers.data,
+                pending: Vec::new(),
+                offset: 0,
+            },
+            writer: ADBStreamWriter {
+                inner,
+                ack: receivers.ack,
+            },
+        }
+    }
+
+    /// Local id of this stream
+    pub fn local_id(&self) -> u32 {
+        self.reader.inner.local_id
+    }
+
+    /// Remote id of this stream, allocated by device
+    pub fn remote_id(&self) -> u32 {
+        self.reader.inner.remote_id
+    }
+
+    /// Split this stream into independent reading and writing halves, which can be moved to different threads.
+    pub fn split(self) -> (ADBStreamReader<T>, ADBStreamWriter<T>) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<T: ADBMessageTransport> ADBStreamReader<T> {
+    /// Close stream on both sides. Pending and future reads on the other half will return EOF.
+    pub fn close(self) -> std::io::Result<()> {
+        self.inner.close()
+    }
+}
+
+impl<T: ADBMessageTransport> ADBStreamWriter<T> {
+    /// Close stream on both sides. Pending and future reads on the other half will return EOF.
+    pub fn close(self) -> std::io::Result<()> {
+        self.inner.close()
+    }
+}
+
+impl<T: ADBMessageTransport> Read for ADBStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<T: ADBMessageTransport> Write for ADBStream<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<T: ADBMessageTransport> Read for ADBStreamReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset == self.pending.len() {
+            match self.data.recv() {
+                Ok(payload) => {
+                    self.pending = payload;
+                    self.offset = 0;
+                    // Acknowledge payload so device can send the next one
+                    self.inner.send(MessageCommand::Okay, &[])?;
+                }
+                // Stream has been closed, this is EOF
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.pending.len() - self.offset);
+        buf[..len].copy_from_slice(&self.pending[self.offset..self.offset + len]);
+        self.offset += len;
+
+        Ok(len)
+    }
+}
+
+impl<T: ADBMessageTransport> Write for ADBStreamWriter<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(BUFFER_SIZE);
+        self.inner.send(MessageCommand::Write, &buf[..len])?;
+
+        // Device must acknowledge each WRTE before receiving another one
+        self.ack
+            .recv()
+            .map_err(|_| std::io::Error::new(ErrorKind::BrokenPipe, "stream closed by device"))?;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,20 +1,26 @@
 use std::io::Write;
+use std::net::TcpStream;
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::Arc;
 use std::{io::Read, net::SocketAddr};
 
+use rustls::{ClientConnection, StreamOwned};
+
 use super::adb_message_device::ADBMessageDevice;
+use super::{ADBDeviceBanner, ADBMultiplexer};
 use super::models::MessageCommand;
 use super::ADBTransportMessage;
-use super::{get_default_adb_key_path, read_adb_private_key, ADBRsaKey};
-use crate::device::adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN};
+use super::{get_default_adb_key_path, load_or_generate_key, ADBKeyStore};
+use crate::models::SyncCompression;
+use crate::pairing::{PeerInfo, Spake2Role, exchange_peer_info, pairing_password};
+use crate::transports::tls_client_config;
 use crate::{ADBDeviceExt, ADBMessageTransport, ADBTransport, Result, RustADBError, TcpTransport};
 
 /// Represent a device reached and available over TCP.
 #[derive(Debug)]
 pub struct ADBTcpDevice {
-    private_key: ADBRsaKey,
+    keys: ADBKeyStore,
     inner: ADBMessageDevice<TcpTransport>,
 }
 
@@ -29,7 +35,9 @@ impl ADBTcpDevice {
         address: SocketAddr,
         private_key_path: PathBuf,
     ) -> Result<Self> {
-        Self::new_from_transport_inner(TcpTransport::new(address)?, private_key_path)
+        let transport =
+            TcpTransport::new_with_custom_private_key(address, private_key_path.clone())?;
+        Self::new_from_transport_inner(transport, private_key_path)
     }
 
     /// Instantiate a new [`ADBTcpDevice`] from a [`TcpTransport`] and an optional private key path.
@@ -45,23 +53,13 @@ impl ADBTcpDevice {
         Self::new_from_transport_inner(transport, private_key_path)
     }
 
-    fn new_from_transport_inner(
+    /// Instantiate a new [`ADBTcpDevice`] from a [`TcpTransport`], authenticating with given keys.
+    pub fn new_from_transport_with_keys(
         transport: TcpTransport,
-        private_key_path: PathBuf,
+        keys: ADBKeyStore,
     ) -> Result<Self> {
-        let private_key = match read_adb_private_key(&private_key_path)? {
-            Some(pk) => pk,
-            None => {
-                log::warn!(
-                    "No private key found at path {}. Using a temporary random one.",
-                    private_key_path.display()
-                );
-                ADBRsaKey::new_random()?
-            }
-        };
-
         let mut s = Self {
-            private_key,
+            keys,
             inner: ADBMessageDevice::new(transport),
         };
 
@@ -70,6 +68,50 @@ impl ADBTcpDevice {
         Ok(s)
     }
 
+    fn new_from_transport_inner(
+        transport: TcpTransport,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        Self::new_from_transport_with_keys(transport, ADBKeyStore::load(&private_key_path)?)
+    }
+
+    /// Pair with a device listening for pairing requests on `address`, using `code` displayed by its "Pair device with pairing code" dialog.
+    ///
+    /// Once paired, device accepts connections authenticated by default private key. Returns device GUID, which is also its mDNS instance name.
+    pub fn pair(address: SocketAddr, code: &str) -> Result<String> {
+        Self::pair_with_custom_private_key(address, code, get_default_adb_key_path()?)
+    }
+
+    /// Pair with a device listening for pairing requests on `address`, registering public key of given private key.
+    pub fn pair_with_custom_private_key(
+        address: SocketAddr,
+        code: &str,
+        private_key_path: PathBuf,
+    ) -> Result<String> {
+        // Key is generated if needed, as it is also used as TLS client certificate
+        let private_key = load_or_generate_key(&private_key_path)?;
+
+        let config = Arc::new(tls_client_config(&private_key_path, false)?);
+        let mut connection = ClientConnection::new(config, address.ip().into())?;
+        let mut socket = TcpStream::connect(address)?;
+        while connection.is_handshaking() {
+            connection.complete_io(&mut socket)?;
+        }
+
+        let password = pairing_password(code, &connection)?;
+        let mut stream = StreamOwned::new(connection, socket);
+        let public_key = PeerInfo::RsaPublicKey(private_key.android_pubkey_encode()?);
+        match exchange_peer_info(&mut stream, Spake2Role::Alice, &password, &public_key)? {
+            PeerInfo::DeviceGuid(guid) => {
+                log::debug!("paired with device {guid}");
+                Ok(guid)
+            }
+            PeerInfo::RsaPublicKey(_) => Err(RustADBError::PairingError(
+                "device did not send its GUID".into(),
+            )),
+        }
+    }
+
     /// Send initial connect
     pub fn connect(&mut self) -> Result<()> {
         self.get_transport_mut().connect()?;
@@ -83,7 +125,7 @@ impl ADBTcpDevice {
 
         self.get_transport_mut().write_message(message)?;
 
-        let message = self.get_transport_mut().read_message()?;
+        let message = self.inner.read_message()?;
 
         // Check if client is requesting a secure connection and upgrade it if necessary
         match message.header().command() {
@@ -101,17 +143,18 @@ impl ADBTcpDevice {
                     format!("host::{}\0", env!("CARGO_PKG_NAME")).as_bytes(),
                 );
                 self.get_transport_mut().write_message(message)?;
-                let message = self.get_transport_mut().read_message()?;
+                let message = self.inner.read_message()?;
 
                 // After TLS, device might require authentication or accept connection
                 match message.header().command() {
                     MessageCommand::Cnxn => {
                         log::debug!("Secure connection established without authentication");
+                        self.inner.set_banner(&message);
                         return Ok(());
                     }
                     MessageCommand::Auth => {
                         // Continue to authentication flow below
-                        self.handle_authentication(message)?;
+                        self.inner.authenticate(&self.keys, message)?;
                     }
                     _ => {
                         return Err(RustADBError::WrongResponseReceived(
@@ -123,11 +166,12 @@ impl ADBTcpDevice {
             }
             MessageCommand::Cnxn => {
                 log::debug!("Unencrypted connection established without authentication");
+                self.inner.set_banner(&message);
                 return Ok(());
             }
             MessageCommand::Auth => {
                 log::debug!("Authentication required");
-                self.handle_authentication(message)?;
+                self.inner.authenticate(&self.keys, message)?;
             }
             _ => {
                 return Err(RustADBError::WrongResponseReceived(
@@ -140,55 +184,21 @@ impl ADBTcpDevice {
         Ok(())
     }
 
-    /// Handle the authentication flow with the device
-    fn handle_authentication(&mut self, auth_message: ADBTransportMessage) -> Result<()> {
-        // At this point, we should have received an AUTH message with arg0 == 1
-        let auth_message = match auth_message.header().arg0() {
-            AUTH_TOKEN => auth_message,
-            v => {
-                return Err(RustADBError::ADBRequestFailed(format!(
-                    "Received AUTH message with type != 1 ({v})"
-                )));
-            }
-        };
-
-        let sign = self.private_key.sign(auth_message.into_payload())?;
-
-        let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_SIGNATURE, 0, &sign);
-
-        self.get_transport_mut().write_message(message)?;
-
-        let received_response = self.get_transport_mut().read_message()?;
-
-        if received_response.header().command() == MessageCommand::Cnxn {
-            log::info!(
-                "Authentication OK, device info {}",
-                String::from_utf8(received_response.into_payload())?
-            );
-            return Ok(());
-        }
-
-        let mut pubkey = self.private_key.android_pubkey_encode()?.into_bytes();
-        pubkey.push(b'\0');
-
-        let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_RSAPUBLICKEY, 0, &pubkey);
-
-        self.get_transport_mut().write_message(message)?;
+    /// Get banner advertised by device when connecting, if any.
+    pub fn banner(&self) -> Option<&ADBDeviceBanner> {
+        self.inner.get_banner()
+    }
 
-        let response = self
-            .get_transport_mut()
-            .read_message_with_timeout(Duration::from_secs(10))
-            .and_then(|message| {
-                message.assert_command(MessageCommand::Cnxn)?;
-                Ok(message)
-            })?;
-
-        log::info!(
-            "Authentication OK, device info {}",
-            String::from_utf8(response.into_payload())?
-        );
+    /// Compress contents of pushed and pulled files with `compression` when device supports it, negotiating it if `None`.
+    /// See [`crate::ADBServerDevice::set_sync_compression`].
+    pub fn set_sync_compression(&mut self, compression: Option<SyncCompression>) {
+        self.inner.set_sync_compression(compression);
+    }
 
-        Ok(())
+    /// Turn this device into an [`ADBMultiplexer`], allowing to run many services concurrently on the current connection.
+    pub fn into_multiplexer(mut self) -> ADBMultiplexer<TcpTransport> {
+        let banner = self.inner.get_banner().cloned();
+        ADBMultiplexer::new(self.get_transport_mut().detach()).with_banner(banner)
     }
 
     #[inline]
@@ -204,15 +214,42 @@ impl ADBDeviceExt for ADBTcpDevice {
     }
 
     #[inline]
-    fn shell(&mut self, reader: &mut dyn Read, writer: Box<(dyn Write + Send)>) -> Result<()> {
+    fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        self.inner
+            .shell_command_with_status(command, stdout, stderr)
+    }
+
+    #[inline]
+    fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
         self.inner.shell(reader, writer)
     }
 
+    #[inline]
+    fn pty_shell(
+        &mut self,
+        term: &str,
+        size: crate::TerminalSize,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<crate::ADBPtyShell> {
+        self.inner.pty_shell(term, size, reader, writer)
+    }
+
     #[inline]
     fn stat(&mut self, remote_path: &str) -> Result<crate::AdbStatResponse> {
         self.inner.stat(remote_path)
     }
 
+    #[inline]
+    fn list_dir(&mut self, path: &str) -> Result<Vec<crate::AdbDirEntry>> {
+        self.inner.list_dir(path)
+    }
+
     #[inline]
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
         self.inner.pull(source, output)
@@ -223,19 +260,75 @@ impl ADBDeviceExt for ADBTcpDevice {
         self.inner.push(stream, path)
     }
 
+    #[inline]
+    fn push_with_options(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        options: crate::PushOptions,
+    ) -> Result<()> {
+        self.inner.push_with_options(stream, path, options)
+    }
+
+    #[inline]
+    fn push_dir(
+        &mut self,
+        source: &dyn AsRef<Path>,
+        destination: &str,
+    ) -> Result<Vec<crate::FileTransfer>> {
+        self.inner.push_dir(source, destination)
+    }
+
+    #[inline]
+    fn pull_dir(
+        &mut self,
+        source: &str,
+        destination: &dyn AsRef<Path>,
+    ) -> Result<Vec<crate::FileTransfer>> {
+        self.inner.pull_dir(source, destination)
+    }
+
     #[inline]
     fn reboot(&mut self, reboot_type: crate::RebootType) -> Result<()> {
         self.inner.reboot(reboot_type)
     }
 
     #[inline]
-    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
-        self.inner.install(apk_path)
+    fn wait_for_boot_since(
+        &mut self,
+        previous_boot: Option<&str>,
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        crate::boot::wait_for_boot(self, previous_boot, timeout)
+    }
+
+    #[inline]
+    fn install_from_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        options: &crate::InstallOptions,
+        monitor: &mut crate::TransferMonitor,
+    ) -> Result<()> {
+        self.inner.install_from_reader(reader, size, options, monitor)
     }
 
     #[inline]
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.inner.uninstall(package)
+    fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.inner.install_multiple_with_options(apk_paths, options)
+    }
+
+    #[inline]
+    fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &crate::UninstallOptions,
+    ) -> Result<()> {
+        self.inner.uninstall_with_options(package, options)
     }
 
     #[inline]
@@ -244,6 +337,16 @@ impl ADBDeviceExt for ADBTcpDevice {
     }
 }
 
+impl crate::boot::BootTarget for ADBTcpDevice {
+    fn reconnect(&mut self) -> Result<()> {
+        self.connect()
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<std::time::Instant>) {
+        self.inner.set_read_deadline(deadline);
+    }
+}
+
 impl Drop for ADBTcpDevice {
     fn drop(&mut self) {
         // Best effort here
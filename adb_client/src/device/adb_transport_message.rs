@@ -8,6 +8,9 @@ pub const AUTH_TOKEN: u32 = 1;
 pub const AUTH_SIGNATURE: u32 = 2;
 pub const AUTH_RSAPUBLICKEY: u32 = 3;
 
+/// Size of a serialized [`ADBTransportMessageHeader`].
+pub(crate) const HEADER_SIZE: usize = 24;
+
 #[derive(Debug)]
 pub struct ADBTransportMessage {
     header: ADBTransportMessageHeader,
@@ -83,6 +86,20 @@ impl ADBTransportMessage {
         Self { header, payload }
     }
 
+    /// Assemble a message received from device as `header` and `payload`, checking its integrity.
+    pub(crate) fn decode(header: ADBTransportMessageHeader, payload: Vec<u8>) -> Result<Self> {
+        let message = Self::from_header_and_payload(header, payload);
+        // Messages without payload are not checked
+        if !message.payload.is_empty() && !message.check_message_integrity() {
+            return Err(RustADBError::InvalidIntegrity(
+                ADBTransportMessageHeader::compute_crc32(message.payload()),
+                message.header().data_crc32(),
+            ));
+        }
+
+        Ok(message)
+    }
+
     pub fn check_message_integrity(&self) -> bool {
         ADBTransportMessageHeader::compute_magic(self.header.command) == self.header.magic
             && ADBTransportMessageHeader::compute_crc32(&self.payload) == self.header.data_crc32
@@ -113,10 +130,83 @@ impl ADBTransportMessage {
     }
 }
 
-impl TryFrom<[u8; 24]> for ADBTransportMessageHeader {
+impl TryFrom<[u8; HEADER_SIZE]> for ADBTransportMessageHeader {
     type Error = RustADBError;
 
-    fn try_from(value: [u8; 24]) -> Result<Self> {
+    fn try_from(value: [u8; HEADER_SIZE]) -> Result<Self> {
         bincode::deserialize(&value).map_err(|_e| RustADBError::ConversionError)
     }
 }
+
+/// Reassemble [`ADBTransportMessage`]s from data received in arbitrary chunks.
+///
+/// Data is kept until a whole message is received, so reading can be stopped and resumed at any point without losing a message.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Default)]
+pub(crate) struct ADBTransportMessageDecoder {
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "tokio")]
+impl ADBTransportMessageDecoder {
+    /// Append received `data`.
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Next complete message, if any, checking its integrity.
+    pub(crate) fn next_message(&mut self) -> Result<Option<ADBTransportMessage>> {
+        let Some(header) = self.buffer.first_chunk::<HEADER_SIZE>() else {
+            return Ok(None);
+        };
+        let header = ADBTransportMessageHeader::try_from(*header)?;
+        let message_size = HEADER_SIZE + header.data_length() as usize;
+        if self.buffer.len() < message_size {
+            return Ok(None);
+        }
+
+        let payload = self.buffer[HEADER_SIZE..message_size].to_vec();
+        self.buffer.drain(..message_size);
+        ADBTransportMessage::decode(header, payload).map(Some)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_message_decoder() {
+    let message = ADBTransportMessage::new(MessageCommand::Write, 1, 2, b"hello");
+    let mut bytes = message
+        .header()
+        .as_bytes()
+        .expect("cannot serialize header");
+    bytes.extend_from_slice(message.payload());
+    bytes.extend_from_slice(
+        &ADBTransportMessage::new(MessageCommand::Okay, 1, 2, &[])
+            .header()
+            .as_bytes()
+            .expect("cannot serialize header"),
+    );
+
+    // Messages come back whole, whatever chunks they are received in
+    let mut decoder = ADBTransportMessageDecoder::default();
+    let mut messages = Vec::new();
+    for chunk in bytes.chunks(7) {
+        decoder.push(chunk);
+        while let Some(message) = decoder.next_message().expect("invalid message") {
+            messages.push(message);
+        }
+    }
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].header().command(), MessageCommand::Write);
+    assert_eq!(messages[0].payload(), b"hello");
+    assert_eq!(messages[1].header().command(), MessageCommand::Okay);
+
+    // Corrupted payloads are reported
+    bytes[HEADER_SIZE] = b'j';
+    let mut decoder = ADBTransportMessageDecoder::default();
+    decoder.push(&bytes);
+    assert!(matches!(
+        decoder.next_message(),
+        Err(RustADBError::InvalidIntegrity(_, _))
+    ));
+}
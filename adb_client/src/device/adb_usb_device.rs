@@ -7,15 +7,15 @@ use std::io::Read;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::Duration;
 
 use super::adb_message_device::ADBMessageDevice;
+use super::{ADBDeviceBanner, ADBMultiplexer};
 use super::models::MessageCommand;
-use super::{ADBRsaKey, ADBTransportMessage};
+use super::{ADBKeyStore, ADBRsaKey, ADBTransportMessage};
 use crate::ADBDeviceExt;
 use crate::ADBMessageTransport;
 use crate::ADBTransport;
-use crate::device::adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN};
+use crate::models::SyncCompression;
 use crate::{Result, RustADBError, USBTransport};
 
 pub fn read_adb_private_key<P: AsRef<Path>>(private_key_path: P) -> Result<Option<ADBRsaKey>> {
@@ -92,6 +92,7 @@ pub fn is_adb_device<T: UsbContext>(device: &Device<T>, des: &DeviceDescriptor)
     false
 }
 
+/// Default path of the user private key, `~/.android/adbkey`
 pub fn get_default_adb_key_path() -> Result<PathBuf> {
     homedir::my_home()
         .ok()
@@ -103,7 +104,7 @@ pub fn get_default_adb_key_path() -> Result<PathBuf> {
 /// Represent a device reached and available over USB.
 #[derive(Debug)]
 pub struct ADBUSBDevice {
-    private_key: ADBRsaKey,
+    keys: ADBKeyStore,
     inner: ADBMessageDevice<USBTransport>,
 }
 
@@ -135,23 +136,13 @@ impl ADBUSBDevice {
         Self::new_from_transport_inner(transport, private_key_path)
     }
 
-    fn new_from_transport_inner(
+    /// Instantiate a new [`ADBUSBDevice`] from a [`USBTransport`], authenticating with given keys.
+    pub fn new_from_transport_with_keys(
         transport: USBTransport,
-        private_key_path: PathBuf,
+        keys: ADBKeyStore,
     ) -> Result<Self> {
-        let private_key = match read_adb_private_key(&private_key_path)? {
-            Some(pk) => pk,
-            None => {
-                log::warn!(
-                    "No private key found at path {}. Using a temporary random one.",
-                    private_key_path.display()
-                );
-                ADBRsaKey::new_random()?
-            }
-        };
-
         let mut s = Self {
-            private_key,
+            keys,
             inner: ADBMessageDevice::new(transport),
         };
 
@@ -160,6 +151,13 @@ impl ADBUSBDevice {
         Ok(s)
     }
 
+    fn new_from_transport_inner(
+        transport: USBTransport,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        Self::new_from_transport_with_keys(transport, ADBKeyStore::load(&private_key_path)?)
+    }
+
     /// autodetect connected ADB devices and establish a connection with the first device found
     pub fn autodetect() -> Result<Self> {
         Self::autodetect_with_custom_private_key(get_default_adb_key_path()?)
@@ -190,61 +188,33 @@ impl ADBUSBDevice {
 
         self.get_transport_mut().write_message(message)?;
 
-        let message = self.get_transport_mut().read_message()?;
+        let message = self.inner.read_message()?;
         // If the device returned CNXN instead of AUTH it does not require authentication,
         // so we can skip the auth steps.
         if message.header().command() == MessageCommand::Cnxn {
+            self.inner.set_banner(&message);
             return Ok(());
         }
         message.assert_command(MessageCommand::Auth)?;
 
-        // At this point, we should have receive an AUTH message with arg0 == 1
-        let auth_message = match message.header().arg0() {
-            AUTH_TOKEN => message,
-            v => {
-                return Err(RustADBError::ADBRequestFailed(format!(
-                    "Received AUTH message with type != 1 ({v})"
-                )));
-            }
-        };
-
-        let sign = self.private_key.sign(auth_message.into_payload())?;
-
-        let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_SIGNATURE, 0, &sign);
-
-        self.get_transport_mut().write_message(message)?;
-
-        let received_response = self.get_transport_mut().read_message()?;
-
-        if received_response.header().command() == MessageCommand::Cnxn {
-            log::info!(
-                "Authentication OK, device info {}",
-                String::from_utf8(received_response.into_payload())?
-            );
-            return Ok(());
-        }
-
-        let mut pubkey = self.private_key.android_pubkey_encode()?.into_bytes();
-        pubkey.push(b'\0');
-
-        let message = ADBTransportMessage::new(MessageCommand::Auth, AUTH_RSAPUBLICKEY, 0, &pubkey);
+        self.inner.authenticate(&self.keys, message)
+    }
 
-        self.get_transport_mut().write_message(message)?;
+    /// Get banner advertised by device when connecting, if any.
+    pub fn banner(&self) -> Option<&ADBDeviceBanner> {
+        self.inner.get_banner()
+    }
 
-        let response = self
-            .get_transport_mut()
-            .read_message_with_timeout(Duration::from_secs(10))
-            .and_then(|message| {
-                message.assert_command(MessageCommand::Cnxn)?;
-                Ok(message)
-            })?;
-
-        log::info!(
-            "Authentication OK, device info {}",
-            String::from_utf8(response.into_payload())?
-        );
+    /// Compress contents of pushed and pulled files with `compression` when device supports it, negotiating it if `None`.
+    /// See [`crate::ADBServerDevice::set_sync_compression`].
+    pub fn set_sync_compression(&mut self, compression: Option<SyncCompression>) {
+        self.inner.set_sync_compression(compression);
+    }
 
-        Ok(())
+    /// Turn this device into an [`ADBMultiplexer`], allowing to run many services concurrently on the current connection.
+    pub fn into_multiplexer(mut self) -> ADBMultiplexer<USBTransport> {
+        let banner = self.inner.get_banner().cloned();
+        ADBMultiplexer::new(self.get_transport_mut().detach()).with_banner(banner)
     }
 
     #[inline]
@@ -260,15 +230,42 @@ impl ADBDeviceExt for ADBUSBDevice {
     }
 
     #[inline]
-    fn shell<'a>(&mut self, reader: &mut dyn Read, writer: Box<(dyn Write + Send)>) -> Result<()> {
+    fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        self.inner
+            .shell_command_with_status(command, stdout, stderr)
+    }
+
+    #[inline]
+    fn shell<'a>(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
         self.inner.shell(reader, writer)
     }
 
+    #[inline]
+    fn pty_shell(
+        &mut self,
+        term: &str,
+        size: crate::TerminalSize,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<crate::ADBPtyShell> {
+        self.inner.pty_shell(term, size, reader, writer)
+    }
+
     #[inline]
     fn stat(&mut self, remote_path: &str) -> Result<crate::AdbStatResponse> {
         self.inner.stat(remote_path)
     }
 
+    #[inline]
+    fn list_dir(&mut self, path: &str) -> Result<Vec<crate::AdbDirEntry>> {
+        self.inner.list_dir(path)
+    }
+
     #[inline]
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
         self.inner.pull(source, output)
@@ -279,19 +276,75 @@ impl ADBDeviceExt for ADBUSBDevice {
         self.inner.push(stream, path)
     }
 
+    #[inline]
+    fn push_with_options(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        options: crate::PushOptions,
+    ) -> Result<()> {
+        self.inner.push_with_options(stream, path, options)
+    }
+
+    #[inline]
+    fn push_dir(
+        &mut self,
+        source: &dyn AsRef<Path>,
+        destination: &str,
+    ) -> Result<Vec<crate::FileTransfer>> {
+        self.inner.push_dir(source, destination)
+    }
+
+    #[inline]
+    fn pull_dir(
+        &mut self,
+        source: &str,
+        destination: &dyn AsRef<Path>,
+    ) -> Result<Vec<crate::FileTransfer>> {
+        self.inner.pull_dir(source, destination)
+    }
+
     #[inline]
     fn reboot(&mut self, reboot_type: crate::RebootType) -> Result<()> {
         self.inner.reboot(reboot_type)
     }
 
     #[inline]
-    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
-        self.inner.install(apk_path)
+    fn wait_for_boot_since(
+        &mut self,
+        previous_boot: Option<&str>,
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        crate::boot::wait_for_boot(self, previous_boot, timeout)
+    }
+
+    #[inline]
+    fn install_from_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        options: &crate::InstallOptions,
+        monitor: &mut crate::TransferMonitor,
+    ) -> Result<()> {
+        self.inner.install_from_reader(reader, size, options, monitor)
+    }
+
+    #[inline]
+    fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.inner.install_multiple_with_options(apk_paths, options)
     }
 
     #[inline]
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.inner.uninstall(package)
+    fn uninstall_with_options(
+        &mut self,
+        package: &str,
+        options: &crate::UninstallOptions,
+    ) -> Result<()> {
+        self.inner.uninstall_with_options(package, options)
     }
 
     #[inline]
@@ -300,6 +353,16 @@ impl ADBDeviceExt for ADBUSBDevice {
     }
 }
 
+impl crate::boot::BootTarget for ADBUSBDevice {
+    fn reconnect(&mut self) -> Result<()> {
+        self.connect()
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<std::time::Instant>) {
+        self.inner.set_read_deadline(deadline);
+    }
+}
+
 impl Drop for ADBUSBDevice {
     fn drop(&mut self) {
         // Best effort here
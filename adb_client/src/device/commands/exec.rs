@@ -0,0 +1,27 @@
+use std::io::Read;
+
+use crate::{
+    ADBMessageTransport, Result,
+    device::{MessageWriter, adb_message_device::ADBMessageDevice},
+    install_session::ExecDevice,
+};
+
+impl<T: ADBMessageTransport> ExecDevice for ADBMessageDevice<T> {
+    fn supports_abb_exec(&mut self) -> Result<bool> {
+        Ok(self
+            .get_banner()
+            .is_some_and(|banner| banner.has_feature("abb_exec")))
+    }
+
+    fn exec(&mut self, service: &str, input: &mut dyn Read) -> Result<Vec<u8>> {
+        self.open_shell_session(service)?;
+
+        let transport = self.get_transport().clone();
+        let mut writer = MessageWriter::new(transport, self.get_local_id()?, self.get_remote_id()?);
+        std::io::copy(input, &mut writer)?;
+
+        let mut output = Vec::new();
+        self.read_shell_session(&mut output)?;
+        Ok(output)
+    }
+}
@@ -1,40 +1,42 @@
-use std::{fs::File, path::Path};
+use std::{io::Read, path::Path};
 
 use crate::{
-    ADBMessageTransport, Result,
+    ADBMessageTransport, InstallOptions, Result, TransferMonitor,
     device::{MessageWriter, adb_message_device::ADBMessageDevice},
-    utils::check_extension_is_apk,
+    install_session,
+    utils::{check_apk_magic, check_package_manager_response, copy_apk},
 };
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
-    pub(crate) fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
-        let mut apk_file = File::open(apk_path)?;
-
-        check_extension_is_apk(apk_path)?;
+    pub(crate) fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &InstallOptions,
+    ) -> Result<()> {
+        install_session::install_multiple(self, apk_paths, options)
+    }
 
-        let file_size = apk_file.metadata()?.len();
+    pub(crate) fn install_from_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        let apk = check_apk_magic(reader)?;
 
-        self.open_session(format!("exec:cmd package 'install' -S {file_size}\0").as_bytes())?;
+        self.open_session(format!("{}\0", options.install_service(size)).as_bytes())?;
 
         let transport = self.get_transport().clone();
 
         let mut writer = MessageWriter::new(transport, self.get_local_id()?, self.get_remote_id()?);
 
-        std::io::copy(&mut apk_file, &mut writer)?;
-
-        let final_status = self.get_transport_mut().read_message()?;
-
-        match final_status.into_payload().as_slice() {
-            b"Success\n" => {
-                log::info!(
-                    "APK file {} successfully installed",
-                    apk_path.as_ref().display()
-                );
-                Ok(())
-            }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
-        }
+        let copied = copy_apk(apk, size, &mut writer, monitor);
+        // Package manager would otherwise keep waiting for the rest of the APK
+        self.close_session_on_error(copied)?;
+
+        let final_status = self.read_message()?;
+
+        check_package_manager_response(&final_status.into_payload())
     }
 }
@@ -0,0 +1,37 @@
+use crate::{
+    ADBMessageTransport, Result,
+    device::{
+        ADBTransportMessage, MessageCommand, MessageReader, adb_message_device::ADBMessageDevice,
+        models::MessageSubcommand,
+    },
+    models::AdbDirEntry,
+};
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    pub(crate) fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        let list_v2 = self
+            .get_banner()
+            .is_some_and(|banner| banner.has_feature("ls_v2"));
+        self.begin_synchronization()?;
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+
+        let list_buffer = match list_v2 {
+            true => MessageSubcommand::List2,
+            false => MessageSubcommand::List,
+        }
+        .encode(path.len() as u32, path.as_bytes());
+        self.send_and_expect_okay(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &list_buffer,
+        ))?;
+
+        let mut reader = MessageReader::new(self.get_transport().clone(), local_id, remote_id);
+        let entries = AdbDirEntry::read_all(&mut reader, list_v2)?;
+        self.end_transaction()?;
+
+        Ok(entries)
+    }
+}
@@ -1,5 +1,7 @@
+mod exec;
 mod framebuffer;
 mod install;
+mod list;
 mod pull;
 mod push;
 mod reboot;
@@ -3,16 +3,30 @@ use std::io::Write;
 use crate::{
     ADBMessageTransport, Result, RustADBError,
     device::{
-        ADBTransportMessage, MessageCommand, adb_message_device::ADBMessageDevice,
+        ADBTransportMessage, MessageCommand, MessageReader, adb_message_device::ADBMessageDevice,
         models::MessageSubcommand,
     },
+    models::{SyncCompression, SyncDataReader},
 };
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
-    pub(crate) fn pull<A: AsRef<str>, W: Write>(&mut self, source: A, output: W) -> Result<()> {
+    pub(crate) fn pull<A: AsRef<str>, W: Write>(&mut self, source: A, mut output: W) -> Result<()> {
+        let compression = self.sync_compression();
         self.begin_synchronization()?;
-        let source = source.as_ref();
+        let result = self.pull_in_session(source.as_ref(), &mut output, compression);
+        // Device would otherwise keep sending the rest of the file
+        self.close_session_on_error(result)?;
 
+        self.end_transaction()
+    }
+
+    /// Pull `source` into `output` once sync session is opened, using sync protocol v2 if `compression` is set.
+    fn pull_in_session(
+        &mut self,
+        source: &str,
+        output: &mut dyn Write,
+        compression: Option<SyncCompression>,
+    ) -> Result<()> {
         let adb_stat_response = self.stat_with_explicit_ids(source)?;
 
         if adb_stat_response.file_perm == 0 {
@@ -29,9 +43,11 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             std::time::Duration::from_secs(4),
         )?;
 
-        let recv_buffer = MessageSubcommand::Recv.with_arg(source.len() as u32);
-        let recv_buffer =
-            bincode::serialize(&recv_buffer).map_err(|_e| RustADBError::ConversionError)?;
+        if let Some(compression) = compression {
+            return self.pull_v2(source, output, compression);
+        }
+
+        let recv_buffer = MessageSubcommand::Recv.encode(source.len() as u32, &[]);
         self.send_and_expect_okay(ADBTransportMessage::new(
             MessageCommand::Write,
             self.get_local_id()?,
@@ -45,8 +61,42 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             source.as_bytes(),
         ))?;
 
-        self.recv_file(output)?;
-        self.end_transaction()?;
+        self.recv_file(output)
+    }
+
+    /// Pull `source` into `output` using sync protocol v2, device compressing it with `compression`.
+    fn pull_v2(
+        &mut self,
+        source: &str,
+        output: &mut dyn Write,
+        compression: SyncCompression,
+    ) -> Result<()> {
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+
+        // Path is sent alone, compression follows in another request
+        let recv_buffer = MessageSubcommand::Recv2.encode(source.len() as u32, source.as_bytes());
+        self.send_and_expect_okay(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &recv_buffer,
+        ))?;
+
+        let setup_buffer = MessageSubcommand::Recv2.encode(compression.flags(), &[]);
+        self.send_and_expect_okay(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &setup_buffer,
+        ))?;
+
+        let reader = MessageReader::new(self.get_transport().clone(), local_id, remote_id);
+        let mut reader = SyncDataReader::new(reader);
+        compression.decompress(&mut reader, output)?;
+        // Compressed stream may end before "DONE" is read
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+
         Ok(())
     }
 }
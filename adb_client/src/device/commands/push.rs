@@ -1,23 +1,54 @@
-use std::io::Read;
+use std::io::{BufWriter, Read, Write};
 
 use crate::{
     ADBMessageTransport, Result, RustADBError,
+    constants::BUFFER_SIZE,
     device::{
-        ADBTransportMessage, MessageCommand, MessageSubcommand,
+        ADBTransportMessage, MessageCommand, MessageSubcommand, MessageWriter,
         adb_message_device::ADBMessageDevice,
     },
+    dir_transfer,
+    models::{PushOptions, SyncCompression, SyncDataWriter},
 };
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
-    pub(crate) fn push<R: Read, A: AsRef<str>>(&mut self, stream: R, path: A) -> Result<()> {
+    /// Push `stream` to `path`, creating it with mode and modification time from `options`.
+    pub(crate) fn push_with_options<R: Read, A: AsRef<str>>(
+        &mut self,
+        stream: R,
+        path: A,
+        options: PushOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let (mode, mtime) = (options.mode, options.mtime_or_now());
+
+        // Older devices fail to push files into missing directories
+        if !self
+            .get_banner()
+            .is_some_and(|banner| banner.has_feature("fixed_push_mkdir"))
+        {
+            dir_transfer::make_remote_parent_dir(self, path)?;
+        }
+
+        let compression = self.sync_compression();
         self.begin_synchronization()?;
+        let result = match compression {
+            Some(compression) => self.push_v2(stream, path, mode, mtime, compression),
+            None => self.push_v1(stream, path, mode, mtime),
+        };
+        // Device would otherwise keep waiting for the rest of the file
+        self.close_session_on_error(result)?;
 
-        let path_header = format!("{},0777", path.as_ref());
+        self.end_transaction()
+    }
 
-        let send_buffer = MessageSubcommand::Send.with_arg(path_header.len() as u32);
-        let mut send_buffer =
-            bincode::serialize(&send_buffer).map_err(|_e| RustADBError::ConversionError)?;
-        send_buffer.append(&mut path_header.as_bytes().to_vec());
+    /// Push `stream` to `path` using legacy `SEND` request.
+    fn push_v1<R: Read>(&mut self, stream: R, path: &str, mode: u32, mtime: u32) -> Result<()> {
+        // Mode is formatted as `adb` does, devices also accept octal values starting with a 0
+        let path_header = format!("{path},{mode}");
+
+        let send_buffer =
+            MessageSubcommand::Send.encode(path_header.len() as u32, path_header.as_bytes());
 
         self.send_and_expect_okay(ADBTransportMessage::new(
             MessageCommand::Write,
@@ -26,10 +57,63 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             &send_buffer,
         ))?;
 
-        self.push_file(self.get_local_id()?, self.get_remote_id()?, stream)?;
+        self.push_file(self.get_local_id()?, self.get_remote_id()?, stream, mtime)
+    }
+
+    /// Push `stream` to `path` using sync protocol v2, compressing it with `compression`.
+    fn push_v2<R: Read>(
+        &mut self,
+        mut stream: R,
+        path: &str,
+        mode: u32,
+        mtime: u32,
+        compression: SyncCompression,
+    ) -> Result<()> {
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
 
-        self.end_transaction()?;
+        // Path is sent alone, mode and compression follow in another request
+        let send_buffer = MessageSubcommand::Send2.encode(path.len() as u32, path.as_bytes());
+        self.send_and_expect_okay(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &send_buffer,
+        ))?;
+
+        let setup_buffer =
+            MessageSubcommand::Send2.encode(mode, &compression.flags().to_le_bytes());
+        self.send_and_expect_okay(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &setup_buffer,
+        ))?;
+
+        let writer = MessageWriter::new(self.get_transport().clone(), local_id, remote_id);
+        let writer = BufWriter::with_capacity(BUFFER_SIZE, SyncDataWriter::new(writer));
+        compression.compress(&mut stream, writer)?.flush()?;
+
+        let done_buffer = MessageSubcommand::Done.encode(mtime, &[]);
+        self.send_and_expect_okay(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &done_buffer,
+        ))?;
 
-        Ok(())
+        // Command should end with a Write holding status of transfer
+        let received = self.read_message()?;
+        match received.header().command() {
+            MessageCommand::Write => match received.into_payload() {
+                payload if payload.starts_with(b"FAIL") => Err(RustADBError::ADBRequestFailed(
+                    String::from_utf8_lossy(payload.get(8..).unwrap_or_default()).to_string(),
+                )),
+                _ => Ok(()),
+            },
+            c => Err(RustADBError::ADBRequestFailed(format!(
+                "Wrong command received {c}"
+            ))),
+        }
     }
 }
@@ -5,10 +5,16 @@ use crate::{
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     pub(crate) fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
-        self.open_session(format!("reboot:{reboot_type}\0").as_bytes())?;
+        self.open_session(format!("reboot:{reboot_type}\0").as_bytes())?
+            .assert_command(MessageCommand::Okay)?;
 
-        self.get_transport_mut()
-            .read_message()
-            .and_then(|message| message.assert_command(MessageCommand::Okay))
+        match self.read_message() {
+            Ok(message) => message.assert_command(MessageCommand::Okay),
+            Err(e) => {
+                // Device may go down before saying anything else
+                log::debug!("connection lost while rebooting: {e}");
+                Ok(())
+            }
+        }
     }
 }
@@ -1,16 +1,57 @@
 use std::io::{ErrorKind, Read, Write};
 
-use crate::device::ShellMessageWriter;
+use crate::device::{MessageReader, ShellMessageWriter};
+use crate::models::{encode_shell_packet, ShellPacketId, ShellV2Output, TerminalSize};
 use crate::Result;
 use crate::{
     device::{ADBMessageDevice, ADBTransportMessage, MessageCommand},
-    ADBMessageTransport, RustADBError,
+    ADBMessageTransport, ADBPtyShell, RustADBError,
 };
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     /// Runs 'command' in a shell on the device, and write its output and error streams into output.
     pub(crate) fn shell_command(&mut self, command: &[&str], output: &mut dyn Write) -> Result<()> {
-        let response = self.open_session(format!("shell:{}\0", command.join(" "),).as_bytes())?;
+        self.open_shell_session(&format!("shell:{}", command.join(" ")))?;
+        self.read_shell_session(output)
+    }
+
+    /// Runs 'command' in a shell on the device, writing its output and error streams into `stdout` and `stderr`.
+    ///
+    /// Returns exit code of command if device supports shell protocol v2, else both streams are written into `stdout`.
+    pub(crate) fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        if !self
+            .get_banner()
+            .is_some_and(|banner| banner.has_feature("shell_v2"))
+        {
+            log::debug!("device does not support shell v2, exit code will not be available");
+            self.shell_command(command, stdout)?;
+            return Ok(None);
+        }
+
+        self.open_shell_session(&format!("shell,v2,raw:{}", command.join(" ")))?;
+
+        // Command does not get any input
+        let close_stdin = ADBTransportMessage::new(
+            MessageCommand::Write,
+            self.get_local_id()?,
+            self.get_remote_id()?,
+            &encode_shell_packet(ShellPacketId::CloseStdin, &[]),
+        );
+        self.get_transport_mut().write_message(close_stdin)?;
+
+        let mut output = ShellV2Output::new(stdout, stderr);
+        self.read_shell_session(&mut output)?;
+
+        Ok(output.exit_code())
+    }
+
+    pub(crate) fn open_shell_session(&mut self, service: &str) -> Result<()> {
+        let response = self.open_session(format!("{service}\0").as_bytes())?;
 
         if response.header().command() != MessageCommand::Okay {
             return Err(RustADBError::ADBRequestFailed(format!(
@@ -19,14 +60,17 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             )));
         }
 
+        Ok(())
+    }
+
+    /// Write everything sent by device on current session into `output`, until device closes it.
+    pub(crate) fn read_shell_session(&mut self, output: &mut dyn Write) -> Result<()> {
         let local_id = self.get_local_id()?;
         let remote_id = self.get_remote_id()?;
 
-        // Device "Write" is followed by device "Close" that we need to confirm with a "Close"
-        let mut have_unconfirmed_writes = false;
-
+        // Device "Write" messages are followed by a device "Close" that we need to confirm with a "Close"
         loop {
-            let response = self.get_transport_mut().read_message()?;
+            let response = self.read_message()?;
 
             match response.header().command() {
                 MessageCommand::Write => {
@@ -37,9 +81,6 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
 
                     // Write the payload to output
                     output.write_all(&response.into_payload())?;
-
-                    // Mark that we have unconfirmed writes
-                    have_unconfirmed_writes = true;
                 }
                 MessageCommand::Okay => {
                     // Device acknowledged our message, continue
@@ -58,14 +99,8 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
                         );
                         self.get_transport_mut().write_message(close_msg)?;
 
-                        // If we have unconfirmed writes, continue the loop
-                        if have_unconfirmed_writes {
-                            // Reset the flag
-                            have_unconfirmed_writes = false;
-                        } else {
-                            // No unconfirmed writes, meaning we have received the final Close message
-                            break;
-                        }
+                        // Device does not send anything else once it closed the session
+                        break;
                     }
                     // Close is for a different session, ignore and continue
                     continue;
@@ -88,7 +123,7 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     pub(crate) fn shell(
         &mut self,
         mut reader: &mut dyn Read,
-        mut writer: Box<(dyn Write + Send)>,
+        mut writer: Box<dyn Write + Send>,
     ) -> Result<()> {
         self.open_session(b"shell:\0")?;
 
@@ -131,4 +166,39 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
 
         Ok(())
     }
+
+    /// Starts an interactive shell session on the device, in a pseudo-terminal of type `term` sized `size`.
+    /// Input data is read from [reader] and write to [writer] by background threads.
+    pub(crate) fn pty_shell(
+        &mut self,
+        term: &str,
+        size: TerminalSize,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<ADBPtyShell> {
+        let shell_v2 = self
+            .get_banner()
+            .is_some_and(|banner| banner.has_feature("shell_v2"));
+        match shell_v2 {
+            true => self.open_shell_session(&format!("shell,v2,TERM={term},pty:"))?,
+            false => {
+                log::debug!("device does not support shell v2, terminal size will not be updated");
+                self.open_shell_session("shell:")?
+            }
+        }
+
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+        let input = ShellMessageWriter::new(self.get_transport().clone(), local_id, remote_id);
+        let output = MessageReader::new(self.get_transport().clone(), local_id, remote_id);
+
+        ADBPtyShell::start(
+            Box::new(input),
+            Box::new(output),
+            shell_v2,
+            size,
+            reader,
+            writer,
+        )
+    }
 }
@@ -1,19 +1,20 @@
-use crate::{ADBMessageTransport, Result, device::adb_message_device::ADBMessageDevice};
+use crate::{
+    ADBMessageTransport, Result, UninstallOptions, device::adb_message_device::ADBMessageDevice,
+    utils::check_package_manager_response,
+};
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
-    pub(crate) fn uninstall(&mut self, package_name: &str) -> Result<()> {
-        self.open_session(format!("exec:cmd package 'uninstall' {package_name}\0").as_bytes())?;
+    pub(crate) fn uninstall_with_options(
+        &mut self,
+        package_name: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
+        self.open_session(format!("{}\0", options.uninstall_service(package_name)).as_bytes())?;
 
-        let final_status = self.get_transport_mut().read_message()?;
+        let final_status = self.read_message()?;
 
-        match final_status.into_payload().as_slice() {
-            b"Success\n" => {
-                log::info!("Package {package_name} successfully uninstalled");
-                Ok(())
-            }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
-        }
+        check_package_manager_response(&final_status.into_payload())?;
+        log::info!("Package {package_name} successfully uninstalled");
+        Ok(())
     }
 }
@@ -0,0 +1,79 @@
+use std::io::Read;
+
+use crate::{ADBMessageTransport, RustADBError};
+
+use super::{ADBTransportMessage, models::MessageCommand};
+
+/// [`Read`] trait implementation to hide underlying ADB protocol read logic.
+///
+/// Reading returns end of stream once device closed the session.
+pub(crate) struct MessageReader<T: ADBMessageTransport> {
+    transport: T,
+    local_id: u32,
+    remote_id: u32,
+    /// Data of last message not returned yet
+    pending: Vec<u8>,
+    closed: bool,
+}
+
+impl<T: ADBMessageTransport> MessageReader<T> {
+    pub(crate) fn new(transport: T, local_id: u32, remote_id: u32) -> Self {
+        Self {
+            transport,
+            local_id,
+            remote_id,
+            pending: Vec::new(),
+            closed: false,
+        }
+    }
+
+    fn write_message(&mut self, command: MessageCommand) -> std::io::Result<()> {
+        let message = ADBTransportMessage::new(command, self.local_id, self.remote_id, &[]);
+        self.transport
+            .write_message(message)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<T: ADBMessageTransport> Read for MessageReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pending.is_empty() {
+            if self.closed {
+                return Ok(0);
+            }
+
+            let message = self
+                .transport
+                .read_message()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            match message.header().command() {
+                MessageCommand::Write => {
+                    // Acknowledge for more data
+                    self.write_message(MessageCommand::Okay)?;
+                    self.pending = message.into_payload();
+                }
+                MessageCommand::Okay => continue,
+                MessageCommand::Clse => {
+                    // Close may be for another session
+                    if message.header().arg1() == self.local_id
+                        && message.header().arg0() == self.remote_id
+                    {
+                        self.write_message(MessageCommand::Clse)?;
+                        self.closed = true;
+                    }
+                }
+                command => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        RustADBError::ADBRequestFailed(format!("unexpected command: {command}")),
+                    ));
+                }
+            }
+        }
+
+        let size = buf.len().min(self.pending.len());
+        buf[..size].copy_from_slice(&self.pending[..size]);
+        self.pending.drain(..size);
+        Ok(size)
+    }
+}
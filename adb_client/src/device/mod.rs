@@ -1,19 +1,30 @@
 mod adb_message_device;
 mod adb_message_device_commands;
+mod adb_multiplexer;
+mod adb_stream;
 mod adb_tcp_device;
-mod adb_transport_message;
+pub(crate) mod adb_transport_message;
 mod adb_usb_device;
 mod commands;
+mod message_reader;
 mod message_writer;
 mod models;
 mod shell_message_writer;
 
-use adb_message_device::ADBMessageDevice;
+pub use adb_message_device::ADBMessageDevice;
+pub use adb_multiplexer::ADBMultiplexer;
+pub use adb_stream::{ADBStream, ADBStreamReader, ADBStreamWriter};
 pub use adb_tcp_device::ADBTcpDevice;
 pub use adb_transport_message::{ADBTransportMessage, ADBTransportMessageHeader};
+#[cfg(feature = "tokio")]
+pub(crate) use adb_transport_message::ADBTransportMessageDecoder;
 pub use adb_usb_device::{
     get_default_adb_key_path, is_adb_device, read_adb_private_key, search_adb_devices, ADBUSBDevice,
 };
+pub(crate) use message_reader::MessageReader;
 pub use message_writer::MessageWriter;
-pub use models::{ADBRsaKey, MessageCommand, MessageSubcommand};
+pub use models::{ADBDeviceBanner, ADBKeyStore, ADBRsaKey, MessageCommand, MessageSubcommand};
+#[cfg(test)]
+pub(crate) use models::TEST_PRIVATE_KEY;
+pub(crate) use models::{android_pubkey_decode, load_or_generate_key, verify_signature};
 pub use shell_message_writer::ShellMessageWriter;
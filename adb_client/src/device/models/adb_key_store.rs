@@ -0,0 +1,195 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use super::ADBRsaKey;
+use crate::Result;
+use crate::device::read_adb_private_key;
+
+/// Environment variable listing vendor keys, separated as in `PATH`.
+const VENDOR_KEYS_ENV_VAR: &str = "ADB_VENDOR_KEYS";
+/// Extension of keys loaded from vendor keys directories.
+const VENDOR_KEY_EXTENSION: &str = "adb_key";
+
+/// Private keys tried in turn to authenticate with devices: the user key first, then vendor keys.
+///
+/// When devices accept none of them, the public key of the user key is sent, to be allowed on device.
+#[derive(Debug, Clone)]
+pub struct ADBKeyStore {
+    keys: Vec<ADBRsaKey>,
+}
+
+impl ADBKeyStore {
+    /// Instantiate a new [`ADBKeyStore`] holding only `user_key`.
+    pub fn new(user_key: ADBRsaKey) -> Self {
+        Self {
+            keys: vec![user_key],
+        }
+    }
+
+    /// Load user key stored at `private_key_path`, and vendor keys listed by `ADB_VENDOR_KEYS` environment variable.
+    ///
+    /// If user key does not exist, a new one is generated and saved with its public key, as `adb` does.
+    pub fn load<P: AsRef<Path>>(private_key_path: P) -> Result<Self> {
+        let vendor_paths = std::env::var_os(VENDOR_KEYS_ENV_VAR)
+            .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        Self::load_with_vendor_keys(private_key_path, vendor_paths)
+    }
+
+    /// Load user key stored at `private_key_path`, generating it if needed, and vendor keys found at `vendor_paths`.
+    ///
+    /// Each vendor path is either a key file, or a directory whose `*.adb_key` files are loaded.
+    pub fn load_with_vendor_keys<P, I>(private_key_path: P, vendor_paths: I) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let mut store = Self::new(load_or_generate_key(private_key_path.as_ref())?);
+        for vendor_path in vendor_paths {
+            for key_path in vendor_key_paths(vendor_path.as_ref()) {
+                match read_adb_private_key(&key_path) {
+                    Ok(Some(key)) => store.keys.push(key),
+                    Ok(None) => log::warn!("vendor key {} not found", key_path.display()),
+                    Err(e) => log::warn!("cannot load vendor key {}: {e}", key_path.display()),
+                }
+            }
+        }
+
+        Ok(store)
+    }
+
+    /// Add `key` to keys tried during authentication, after the ones already held.
+    pub fn with_key(mut self, key: ADBRsaKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Key whose public key is sent to devices accepting none of the keys.
+    pub fn user_key(&self) -> &ADBRsaKey {
+        &self.keys[0]
+    }
+
+    /// All keys, in the order they are tried.
+    pub fn keys(&self) -> &[ADBRsaKey] {
+        &self.keys
+    }
+}
+
+/// Read key stored at `private_key_path`, or generate and save a new one if there is none.
+pub(crate) fn load_or_generate_key(private_key_path: &Path) -> Result<ADBRsaKey> {
+    if let Some(key) = read_adb_private_key(private_key_path)? {
+        return Ok(key);
+    }
+
+    let key = ADBRsaKey::new_random()?;
+    match key.save(private_key_path) {
+        Ok(()) => log::info!(
+            "generated new private key {} with fingerprint {}",
+            private_key_path.display(),
+            key.fingerprint()?
+        ),
+        Err(e) => log::warn!(
+            "cannot save generated private key to {}, using it for this session only: {e}",
+            private_key_path.display()
+        ),
+    }
+
+    Ok(key)
+}
+
+/// Key files designated by a vendor path, sorted by name when it is a directory.
+fn vendor_key_paths(vendor_path: &Path) -> Vec<PathBuf> {
+    if !vendor_path.is_dir() {
+        return vec![vendor_path.to_path_buf()];
+    }
+
+    let mut paths = match std::fs::read_dir(vendor_path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension() == Some(OsStr::new(VENDOR_KEY_EXTENSION)))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            log::warn!(
+                "cannot read vendor keys directory {}: {e}",
+                vendor_path.display()
+            );
+            Vec::new()
+        }
+    };
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_key_store_generation() {
+    let directory =
+        std::env::temp_dir().join(format!("adb_client_key_store_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let private_key_path = directory.join("adbkey");
+
+    // Missing key gets generated along its public key, then reused
+    let store = ADBKeyStore::load_with_vendor_keys(&private_key_path, Vec::<PathBuf>::new())
+        .expect("cannot generate key");
+    let fingerprint = store
+        .user_key()
+        .fingerprint()
+        .expect("cannot compute fingerprint");
+    let public_key =
+        std::fs::read_to_string(directory.join("adbkey.pub")).expect("public key not saved");
+    assert_eq!(
+        public_key.trim_end(),
+        store
+            .user_key()
+            .android_pubkey_encode()
+            .expect("cannot encode public key")
+    );
+
+    let vendor_key = ADBRsaKey::new_random().expect("cannot generate key");
+    vendor_key
+        .save(directory.join("vendor").join("lab.adb_key"))
+        .expect("cannot save vendor key");
+    let store = ADBKeyStore::load_with_vendor_keys(&private_key_path, [directory.join("vendor")])
+        .expect("cannot load keys");
+    let fingerprints = store
+        .keys()
+        .iter()
+        .map(|key| key.fingerprint().expect("cannot compute fingerprint"))
+        .collect::<Vec<_>>();
+    assert_eq!(fingerprints.len(), 2);
+    assert_eq!(fingerprints[0], fingerprint);
+    assert_eq!(
+        fingerprints[1],
+        vendor_key
+            .fingerprint()
+            .expect("cannot compute fingerprint")
+    );
+}
+
+#[test]
+fn test_key_store_vendor_key_authentication() {
+    use crate::{ADBTcpDevice, FakeADBDevice, FakeAuthMode, TcpTransport};
+
+    let private_key_path = crate::fake_device::write_test_private_key("key_store_vendor_key");
+    let vendor_key = ADBRsaKey::new_random().expect("cannot generate key");
+    let public_key = vendor_key
+        .android_pubkey_encode()
+        .expect("cannot encode public key");
+
+    // Device only knows vendor key, which is tried after user key
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.set_auth_mode(FakeAuthMode::AuthorizedKeys(vec![public_key]));
+    let address = fake_device.listen().expect("cannot listen");
+
+    let keys = ADBKeyStore::load_with_vendor_keys(&private_key_path, Vec::<PathBuf>::new())
+        .expect("cannot load keys");
+    let transport = || {
+        TcpTransport::new_with_custom_private_key(address, private_key_path.clone())
+            .expect("cannot create transport")
+    };
+    ADBTcpDevice::new_from_transport_with_keys(transport(), keys.clone())
+        .expect_err("device accepted unknown key");
+    ADBTcpDevice::new_from_transport_with_keys(transport(), keys.with_key(vendor_key))
+        .expect("cannot authenticate with vendor key");
+}
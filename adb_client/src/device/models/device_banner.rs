@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::RustADBError;
+
+/// Identity advertised by a device in the payload of its `CNXN` message.
+///
+/// Banner looks like `device::ro.product.name=x;ro.product.model=y;ro.product.device=z;features=shell_v2,cmd`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ADBDeviceBanner {
+    /// System type of device (`device`, `recovery`, `sideload`, `bootloader`...).
+    pub system_type: String,
+    /// Properties advertised by device (e.g. `ro.product.model`).
+    pub properties: HashMap<String, String>,
+    /// Features supported by device (e.g. `shell_v2`, `cmd`).
+    pub features: Vec<String>,
+}
+
+impl ADBDeviceBanner {
+    /// Get `ro.product.name` property, if advertised.
+    pub fn product(&self) -> Option<&str> {
+        self.properties.get("ro.product.name").map(String::as_str)
+    }
+
+    /// Get `ro.product.model` property, if advertised.
+    pub fn model(&self) -> Option<&str> {
+        self.properties.get("ro.product.model").map(String::as_str)
+    }
+
+    /// Get `ro.product.device` property, if advertised.
+    pub fn device(&self) -> Option<&str> {
+        self.properties.get("ro.product.device").map(String::as_str)
+    }
+
+    /// Whether device supports given `feature`.
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+impl TryFrom<&[u8]> for ADBDeviceBanner {
+    type Error = RustADBError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let banner = std::str::from_utf8(value)?.trim_end_matches('\0');
+
+        // Second field (serial number) is always empty on devices
+        let mut parts = banner.splitn(3, ':');
+        let system_type = parts.next().unwrap_or_default().to_string();
+        let _ = parts.next();
+
+        let mut properties = HashMap::new();
+        let mut features = vec![];
+        for property in parts.next().unwrap_or_default().split(';') {
+            let Some((key, value)) = property.split_once('=') else {
+                continue;
+            };
+            if key == "features" {
+                features = value
+                    .split(',')
+                    .filter(|f| !f.is_empty())
+                    .map(String::from)
+                    .collect();
+            } else {
+                properties.insert(key.to_string(), value.to_string());
+            }
+        }
+
+        Ok(Self {
+            system_type,
+            properties,
+            features,
+        })
+    }
+}
+
+impl Display for ADBDeviceBanner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::", self.system_type)?;
+        for key in ["ro.product.name", "ro.product.model", "ro.product.device"] {
+            if let Some(value) = self.properties.get(key) {
+                write!(f, "{key}={value};")?;
+            }
+        }
+        write!(f, "features={}", self.features.join(","))
+    }
+}
+
+#[test]
+fn test_parse_banner() {
+    let banner = ADBDeviceBanner::try_from(
+        &b"device::ro.product.name=beyond1lteeea;ro.product.model=SM-G973F;ro.product.device=beyond1;features=shell_v2,cmd,stat_v2\0"[..],
+    )
+    .expect("cannot parse banner");
+
+    assert_eq!(banner.system_type, "device");
+    assert_eq!(banner.model(), Some("SM-G973F"));
+    assert!(banner.has_feature("shell_v2"));
+    assert!(!banner.has_feature("ls_v2"));
+    assert_eq!(
+        ADBDeviceBanner::try_from(banner.to_string().as_bytes()).expect("cannot parse banner"),
+        banner
+    );
+}
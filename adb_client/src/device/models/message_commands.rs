@@ -1,4 +1,3 @@
-use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::fmt::Display;
 
@@ -32,20 +31,19 @@ pub enum MessageSubcommand {
     Done = 0x454E4F44,
     Data = 0x41544144,
     List = 0x5453494C,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SubcommandWithArg {
-    subcommand: MessageSubcommand,
-    arg: u32,
+    List2 = 0x3253494C,
+    Send2 = 0x32444E53,
+    Recv2 = 0x32564352,
 }
 
 impl MessageSubcommand {
-    pub fn with_arg(self, arg: u32) -> SubcommandWithArg {
-        SubcommandWithArg {
-            subcommand: self,
-            arg,
-        }
+    /// Encode a sync packet made of this subcommand and `arg`, followed by `data`.
+    pub(crate) fn encode(self, arg: u32, data: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(8 + data.len());
+        packet.extend_from_slice(&(self as u32).to_le_bytes());
+        packet.extend_from_slice(&arg.to_le_bytes());
+        packet.extend_from_slice(data);
+        packet
     }
 }
 
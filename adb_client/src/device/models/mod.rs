@@ -1,5 +1,13 @@
+mod adb_key_store;
 mod adb_rsa_key;
+mod device_banner;
 mod message_commands;
 
+pub use adb_key_store::ADBKeyStore;
+pub(crate) use adb_key_store::load_or_generate_key;
 pub use adb_rsa_key::ADBRsaKey;
+#[cfg(test)]
+pub(crate) use adb_rsa_key::TEST_PRIVATE_KEY;
+pub(crate) use adb_rsa_key::{android_pubkey_decode, verify_signature};
+pub use device_banner::ADBDeviceBanner;
 pub use message_commands::{MessageCommand, MessageSubcommand};
@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, Metadata};
+use std::path::{Path, PathBuf};
+
+use md5::{Digest, Md5};
+
+use crate::dir_transfer::{local_file_type, make_remote_dir, push_local_file, remove_remote_path};
+use crate::models::{
+    AdbDirEntry, AdbFileType, DirSyncChange, DirSyncChangeKind, DirSyncOptions, DirSyncReport,
+    PushOptions,
+};
+use crate::utils::shell_quote;
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// Maximum amount of files hashed by a single `md5sum` command, keeping command line short.
+const HASH_BATCH_SIZE: usize = 64;
+
+/// Make remote directory `destination` a copy of local directory `source`. See [`ADBDeviceExt::sync_dir`].
+pub(crate) fn sync_dir<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &Path,
+    destination: &str,
+    options: DirSyncOptions,
+) -> Result<DirSyncReport> {
+    if !fs::metadata(source)?.is_dir() {
+        return Err(RustADBError::NotADirectory(source.display().to_string()));
+    }
+
+    let destination = destination.trim_end_matches('/');
+    // Devices stat files without following symbolic links, unless their path ends with a `/`
+    let exists = device.stat(&format!("{destination}/"))?.file_type() == AdbFileType::Directory;
+
+    let mut sync = DirSync {
+        device,
+        options,
+        report: DirSyncReport::default(),
+    };
+    sync.sync_entries(source, destination, exists)?;
+    Ok(sync.report)
+}
+
+/// State of an ongoing directory sync.
+struct DirSync<'d, D: ?Sized> {
+    device: &'d mut D,
+    options: DirSyncOptions,
+    report: DirSyncReport,
+}
+
+impl<D: ADBDeviceExt + ?Sized> DirSync<'_, D> {
+    /// Sync entries of local directory `source` into remote `destination`, which is known to be missing unless it `exists`.
+    fn sync_entries(&mut self, source: &Path, destination: &str, exists: bool) -> Result<()> {
+        let mut entries = fs::read_dir(source)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut remote_entries = BTreeMap::new();
+        if exists {
+            for entry in self.device.list_dir(destination)? {
+                remote_entries.insert(entry.name.clone(), entry);
+            }
+        }
+
+        // Directories are created by device along with files they contain, empty ones have to be created explicitly
+        if entries.is_empty() && !exists {
+            self.apply(
+                DirSyncChangeKind::Added,
+                Some(source.to_path_buf()),
+                destination,
+                AdbFileType::Directory,
+                0,
+                |device| make_remote_dir(device, destination),
+            );
+        }
+
+        // Files of same size whose contents have to be compared
+        let mut candidates = Vec::new();
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let local_path = entry.path();
+            let remote_path = format!("{destination}/{name}");
+            let metadata = entry.metadata()?;
+            let remote = remote_entries.remove(&name);
+
+            let file_type = local_file_type(&metadata);
+            match file_type {
+                AdbFileType::Directory => {
+                    self.sync_dir_entry(&local_path, &remote_path, remote)?;
+                    continue;
+                }
+                AdbFileType::File | AdbFileType::Symlink => {}
+                _ => {
+                    log::warn!("skipping special file {}", local_path.display());
+                    continue;
+                }
+            }
+
+            let kind = match &remote {
+                None => Some(DirSyncChangeKind::Added),
+                Some(remote)
+                    if remote.file_type != file_type || remote.file_size != metadata.len() =>
+                {
+                    Some(DirSyncChangeKind::Modified)
+                }
+                // Devices do not keep modification time of links, whose target path has the same size here
+                Some(_) if file_type == AdbFileType::Symlink => None,
+                Some(_) if self.options.compare_hash => {
+                    candidates.push((local_path, remote_path, metadata));
+                    continue;
+                }
+                Some(remote) if remote.mod_time != local_mtime(&metadata) => {
+                    Some(DirSyncChangeKind::Modified)
+                }
+                Some(_) => None,
+            };
+
+            match kind {
+                Some(kind) => self.push(kind, local_path, &remote_path, &metadata, remote),
+                None => self.report.unchanged += 1,
+            }
+        }
+
+        for batch in candidates.chunks(HASH_BATCH_SIZE) {
+            let remote_hashes =
+                self.remote_hashes(batch.iter().map(|(_, path, _)| path.as_str()))?;
+            for (local_path, remote_path, metadata) in batch {
+                let local_hash = local_hash(local_path)?;
+                match remote_hashes.get(remote_path) {
+                    Some(remote_hash) if *remote_hash == local_hash => self.report.unchanged += 1,
+                    _ => self.push(
+                        DirSyncChangeKind::Modified,
+                        local_path.clone(),
+                        remote_path,
+                        metadata,
+                        None,
+                    ),
+                }
+            }
+        }
+
+        if self.options.delete_extraneous {
+            for (name, remote) in remote_entries {
+                let remote_path = format!("{destination}/{name}");
+                self.apply(
+                    DirSyncChangeKind::Extraneous,
+                    None,
+                    &remote_path,
+                    remote.file_type,
+                    remote.file_size,
+                    |device| remove_remote_path(device, &remote_path),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sync local directory `local_path` into `remote_path`, whose entry in its parent directory is `remote` if any.
+    fn sync_dir_entry(
+        &mut self,
+        local_path: &Path,
+        remote_path: &str,
+        remote: Option<AdbDirEntry>,
+    ) -> Result<()> {
+        let exists = match remote {
+            Some(remote) if remote.file_type == AdbFileType::Directory => true,
+            // Something else is in the way of directory
+            Some(_) => {
+                self.apply(
+                    DirSyncChangeKind::Modified,
+                    Some(local_path.to_path_buf()),
+                    remote_path,
+                    AdbFileType::Directory,
+                    0,
+                    |device| remove_remote_path(device, remote_path),
+                );
+                false
+            }
+            None => false,
+        };
+
+        self.sync_entries(local_path, remote_path, exists)
+    }
+
+    /// Push local file `local_path` to `remote_path`, replacing `remote` entry if it is a directory.
+    fn push(
+        &mut self,
+        kind: DirSyncChangeKind,
+        local_path: PathBuf,
+        remote_path: &str,
+        metadata: &Metadata,
+        remote: Option<AdbDirEntry>,
+    ) {
+        let replace_dir = remote.is_some_and(|remote| remote.file_type == AdbFileType::Directory);
+        self.apply(
+            kind,
+            Some(local_path.clone()),
+            remote_path,
+            local_file_type(metadata),
+            metadata.len(),
+            |device| {
+                if replace_dir {
+                    remove_remote_path(device, remote_path)?;
+                }
+                push_local_file(device, &local_path, remote_path, metadata)
+            },
+        );
+    }
+
+    /// Record a change of remote tree, making it with `change` unless running in dry-run mode.
+    fn apply<F: FnOnce(&mut D) -> Result<()>>(
+        &mut self,
+        kind: DirSyncChangeKind,
+        local_path: Option<PathBuf>,
+        remote_path: &str,
+        file_type: AdbFileType,
+        size: u64,
+        change: F,
+    ) {
+        let result = match self.options.dry_run {
+            true => Ok(()),
+            false => change(self.device),
+        };
+
+        self.report.changes.push(DirSyncChange {
+            kind,
+            local_path,
+            remote_path: remote_path.to_string(),
+            file_type,
+            size,
+            result,
+        });
+    }
+
+    /// MD5 hashes of remote files `paths`, in hexadecimal. Files which cannot be read are missing.
+    fn remote_hashes<'p, I: Iterator<Item = &'p str>>(
+        &mut self,
+        paths: I,
+    ) -> Result<HashMap<String, String>> {
+        let paths = paths.map(shell_quote).collect::<Vec<_>>();
+        let mut command = vec!["md5sum"];
+        command.extend(paths.iter().map(String::as_str));
+
+        let mut stdout = Vec::new();
+        self.device
+            .shell_command_with_status(&command, &mut stdout, &mut std::io::sink())?;
+
+        // Each line is made of a hash and a path, separated by two spaces
+        Ok(String::from_utf8_lossy(&stdout)
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, path)| (path.to_string(), hash.to_lowercase()))
+            .collect())
+    }
+}
+
+/// Modification time of a local file, in seconds since epoch.
+fn local_mtime(metadata: &Metadata) -> u64 {
+    PushOptions::from_metadata(metadata)
+        .mtime
+        .map_or(0, u64::from)
+}
+
+/// MD5 hash of local file `path`, in hexadecimal.
+fn local_hash(path: &Path) -> Result<String> {
+    let mut hasher = Md5::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sync_dir() {
+    use crate::FakeADBDevice;
+
+    let private_key_path = crate::fake_device::write_test_private_key("dir_sync");
+    let source = private_key_path.with_file_name("source");
+    fs::create_dir_all(source.join("lib")).expect("cannot create directory");
+    fs::write(source.join("lib/libfoo.so"), b"foo").expect("cannot write file");
+    fs::write(source.join("app.bin"), b"app v1").expect("cannot write file");
+
+    let fake_device = FakeADBDevice::new();
+    fake_device
+        .add_file("/data/local/tmp/out/stale.txt", b"stale")
+        .expect("cannot add file");
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let changes = |report: &DirSyncReport| {
+        report
+            .changes
+            .iter()
+            .map(|change| (change.kind, change.remote_path.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    // Dry run reports changes without making them
+    let options = DirSyncOptions {
+        delete_extraneous: true,
+        dry_run: true,
+        ..Default::default()
+    };
+    let report = device
+        .sync_dir(&source, "/data/local/tmp/out", options)
+        .expect("cannot sync directory");
+    let expected = [
+        (DirSyncChangeKind::Added, "/data/local/tmp/out/app.bin"),
+        (
+            DirSyncChangeKind::Added,
+            "/data/local/tmp/out/lib/libfoo.so",
+        ),
+        (
+            DirSyncChangeKind::Extraneous,
+            "/data/local/tmp/out/stale.txt",
+        ),
+    ]
+    .map(|(kind, path)| (kind, path.to_string()));
+    assert_eq!(changes(&report), expected);
+    assert_eq!(
+        fake_device.file("/data/local/tmp/out/app.bin").ok(),
+        Some(None)
+    );
+
+    let options = DirSyncOptions {
+        dry_run: false,
+        ..options
+    };
+    let report = device
+        .sync_dir(&source, "/data/local/tmp/out", options)
+        .expect("cannot sync directory");
+    assert_eq!(changes(&report), expected);
+    assert_eq!(report.failures().count(), 0);
+    assert_eq!(
+        fake_device.file("/data/local/tmp/out/stale.txt").ok(),
+        Some(None)
+    );
+
+    // Only modified files are pushed again
+    fs::write(source.join("app.bin"), b"app v2!").expect("cannot write file");
+    let report = device
+        .sync_dir(&source, "/data/local/tmp/out", options)
+        .expect("cannot sync directory");
+    assert_eq!(
+        changes(&report),
+        [(
+            DirSyncChangeKind::Modified,
+            "/data/local/tmp/out/app.bin".to_string()
+        )]
+    );
+    assert_eq!(report.unchanged, 1);
+
+    // Identical contents are not pushed again when comparing hashes, whatever their modification time
+    File::options()
+        .write(true)
+        .open(source.join("lib/libfoo.so"))
+        .and_then(|file| file.set_modified(std::time::UNIX_EPOCH))
+        .expect("cannot set modification time");
+    let options = DirSyncOptions {
+        compare_hash: true,
+        ..options
+    };
+    let report = device
+        .sync_dir(&source, "/data/local/tmp/out", options)
+        .expect("cannot sync directory");
+    assert!(report.changes.is_empty(), "{:?}", report.changes);
+    assert_eq!(report.unchanged, 2);
+}
@@ -0,0 +1,380 @@
+use std::fs::{self, File, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::models::{AdbDirEntry, AdbFileType, FileTransfer, PushOptions};
+use crate::utils::shell_quote;
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// Push local directory `source` to `destination` on device. See [`ADBDeviceExt::push_dir`].
+pub(crate) fn push_dir<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &Path,
+    destination: &str,
+) -> Result<Vec<FileTransfer>> {
+    if !fs::metadata(source)?.is_dir() {
+        return Err(RustADBError::NotADirectory(source.display().to_string()));
+    }
+
+    let mut transfers = Vec::new();
+    for entry in local_entries(source, destination.trim_end_matches('/'))? {
+        let result = match entry.file_type {
+            // Directories are created by device along with files they contain, empty ones have to be created explicitly
+            AdbFileType::Directory => make_remote_dir(device, &entry.remote_path),
+            _ => push_local_file(
+                device,
+                &entry.local_path,
+                &entry.remote_path,
+                &entry.metadata,
+            ),
+        };
+        transfers.push(entry.into_transfer(result));
+    }
+    Ok(transfers)
+}
+
+/// Pull directory `source` from device into local `destination`. See [`ADBDeviceExt::pull_dir`].
+pub(crate) fn pull_dir<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &str,
+    destination: &Path,
+) -> Result<Vec<FileTransfer>> {
+    let source = source.trim_end_matches('/');
+    // Devices stat files without following symbolic links, unless their path ends with a `/`
+    if device.stat(&format!("{source}/"))?.file_type() != AdbFileType::Directory {
+        return Err(RustADBError::NotADirectory(source.to_string()));
+    }
+
+    let mut transfers = Vec::new();
+    pull_entries(device, source, destination, &mut transfers)?;
+    Ok(transfers)
+}
+
+/// File, symbolic link or empty directory found in a local directory, to be pushed to device.
+pub(crate) struct LocalEntry {
+    pub(crate) local_path: PathBuf,
+    pub(crate) remote_path: String,
+    pub(crate) file_type: AdbFileType,
+    pub(crate) metadata: Metadata,
+}
+
+impl LocalEntry {
+    pub(crate) fn into_transfer(self, result: Result<()>) -> FileTransfer {
+        FileTransfer {
+            size: match self.file_type {
+                AdbFileType::Directory => 0,
+                _ => self.metadata.len(),
+            },
+            local_path: self.local_path,
+            remote_path: self.remote_path,
+            file_type: self.file_type,
+            result,
+        }
+    }
+}
+
+/// Entries of local directory `source` and of its subdirectories to push to `destination`, sorted by path.
+/// Special files are skipped.
+pub(crate) fn local_entries(source: &Path, destination: &str) -> Result<Vec<LocalEntry>> {
+    let mut local_entries = Vec::new();
+    add_local_entries(source, destination, &mut local_entries)?;
+    Ok(local_entries)
+}
+
+fn add_local_entries(
+    source: &Path,
+    destination: &str,
+    local_entries: &mut Vec<LocalEntry>,
+) -> Result<()> {
+    let mut entries = fs::read_dir(source)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    if entries.is_empty() {
+        local_entries.push(LocalEntry {
+            local_path: source.to_path_buf(),
+            remote_path: destination.to_string(),
+            file_type: AdbFileType::Directory,
+            metadata: fs::symlink_metadata(source)?,
+        });
+    }
+
+    for entry in entries {
+        let local_path = entry.path();
+        let remote_path = format!("{destination}/{}", entry.file_name().to_string_lossy());
+        let metadata = entry.metadata()?;
+
+        let file_type = local_file_type(&metadata);
+        match file_type {
+            AdbFileType::Directory => add_local_entries(&local_path, &remote_path, local_entries)?,
+            AdbFileType::File | AdbFileType::Symlink => local_entries.push(LocalEntry {
+                local_path,
+                remote_path,
+                file_type,
+                metadata,
+            }),
+            _ => log::warn!("skipping special file {}", local_path.display()),
+        }
+    }
+
+    Ok(())
+}
+
+fn pull_entries<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &str,
+    destination: &Path,
+    transfers: &mut Vec<FileTransfer>,
+) -> Result<()> {
+    fs::create_dir_all(destination)?;
+
+    let mut entries = device.list_dir(source)?;
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if entries.is_empty() {
+        transfers.push(FileTransfer {
+            local_path: destination.to_path_buf(),
+            remote_path: source.to_string(),
+            file_type: AdbFileType::Directory,
+            size: 0,
+            result: Ok(()),
+        });
+    }
+
+    for entry in entries {
+        let remote_path = format!("{source}/{}", entry.name);
+        let local_path = destination.join(&entry.name);
+
+        let result = match entry.file_type {
+            AdbFileType::Directory => {
+                pull_entries(device, &remote_path, &local_path, transfers)?;
+                continue;
+            }
+            AdbFileType::File | AdbFileType::Symlink => {
+                pull_file(device, &remote_path, &local_path, &entry)
+            }
+            _ => {
+                log::warn!("skipping special file {remote_path}");
+                continue;
+            }
+        };
+
+        transfers.push(FileTransfer {
+            local_path,
+            remote_path,
+            file_type: entry.file_type,
+            size: entry.file_size,
+            result,
+        });
+    }
+
+    Ok(())
+}
+
+/// Pull file `source` into `destination`, giving it mode and modification time of `entry`.
+///
+/// Symbolic links are followed by device, file they point to being pulled instead.
+fn pull_file<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    source: &str,
+    destination: &Path,
+    entry: &AdbDirEntry,
+) -> Result<()> {
+    let mut file = File::create(destination)?;
+    if let Err(e) = device.pull(&source, &mut file) {
+        drop(file);
+        let _ = fs::remove_file(destination);
+        return Err(e);
+    }
+
+    apply_entry_metadata(&file, entry)
+}
+
+/// Give pulled `file` mode and modification time of remote `entry` it was pulled from.
+pub(crate) fn apply_entry_metadata(file: &File, entry: &AdbDirEntry) -> Result<()> {
+    // Mode and modification time of a link are not the ones of the file it points to
+    if entry.file_type == AdbFileType::Symlink {
+        return Ok(());
+    }
+
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(entry.mod_time))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(entry.file_perm & 0o7777))?;
+    }
+
+    Ok(())
+}
+
+/// Type of a local file, given its metadata not following symbolic links.
+pub(crate) fn local_file_type(metadata: &Metadata) -> AdbFileType {
+    if metadata.is_dir() {
+        AdbFileType::Directory
+    } else if metadata.is_symlink() {
+        AdbFileType::Symlink
+    } else if metadata.is_file() {
+        AdbFileType::File
+    } else {
+        AdbFileType::Unknown
+    }
+}
+
+/// Push local file or symbolic link `local_path` to `remote_path`, keeping mode and modification time from its `metadata`.
+pub(crate) fn push_local_file<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    local_path: &Path,
+    remote_path: &str,
+    metadata: &Metadata,
+) -> Result<()> {
+    if metadata.is_symlink() {
+        let target = fs::read_link(local_path)?;
+        let target = target.to_string_lossy();
+        return device.push_with_options(
+            &mut target.as_bytes(),
+            &remote_path,
+            local_push_options(metadata),
+        );
+    }
+
+    let mut file = File::open(local_path)?;
+    device.push_with_options(&mut file, &remote_path, local_push_options(metadata))
+}
+
+/// Options to push a local file or symbolic link, whose contents is its target, keeping mode and modification time from its `metadata`.
+pub(crate) fn local_push_options(metadata: &Metadata) -> PushOptions {
+    match metadata.is_symlink() {
+        true => PushOptions {
+            mode: AdbFileType::Symlink.mode_bits() | 0o777,
+            ..PushOptions::from_metadata(metadata)
+        },
+        false => PushOptions::from_metadata(metadata),
+    }
+}
+
+/// Create directory `path` on device, along with its missing parents.
+pub(crate) fn make_remote_dir<D: ADBDeviceExt + ?Sized>(device: &mut D, path: &str) -> Result<()> {
+    run_remote_command(device, &["mkdir", "-p", &shell_quote(path)])
+}
+
+/// Remove file or directory `path` from device, along with everything it contains.
+pub(crate) fn remove_remote_path<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    path: &str,
+) -> Result<()> {
+    run_remote_command(device, &["rm", "-rf", &shell_quote(path)])
+}
+
+/// Run shell `command` on device, failing with its error output if it does not succeed.
+fn run_remote_command<D: ADBDeviceExt + ?Sized>(device: &mut D, command: &[&str]) -> Result<()> {
+    let mut stderr = Vec::new();
+    let status = device.shell_command_with_status(command, &mut std::io::sink(), &mut stderr)?;
+    check_remote_command(status, &stderr)
+}
+
+/// Check exit `status` of a shell command, failing with its error output `stderr` if it did not succeed.
+pub(crate) fn check_remote_command(status: Option<u8>, stderr: &[u8]) -> Result<()> {
+    match status {
+        None | Some(0) => Ok(()),
+        Some(_) => Err(RustADBError::ADBRequestFailed(
+            String::from_utf8_lossy(stderr).trim().to_string(),
+        )),
+    }
+}
+
+/// Create parent directory of remote file `path`, for devices which do not create it when a file is pushed.
+pub(crate) fn make_remote_parent_dir<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    path: &str,
+) -> Result<()> {
+    match path.rsplit_once('/') {
+        Some((parent, _)) if !parent.is_empty() => make_remote_dir(device, parent),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_push_pull_dir() {
+    use crate::FakeADBDevice;
+    use std::os::unix::fs::PermissionsExt;
+
+    let private_key_path = crate::fake_device::write_test_private_key("dir_transfer");
+    let directory = private_key_path.parent().expect("no parent directory");
+    let source = directory.join("source");
+    fs::create_dir_all(source.join("bin")).expect("cannot create directory");
+    fs::create_dir_all(source.join("empty")).expect("cannot create directory");
+    fs::write(source.join("bin/tool"), b"#!/bin/sh\n").expect("cannot write file");
+    fs::set_permissions(source.join("bin/tool"), fs::Permissions::from_mode(0o755))
+        .expect("cannot set permissions");
+    fs::write(source.join("data.txt"), b"data").expect("cannot write file");
+    let data = File::options()
+        .write(true)
+        .open(source.join("data.txt"))
+        .expect("cannot open file");
+    data.set_permissions(fs::Permissions::from_mode(0o600))
+        .expect("cannot set permissions");
+    data.set_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        .expect("cannot set modification time");
+    std::os::unix::fs::symlink("bin/tool", source.join("tool")).expect("cannot create link");
+
+    let fake_device = FakeADBDevice::new();
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let transfers = device
+        .push_dir(&source, "/data/local/tmp/tree/")
+        .expect("cannot push directory");
+    assert!(
+        transfers.iter().all(|transfer| transfer.result.is_ok()),
+        "{transfers:?}"
+    );
+    assert_eq!(
+        transfers
+            .iter()
+            .map(|transfer| (transfer.remote_path.as_str(), transfer.file_type))
+            .collect::<Vec<_>>(),
+        [
+            ("/data/local/tmp/tree/bin/tool", AdbFileType::File),
+            ("/data/local/tmp/tree/data.txt", AdbFileType::File),
+            ("/data/local/tmp/tree/empty", AdbFileType::Directory),
+            ("/data/local/tmp/tree/tool", AdbFileType::Symlink),
+        ]
+    );
+
+    let stat = device
+        .stat("/data/local/tmp/tree/data.txt")
+        .expect("cannot stat file");
+    assert_eq!((stat.file_perm, stat.mod_time), (0o100600, 1_700_000_000));
+    let stat = device
+        .stat("/data/local/tmp/tree/tool")
+        .expect("cannot stat link");
+    assert_eq!(stat.file_type(), AdbFileType::Symlink);
+
+    let destination = directory.join("destination");
+    let transfers = device
+        .pull_dir("/data/local/tmp/tree", &destination)
+        .expect("cannot pull directory");
+    assert!(
+        transfers.iter().all(|transfer| transfer.result.is_ok()),
+        "{transfers:?}"
+    );
+    assert_eq!(transfers.len(), 4);
+
+    let tool = fs::metadata(destination.join("bin/tool")).expect("cannot stat file");
+    assert_eq!(tool.permissions().mode(), 0o100755);
+    let data = fs::metadata(destination.join("data.txt")).expect("cannot stat file");
+    assert_eq!(
+        data.modified().expect("no modification time"),
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    );
+    // Link is followed
+    assert_eq!(
+        fs::read(destination.join("tool")).expect("cannot read file"),
+        b"#!/bin/sh\n"
+    );
+    assert!(destination.join("empty").is_dir());
+
+    assert!(matches!(
+        device.pull_dir("/data/local/tmp/tree/data.txt", &destination),
+        Err(RustADBError::NotADirectory(_))
+    ));
+}
@@ -120,6 +120,55 @@ pub enum RustADBError {
     /// An unknown transport has been provided
     #[error("unknown transport: {0}")]
     UnknownTransport(String),
+    /// Received request is not supported
+    #[error("unsupported request: {0}")]
+    UnsupportedRequest(String),
+    /// Host did not behave as in the session being replayed
+    #[error("replay mismatch: {0}")]
+    ReplayMismatch(String),
+    /// File does not contain a recorded session
+    #[error("invalid session file: {0}")]
+    InvalidSessionFile(String),
+    /// Pairing with a device failed
+    #[error("pairing error: {0}")]
+    PairingError(String),
+    /// TLS certificate of device differs from the one pinned in [`crate::KnownDevices`] store. Contains device identifier and new certificate fingerprint.
+    #[error("TLS certificate of device {0} changed, new fingerprint is {1}")]
+    DeviceCertificateChanged(String, String),
+    /// Path was expected to be a directory
+    #[error("{0} is not a directory")]
+    NotADirectory(String),
+    /// Transfer was cancelled through its [`crate::CancellationToken`]
+    #[error("transfer cancelled")]
+    TransferCancelled,
+    /// An APK archive could not be read
+    #[error(transparent)]
+    ZipError(#[from] zip::result::ZipError),
+    /// Data to install is not an APK
+    #[error("invalid APK: {0}")]
+    InvalidApk(String),
+    /// Package manager refused to install an app
+    #[error("install failed with {0}: {1}")]
+    InstallFailed(crate::InstallFailureReason, String),
+    /// An install session failed, and was abandoned
+    #[error("install session {0} failed: {1}")]
+    InstallSessionFailed(u32, String),
+    /// Package is not installed on device
+    #[error("package {0} not found")]
+    PackageNotFound(String),
+    /// A permission or app op could not be changed
+    #[error("cannot change permission ({0}): {1}")]
+    PermissionChangeFailed(crate::PermissionFailureReason, String),
+    /// Device did not finish booting in time. Contains last boot stage it reached
+    #[error("device did not finish booting in time, last stage reached: {0}")]
+    BootTimeout(crate::BootStage),
+    /// Device cannot be connected to again once its connection is lost
+    #[error("device cannot be reconnected")]
+    ReconnectUnsupported,
+    /// An asynchronous task could not run to completion
+    #[cfg(feature = "tokio")]
+    #[error(transparent)]
+    JoinError(#[from] tokio::task::JoinError),
 }
 
 impl<T> From<std::sync::PoisonError<T>> for RustADBError {
@@ -0,0 +1,609 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::fake_adb_transport::FakeADBTransport;
+use super::fake_connection::{FakeConnection, FakeLink};
+use super::fake_pairing::serve_pairing;
+use super::models::{FakeAuthMode, FakeFault};
+use crate::{ADBDeviceBanner, Result};
+
+/// A file stored on a [`FakeADBDevice`].
+#[derive(Clone, Debug)]
+pub(crate) struct FakeFile {
+    pub(crate) contents: Vec<u8>,
+    pub(crate) mode: u32,
+    pub(crate) mtime: u32,
+}
+
+/// Scripted result of a shell command run on a [`FakeADBDevice`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FakeShellResponse {
+    pub(crate) stdout: Vec<u8>,
+    pub(crate) stderr: Vec<u8>,
+    pub(crate) exit_code: u8,
+}
+
+/// A package manager install session created on a [`FakeADBDevice`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FakeInstallSession {
+    /// Names and contents of APKs written into session
+    pub(crate) apks: Vec<(String, Vec<u8>)>,
+    pub(crate) committed: bool,
+}
+
+/// Configuration of a [`FakeADBDevice`], copied by each connection when it starts.
+#[derive(Clone, Debug)]
+pub(crate) struct FakeDeviceConfig {
+    pub(crate) banner: ADBDeviceBanner,
+    pub(crate) auth_mode: FakeAuthMode,
+    pub(crate) tls: bool,
+    pub(crate) shell_responses: HashMap<String, FakeShellResponse>,
+    pub(crate) faults: Vec<FakeFault>,
+}
+
+/// Data of a [`FakeADBDevice`] shared with, and modified by, its connections.
+#[derive(Debug, Default)]
+pub(crate) struct FakeDeviceState {
+    pub(crate) files: Mutex<BTreeMap<String, FakeFile>>,
+    pub(crate) packages: Mutex<BTreeSet<String>>,
+    /// Install sessions which were not abandoned, by id
+    pub(crate) install_sessions: Mutex<BTreeMap<u32, FakeInstallSession>>,
+    pub(crate) services: Mutex<Vec<String>>,
+    /// Payloads of window size change packets sent to interactive shells
+    pub(crate) window_sizes: Mutex<Vec<String>>,
+    /// Public keys accepted during previous connections
+    pub(crate) authorized_keys: Mutex<Vec<String>>,
+    /// Certificate presented when upgrading connections to TLS, generated on first use
+    pub(crate) tls_certificate: Mutex<Option<FakeCertificate>>,
+    /// Times at which device got rebooted
+    pub(crate) reboots: Mutex<Vec<Instant>>,
+}
+
+impl FakeDeviceState {
+    pub(crate) fn tls_certificate(&self) -> Result<FakeCertificate> {
+        let mut tls_certificate = self.tls_certificate.lock()?;
+        if let Some(certificate) = tls_certificate.as_ref() {
+            return Ok(certificate.clone());
+        }
+
+        let certificate = FakeCertificate::generate()?;
+        *tls_certificate = Some(certificate.clone());
+        Ok(certificate)
+    }
+}
+
+/// Self-signed certificate of a [`FakeADBDevice`], DER encoded.
+#[derive(Clone, Debug)]
+pub(crate) struct FakeCertificate {
+    pub(crate) certificate: Vec<u8>,
+    pub(crate) private_key: Vec<u8>,
+}
+
+impl FakeCertificate {
+    fn generate() -> Result<Self> {
+        let certified_key = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        Ok(Self {
+            certificate: certified_key.cert.der().to_vec(),
+            private_key: certified_key.key_pair.serialize_der(),
+        })
+    }
+}
+
+/// An in-process fake `adbd`, implementing the device side of the ADB message protocol.
+///
+/// Hosts can reach it over a loopback TCP socket (see [`FakeADBDevice::listen`]) or through an in-memory [`FakeADBTransport`]
+/// (see [`FakeADBDevice::transport`]), allowing to test code talking to devices without any phone attached.
+///
+/// It supports scripted shell responses, a virtual filesystem served by the `sync:` service, package install and uninstall,
+/// authentication and TLS modes, and fault injection.
+#[derive(Clone, Debug)]
+pub struct FakeADBDevice {
+    config: FakeDeviceConfig,
+    state: Arc<FakeDeviceState>,
+}
+
+impl Default for FakeADBDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeADBDevice {
+    /// Instantiate a new [`FakeADBDevice`], accepting any host without authentication.
+    pub fn new() -> Self {
+        let banner = ADBDeviceBanner {
+            system_type: "device".to_string(),
+            properties: HashMap::from([
+                ("ro.product.name".to_string(), "fake".to_string()),
+                ("ro.product.model".to_string(), "FakeADBDevice".to_string()),
+                ("ro.product.device".to_string(), "fake".to_string()),
+            ]),
+            features: [
+                "cmd",
+                "shell_v2",
+                "ls_v2",
+                "fixed_push_mkdir",
+                "abb_exec",
+                "sendrecv_v2",
+                "sendrecv_v2_brotli",
+                "sendrecv_v2_lz4",
+                "sendrecv_v2_zstd",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        };
+
+        Self {
+            config: FakeDeviceConfig {
+                banner,
+                auth_mode: FakeAuthMode::default(),
+                tls: false,
+                shell_responses: HashMap::new(),
+                faults: Vec::new(),
+            },
+            state: Arc::default(),
+        }
+    }
+
+    /// Set banner sent by device in its `CNXN` messages.
+    pub fn set_banner(&mut self, banner: ADBDeviceBanner) {
+        self.config.banner = banner;
+    }
+
+    /// Set how hosts connecting over TCP get authenticated.
+    pub fn set_auth_mode(&mut self, auth_mode: FakeAuthMode) {
+        self.config.auth_mode = auth_mode;
+    }
+
+    /// Require hosts connecting over TCP to upgrade their connection to TLS. Host is then authenticated by the TLS handshake.
+    pub fn set_tls(&mut self, enabled: bool) {
+        self.config.tls = enabled;
+    }
+
+    /// Replace TLS certificate of device by a new one, as a factory reset would.
+    pub fn renew_tls_certificate(&self) -> Result<()> {
+        *self.state.tls_certificate.lock()? = Some(FakeCertificate::generate()?);
+        Ok(())
+    }
+
+    /// Reply `output` when `command` is run through `shell:` or `exec:` services.
+    ///
+    /// Unknown commands output a `not found` error, as a real shell would.
+    pub fn add_shell_response(&mut self, command: &str, output: &[u8]) {
+        self.add_shell_response_with_status(command, output, &[], 0);
+    }
+
+    /// Reply `stdout` and `stderr` when `command` is run, exiting with `exit_code`.
+    ///
+    /// Both streams are sent separately along with exit code to hosts using shell protocol v2, and concatenated otherwise.
+    pub fn add_shell_response_with_status(
+        &mut self,
+        command: &str,
+        stdout: &[u8],
+        stderr: &[u8],
+        exit_code: u8,
+    ) {
+        let response = FakeShellResponse {
+            stdout: stdout.to_vec(),
+            stderr: stderr.to_vec(),
+            exit_code,
+        };
+        self.config
+            .shell_responses
+            .insert(command.to_string(), response);
+    }
+
+    /// Inject a fault in next connections.
+    pub fn add_fault(&mut self, fault: FakeFault) {
+        self.config.faults.push(fault);
+    }
+
+    /// Banner sent by device in its `CNXN` messages.
+    pub(crate) fn banner(&self) -> &ADBDeviceBanner {
+        &self.config.banner
+    }
+
+    /// Store a file on device. Its parent directories implicitly exist.
+    pub fn add_file(&self, path: &str, contents: &[u8]) -> Result<()> {
+        let file = FakeFile {
+            contents: contents.to_vec(),
+            mode: 0o100644,
+            mtime: 0,
+        };
+        self.state.files.lock()?.insert(path.to_string(), file);
+        Ok(())
+    }
+
+    /// Get content of file stored on device at `path`, if any.
+    pub fn file(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .state
+            .files
+            .lock()?
+            .get(path)
+            .map(|file| file.contents.clone()))
+    }
+
+    /// Mark `package` as installed, allowing to uninstall it.
+    pub fn add_package(&self, package: &str) -> Result<()> {
+        self.state.packages.lock()?.insert(package.to_string());
+        Ok(())
+    }
+
+    /// Packages currently installed on device.
+    pub fn packages(&self) -> Result<Vec<String>> {
+        Ok(self.state.packages.lock()?.iter().cloned().collect())
+    }
+
+    /// Names of APKs written into each install session not abandoned yet, along with whether session got committed.
+    pub fn install_sessions(&self) -> Result<Vec<(Vec<String>, bool)>> {
+        Ok(self
+            .state
+            .install_sessions
+            .lock()?
+            .values()
+            .map(|session| {
+                let names = session.apks.iter().map(|(name, _)| name.clone()).collect();
+                (names, session.committed)
+            })
+            .collect())
+    }
+
+    /// Every service opened by hosts so far (e.g. `shell:ls`, `sync:`), in order.
+    pub fn opened_services(&self) -> Result<Vec<String>> {
+        Ok(self.state.services.lock()?.clone())
+    }
+
+    /// Terminal sizes sent by hosts to interactive shells using shell protocol v2 (e.g. `24x80,0x0`), in order.
+    pub fn window_sizes(&self) -> Result<Vec<String>> {
+        Ok(self.state.window_sizes.lock()?.clone())
+    }
+
+    /// Start accepting hosts on a loopback TCP socket, returning its address. Device keeps listening until process ends.
+    ///
+    /// Configuration changes made afterwards are not taken into account by this socket.
+    pub fn listen(&self) -> Result<SocketAddr> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let address = listener.local_addr()?;
+
+        let config = self.config.clone();
+        let state = self.state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let connection = FakeConnection::new(
+                    FakeLink::Tcp(stream),
+                    config.clone(),
+                    state.clone(),
+                    false,
+                );
+                std::thread::spawn(move || connection.run());
+            }
+        });
+
+        Ok(address)
+    }
+
+    /// Start accepting pairing requests on a loopback TCP socket, returning its address, as a device showing
+    /// its "Pair device with pairing code" dialog would.
+    ///
+    /// Hosts knowing `code` get their public key authorized, then connect without being prompted.
+    pub fn listen_pairing(&self, code: &str) -> Result<SocketAddr> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let address = listener.local_addr()?;
+
+        let code = code.to_string();
+        let state = self.state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if let Err(e) = serve_pairing(stream, &code, &state) {
+                    log::debug!("pairing failed: {e}");
+                }
+            }
+        });
+
+        Ok(address)
+    }
+
+    /// Get a new in-memory transport connected to this device, already authenticated.
+    pub fn transport(&self) -> FakeADBTransport {
+        let (host_sender, device_receiver) = mpsc::channel();
+        let (device_sender, host_receiver) = mpsc::channel();
+
+        let link = FakeLink::Memory {
+            incoming: device_receiver,
+            outgoing: Some(device_sender),
+        };
+        let connection = FakeConnection::new(link, self.config.clone(), self.state.clone(), true);
+        std::thread::spawn(move || connection.run());
+
+        FakeADBTransport::new(host_receiver, host_sender)
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn write_test_private_key(name: &str) -> std::path::PathBuf {
+    // Each test gets its own directory, as certificates of devices get pinned next to private key
+    let directory = std::env::temp_dir().join(format!("adb_client_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).expect("cannot create directory");
+    let path = directory.join("adbkey");
+    std::fs::write(&path, crate::device::TEST_PRIVATE_KEY).expect("cannot write private key");
+    path
+}
+
+/// Connect an [`crate::ADBTcpDevice`] to `fake_device`, authenticating with test private key.
+#[cfg(test)]
+pub(crate) fn connected_tcp_device(fake_device: &FakeADBDevice) -> crate::ADBTcpDevice {
+    let address = fake_device.listen().expect("cannot listen");
+    // Devices listen on distinct ports, which keeps their keys and pinned certificates apart
+    let private_key_path = write_test_private_key(&format!("device_{}", address.port()));
+    crate::ADBTcpDevice::new_with_custom_private_key(address, private_key_path)
+        .expect("cannot connect to fake device")
+}
+
+/// Connect an [`crate::AsyncADBTcpDevice`] to `fake_device`, authenticating with test private key.
+#[cfg(all(test, feature = "tokio"))]
+pub(crate) async fn connected_async_tcp_device(
+    fake_device: &FakeADBDevice,
+) -> crate::AsyncADBTcpDevice {
+    let address = fake_device.listen().expect("cannot listen");
+    let private_key_path = write_test_private_key(&format!("async_device_{}", address.port()));
+    crate::AsyncADBTcpDevice::new_with_custom_private_key(address, private_key_path)
+        .await
+        .expect("cannot connect to fake device")
+}
+
+#[test]
+fn test_fake_device_authentication() {
+    use crate::{ADBDeviceExt, ADBTcpDevice, device::ADBRsaKey};
+
+    let private_key_path = write_test_private_key("fake_device_authentication");
+    let public_key = ADBRsaKey::new_from_pkcs8(crate::device::TEST_PRIVATE_KEY)
+        .and_then(|key| key.android_pubkey_encode())
+        .expect("cannot encode public key");
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.set_auth_mode(FakeAuthMode::AuthorizedKeys(vec![public_key]));
+    fake_device.add_shell_response("getprop ro.product.model", b"Pixel 9\n");
+    let address = fake_device.listen().expect("cannot listen");
+
+    let mut device = ADBTcpDevice::new_with_custom_private_key(address, private_key_path)
+        .expect("cannot connect to fake device");
+    assert_eq!(
+        device.banner().and_then(|b| b.model()),
+        Some("FakeADBDevice")
+    );
+
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut output)
+        .expect("cannot run shell command");
+    assert_eq!(output, b"Pixel 9\n");
+
+    // An unknown key is refused
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.set_auth_mode(FakeAuthMode::AuthorizedKeys(vec![]));
+    let address = fake_device.listen().expect("cannot listen");
+    let private_key_path = write_test_private_key("fake_device_authentication");
+    assert!(ADBTcpDevice::new_with_custom_private_key(address, private_key_path).is_err());
+}
+
+#[test]
+fn test_fake_device_tls_sync() {
+    use crate::ADBDeviceExt;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.set_tls(true);
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+    let mut device = connected_tcp_device(&fake_device);
+
+    let contents = vec![42; 200_000];
+    device
+        .push(&mut contents.as_slice(), &"/sdcard/data.bin")
+        .expect("cannot push file");
+    assert_eq!(
+        fake_device
+            .file("/sdcard/data.bin")
+            .expect("cannot get file"),
+        Some(contents.clone())
+    );
+
+    let stat = device.stat("/sdcard/data.bin").expect("cannot stat file");
+    assert_eq!(stat.file_size, 200_000);
+    assert_eq!(stat.file_perm, 0o100644);
+
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/hello.txt", &mut pulled)
+        .expect("cannot pull file");
+    assert_eq!(pulled, b"hello");
+}
+
+#[test]
+fn test_fake_device_shell_v2() {
+    use crate::ADBDeviceExt;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response_with_status(
+        "ls /data",
+        b"",
+        b"ls: /data: Permission denied\n",
+        1,
+    );
+    let mut device = connected_tcp_device(&fake_device);
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let exit_code = device
+        .shell_command_with_status(&["ls", "/data"], &mut stdout, &mut stderr)
+        .expect("cannot run shell command");
+    assert_eq!(exit_code, Some(1));
+    assert!(stdout.is_empty());
+    assert_eq!(stderr, b"ls: /data: Permission denied\n");
+    assert_eq!(
+        fake_device.opened_services().expect("cannot get services"),
+        ["shell,v2,raw:ls /data"]
+    );
+
+    // Devices without shell v2 only provide merged output
+    let mut banner = fake_device.banner().clone();
+    banner.features.retain(|feature| feature != "shell_v2");
+    fake_device.set_banner(banner);
+    let mut device = connected_tcp_device(&fake_device);
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let exit_code = device
+        .shell_command_with_status(&["ls", "/data"], &mut stdout, &mut stderr)
+        .expect("cannot run shell command");
+    assert_eq!(exit_code, None);
+    assert_eq!(stdout, b"ls: /data: Permission denied\n");
+}
+
+#[test]
+fn test_fake_device_sync_v2() {
+    use crate::{ADBDeviceExt, SyncCompression};
+
+    let contents = b"compressed ".repeat(20_000);
+    // Each compression, then no compression at all, then legacy sync requests
+    for features in [
+        &["sendrecv_v2", "sendrecv_v2_brotli"][..],
+        &["sendrecv_v2", "sendrecv_v2_lz4"],
+        &["sendrecv_v2", "sendrecv_v2_zstd"],
+        &["sendrecv_v2"],
+        &[],
+    ] {
+        let mut fake_device = FakeADBDevice::new();
+        let mut banner = fake_device.banner().clone();
+        banner
+            .features
+            .retain(|feature| !feature.starts_with("sendrecv_v2"));
+        banner
+            .features
+            .extend(features.iter().map(|feature| feature.to_string()));
+        fake_device.set_banner(banner);
+        let mut device = connected_tcp_device(&fake_device);
+        // Zstd is used only when requested, and when device supports it
+        device.set_sync_compression(Some(SyncCompression::Zstd));
+        device
+            .push(&mut contents.as_slice(), &"/sdcard/data.txt")
+            .expect("cannot push file");
+        assert_eq!(
+            fake_device
+                .file("/sdcard/data.txt")
+                .expect("cannot get file")
+                .as_ref(),
+            Some(&contents),
+            "{features:?}"
+        );
+
+        let mut pulled = Vec::new();
+        device
+            .pull(&"/sdcard/data.txt", &mut pulled)
+            .expect("cannot pull file");
+        assert_eq!(pulled, contents, "{features:?}");
+    }
+}
+
+#[test]
+fn test_fake_device_memory_transport() {
+    use crate::{ADBDeviceExt, ADBMultiplexer, FakeFault, device::ADBMessageDevice};
+    use std::io::Read;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_fault(FakeFault::RefuseService("reboot:".to_string()));
+    fake_device
+        .add_package("com.example.app")
+        .expect("cannot add package");
+
+    let mut device = ADBMessageDevice::new(fake_device.transport());
+    device
+        .uninstall("com.example.app")
+        .expect("cannot uninstall package");
+    let mut device = ADBMessageDevice::new(fake_device.transport());
+    assert!(device.uninstall("com.example.app").is_err());
+    assert!(
+        fake_device
+            .packages()
+            .expect("cannot get packages")
+            .is_empty()
+    );
+
+    let multiplexer = ADBMultiplexer::new(fake_device.transport());
+    assert!(multiplexer.open_stream("reboot:").is_err());
+    let mut stream = multiplexer
+        .open_stream("shell:unknown")
+        .expect("cannot open stream");
+    let mut output = String::new();
+    stream
+        .read_to_string(&mut output)
+        .expect("cannot read stream");
+    assert_eq!(
+        output,
+        "/system/bin/sh: unknown: inaccessible or not found\n"
+    );
+
+    assert_eq!(
+        fake_device
+            .opened_services()
+            .expect("cannot get opened services"),
+        [
+            "exec:cmd package 'uninstall' 'com.example.app'",
+            "exec:cmd package 'uninstall' 'com.example.app'",
+            "reboot:",
+            "shell:unknown"
+        ]
+    );
+}
+
+#[test]
+fn test_fake_device_pairing() {
+    use super::fake_pairing::FAKE_DEVICE_GUID;
+    use crate::{ADBDeviceExt, ADBTcpDevice};
+
+    let private_key_path = write_test_private_key("fake_device_pairing");
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.set_auth_mode(FakeAuthMode::AuthorizedKeys(vec![]));
+    fake_device.add_shell_response("getprop ro.product.model", b"Pixel 9\n");
+    let pairing_address = fake_device
+        .listen_pairing("482916")
+        .expect("cannot listen for pairing");
+    let address = fake_device.listen().expect("cannot listen");
+
+    // Host is not known yet
+    assert!(ADBTcpDevice::new_with_custom_private_key(address, private_key_path.clone()).is_err());
+
+    // A wrong code is refused
+    assert!(
+        ADBTcpDevice::pair_with_custom_private_key(
+            pairing_address,
+            "000000",
+            private_key_path.clone()
+        )
+        .is_err()
+    );
+
+    let guid = ADBTcpDevice::pair_with_custom_private_key(
+        pairing_address,
+        "482916",
+        private_key_path.clone(),
+    )
+    .expect("cannot pair with fake device");
+    assert_eq!(guid, FAKE_DEVICE_GUID);
+
+    let mut device = ADBTcpDevice::new_with_custom_private_key(address, private_key_path)
+        .expect("cannot connect to paired device");
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut output)
+        .expect("cannot run shell command");
+    assert_eq!(output, b"Pixel 9\n");
+}
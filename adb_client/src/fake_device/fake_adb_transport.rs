@@ -0,0 +1,81 @@
+use std::io::ErrorKind;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::device::{ADBTransportMessage, ADBTransportMessageHeader};
+use crate::{ADBMessageTransport, ADBTransport, Result, RustADBError};
+
+/// An in-memory [`ADBMessageTransport`] to a [`crate::FakeADBDevice`], obtained with [`crate::FakeADBDevice::transport`].
+///
+/// Transport is already connected and authenticated: there is no need to send a `CNXN` message before opening services.
+#[derive(Clone, Debug)]
+pub struct FakeADBTransport {
+    incoming: Arc<Mutex<Receiver<ADBTransportMessage>>>,
+    outgoing: Arc<Mutex<Option<Sender<ADBTransportMessage>>>>,
+}
+
+impl FakeADBTransport {
+    pub(crate) fn new(
+        incoming: Receiver<ADBTransportMessage>,
+        outgoing: Sender<ADBTransportMessage>,
+    ) -> Self {
+        Self {
+            incoming: Arc::new(Mutex::new(incoming)),
+            outgoing: Arc::new(Mutex::new(Some(outgoing))),
+        }
+    }
+}
+
+impl ADBTransport for FakeADBTransport {
+    fn connect(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<()> {
+        // Fake device stops serving this connection once it cannot receive messages anymore
+        self.outgoing.lock()?.take();
+        Ok(())
+    }
+}
+
+impl ADBMessageTransport for FakeADBTransport {
+    fn read_message_with_timeout(&mut self, read_timeout: Duration) -> Result<ADBTransportMessage> {
+        let message = self
+            .incoming
+            .lock()?
+            .recv_timeout(read_timeout)
+            .map_err(|e| match e {
+                RecvTimeoutError::Timeout => {
+                    std::io::Error::new(ErrorKind::TimedOut, "no message received from device")
+                }
+                RecvTimeoutError::Disconnected => {
+                    std::io::Error::new(ErrorKind::ConnectionAborted, "device closed connection")
+                }
+            })?;
+
+        if !message.check_message_integrity() {
+            return Err(RustADBError::InvalidIntegrity(
+                ADBTransportMessageHeader::compute_crc32(message.payload()),
+                message.header().data_crc32(),
+            ));
+        }
+
+        Ok(message)
+    }
+
+    fn write_message_with_timeout(
+        &mut self,
+        message: ADBTransportMessage,
+        _write_timeout: Duration,
+    ) -> Result<()> {
+        self.outgoing
+            .lock()?
+            .as_ref()
+            .ok_or_else(|| std::io::Error::from(ErrorKind::NotConnected))?
+            .send(message)
+            .map_err(|_| std::io::Error::new(ErrorKind::BrokenPipe, "device closed connection"))?;
+
+        Ok(())
+    }
+}
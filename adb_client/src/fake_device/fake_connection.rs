@@ -0,0 +1,428 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Instant;
+
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use rustls_pki_types::PrivatePkcs8KeyDer;
+
+use super::fake_adb_device::{FakeCertificate, FakeDeviceConfig, FakeDeviceState};
+use super::fake_services::{FakeService, FakeServiceOutput, open_service};
+use super::models::{FakeAuthMode, FakeFault};
+use crate::device::adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN};
+use crate::device::{
+    ADBTransportMessage, ADBTransportMessageHeader, MessageCommand, android_pubkey_decode,
+    verify_signature,
+};
+use crate::{Result, RustADBError};
+
+/// Protocol version advertised in `CNXN` messages
+const PROTOCOL_VERSION: u32 = 0x01000000;
+/// Maximum payload size advertised in `CNXN` messages
+const MAX_PAYLOAD: u32 = 1024 * 1024;
+/// Version of TLS upgrade advertised in `STLS` messages
+const STLS_VERSION: u32 = 0x01000000;
+/// Length of tokens that hosts have to sign
+const AUTH_TOKEN_LENGTH: usize = 20;
+
+/// Link between a fake device and a host.
+pub(crate) enum FakeLink {
+    Tcp(TcpStream),
+    Tls(Box<StreamOwned<ServerConnection, TcpStream>>),
+    Memory {
+        incoming: Receiver<ADBTransportMessage>,
+        outgoing: Option<Sender<ADBTransportMessage>>,
+    },
+}
+
+fn read_message_from(reader: &mut dyn Read) -> Result<ADBTransportMessage> {
+    let mut header = [0; 24];
+    reader.read_exact(&mut header)?;
+    let header = ADBTransportMessageHeader::try_from(header)?;
+
+    let mut payload = vec![0; header.data_length() as usize];
+    reader.read_exact(&mut payload)?;
+
+    Ok(ADBTransportMessage::from_header_and_payload(
+        header, payload,
+    ))
+}
+
+fn write_message_to(writer: &mut dyn Write, message: ADBTransportMessage) -> Result<()> {
+    let mut bytes = message.header().as_bytes()?;
+    bytes.extend_from_slice(message.payload());
+    writer.write_all(&bytes)?;
+    Ok(writer.flush()?)
+}
+
+impl FakeLink {
+    fn read_message(&mut self) -> Result<ADBTransportMessage> {
+        match self {
+            FakeLink::Tcp(stream) => read_message_from(stream),
+            FakeLink::Tls(stream) => read_message_from(stream.as_mut()),
+            FakeLink::Memory { incoming, .. } => incoming
+                .recv()
+                .map_err(|_| std::io::Error::from(ErrorKind::ConnectionAborted).into()),
+        }
+    }
+
+    fn write_message(&mut self, message: ADBTransportMessage) -> Result<()> {
+        match self {
+            FakeLink::Tcp(stream) => write_message_to(stream, message),
+            FakeLink::Tls(stream) => write_message_to(stream.as_mut(), message),
+            FakeLink::Memory { outgoing, .. } => outgoing
+                .as_ref()
+                .ok_or_else(|| std::io::Error::from(ErrorKind::NotConnected))?
+                .send(message)
+                .map_err(|_| std::io::Error::from(ErrorKind::BrokenPipe).into()),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        match self {
+            FakeLink::Tcp(stream) => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            FakeLink::Tls(stream) => {
+                let _ = stream.sock.shutdown(Shutdown::Both);
+            }
+            FakeLink::Memory { outgoing, .. } => {
+                outgoing.take();
+            }
+        }
+    }
+
+    /// Perform TLS handshake as a server, using given self-signed certificate.
+    fn upgrade_to_tls(&mut self, certificate: FakeCertificate) -> Result<()> {
+        let FakeLink::Tcp(stream) = self else {
+            return Err(RustADBError::UpgradeError(
+                "only TCP connections can be upgraded".into(),
+            ));
+        };
+
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![certificate.certificate.into()],
+                PrivatePkcs8KeyDer::from(certificate.private_key).into(),
+            )?;
+
+        let connection = ServerConnection::new(Arc::new(config))?;
+        let mut tls_stream = StreamOwned::new(connection, stream.try_clone()?);
+        while tls_stream.conn.is_handshaking() {
+            tls_stream.conn.complete_io(&mut tls_stream.sock)?;
+        }
+
+        *self = FakeLink::Tls(Box::new(tls_stream));
+        Ok(())
+    }
+}
+
+/// A stream opened by host on a fake device.
+struct FakeStream {
+    host_id: u32,
+    service: Box<dyn FakeService>,
+    /// Chunks waiting to be sent in `WRTE` messages
+    pending: VecDeque<Vec<u8>>,
+    /// Whether last `WRTE` message has not been acknowledged yet by host
+    awaiting_ack: bool,
+    /// Whether stream has to be closed once every pending chunk is sent
+    closing: bool,
+}
+
+/// Device side of a single connection to a [`crate::FakeADBDevice`].
+pub(crate) struct FakeConnection {
+    link: FakeLink,
+    config: FakeDeviceConfig,
+    state: Arc<FakeDeviceState>,
+    connected: bool,
+    token: Vec<u8>,
+    streams: HashMap<u32, FakeStream>,
+    next_stream_id: u32,
+    sent_messages: usize,
+}
+
+impl FakeConnection {
+    /// Create a connection over `link`. If `connected` is set, host does not have to send `CNXN` first.
+    pub(crate) fn new(
+        link: FakeLink,
+        config: FakeDeviceConfig,
+        state: Arc<FakeDeviceState>,
+        connected: bool,
+    ) -> Self {
+        Self {
+            link,
+            config,
+            state,
+            connected,
+            token: Vec::new(),
+            streams: HashMap::new(),
+            next_stream_id: 1,
+            sent_messages: 0,
+        }
+    }
+
+    /// Serve host until connection ends.
+    pub(crate) fn run(mut self) {
+        if self.is_booting().unwrap_or_default() {
+            log::debug!("fake device is still booting");
+            self.link.shutdown();
+            return;
+        }
+
+        loop {
+            let result = self
+                .link
+                .read_message()
+                .and_then(|message| self.handle_message(message));
+
+            match result {
+                Ok(true) => continue,
+                Ok(false) => log::debug!("fake device closed connection"),
+                Err(e) => log::debug!("fake device connection ended: {e}"),
+            }
+
+            self.link.shutdown();
+            return;
+        }
+    }
+
+    /// Whether device did not come back yet from its last reboot.
+    fn is_booting(&self) -> Result<bool> {
+        let Some(rebooted_at) = self.state.reboots.lock()?.last().copied() else {
+            return Ok(false);
+        };
+
+        Ok(self.config.faults.iter().any(
+            |fault| matches!(fault, FakeFault::BootDelay(delay) if rebooted_at.elapsed() < *delay),
+        ))
+    }
+
+    /// Handle a message received from host. Returns whether connection has to be kept open.
+    fn handle_message(&mut self, message: ADBTransportMessage) -> Result<bool> {
+        let header = message.header();
+        let (command, arg0, arg1) = (header.command(), header.arg0(), header.arg1());
+
+        match command {
+            MessageCommand::Cnxn => self.handle_connect()?,
+            MessageCommand::Stls => {
+                self.link.upgrade_to_tls(self.state.tls_certificate()?)?;
+                // Host got authenticated by TLS handshake
+                self.accept()?;
+            }
+            MessageCommand::Auth => return self.handle_auth(arg0, message.payload()),
+            _ if !self.connected => {
+                log::debug!("fake device ignored {command} received before authentication");
+            }
+            MessageCommand::Open => return self.open_stream(arg0, message.payload()),
+            MessageCommand::Write => {
+                self.send(MessageCommand::Okay, arg1, arg0, &[])?;
+                if let Some(stream) = self.streams.get_mut(&arg1) {
+                    let output = stream.service.on_data(message.payload())?;
+                    self.push_output(arg1, output)?;
+                }
+            }
+            MessageCommand::Okay => {
+                if let Some(stream) = self.streams.get_mut(&arg1) {
+                    stream.awaiting_ack = false;
+                    self.flush_stream(arg1)?;
+                }
+            }
+            MessageCommand::Clse => {
+                // Like adbd, acknowledge closing of streams still opened on device side
+                if let Some(stream) = self.streams.remove(&arg1) {
+                    self.send(MessageCommand::Clse, arg1, stream.host_id, &[])?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn handle_connect(&mut self) -> Result<()> {
+        if self.connected {
+            return self.accept();
+        }
+        if self.config.tls {
+            return self.send(MessageCommand::Stls, STLS_VERSION, 0, &[]);
+        }
+        if self.config.auth_mode == FakeAuthMode::Disabled {
+            return self.accept();
+        }
+
+        self.send_token()
+    }
+
+    fn handle_auth(&mut self, kind: u32, payload: &[u8]) -> Result<bool> {
+        match kind {
+            AUTH_SIGNATURE => {
+                if self.is_authorized(payload)? {
+                    self.accept()?;
+                } else {
+                    // Host will try another key, or send its public key
+                    self.send_token()?;
+                }
+            }
+            AUTH_RSAPUBLICKEY => {
+                if self.config.auth_mode != FakeAuthMode::AcceptPublicKey {
+                    // Do not wait forever for a confirmation that will never happen
+                    return Ok(false);
+                }
+                let public_key = String::from_utf8_lossy(payload)
+                    .trim_end_matches('\0')
+                    .to_string();
+                self.state.authorized_keys.lock()?.push(public_key);
+                self.accept()?;
+            }
+            kind => log::debug!("fake device ignored AUTH message of type {kind}"),
+        }
+
+        Ok(true)
+    }
+
+    /// Whether `signature` of last token has been made with an authorized key.
+    fn is_authorized(&self, signature: &[u8]) -> Result<bool> {
+        let mut keys = self.state.authorized_keys.lock()?.clone();
+        if let FakeAuthMode::AuthorizedKeys(authorized_keys) = &self.config.auth_mode {
+            keys.extend(authorized_keys.iter().cloned());
+        }
+
+        Ok(keys.iter().any(|key| {
+            android_pubkey_decode(key)
+                .map(|key| verify_signature(&key, &self.token, signature))
+                .unwrap_or(false)
+        }))
+    }
+
+    fn send_token(&mut self) -> Result<()> {
+        self.token = rand::random::<[u8; AUTH_TOKEN_LENGTH]>().to_vec();
+        let token = self.token.clone();
+        self.send(MessageCommand::Auth, AUTH_TOKEN, 0, &token)
+    }
+
+    fn accept(&mut self) -> Result<()> {
+        self.connected = true;
+        let banner = self.config.banner.to_string();
+        self.send(
+            MessageCommand::Cnxn,
+            PROTOCOL_VERSION,
+            MAX_PAYLOAD,
+            banner.as_bytes(),
+        )
+    }
+
+    fn open_stream(&mut self, host_id: u32, payload: &[u8]) -> Result<bool> {
+        let service = String::from_utf8_lossy(payload)
+            .trim_end_matches('\0')
+            .to_string();
+        self.state.services.lock()?.push(service.clone());
+
+        let refused = self.config.faults.iter().any(|fault| {
+            matches!(fault, FakeFault::RefuseService(prefix) if service.starts_with(prefix.as_str()))
+        });
+        let opened = match refused {
+            true => None,
+            false => open_service(&service, &self.config, &self.state)?,
+        };
+
+        let stream_id = self.next_stream_id;
+        if service.starts_with("reboot:") && !refused {
+            self.send(MessageCommand::Okay, stream_id, host_id, &[])?;
+            self.state.reboots.lock()?.push(Instant::now());
+            // Device is rebooting, connection gets lost
+            return Ok(false);
+        }
+
+        let Some((service, output)) = opened else {
+            self.send(MessageCommand::Clse, 0, host_id, &[])?;
+            return Ok(true);
+        };
+
+        self.next_stream_id += 1;
+        self.streams.insert(
+            stream_id,
+            FakeStream {
+                host_id,
+                service,
+                pending: VecDeque::new(),
+                awaiting_ack: false,
+                closing: false,
+            },
+        );
+        self.send(MessageCommand::Okay, stream_id, host_id, &[])?;
+        self.push_output(stream_id, output)?;
+
+        Ok(true)
+    }
+
+    fn push_output(&mut self, stream_id: u32, output: FakeServiceOutput) -> Result<()> {
+        if let Some(stream) = self.streams.get_mut(&stream_id) {
+            stream.pending.extend(output.chunks);
+            stream.closing |= output.close;
+        }
+
+        self.flush_stream(stream_id)
+    }
+
+    /// Send next pending chunk of stream if host acknowledged the previous one, and close stream once everything has been sent.
+    fn flush_stream(&mut self, stream_id: u32) -> Result<()> {
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return Ok(());
+        };
+        let host_id = stream.host_id;
+
+        if !stream.awaiting_ack {
+            if let Some(chunk) = stream.pending.pop_front() {
+                stream.awaiting_ack = true;
+                self.send(MessageCommand::Write, stream_id, host_id, &chunk)?;
+            }
+        }
+
+        if let Some(stream) = self.streams.get(&stream_id) {
+            if stream.closing && stream.pending.is_empty() {
+                self.streams.remove(&stream_id);
+                self.send(MessageCommand::Clse, stream_id, host_id, &[])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a message to host, applying configured faults.
+    fn send(&mut self, command: MessageCommand, arg0: u32, arg1: u32, data: &[u8]) -> Result<()> {
+        let mut message = ADBTransportMessage::new(command, arg0, arg1, data);
+
+        for fault in &self.config.faults {
+            match fault {
+                FakeFault::DisconnectAfter(count) if self.sent_messages >= *count => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::ConnectionAborted,
+                        "disconnecting because of injected fault",
+                    )
+                    .into());
+                }
+                FakeFault::CorruptChecksums if command == MessageCommand::Write => {
+                    message = corrupt_checksum(message)?;
+                }
+                FakeFault::Delay(delay) => std::thread::sleep(*delay),
+                _ => {}
+            }
+        }
+
+        self.sent_messages += 1;
+        self.link.write_message(message)
+    }
+}
+
+fn corrupt_checksum(message: ADBTransportMessage) -> Result<ADBTransportMessage> {
+    let mut header: [u8; 24] = message.header().as_bytes()?.as_slice().try_into()?;
+    // Payload checksum is the fifth field of header
+    header[16] = header[16].wrapping_add(1);
+
+    Ok(ADBTransportMessage::from_header_and_payload(
+        ADBTransportMessageHeader::try_from(header)?,
+        message.into_payload(),
+    ))
+}
@@ -0,0 +1,98 @@
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use rustls::pki_types::{CertificateDer, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{
+    DigitallySignedStruct, DistinguishedName, ServerConfig, ServerConnection, SignatureScheme,
+    StreamOwned, client::danger::HandshakeSignatureValid,
+};
+use rustls_pki_types::PrivatePkcs8KeyDer;
+
+use super::fake_adb_device::FakeDeviceState;
+use crate::pairing::{PeerInfo, Spake2Role, exchange_peer_info, pairing_password};
+use crate::{Result, RustADBError};
+
+/// GUID sent by fake devices once paired.
+pub(crate) const FAKE_DEVICE_GUID: &str = "adb-FAKE0001-fake";
+
+/// Serve a single pairing request, authorizing public key of host if it knows `code`.
+pub(crate) fn serve_pairing(stream: TcpStream, code: &str, state: &FakeDeviceState) -> Result<()> {
+    let certified_key = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let config = ServerConfig::builder()
+        .with_client_cert_verifier(Arc::new(AnyClientCertificate))
+        .with_single_cert(
+            vec![certified_key.cert.der().clone()],
+            PrivatePkcs8KeyDer::from(certified_key.key_pair.serialize_der()).into(),
+        )?;
+
+    let mut connection = ServerConnection::new(Arc::new(config))?;
+    let mut socket = stream;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut socket)?;
+    }
+
+    let password = pairing_password(code, &connection)?;
+    let mut stream = StreamOwned::new(connection, socket);
+    let guid = PeerInfo::DeviceGuid(FAKE_DEVICE_GUID.to_string());
+    match exchange_peer_info(&mut stream, Spake2Role::Bob, &password, &guid)? {
+        PeerInfo::RsaPublicKey(public_key) => {
+            state.authorized_keys.lock()?.push(public_key);
+            Ok(())
+        }
+        PeerInfo::DeviceGuid(_) => Err(RustADBError::PairingError(
+            "host did not send its public key".into(),
+        )),
+    }
+}
+
+/// Accept any certificate from hosts, as `adbd` does: hosts are authenticated by pairing code.
+#[derive(Debug)]
+struct AnyClientCertificate;
+
+impl ClientCertVerifier for AnyClientCertificate {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> std::result::Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::ED25519,
+        ]
+    }
+}
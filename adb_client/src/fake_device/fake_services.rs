@@ -0,0 +1,667 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use byteorder::{ByteOrder, LittleEndian};
+use md5::{Digest, Md5};
+
+use super::fake_adb_device::{
+    FakeDeviceConfig, FakeDeviceState, FakeFile, FakeInstallSession, FakeShellResponse,
+};
+use crate::Result;
+use crate::constants::BUFFER_SIZE;
+use crate::models::{ShellPacketDecoder, ShellPacketId, SyncCompression, encode_shell_packet};
+
+/// File type bits of a regular file
+const S_IFREG: u32 = 0o100000;
+/// File type bits of a directory
+const S_IFDIR: u32 = 0o040000;
+/// File type bits of a symbolic link
+const S_IFLNK: u32 = 0o120000;
+/// Mask of file type bits
+const S_IFMT: u32 = 0o170000;
+
+/// First bytes of ZIP files, such as APKs
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Mode, size and modification time of a file
+type FileStat = (u32, u32, u32);
+
+/// Data sent back by a fake service, each chunk being sent in its own `WRTE` message.
+#[derive(Debug, Default)]
+pub(crate) struct FakeServiceOutput {
+    pub(crate) chunks: Vec<Vec<u8>>,
+    pub(crate) close: bool,
+}
+
+impl FakeServiceOutput {
+    /// Send `data` and close service.
+    fn last(data: &[u8]) -> Self {
+        Self {
+            chunks: data.chunks(BUFFER_SIZE).map(<[u8]>::to_vec).collect(),
+            close: true,
+        }
+    }
+}
+
+/// A service opened on a [`crate::FakeADBDevice`].
+pub(crate) trait FakeService: Send {
+    /// Handle `data` written by host.
+    fn on_data(&mut self, data: &[u8]) -> Result<FakeServiceOutput>;
+}
+
+/// Open `service`, returning it along with its initial output, or `None` if device does not know it.
+pub(crate) fn open_service(
+    service: &str,
+    config: &FakeDeviceConfig,
+    state: &Arc<FakeDeviceState>,
+) -> Result<Option<(Box<dyn FakeService>, FakeServiceOutput)>> {
+    if service == "sync:" {
+        return Ok(Some((
+            Box::new(SyncService::new(state.clone())),
+            FakeServiceOutput::default(),
+        )));
+    }
+
+    if let Some(args) = install_session_args(service) {
+        return open_install_session_command(&args, state).map(Some);
+    }
+
+    // Shell services may carry options, such as `shell,v2,raw:ls`
+    let (command, interactive, shell_v2) = match service.split_once(':') {
+        Some((kind, command)) if kind == "shell" || kind.starts_with("shell,") => {
+            (command, true, kind.split(',').any(|option| option == "v2"))
+        }
+        Some(("exec", command)) => (command, false, false),
+        _ => return Ok(None),
+    };
+
+    if command.is_empty() && interactive {
+        let shell = EchoShell {
+            state: state.clone(),
+            shell_v2,
+            decoder: ShellPacketDecoder::default(),
+        };
+        return Ok(Some((Box::new(shell), FakeServiceOutput::default())));
+    }
+
+    if let Some(args) = command.strip_prefix("cmd package 'install' ") {
+        // Options come first, followed by size of APK
+        let size = args
+            .split_whitespace()
+            .skip_while(|arg| *arg != "-S")
+            .nth(1)
+            .unwrap_or_default();
+        let mut install = InstallService {
+            remaining: size.parse()?,
+            contents: Vec::new(),
+            allow_test: args.split_whitespace().any(|arg| arg == "-t"),
+        };
+        let output = install.on_data(&[])?;
+        return Ok(Some((Box::new(install), output)));
+    }
+
+    let response = if let Some(paths) = command.strip_prefix("mkdir -p ") {
+        let mut files = state.files.lock()?;
+        for path in paths.split_whitespace() {
+            let directory = FakeFile {
+                contents: Vec::new(),
+                mode: S_IFDIR | 0o755,
+                mtime: 0,
+            };
+            files.insert(path.trim_matches('\'').to_string(), directory);
+        }
+        FakeShellResponse::default()
+    } else if let Some(paths) = command.strip_prefix("rm -rf ") {
+        let mut files = state.files.lock()?;
+        for path in paths.split_whitespace() {
+            let path = path.trim_matches('\'');
+            let prefix = directory_prefix(path);
+            files.retain(|p, _| p != path && !p.starts_with(&prefix));
+        }
+        FakeShellResponse::default()
+    } else if let Some(paths) = command.strip_prefix("md5sum ") {
+        let files = state.files.lock()?;
+        let mut response = FakeShellResponse::default();
+        for path in paths.split_whitespace().map(|path| path.trim_matches('\'')) {
+            match files.get(path) {
+                Some(file) => response.stdout.extend_from_slice(
+                    format!("{:x}  {path}\n", Md5::digest(&file.contents)).as_bytes(),
+                ),
+                None => {
+                    response.stderr.extend_from_slice(
+                        format!("md5sum: {path}: No such file or directory\n").as_bytes(),
+                    );
+                    response.exit_code = 1;
+                }
+            }
+        }
+        response
+    } else if let Some(args) = command.strip_prefix("cmd package 'uninstall' ") {
+        // Options come first, followed by package name
+        let package = args
+            .split_whitespace()
+            .last()
+            .unwrap_or_default()
+            .trim_matches('\'');
+        let stdout = if state.packages.lock()?.remove(package) {
+            b"Success\n".to_vec()
+        } else {
+            b"Failure [DELETE_FAILED_INTERNAL_ERROR]\n".to_vec()
+        };
+        FakeShellResponse {
+            stdout,
+            ..Default::default()
+        }
+    } else if command == "cat /proc/sys/kernel/random/boot_id" {
+        // Kernel generates a new identifier at each boot
+        let boot_count = state.reboots.lock()?.len();
+        FakeShellResponse {
+            stdout: format!("00000000-0000-4000-8000-{boot_count:012x}\n").into_bytes(),
+            ..Default::default()
+        }
+    } else if let Some(response) = config.shell_responses.get(command) {
+        response.clone()
+    } else {
+        let program = command.split_whitespace().next().unwrap_or_default();
+        FakeShellResponse {
+            stdout: Vec::new(),
+            stderr: format!("/system/bin/sh: {program}: inaccessible or not found\n").into_bytes(),
+            exit_code: 127,
+        }
+    };
+
+    let output = match shell_v2 {
+        true => {
+            // Leave room for packet headers in each message
+            let chunk_size = BUFFER_SIZE - 5;
+            let stdout = response.stdout.chunks(chunk_size);
+            let stderr = response.stderr.chunks(chunk_size);
+            let mut chunks = stdout
+                .map(|chunk| encode_shell_packet(ShellPacketId::Stdout, chunk))
+                .chain(stderr.map(|chunk| encode_shell_packet(ShellPacketId::Stderr, chunk)))
+                .collect::<Vec<_>>();
+            chunks.push(encode_shell_packet(
+                ShellPacketId::Exit,
+                &[response.exit_code],
+            ));
+            FakeServiceOutput {
+                chunks,
+                close: true,
+            }
+        }
+        false => FakeServiceOutput::last(&[response.stdout, response.stderr].concat()),
+    };
+
+    Ok(Some((Box::new(Finished), output)))
+}
+
+/// Service whose whole output has been sent when opening it.
+struct Finished;
+
+impl FakeService for Finished {
+    fn on_data(&mut self, _data: &[u8]) -> Result<FakeServiceOutput> {
+        Ok(FakeServiceOutput::default())
+    }
+}
+
+/// Interactive shell, echoing everything written to it until host closes it, or closes its input when using shell protocol v2.
+struct EchoShell {
+    state: Arc<FakeDeviceState>,
+    shell_v2: bool,
+    decoder: ShellPacketDecoder,
+}
+
+impl FakeService for EchoShell {
+    fn on_data(&mut self, data: &[u8]) -> Result<FakeServiceOutput> {
+        if !self.shell_v2 {
+            return Ok(FakeServiceOutput {
+                chunks: vec![data.to_vec()],
+                close: false,
+            });
+        }
+
+        let mut output = FakeServiceOutput::default();
+        self.decoder.push(data);
+        while let Some((id, data)) = self.decoder.next_packet()? {
+            match id {
+                Ok(ShellPacketId::Stdin) => output
+                    .chunks
+                    .push(encode_shell_packet(ShellPacketId::Stdout, &data)),
+                Ok(ShellPacketId::CloseStdin) => {
+                    output
+                        .chunks
+                        .push(encode_shell_packet(ShellPacketId::Exit, &[0]));
+                    output.close = true;
+                }
+                Ok(ShellPacketId::WindowSizeChange) => self.state.window_sizes.lock()?.push(
+                    String::from_utf8_lossy(&data)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                ),
+                _ => {}
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// `cmd package install`, reading given amount of bytes before reporting its outcome.
+///
+/// APKs containing `testOnly` are considered as test packages, only installed with `-t`.
+struct InstallService {
+    remaining: u64,
+    contents: Vec<u8>,
+    allow_test: bool,
+}
+
+impl FakeService for InstallService {
+    fn on_data(&mut self, data: &[u8]) -> Result<FakeServiceOutput> {
+        self.contents.extend_from_slice(data);
+        self.remaining = self.remaining.saturating_sub(data.len() as u64);
+        if self.remaining > 0 {
+            return Ok(FakeServiceOutput::default());
+        }
+
+        let is_test = self.contents.windows(8).any(|window| window == b"testOnly");
+        let response: &[u8] = if !self.contents.starts_with(ZIP_MAGIC) {
+            // Package manager only accepts ZIP files
+            b"Failure [INSTALL_FAILED_INVALID_APK: Failed to parse APK]\n"
+        } else if is_test && !self.allow_test {
+            b"Failure [INSTALL_FAILED_TEST_ONLY: installPackageLI]\n"
+        } else {
+            b"Success\n"
+        };
+        Ok(FakeServiceOutput::last(response))
+    }
+}
+
+/// Arguments of `service` if it runs a package manager install session command, through `cmd` or `abb_exec:`.
+fn install_session_args(service: &str) -> Option<Vec<String>> {
+    let args = match service.strip_prefix("abb_exec:package\0") {
+        Some(args) => args.split('\0').map(String::from).collect::<Vec<_>>(),
+        None => service
+            .strip_prefix("exec:cmd package ")?
+            .split_whitespace()
+            .map(|arg| arg.trim_matches('\'').to_string())
+            .collect(),
+    };
+
+    args.first()
+        .is_some_and(|command| command.starts_with("install-"))
+        .then_some(args)
+}
+
+/// Run install session command `args`, such as `install-create -S 1234`.
+fn open_install_session_command(
+    args: &[String],
+    state: &Arc<FakeDeviceState>,
+) -> Result<(Box<dyn FakeService>, FakeServiceOutput)> {
+    let mut sessions = state.install_sessions.lock()?;
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let response = match args.as_slice() {
+        ["install-create", ..] => {
+            let id = sessions.keys().next_back().map_or(1, |id| id + 1);
+            sessions.insert(id, FakeInstallSession::default());
+            format!("Success: created install session [{id}]\n")
+        }
+        ["install-write", "-S", size, id, name, "-"] => {
+            let id = id.parse()?;
+            if !sessions.contains_key(&id) {
+                return Ok((
+                    Box::new(Finished),
+                    FakeServiceOutput::last(b"Failure [Invalid session]\n"),
+                ));
+            }
+            drop(sessions);
+
+            let mut write = SessionWriteService {
+                state: state.clone(),
+                id,
+                name: name.to_string(),
+                remaining: size.parse()?,
+                contents: Vec::new(),
+            };
+            let output = write.on_data(&[])?;
+            return Ok((Box::new(write), output));
+        }
+        ["install-commit", id] => match sessions.get_mut(&id.parse()?) {
+            Some(session)
+                if session
+                    .apks
+                    .iter()
+                    .all(|(_, contents)| contents.starts_with(ZIP_MAGIC)) =>
+            {
+                session.committed = true;
+                "Success\n".to_string()
+            }
+            Some(_) => "Failure [INSTALL_FAILED_INVALID_APK: Failed to parse APK]\n".to_string(),
+            None => "Failure [Invalid session]\n".to_string(),
+        },
+        ["install-abandon", id] => match sessions.remove(&id.parse()?) {
+            Some(_) => "Success\n".to_string(),
+            None => "Failure [Invalid session]\n".to_string(),
+        },
+        _ => format!("Unknown command: {}\n", args.join(" ")),
+    };
+
+    Ok((
+        Box::new(Finished),
+        FakeServiceOutput::last(response.as_bytes()),
+    ))
+}
+
+/// `install-write` of an install session, reading given amount of bytes into it.
+struct SessionWriteService {
+    state: Arc<FakeDeviceState>,
+    id: u32,
+    name: String,
+    remaining: u64,
+    contents: Vec<u8>,
+}
+
+impl FakeService for SessionWriteService {
+    fn on_data(&mut self, data: &[u8]) -> Result<FakeServiceOutput> {
+        let data = &data[..data.len().min(self.remaining as usize)];
+        self.contents.extend_from_slice(data);
+        self.remaining -= data.len() as u64;
+        if self.remaining > 0 {
+            return Ok(FakeServiceOutput::default());
+        }
+
+        let contents = std::mem::take(&mut self.contents);
+        let size = contents.len();
+        if let Some(session) = self.state.install_sessions.lock()?.get_mut(&self.id) {
+            session.apks.push((self.name.clone(), contents));
+        }
+        Ok(FakeServiceOutput::last(
+            format!("Success: streamed {size} bytes\n").as_bytes(),
+        ))
+    }
+}
+
+enum SyncState {
+    Idle,
+    /// Waiting for mode and compression of a file sent with `SND2`
+    SendSetup {
+        path: String,
+    },
+    /// Waiting for compression of a file requested with `RCV2`
+    RecvSetup {
+        path: String,
+    },
+    /// Receiving content of a file sent with `SEND` or `SND2`
+    Receiving {
+        path: String,
+        mode: u32,
+        compression: SyncCompression,
+        contents: Vec<u8>,
+    },
+}
+
+/// `sync:` service, working on files of the fake device.
+struct SyncService {
+    state: Arc<FakeDeviceState>,
+    buffer: Vec<u8>,
+    sync_state: SyncState,
+}
+
+impl SyncService {
+    fn new(state: Arc<FakeDeviceState>) -> Self {
+        Self {
+            state,
+            buffer: Vec::new(),
+            sync_state: SyncState::Idle,
+        }
+    }
+
+    /// Handle a single sync request. Returns whether service has to be closed.
+    fn handle_request(
+        &mut self,
+        id: &[u8],
+        arg: u32,
+        payload: &[u8],
+        output: &mut FakeServiceOutput,
+    ) -> Result<bool> {
+        let path = String::from_utf8_lossy(payload).to_string();
+
+        match (&mut self.sync_state, id) {
+            (SyncState::Idle, b"STAT") => {
+                let (mode, size, mtime) = self.stat(&path)?;
+                output
+                    .chunks
+                    .push(sync_response(b"STAT", &[mode, size, mtime], &[]));
+            }
+            (SyncState::Idle, b"LIST") => {
+                for (name, (mode, size, mtime)) in self.list(&path)? {
+                    let entry = sync_response(
+                        b"DENT",
+                        &[mode, size, mtime, name.len() as u32],
+                        name.as_bytes(),
+                    );
+                    output.chunks.push(entry);
+                }
+                output.chunks.push(sync_response(b"DONE", &[0; 4], &[]));
+            }
+            (SyncState::Idle, b"LIS2") => {
+                for (name, (mode, size, mtime)) in self.list(&path)? {
+                    // Error, device and inode numbers, mode, link count, user and group ids, size, access, modification and change times
+                    let stat = [0, 0, 0, 0, 0, mode, 1, 0, 0, size, 0, 0, 0, mtime, 0, 0, 0];
+                    let entry = sync_response(
+                        b"DNT2",
+                        &[&stat[..], &[name.len() as u32]].concat(),
+                        name.as_bytes(),
+                    );
+                    output.chunks.push(entry);
+                }
+                output.chunks.push(sync_response(b"DONE", &[0; 18], &[]));
+            }
+            (SyncState::Idle, b"RECV") => self.send_file(&path, SyncCompression::None, output)?,
+            (SyncState::Idle, b"RCV2") => self.sync_state = SyncState::RecvSetup { path },
+            (SyncState::RecvSetup { path }, b"RCV2") => {
+                let path = std::mem::take(path);
+                self.sync_state = SyncState::Idle;
+                self.send_file(&path, SyncCompression::from_flags(arg), output)?;
+            }
+            (SyncState::Idle, b"SEND") => {
+                let (path, mode) = path.rsplit_once(',').unwrap_or((&path, "0644"));
+                self.sync_state = SyncState::Receiving {
+                    path: path.to_string(),
+                    mode: file_mode(parse_mode(mode)?),
+                    compression: SyncCompression::None,
+                    contents: Vec::new(),
+                };
+            }
+            (SyncState::Idle, b"SND2") => self.sync_state = SyncState::SendSetup { path },
+            (SyncState::SendSetup { path }, b"SND2") => {
+                // Payload holds flags of request
+                let flags = LittleEndian::read_u32(payload);
+                self.sync_state = SyncState::Receiving {
+                    path: std::mem::take(path),
+                    mode: file_mode(arg),
+                    compression: SyncCompression::from_flags(flags),
+                    contents: Vec::new(),
+                };
+            }
+            (SyncState::Receiving { contents, .. }, b"DATA") => {
+                contents.extend_from_slice(payload);
+            }
+            (SyncState::Receiving { .. }, b"DONE") => {
+                if let SyncState::Receiving {
+                    path,
+                    mode,
+                    compression,
+                    contents,
+                } = std::mem::replace(&mut self.sync_state, SyncState::Idle)
+                {
+                    let mut decompressed = Vec::new();
+                    compression.decompress(&mut contents.as_slice(), &mut decompressed)?;
+                    let file = FakeFile {
+                        contents: decompressed,
+                        mode,
+                        mtime: arg,
+                    };
+                    self.state.files.lock()?.insert(path, file);
+                }
+                output.chunks.push(sync_response(b"OKAY", &[0], &[]));
+            }
+            (SyncState::Idle, b"QUIT") => return Ok(true),
+            _ => {
+                output.chunks.push(sync_failure(&format!(
+                    "unexpected sync request {}",
+                    String::from_utf8_lossy(id)
+                )));
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Send contents of file at `path`, compressed with `compression`, in `DATA` packets.
+    fn send_file(
+        &self,
+        path: &str,
+        compression: SyncCompression,
+        output: &mut FakeServiceOutput,
+    ) -> Result<()> {
+        let Some(file) = resolve_links(&*self.state.files.lock()?, path) else {
+            output
+                .chunks
+                .push(sync_failure("No such file or directory"));
+            return Ok(());
+        };
+        let contents = compression.compress(&mut file.contents.as_slice(), Vec::new())?;
+        for chunk in contents.chunks(BUFFER_SIZE) {
+            output
+                .chunks
+                .push(sync_response(b"DATA", &[chunk.len() as u32], chunk));
+        }
+        output.chunks.push(sync_response(b"DONE", &[0], &[]));
+        Ok(())
+    }
+
+    /// Mode, size and modification time of `path`, all zeroes if it does not exist.
+    fn stat(&self, path: &str) -> Result<FileStat> {
+        let files = self.state.files.lock()?;
+        if let Some(file) = files.get(path) {
+            return Ok((file.mode, file.contents.len() as u32, file.mtime));
+        }
+
+        let prefix = directory_prefix(path);
+        if prefix == "/" || files.keys().any(|p| p.starts_with(&prefix)) {
+            return Ok((S_IFDIR | 0o755, 0, 0));
+        }
+
+        Ok((0, 0, 0))
+    }
+
+    /// Entries of directory `path`, with their stat.
+    fn list(&self, path: &str) -> Result<Vec<(String, FileStat)>> {
+        let prefix = directory_prefix(path);
+        let names = self
+            .state
+            .files
+            .lock()?
+            .keys()
+            .filter_map(|p| p.strip_prefix(&prefix))
+            .map(|name| name.split('/').next().unwrap_or(name).to_string())
+            .collect::<BTreeSet<String>>();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let stat = self.stat(&format!("{prefix}{name}"))?;
+                Ok((name, stat))
+            })
+            .collect()
+    }
+}
+
+impl FakeService for SyncService {
+    fn on_data(&mut self, data: &[u8]) -> Result<FakeServiceOutput> {
+        self.buffer.extend_from_slice(data);
+        let mut output = FakeServiceOutput::default();
+
+        while self.buffer.len() >= 8 {
+            let arg = LittleEndian::read_u32(&self.buffer[4..8]);
+            let id = &self.buffer[..4];
+            // Argument of these requests is the length of data following them
+            let has_payload = matches!(id, b"STAT" | b"LIST" | b"LIS2" | b"RECV" | b"SEND")
+                || (matches!(id, b"RCV2" | b"SND2") && matches!(self.sync_state, SyncState::Idle))
+                || (id == b"DATA" && matches!(self.sync_state, SyncState::Receiving { .. }));
+            let payload_length = match (has_payload, &self.sync_state) {
+                (true, _) => arg as usize,
+                // Setup of `SND2` has flags following mode
+                (false, SyncState::SendSetup { .. }) if id == b"SND2" => 4,
+                (false, _) => 0,
+            };
+            if self.buffer.len() < 8 + payload_length {
+                break;
+            }
+
+            let request = self.buffer.drain(..8 + payload_length).collect::<Vec<u8>>();
+            if self.handle_request(&request[..4], arg, &request[8..], &mut output)? {
+                output.close = true;
+                break;
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn sync_response(id: &[u8; 4], args: &[u32], data: &[u8]) -> Vec<u8> {
+    let mut response = id.to_vec();
+    for arg in args {
+        response.extend_from_slice(&arg.to_le_bytes());
+    }
+    response.extend_from_slice(data);
+    response
+}
+
+fn sync_failure(message: &str) -> Vec<u8> {
+    sync_response(b"FAIL", &[message.len() as u32], message.as_bytes())
+}
+
+/// Mode of a file sent with mode `mode`: symbolic link when requested, regular file otherwise.
+fn file_mode(mode: u32) -> u32 {
+    match mode & S_IFMT {
+        S_IFLNK => mode,
+        _ => S_IFREG | (mode & 0o7777),
+    }
+}
+
+/// File at `path`, following symbolic links.
+fn resolve_links(files: &BTreeMap<String, FakeFile>, path: &str) -> Option<FakeFile> {
+    let mut path = path.to_string();
+    // Links may form a loop
+    for _ in 0..8 {
+        let file = files.get(&path)?;
+        if file.mode & S_IFMT != S_IFLNK {
+            return Some(file.clone());
+        }
+
+        let target = String::from_utf8_lossy(&file.contents);
+        path = match (target.starts_with('/'), path.rsplit_once('/')) {
+            (false, Some((parent, _))) => format!("{parent}/{target}"),
+            _ => target.to_string(),
+        };
+    }
+    None
+}
+
+/// `path` with a single trailing `/`.
+fn directory_prefix(path: &str) -> String {
+    format!("{}/", path.trim_end_matches('/'))
+}
+
+/// Parse a file mode like `strtoul` does with base 0: octal when starting with `0`, decimal otherwise.
+fn parse_mode(mode: &str) -> Result<u32> {
+    match mode.strip_prefix('0') {
+        Some("") => Ok(0),
+        Some(octal) => Ok(u32::from_str_radix(octal, 8)?),
+        None => Ok(mode.parse()?),
+    }
+}
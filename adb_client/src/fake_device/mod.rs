@@ -0,0 +1,14 @@
+mod fake_adb_device;
+mod fake_adb_transport;
+mod fake_connection;
+mod fake_pairing;
+mod fake_services;
+mod models;
+
+pub use fake_adb_device::FakeADBDevice;
+#[cfg(test)]
+pub(crate) use fake_adb_device::{connected_tcp_device, write_test_private_key};
+#[cfg(all(test, feature = "tokio"))]
+pub(crate) use fake_adb_device::connected_async_tcp_device;
+pub use fake_adb_transport::FakeADBTransport;
+pub use models::{FakeAuthMode, FakeFault};
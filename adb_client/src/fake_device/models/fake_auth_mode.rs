@@ -0,0 +1,13 @@
+/// How a [`crate::FakeADBDevice`] authenticates hosts connecting over TCP.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FakeAuthMode {
+    /// No authentication, connection is accepted right after receiving `CNXN`.
+    #[default]
+    Disabled,
+    /// Any public key sent by host is accepted, as if user confirmed the prompt displayed on device.
+    AcceptPublicKey,
+    /// Only signatures made with one of these keys are accepted. Keys are encoded as in `adbkey.pub` files.
+    ///
+    /// Connection gets closed if host sends an unknown public key, instead of waiting for user confirmation.
+    AuthorizedKeys(Vec<String>),
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// A fault injected by a [`crate::FakeADBDevice`] to simulate misbehaving devices.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FakeFault {
+    /// Close connection once given number of messages have been sent by device.
+    DisconnectAfter(usize),
+    /// Send `WRTE` messages with an invalid payload checksum.
+    CorruptChecksums,
+    /// Wait given duration before sending each message.
+    Delay(Duration),
+    /// Refuse to open services starting with given prefix, by replying `CLSE` to `OPEN`.
+    RefuseService(String),
+    /// Drop connections made less than given duration after a reboot, as a device still booting would.
+    BootDelay(Duration),
+}
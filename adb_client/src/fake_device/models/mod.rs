@@ -0,0 +1,5 @@
+mod fake_auth_mode;
+mod fake_fault;
+
+pub use fake_auth_mode::FakeAuthMode;
+pub use fake_fault::FakeFault;
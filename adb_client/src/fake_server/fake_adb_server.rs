@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use super::fake_server_client::handle_client;
+use crate::host_server::{DeviceConnection, device_description};
+use crate::{
+    ADBMultiplexer, DeviceState, FakeADBDevice, MDNSServices, Result, RustADBError, ServerStatus,
+};
+
+/// A device known by a [`FakeADBServer`].
+pub(crate) struct FakeServerDevice {
+    pub(crate) serial: String,
+    pub(crate) state: DeviceState,
+    pub(crate) transport_id: u32,
+    /// Connection to device, only available for devices in `device` state
+    pub(crate) connection: Option<Box<dyn DeviceConnection>>,
+}
+
+impl FakeServerDevice {
+    /// Line describing this device in `host:devices` or `host:devices-l` responses.
+    pub(crate) fn description(&self, long: bool) -> String {
+        let banner = self.connection.as_ref().and_then(|c| c.banner());
+        device_description(
+            &self.serial,
+            &self.state.to_string(),
+            None,
+            banner,
+            self.transport_id,
+            long,
+        )
+    }
+}
+
+impl std::fmt::Debug for FakeServerDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FakeServerDevice")
+            .field("serial", &self.serial)
+            .field("state", &self.state)
+            .field("transport_id", &self.transport_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Response given to a request, regardless of server state.
+#[derive(Clone, Debug)]
+pub(crate) enum FakeServerResponse {
+    Okay(Vec<u8>),
+    Fail(String),
+}
+
+#[derive(Debug, Default)]
+struct FakeServerDevices {
+    devices: Vec<Arc<FakeServerDevice>>,
+    generation: u64,
+    next_transport_id: u32,
+}
+
+/// Data of a [`FakeADBServer`] shared with its client connections.
+#[derive(Debug, Default)]
+pub(crate) struct FakeServerState {
+    devices: Mutex<FakeServerDevices>,
+    changed: Condvar,
+    responses: Mutex<HashMap<String, FakeServerResponse>>,
+    requests: Mutex<Vec<String>>,
+}
+
+impl FakeServerState {
+    fn update_devices(&self, update: impl FnOnce(&mut FakeServerDevices)) -> Result<()> {
+        let mut devices = self.devices.lock()?;
+        update(&mut devices);
+        devices.generation += 1;
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Currently known devices.
+    pub(crate) fn devices(&self) -> Result<Vec<Arc<FakeServerDevice>>> {
+        Ok(self.devices.lock()?.devices.clone())
+    }
+
+    /// Find device with given `serial`, or the only known device if not specified.
+    pub(crate) fn find(&self, serial: Option<&str>) -> Result<Arc<FakeServerDevice>> {
+        let devices = self.devices()?;
+        match serial {
+            Some(serial) => devices
+                .into_iter()
+                .find(|d| d.serial == serial)
+                .ok_or_else(|| {
+                    RustADBError::DeviceNotFound(format!("device '{serial}' not found"))
+                }),
+            None => match devices.as_slice() {
+                [device] => Ok(device.clone()),
+                [] => Err(RustADBError::DeviceNotFound(
+                    "no devices/emulators found".into(),
+                )),
+                _ => Err(RustADBError::DeviceNotFound(
+                    "more than one device/emulator".into(),
+                )),
+            },
+        }
+    }
+
+    /// Current generation of the device list, bumped on every change.
+    pub(crate) fn generation(&self) -> Result<u64> {
+        Ok(self.devices.lock()?.generation)
+    }
+
+    /// Wait for device list to change from given `generation`, at most `timeout`.
+    pub(crate) fn wait_for_change(&self, generation: u64, timeout: Duration) -> Result<()> {
+        let devices = self.devices.lock()?;
+        let _unused = self
+            .changed
+            .wait_timeout_while(devices, timeout, |devices| devices.generation == generation)?;
+
+        Ok(())
+    }
+
+    /// Record `request`, returning the response configured for it, if any.
+    pub(crate) fn record_request(&self, request: &str) -> Result<Option<FakeServerResponse>> {
+        self.requests.lock()?.push(request.to_string());
+        Ok(self.responses.lock()?.get(request).cloned())
+    }
+}
+
+/// An in-process fake ADB server, speaking the smart-socket protocol used between `adb` clients and their server.
+///
+/// It serves device lists (including `host:track-devices` updates), and forwards services of selected devices to
+/// [`FakeADBDevice`] instances, allowing to test code using [`crate::ADBServer`] and [`crate::ADBServerDevice`]
+/// without running `adb`. Every request received is recorded, and responses to any request can be scripted.
+#[derive(Clone, Debug, Default)]
+pub struct FakeADBServer {
+    state: Arc<FakeServerState>,
+}
+
+impl FakeADBServer {
+    /// Instantiate a new [`FakeADBServer`], without any device.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a device in `device` state, whose services are served by `device`.
+    pub fn add_device(&self, serial: &str, device: &FakeADBDevice) -> Result<()> {
+        let connection =
+            ADBMultiplexer::new(device.transport()).with_banner(Some(device.banner().clone()));
+        self.insert_device(serial, DeviceState::Device, Some(Box::new(connection)))
+    }
+
+    /// Add a device in given `state` (e.g. `unauthorized`), which cannot be used to open services.
+    pub fn add_device_with_state(&self, serial: &str, state: DeviceState) -> Result<()> {
+        self.insert_device(serial, state, None)
+    }
+
+    fn insert_device(
+        &self,
+        serial: &str,
+        state: DeviceState,
+        connection: Option<Box<dyn DeviceConnection>>,
+    ) -> Result<()> {
+        self.state.update_devices(|devices| {
+            devices.next_transport_id += 1;
+            let device = FakeServerDevice {
+                serial: serial.to_string(),
+                state,
+                transport_id: devices.next_transport_id,
+                connection,
+            };
+            devices.devices.retain(|d| d.serial != serial);
+            devices.devices.push(Arc::new(device));
+        })
+    }
+
+    /// Remove device with given `serial`, notifying clients tracking devices.
+    pub fn remove_device(&self, serial: &str) -> Result<()> {
+        self.state
+            .update_devices(|devices| devices.devices.retain(|d| d.serial != serial))
+    }
+
+    /// Reply `OKAY` followed by `body` to `request`, instead of the default behavior.
+    ///
+    /// `body` is prefixed by its length for `host:` requests, and sent as is for device services.
+    pub fn set_response(&self, request: &str, body: &[u8]) -> Result<()> {
+        self.state
+            .responses
+            .lock()?
+            .insert(request.to_string(), FakeServerResponse::Okay(body.to_vec()));
+        Ok(())
+    }
+
+    /// Reply `FAIL` with given `message` to `request`.
+    pub fn set_failure(&self, request: &str, message: &str) -> Result<()> {
+        self.state.responses.lock()?.insert(
+            request.to_string(),
+            FakeServerResponse::Fail(message.to_string()),
+        );
+        Ok(())
+    }
+
+    /// Set protocol version answered to `host:version`.
+    pub fn set_version(&self, version: u32) -> Result<()> {
+        self.set_response("host:version", format!("{version:04x}").as_bytes())
+    }
+
+    /// Set status answered to `host:server-status`.
+    pub fn set_server_status(&self, status: &ServerStatus) -> Result<()> {
+        let body: Vec<u8> = status.try_into()?;
+        self.set_response("host:server-status", &body)
+    }
+
+    /// Enable mDNS discovery, reporting given `services`.
+    pub fn set_mdns_services(&self, services: &[MDNSServices]) -> Result<()> {
+        let body = services
+            .iter()
+            .map(|s| format!("{s}\n"))
+            .collect::<String>();
+        self.set_response("host:mdns:check", b"mdns daemon version [fake]")?;
+        self.set_response("host:mdns:services", body.as_bytes())
+    }
+
+    /// Every request received so far (e.g. `host:devices`, `shell,raw:ls`), in order.
+    pub fn requests(&self) -> Result<Vec<String>> {
+        Ok(self.state.requests.lock()?.clone())
+    }
+
+    /// Start accepting clients on a loopback TCP socket, returning its address. Server keeps listening until process ends.
+    pub fn listen(&self) -> Result<SocketAddrV4> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+
+        let state = self.state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let state = state.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_client(state, stream) {
+                        log::debug!("error while serving fake server client: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))
+    }
+}
+
+#[test]
+fn test_fake_server_host_requests() {
+    use crate::{ADBDeviceExt, ADBServer, MDNSBackend};
+
+    let fake_server = FakeADBServer::new();
+    fake_server
+        .add_device("emulator-5554", &FakeADBDevice::new())
+        .expect("cannot add device");
+    fake_server
+        .add_device_with_state("R58M123", DeviceState::Unauthorized)
+        .expect("cannot add device");
+    let status = ServerStatus {
+        mdns_backend: MDNSBackend::OpenScreen,
+        version: "35.0.2".to_string(),
+        os: "Linux".to_string(),
+        ..Default::default()
+    };
+    fake_server
+        .set_server_status(&status)
+        .expect("cannot set server status");
+    let service = MDNSServices {
+        service_name: "adb-R58M123-abcdef".to_string(),
+        reg_type: "_adb-tls-connect._tcp".to_string(),
+        socket_v4: "192.168.1.12:37123".parse().expect("invalid address"),
+    };
+    fake_server
+        .set_mdns_services(&[service])
+        .expect("cannot set mdns services");
+    let address = fake_server.listen().expect("cannot listen");
+
+    let mut server = ADBServer::new(address);
+    assert_eq!(
+        server.version().expect("cannot get version").to_string(),
+        "1.0.41"
+    );
+
+    let devices = server.devices().expect("cannot list devices");
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[0].identifier, "emulator-5554");
+    assert!(matches!(devices[0].state, DeviceState::Device));
+    assert!(matches!(devices[1].state, DeviceState::Unauthorized));
+
+    let devices = server.devices_long().expect("cannot list devices");
+    assert_eq!(devices[0].model, "FakeADBDevice");
+    assert_eq!(devices[0].transport_id, 1);
+
+    assert_eq!(
+        server.server_status().expect("cannot get server status"),
+        status
+    );
+    assert!(server.mdns_check().expect("cannot check mdns"));
+    let services = server.mdns_services().expect("cannot list mdns services");
+    assert_eq!(services.len(), 1);
+    assert_eq!(services[0].service_name, "adb-R58M123-abcdef");
+
+    // Services of an unauthorized device cannot be used
+    assert!(
+        server
+            .get_device_by_name("R58M123")
+            .and_then(|mut d| d.shell_command(&["ls"], &mut Vec::new()))
+            .is_err()
+    );
+
+    assert_eq!(
+        fake_server.requests().expect("cannot get requests")[..6],
+        [
+            "host:version",
+            "host:devices",
+            "host:devices-l",
+            "host:server-status",
+            "host:mdns:check",
+            "host:mdns:services"
+        ]
+    );
+}
+
+#[test]
+fn test_fake_server_device_services() {
+    use crate::{ADBDeviceExt, ADBServerDevice, SyncCompression};
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop ro.build.version.sdk", b"35\n");
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+    let fake_server = FakeADBServer::new();
+    fake_server
+        .add_device("emulator-5554", &fake_device)
+        .expect("cannot add device");
+    let address = fake_server.listen().expect("cannot listen");
+
+    let mut device = ADBServerDevice::new("emulator-5554".to_string(), Some(address));
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.build.version.sdk"], &mut output)
+        .expect("cannot run shell command");
+    assert_eq!(output, b"35\n");
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let exit_code = device
+        .shell_command_with_status(&["false"], &mut stdout, &mut stderr)
+        .expect("cannot run shell command");
+    assert_eq!(exit_code, Some(127));
+    assert_eq!(
+        stderr,
+        b"/system/bin/sh: false: inaccessible or not found\n"
+    );
+
+    device
+        .push(b"world".as_slice(), "/sdcard/world.txt")
+        .expect("cannot push file");
+    assert_eq!(
+        fake_device
+            .file("/sdcard/world.txt")
+            .expect("cannot get file"),
+        Some(b"world".to_vec())
+    );
+
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/hello.txt", &mut pulled)
+        .expect("cannot pull file");
+    assert_eq!(pulled, b"hello");
+
+    // Zstd is only used when requested
+    device.set_sync_compression(Some(SyncCompression::Zstd));
+    device
+        .push(b"zstd".as_slice(), "/sdcard/zstd.txt")
+        .expect("cannot push file");
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/zstd.txt", &mut pulled)
+        .expect("cannot pull file");
+    assert_eq!(pulled, b"zstd");
+
+    let requests = fake_server.requests().expect("cannot get requests");
+    // Shell options depend on `TERM` environment variable
+    assert!(
+        requests.iter().any(|r| {
+            r.starts_with("shell,") && r.ends_with(",raw:getprop ro.build.version.sdk")
+        })
+    );
+    assert!(requests.contains(&"sync:".to_string()));
+}
+
+#[test]
+fn test_fake_server_track_devices() {
+    use crate::ADBServer;
+    use std::sync::mpsc;
+
+    let fake_server = FakeADBServer::new();
+    fake_server
+        .add_device("emulator-5554", &FakeADBDevice::new())
+        .expect("cannot add device");
+    let address = fake_server.listen().expect("cannot listen");
+
+    let (sender, receiver) = mpsc::channel();
+    let tracker = std::thread::spawn(move || {
+        let mut server = ADBServer::new(address);
+        // Callback failing once second device shows up stops tracking
+        server.track_devices(|device| {
+            sender.send(device.identifier.clone()).ok();
+            match device.identifier.as_str() {
+                "emulator-5556" => Err(RustADBError::DeviceNotFound(device.identifier)),
+                _ => Ok(()),
+            }
+        })
+    });
+
+    assert_eq!(receiver.recv().expect("no device tracked"), "emulator-5554");
+    fake_server
+        .add_device_with_state("emulator-5556", DeviceState::Offline)
+        .expect("cannot add device");
+    assert!(tracker.join().expect("tracker panicked").is_err());
+    assert_eq!(
+        receiver.iter().collect::<Vec<String>>(),
+        ["emulator-5554", "emulator-5556"]
+    );
+}
@@ -0,0 +1,163 @@
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::fake_adb_server::{FakeServerDevice, FakeServerResponse, FakeServerState};
+use crate::host_server::{
+    is_closed, read_request, write_body, write_fail, write_okay, write_okay_with_body,
+};
+use crate::models::AdbServerCommand;
+use crate::{DeviceState, Result, RustADBError, ServerStatus};
+
+/// Protocol version answered to `host:version` by default.
+const DEFAULT_VERSION: u32 = 41;
+/// Interval at which clients tracking devices are checked for disconnection.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Serve a single client until its connection ends.
+pub(crate) fn handle_client(state: Arc<FakeServerState>, mut client: TcpStream) -> Result<()> {
+    // Device selected by a previous `host:transport` request
+    let mut selected_device: Option<Arc<FakeServerDevice>> = None;
+
+    while let Some(request) = read_request(&mut client)? {
+        let is_host_request = request.starts_with("host:") || request.starts_with("host-serial:");
+
+        match state.record_request(&request)? {
+            Some(FakeServerResponse::Okay(body)) if is_host_request => {
+                return write_okay_with_body(&mut client, body);
+            }
+            Some(FakeServerResponse::Okay(body)) => {
+                write_okay(&mut client)?;
+                client.write_all(&body)?;
+                client.shutdown(Shutdown::Both)?;
+                return Ok(());
+            }
+            Some(FakeServerResponse::Fail(message)) => return write_fail(&mut client, &message),
+            None => {}
+        }
+
+        if is_host_request {
+            let command = match AdbServerCommand::from_str(&request) {
+                Ok(command) => command,
+                Err(e) => return write_fail(&mut client, &e.to_string()),
+            };
+
+            match handle_host_command(&state, &mut client, &mut selected_device, command) {
+                Ok(true) => continue,
+                Ok(false) => return Ok(()),
+                Err(e) => return write_fail(&mut client, &e.to_string()),
+            }
+        }
+
+        let Some(connection) = selected_device.as_ref().and_then(|d| d.connection.as_ref()) else {
+            return write_fail(&mut client, "no device selected");
+        };
+
+        return match connection.bridge(&request, &client, true) {
+            Ok(()) => Ok(()),
+            Err(e) => write_fail(&mut client, &e.to_string()),
+        };
+    }
+
+    Ok(())
+}
+
+/// Handle a `host:*` request. Returns whether connection can receive another request.
+fn handle_host_command(
+    state: &FakeServerState,
+    client: &mut TcpStream,
+    selected_device: &mut Option<Arc<FakeServerDevice>>,
+    command: AdbServerCommand,
+) -> Result<bool> {
+    match command {
+        AdbServerCommand::Version => {
+            write_okay_with_body(client, format!("{DEFAULT_VERSION:04x}"))?;
+        }
+        AdbServerCommand::Kill => write_okay(client)?,
+        AdbServerCommand::ServerStatus => {
+            let body: Vec<u8> = (&ServerStatus::default()).try_into()?;
+            write_okay_with_body(client, body)?;
+        }
+        AdbServerCommand::Devices | AdbServerCommand::DevicesLong => {
+            let long = matches!(command, AdbServerCommand::DevicesLong);
+            write_okay_with_body(client, devices_listing(&state.devices()?, long))?;
+        }
+        AdbServerCommand::TrackDevices => {
+            write_okay(client)?;
+            let mut last_listing = None;
+            loop {
+                let generation = state.generation()?;
+                let listing = devices_listing(&state.devices()?, false);
+                if last_listing.as_ref() != Some(&listing) {
+                    write_body(client, &listing)?;
+                    last_listing = Some(listing);
+                }
+                if is_closed(client)? {
+                    break;
+                }
+                state.wait_for_change(generation, DEVICE_POLL_INTERVAL)?;
+            }
+        }
+        AdbServerCommand::HostFeatures => {
+            let device = match selected_device {
+                Some(device) => Some(device.clone()),
+                None => state.find(None).ok(),
+            };
+            let features = device
+                .and_then(|d| {
+                    let connection = d.connection.as_ref()?;
+                    connection.banner().map(|b| b.features.join(","))
+                })
+                .unwrap_or_default();
+            write_okay_with_body(client, features)?;
+        }
+        AdbServerCommand::TransportAny | AdbServerCommand::TransportSerial(_) => {
+            let serial = match &command {
+                AdbServerCommand::TransportSerial(serial) => Some(serial.as_str()),
+                _ => None,
+            };
+            let device = state.find(serial)?;
+            if !matches!(device.state, DeviceState::Device) {
+                return Err(RustADBError::ADBRequestFailed(format!(
+                    "device {}",
+                    device.state
+                )));
+            }
+            *selected_device = Some(device);
+            write_okay(client)?;
+            return Ok(true);
+        }
+        AdbServerCommand::WaitForDevice(wait_state, _, serial) => {
+            // Fake devices can be reached over any transport
+            write_okay(client)?;
+            let wait_state = wait_state.to_string();
+            loop {
+                let generation = state.generation()?;
+                let found = state.devices()?.iter().any(|d| {
+                    serial.as_ref().map_or(true, |s| d.serial == *s)
+                        && d.state.to_string() == wait_state
+                });
+                if found {
+                    break;
+                }
+                if is_closed(client)? {
+                    return Ok(false);
+                }
+                state.wait_for_change(generation, DEVICE_POLL_INTERVAL)?;
+            }
+            write_okay(client)?;
+        }
+        command => {
+            return Err(RustADBError::UnsupportedRequest(command.to_string()));
+        }
+    }
+
+    Ok(false)
+}
+
+/// Body of `host:devices`, `host:devices-l` and `host:track-devices` responses.
+fn devices_listing(devices: &[Arc<FakeServerDevice>], long: bool) -> String {
+    devices.iter().map(|d| d.description(long)).collect()
+}
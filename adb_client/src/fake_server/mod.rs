@@ -0,0 +1,4 @@
+mod fake_adb_server;
+mod fake_server_client;
+
+pub use fake_adb_server::FakeADBServer;
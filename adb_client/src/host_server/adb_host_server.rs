@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusb::{Device, DeviceDescriptor, GlobalContext};
+
+use super::client_handler::handle_client;
+use super::device_registry::{DeviceRegistry, HostDeviceLocation};
+use crate::{
+    ADBTcpDevice, ADBUSBDevice, Result, RustADBError, USBTransport,
+    device::get_default_adb_key_path, is_adb_device,
+};
+
+const USB_SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A local port forwarded to a device service.
+#[derive(Debug)]
+pub(crate) struct ForwardListener {
+    pub(crate) serial: String,
+    running: Arc<AtomicBool>,
+    address: SocketAddr,
+}
+
+impl ForwardListener {
+    fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        // Wake up listening thread so it can notice it has to stop
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// State shared by every thread of a running [`ADBHostServer`].
+#[derive(Debug)]
+pub(crate) struct HostServerContext {
+    pub(crate) registry: DeviceRegistry,
+    pub(crate) private_key_path: PathBuf,
+    forwards: Mutex<HashMap<String, ForwardListener>>,
+    running: AtomicBool,
+    local_address: SocketAddr,
+}
+
+impl HostServerContext {
+    pub(crate) fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Stop server, listening socket gets closed as soon as possible.
+    pub(crate) fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.local_address);
+    }
+
+    /// Connect a device over TCP and register it. Returns the message expected by clients.
+    pub(crate) fn connect_tcp_device(&self, address: SocketAddrV4) -> Result<String> {
+        let serial = address.to_string();
+        if self.registry.find(Some(&serial)).is_ok() {
+            return Ok(format!("already connected to {serial}"));
+        }
+
+        let device = ADBTcpDevice::new_with_custom_private_key(
+            SocketAddr::V4(address),
+            self.private_key_path.clone(),
+        )?;
+        self.registry.add(
+            serial.clone(),
+            HostDeviceLocation::Tcp(address),
+            Box::new(device.into_multiplexer()),
+        )?;
+
+        Ok(format!("connected to {serial}"))
+    }
+
+    /// Forward connections received on `local` to `remote` service of device `serial`.
+    /// Unless `rebind` is set, forwarding a `local` socket which is already forwarded fails.
+    pub(crate) fn add_forward(
+        self: &Arc<Self>,
+        serial: String,
+        local: String,
+        remote: String,
+        rebind: bool,
+    ) -> Result<()> {
+        if !rebind && self.forwards.lock()?.contains_key(&local) {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "cannot rebind existing socket {local}"
+            )));
+        }
+
+        let port = local
+            .strip_prefix("tcp:")
+            .ok_or_else(|| RustADBError::UnsupportedRequest(format!("cannot forward {local}")))?
+            .parse::<u16>()?;
+
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))?;
+        let running = Arc::new(AtomicBool::new(true));
+        let forward = ForwardListener {
+            serial: serial.clone(),
+            running: running.clone(),
+            address: listener.local_addr()?,
+        };
+        if let Some(previous) = self.forwards.lock()?.insert(local.clone(), forward) {
+            previous.stop();
+        }
+
+        let context = self.clone();
+        std::thread::spawn(move || {
+            for client in listener.incoming() {
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(client) = client else {
+                    continue;
+                };
+                let Ok(device) = context.registry.find(Some(&serial)) else {
+                    log::warn!("device {serial} is gone, cannot forward connection on {local}");
+                    continue;
+                };
+                let remote = remote.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = device.connection.bridge(&remote, &client, false) {
+                        log::warn!("cannot forward connection to {remote}: {e}");
+                    }
+                });
+            }
+            log::debug!("stopped forwarding {local}");
+        });
+
+        Ok(())
+    }
+
+    /// Remove forward rule of `local` socket, failing if there is none.
+    pub(crate) fn remove_forward(&self, local: &str) -> Result<()> {
+        match self.forwards.lock()?.remove(local) {
+            Some(forward) => {
+                forward.stop();
+                Ok(())
+            }
+            None => Err(RustADBError::ADBRequestFailed(format!(
+                "listener '{local}' not found"
+            ))),
+        }
+    }
+
+    /// Remove every forward rule, or only the ones targeting device `serial` if specified.
+    pub(crate) fn remove_forwards(&self, serial: Option<&str>) -> Result<()> {
+        self.forwards.lock()?.retain(|_, forward| {
+            let matching = serial.map_or(true, |serial| forward.serial == serial);
+            if matching {
+                forward.stop();
+            }
+            !matching
+        });
+
+        Ok(())
+    }
+}
+
+/// A pure Rust implementation of the ADB host server.
+///
+/// Clients speaking the smart-socket protocol (such as [`crate::ADBServer`], [`crate::ADBServerDevice`] or the `adb` binary) are served,
+/// and their requests are bridged to devices reached over USB (automatically detected) or TCP (after a `host:connect` request).
+#[derive(Debug)]
+pub struct ADBHostServer {
+    address: SocketAddrV4,
+    private_key_path: PathBuf,
+    usb_scan: bool,
+}
+
+impl ADBHostServer {
+    /// Instantiates a new [`ADBHostServer`], listening on given address
+    pub fn new(address: SocketAddrV4) -> Result<Self> {
+        Self::new_with_custom_private_key(address, get_default_adb_key_path()?)
+    }
+
+    /// Instantiates a new [`ADBHostServer`] using a custom private key path to authenticate to devices
+    pub fn new_with_custom_private_key(
+        address: SocketAddrV4,
+        private_key_path: PathBuf,
+    ) -> Result<Self> {
+        Ok(Self {
+            address,
+            private_key_path,
+            usb_scan: true,
+        })
+    }
+
+    /// Enable or disable automatic detection of USB devices (enabled by default).
+    pub fn set_usb_scan(&mut self, enabled: bool) {
+        self.usb_scan = enabled;
+    }
+
+    /// Run server until a `host:kill` request is received.
+    pub fn run(&self) -> Result<()> {
+        self.serve(TcpListener::bind(self.address)?)
+    }
+
+    /// Run server on an already bound `listener` until a `host:kill` request is received.
+    pub fn serve(&self, listener: TcpListener) -> Result<()> {
+        let mut local_address = listener.local_addr()?;
+        if local_address.ip().is_unspecified() {
+            local_address.set_ip(Ipv4Addr::LOCALHOST.into());
+        }
+        log::info!("ADB server listening on {local_address}");
+
+        let context = Arc::new(HostServerContext {
+            registry: DeviceRegistry::default(),
+            private_key_path: self.private_key_path.clone(),
+            forwards: Mutex::default(),
+            running: AtomicBool::new(true),
+            local_address,
+        });
+
+        if self.usb_scan {
+            let context = context.clone();
+            std::thread::spawn(move || {
+                while context.is_running() {
+                    if let Err(e) = scan_usb_devices(&context) {
+                        log::debug!("cannot scan USB devices: {e}");
+                    }
+                    std::thread::sleep(USB_SCAN_INTERVAL);
+                }
+            });
+        }
+
+        for client in listener.incoming() {
+            if !context.is_running() {
+                break;
+            }
+
+            match client {
+                Ok(client) => {
+                    let context = context.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle_client(context, client) {
+                            log::debug!("error while handling client: {e}");
+                        }
+                    });
+                }
+                Err(e) => log::warn!("cannot accept client: {e}"),
+            }
+        }
+
+        context.remove_forwards(None)?;
+        log::info!("ADB server stopped");
+
+        Ok(())
+    }
+}
+
+/// Path of a USB device, as displayed by `adb devices -l` (e.g. `1-4.2`).
+fn usb_path(device: &Device<GlobalContext>) -> String {
+    let ports = device
+        .port_numbers()
+        .unwrap_or_default()
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<String>>()
+        .join(".");
+
+    format!("{}-{ports}", device.bus_number())
+}
+
+fn usb_serial(device: &Device<GlobalContext>, descriptor: &DeviceDescriptor) -> Option<String> {
+    device
+        .open()
+        .and_then(|handle| handle.read_serial_number_string_ascii(descriptor))
+        .ok()
+}
+
+/// Connect every ADB USB device not already known.
+fn scan_usb_devices(context: &HostServerContext) -> Result<()> {
+    for device in rusb::devices()?.iter() {
+        let Ok(descriptor) = device.device_descriptor() else {
+            continue;
+        };
+        if !is_adb_device(&device, &descriptor) {
+            continue;
+        }
+
+        let location = HostDeviceLocation::Usb(usb_path(&device));
+        if context.registry.contains_location(&location)? {
+            continue;
+        }
+
+        let HostDeviceLocation::Usb(path) = &location else {
+            continue;
+        };
+        let serial = usb_serial(&device, &descriptor).unwrap_or_else(|| path.clone());
+
+        match ADBUSBDevice::new_from_transport(
+            USBTransport::new_from_device(device),
+            Some(context.private_key_path.clone()),
+        ) {
+            Ok(adb_device) => {
+                context
+                    .registry
+                    .add(serial, location, Box::new(adb_device.into_multiplexer()))?;
+            }
+            Err(e) => log::debug!("cannot connect to USB device {serial}: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_host_server() {
+    use crate::fake_device::write_test_private_key;
+    use crate::{ADBDeviceExt, ADBServer, ADBServerDevice, FakeADBDevice};
+    use std::io::{Read, Write};
+    use std::sync::mpsc;
+
+    // Requests are sent on raw sockets when `ADBServer` does not expose them
+    fn request(address: SocketAddrV4, request: &str) -> (TcpStream, String) {
+        let mut stream = TcpStream::connect(address).expect("cannot connect to server");
+        stream
+            .write_all(format!("{:04x}{request}", request.len()).as_bytes())
+            .expect("cannot send request");
+        let mut status = [0; 4];
+        stream.read_exact(&mut status).expect("cannot read status");
+        (stream, String::from_utf8_lossy(&status).to_string())
+    }
+
+    fn read_body(stream: &mut TcpStream) -> String {
+        let mut length = [0; 4];
+        stream.read_exact(&mut length).expect("cannot read length");
+        let length =
+            usize::from_str_radix(&String::from_utf8_lossy(&length), 16).expect("invalid length");
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).expect("cannot read body");
+        String::from_utf8_lossy(&body).to_string()
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .expect("cannot bind")
+            .port()
+    }
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop ro.product.model", b"Pixel 9\n");
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+    let SocketAddr::V4(device_address) = fake_device.listen().expect("cannot listen") else {
+        panic!("fake device is not listening on IPv4");
+    };
+    let serial = device_address.to_string();
+
+    let mut host_server = ADBHostServer::new_with_custom_private_key(
+        SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0),
+        write_test_private_key("host_server"),
+    )
+    .expect("cannot create server");
+    host_server.set_usb_scan(false);
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).expect("cannot bind");
+    let SocketAddr::V4(address) = listener.local_addr().expect("cannot get address") else {
+        panic!("server is not listening on IPv4");
+    };
+    let server_thread = std::thread::spawn(move || host_server.serve(listener));
+
+    let mut server = ADBServer::new(address);
+    server
+        .connect_device(device_address)
+        .expect("cannot connect device");
+    let devices = server.devices().expect("cannot list devices");
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].identifier, serial);
+    let devices = server.devices_long().expect("cannot list devices");
+    assert_eq!(devices[0].transport_id, 1);
+
+    let mut device = ADBServerDevice::new(serial.clone(), Some(address));
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut output)
+        .expect("cannot run shell command");
+    assert_eq!(output, b"Pixel 9\n");
+
+    device
+        .push(b"world".as_slice(), "/sdcard/world.txt")
+        .expect("cannot push file");
+    assert_eq!(
+        fake_device
+            .file("/sdcard/world.txt")
+            .expect("cannot get file"),
+        Some(b"world".to_vec())
+    );
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/hello.txt", &mut pulled)
+        .expect("cannot pull file");
+    assert_eq!(pulled, b"hello");
+
+    // Forwarded connections reach an echoing shell
+    let port = free_port();
+    device
+        .forward("shell:".to_string(), format!("tcp:{port}"))
+        .expect("cannot forward");
+    let mut forwarded =
+        TcpStream::connect((Ipv4Addr::LOCALHOST, port)).expect("cannot connect to forward");
+    forwarded.write_all(b"ping").expect("cannot write");
+    let mut echo = [0; 4];
+    forwarded.read_exact(&mut echo).expect("cannot read");
+    assert_eq!(&echo, b"ping");
+
+    // Serial of a `host-serial` request may contain ':'
+    let port = free_port();
+    let forward = format!("host-serial:{serial}:forward:norebind:tcp:{port};shell:");
+    let (mut stream, status) = request(address, &forward);
+    assert_eq!(status, "OKAY");
+    let mut status = [0; 4];
+    stream.read_exact(&mut status).expect("cannot read status");
+    assert_eq!(&status, b"OKAY");
+    let (mut stream, status) = request(address, &forward);
+    assert_eq!(status, "FAIL");
+    assert_eq!(
+        read_body(&mut stream),
+        format!("cannot rebind existing socket tcp:{port}")
+    );
+    let kill_forward = format!("host-serial:{serial}:killforward:tcp:{port}");
+    assert_eq!(request(address, &kill_forward).1, "OKAY");
+    assert_eq!(request(address, &kill_forward).1, "FAIL");
+
+    // Bare requests target the only connected device
+    let (mut stream, status) = request(address, "host:get-state");
+    assert_eq!(status, "OKAY");
+    assert_eq!(read_body(&mut stream), "device");
+    let (mut stream, status) = request(address, &format!("host-serial:{serial}:get-state"));
+    assert_eq!(status, "OKAY");
+    assert_eq!(read_body(&mut stream), "device");
+
+    let (mut stream, status) = request(address, &format!("host:tport:serial:{serial}"));
+    assert_eq!(status, "OKAY");
+    let mut transport_id = [0; 8];
+    stream
+        .read_exact(&mut transport_id)
+        .expect("cannot read transport id");
+    assert_eq!(u64::from_le_bytes(transport_id), 1);
+    assert_eq!(request(address, "host:tport:usb").1, "FAIL");
+    assert_eq!(request(address, "host:transport-id:2").1, "FAIL");
+
+    let (sender, receiver) = mpsc::channel();
+    let result = server.track_devices(|device| {
+        sender
+            .send(device.identifier)
+            .map_err(|_| RustADBError::ConversionError)?;
+        // Stop tracking after first listing
+        Err(RustADBError::ConversionError)
+    });
+    assert!(result.is_err());
+    assert_eq!(receiver.recv().expect("no device tracked"), serial);
+
+    server.kill().expect("cannot kill server");
+    server_thread
+        .join()
+        .expect("server thread panicked")
+        .expect("server failed");
+}
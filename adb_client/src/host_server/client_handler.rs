@@ -0,0 +1,265 @@
+use std::io::Write;
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::adb_host_server::HostServerContext;
+use super::device_registry::{HostDevice, HostDeviceLocation};
+use super::smart_socket::{
+    is_closed, read_request, write_body, write_fail, write_okay, write_okay_with_body,
+};
+use crate::models::{AdbServerCommand, TransportSelector};
+use crate::{Result, RustADBError, WaitForDeviceTransport};
+
+/// Protocol version advertised by `host:version`, matching current `adb` releases.
+const ADB_SERVER_VERSION: u32 = 41;
+/// Features advertised by this server when no device is selected.
+const SERVER_FEATURES: &str = "shell_v2,cmd,stat_v2,ls_v2,fixed_push_mkdir,apex,abb,fixed_push_symlink_timestamp,abb_exec,remount_shell,track_app,sendrecv_v2";
+/// Interval at which connection states are checked while waiting for device changes.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Serve a single client until its connection ends.
+pub(crate) fn handle_client(context: Arc<HostServerContext>, mut client: TcpStream) -> Result<()> {
+    // Device selected by a previous `host:transport` request
+    let mut selected_device: Option<Arc<HostDevice>> = None;
+
+    while let Some(request) = read_request(&mut client)? {
+        log::debug!("received request {request}");
+
+        if request.starts_with("host:") || request.starts_with("host-serial:") {
+            let command = match AdbServerCommand::from_str(&request) {
+                Ok(command) => command,
+                Err(e) => return write_fail(&mut client, &e.to_string()),
+            };
+
+            match handle_host_command(&context, &mut client, &mut selected_device, command) {
+                Ok(true) => continue,
+                Ok(false) => return Ok(()),
+                Err(e) => return write_fail(&mut client, &failure_message(&e)),
+            }
+        }
+
+        let Some(device) = selected_device.take() else {
+            return write_fail(&mut client, "no device selected");
+        };
+
+        return match request.as_str() {
+            "reconnect" => {
+                write_okay(&mut client)?;
+                reconnect_device(&context, device)
+            }
+            r if r.starts_with("reverse:") => {
+                write_fail(&mut client, "reverse forwarding is not supported")
+            }
+            service => match device.connection.bridge(service, &client, true) {
+                Ok(()) => Ok(()),
+                Err(e) => write_fail(&mut client, &e.to_string()),
+            },
+        };
+    }
+
+    Ok(())
+}
+
+/// Handle a `host:*` request. Returns whether connection can receive another request.
+fn handle_host_command(
+    context: &Arc<HostServerContext>,
+    client: &mut TcpStream,
+    selected_device: &mut Option<Arc<HostDevice>>,
+    command: AdbServerCommand,
+) -> Result<bool> {
+    match command {
+        AdbServerCommand::Version => {
+            write_okay_with_body(client, format!("{ADB_SERVER_VERSION:04x}"))?;
+        }
+        AdbServerCommand::Kill => {
+            write_okay(client)?;
+            context.stop();
+        }
+        AdbServerCommand::Devices | AdbServerCommand::DevicesLong => {
+            let long = matches!(command, AdbServerCommand::DevicesLong);
+            let devices = context.registry.devices()?;
+            write_okay_with_body(client, devices_listing(&devices, long))?;
+        }
+        AdbServerCommand::TrackDevices => {
+            write_okay(client)?;
+            let mut last_listing = None;
+            // Stop tracking as soon as client is gone, writing to it is not enough to notice
+            while context.is_running() && !is_closed(client)? {
+                let generation = context.registry.generation()?;
+                let listing = devices_listing(&context.registry.devices()?, false);
+                if last_listing.as_ref() != Some(&listing) {
+                    write_body(client, &listing)?;
+                    last_listing = Some(listing);
+                }
+                context
+                    .registry
+                    .wait_for_change(generation, DEVICE_POLL_INTERVAL)?;
+            }
+        }
+        AdbServerCommand::HostFeatures => {
+            let features = target_device(context, selected_device)
+                .ok()
+                .and_then(|d| d.connection.banner().map(|b| b.features.join(",")))
+                .unwrap_or_else(|| SERVER_FEATURES.to_string());
+            write_okay_with_body(client, &features)?;
+        }
+        AdbServerCommand::Connect(address) => {
+            let message = context
+                .connect_tcp_device(address)
+                .unwrap_or_else(|e| format!("failed to connect to {address}: {e}"));
+            write_okay_with_body(client, &message)?;
+        }
+        AdbServerCommand::Disconnect(address) => {
+            match context.registry.remove(&address.to_string())? {
+                Some(_) => write_okay_with_body(client, format!("disconnected {address}"))?,
+                None => write_fail(client, &format!("no such device '{address}'"))?,
+            }
+        }
+        AdbServerCommand::GetState => {
+            let device = target_device(context, selected_device)?;
+            write_okay_with_body(client, device.state())?;
+        }
+        AdbServerCommand::TransportAny
+        | AdbServerCommand::TransportUsb
+        | AdbServerCommand::TransportLocal
+        | AdbServerCommand::TransportSerial(_)
+        | AdbServerCommand::TransportId(_) => {
+            let selector = match command {
+                AdbServerCommand::TransportUsb => TransportSelector::Usb,
+                AdbServerCommand::TransportLocal => TransportSelector::Local,
+                AdbServerCommand::TransportSerial(serial) => TransportSelector::Serial(serial),
+                AdbServerCommand::TransportId(id) => TransportSelector::Id(id),
+                _ => TransportSelector::Any,
+            };
+            *selected_device = Some(context.registry.select(&selector)?);
+            write_okay(client)?;
+            return Ok(true);
+        }
+        AdbServerCommand::Tport(selector) => {
+            let device = context.registry.select(&selector)?;
+            // Transport id is sent as a raw little-endian integer, not as a length-prefixed body
+            write_okay(client)?;
+            client.write_all(&u64::from(device.transport_id).to_le_bytes())?;
+            *selected_device = Some(device);
+            return Ok(true);
+        }
+        AdbServerCommand::HostSerial(serial, command) => {
+            let device = context.registry.find(Some(&serial))?;
+            return handle_host_command(context, client, &mut Some(device), *command);
+        }
+        AdbServerCommand::ReconnectOffline => {
+            // Lost devices are forgotten while listing, USB ones will be detected again
+            context.registry.devices()?;
+            write_okay(client)?;
+        }
+        AdbServerCommand::WaitForDevice(state, transport, serial) => {
+            write_okay(client)?;
+            let state = state.to_string();
+            loop {
+                if is_closed(client)? {
+                    return Ok(false);
+                }
+                let generation = context.registry.generation()?;
+                let found = context.registry.devices()?.iter().any(|d| {
+                    let matching_transport = match transport {
+                        WaitForDeviceTransport::Usb => {
+                            matches!(d.location, HostDeviceLocation::Usb(_))
+                        }
+                        WaitForDeviceTransport::Local => {
+                            matches!(d.location, HostDeviceLocation::Tcp(_))
+                        }
+                        WaitForDeviceTransport::Any => true,
+                    };
+                    let matching_serial = serial.as_ref().map_or(true, |s| d.serial == *s);
+                    matching_transport && matching_serial && d.state() == state
+                });
+                if found {
+                    break;
+                }
+                context
+                    .registry
+                    .wait_for_change(generation, DEVICE_POLL_INTERVAL)?;
+            }
+            write_okay(client)?;
+        }
+        AdbServerCommand::Forward(remote, local) => {
+            forward(context, client, selected_device, remote, local, true)?;
+        }
+        AdbServerCommand::ForwardNoRebind(remote, local) => {
+            forward(context, client, selected_device, remote, local, false)?;
+        }
+        AdbServerCommand::KillForward(local) => {
+            context.remove_forward(&local)?;
+            write_okay(client)?;
+            write_okay(client)?;
+        }
+        AdbServerCommand::ForwardRemoveAll => {
+            let serial = selected_device.as_ref().map(|d| d.serial.as_str());
+            context.remove_forwards(serial)?;
+            write_okay(client)?;
+        }
+        command => {
+            return Err(RustADBError::UnsupportedRequest(command.to_string()));
+        }
+    }
+
+    Ok(false)
+}
+
+/// Message sent to client when a request fails, which is the one of `adb` when known.
+fn failure_message(error: &RustADBError) -> String {
+    match error {
+        RustADBError::ADBRequestFailed(message) | RustADBError::DeviceNotFound(message) => {
+            message.clone()
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Device targeted by a request: the one previously selected, or the only connected one.
+fn target_device(
+    context: &HostServerContext,
+    selected_device: &Option<Arc<HostDevice>>,
+) -> Result<Arc<HostDevice>> {
+    match selected_device {
+        Some(device) => Ok(device.clone()),
+        None => context.registry.find(None),
+    }
+}
+
+/// Forward `local` socket to `remote` service of targeted device.
+fn forward(
+    context: &Arc<HostServerContext>,
+    client: &mut TcpStream,
+    selected_device: &Option<Arc<HostDevice>>,
+    remote: String,
+    local: String,
+    rebind: bool,
+) -> Result<()> {
+    let device = target_device(context, selected_device)?;
+    context.add_forward(device.serial.clone(), local, remote, rebind)?;
+    // First OKAY acknowledges connection to host, second one the forward itself
+    write_okay(client)?;
+    write_okay(client)
+}
+
+/// Body of `host:devices`, `host:devices-l` and `host:track-devices` responses.
+fn devices_listing(devices: &[Arc<HostDevice>], long: bool) -> String {
+    devices.iter().map(|d| d.description(long)).collect()
+}
+
+/// Drop connection to `device`. USB devices will be detected again, TCP ones are reconnected.
+fn reconnect_device(context: &HostServerContext, device: Arc<HostDevice>) -> Result<()> {
+    context.registry.remove(&device.serial)?;
+    let location = device.location.clone();
+    // Last reference to device, this closes its connection
+    drop(device);
+
+    if let HostDeviceLocation::Tcp(address) = location {
+        log::info!("{}", context.connect_tcp_device(address)?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,311 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddrV4, TcpStream};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::constants::BUFFER_SIZE;
+use crate::models::TransportSelector;
+use crate::{ADBDeviceBanner, ADBMessageTransport, ADBMultiplexer, Result, RustADBError};
+
+/// A connection to a device, able to open streams to its services.
+pub(crate) trait DeviceConnection: Send + Sync {
+    /// Open a stream to `service` on device, and bridge it with `client` until one of them gets closed.
+    /// If `acknowledge` is set, an `OKAY` is sent to client once the stream is opened.
+    fn bridge(&self, service: &str, client: &TcpStream, acknowledge: bool) -> Result<()>;
+
+    /// Whether underlying connection is still usable.
+    fn is_alive(&self) -> bool;
+
+    /// Banner advertised by device when connecting, if any.
+    fn banner(&self) -> Option<&ADBDeviceBanner>;
+}
+
+/// Copy everything from `reader` to `writer` using chunks as big as an ADB payload.
+fn pump(reader: &mut dyn Read, writer: &mut dyn Write) -> std::io::Result<()> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer)? {
+            0 => return Ok(()),
+            size => writer.write_all(&buffer[..size])?,
+        }
+    }
+}
+
+impl<T: ADBMessageTransport + Sync> DeviceConnection for ADBMultiplexer<T> {
+    fn bridge(&self, service: &str, client: &TcpStream, acknowledge: bool) -> Result<()> {
+        let stream = self.open_stream(service)?;
+        let mut client_writer = client;
+        if acknowledge {
+            client_writer.write_all(b"OKAY")?;
+        }
+
+        let (mut reader, mut writer) = stream.split();
+        let mut client_reader = client.try_clone()?;
+        let upstream = std::thread::spawn(move || {
+            if let Err(e) = pump(&mut client_reader, &mut writer) {
+                log::debug!("error while forwarding data to device: {e}");
+            }
+            // Client closed its side, device side has to be closed too
+            let _ = writer.close();
+        });
+
+        if let Err(e) = pump(&mut reader, &mut client_writer) {
+            log::debug!("error while forwarding data to client: {e}");
+        }
+        let _ = client.shutdown(Shutdown::Both);
+        let _ = upstream.join();
+
+        Ok(())
+    }
+
+    fn is_alive(&self) -> bool {
+        ADBMultiplexer::is_alive(self)
+    }
+
+    fn banner(&self) -> Option<&ADBDeviceBanner> {
+        ADBMultiplexer::banner(self)
+    }
+}
+
+/// How a device is reached by the server.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum HostDeviceLocation {
+    /// USB device, identified by its `bus-port.port...` path
+    Usb(String),
+    /// Device reached over TCP
+    Tcp(SocketAddrV4),
+}
+
+/// A device currently known by the server.
+pub(crate) struct HostDevice {
+    pub(crate) serial: String,
+    pub(crate) transport_id: u32,
+    pub(crate) location: HostDeviceLocation,
+    pub(crate) connection: Box<dyn DeviceConnection>,
+}
+
+impl HostDevice {
+    /// State of device, as displayed by `adb devices`.
+    pub(crate) fn state(&self) -> String {
+        match self.connection.banner() {
+            Some(banner) if !banner.system_type.is_empty() => banner.system_type.clone(),
+            _ => "device".to_string(),
+        }
+    }
+
+    /// Line describing this device in `host:devices` or `host:devices-l` responses.
+    pub(crate) fn description(&self, long: bool) -> String {
+        let usb_path = match &self.location {
+            HostDeviceLocation::Usb(path) => Some(path.as_str()),
+            HostDeviceLocation::Tcp(_) => None,
+        };
+        device_description(
+            &self.serial,
+            &self.state(),
+            usb_path,
+            self.connection.banner(),
+            self.transport_id,
+            long,
+        )
+    }
+}
+
+/// Line describing a device in `host:devices` or `host:devices-l` responses.
+pub(crate) fn device_description(
+    serial: &str,
+    state: &str,
+    usb_path: Option<&str>,
+    banner: Option<&ADBDeviceBanner>,
+    transport_id: u32,
+    long: bool,
+) -> String {
+    if !long {
+        return format!("{serial}\t{state}\n");
+    }
+
+    let mut description = format!("{serial:<22} {state} ");
+    if let Some(path) = usb_path {
+        description.push_str(&format!("usb:{path} "));
+    }
+    if let Some(banner) = banner {
+        if let (Some(product), Some(model), Some(device)) =
+            (banner.product(), banner.model(), banner.device())
+        {
+            description.push_str(&format!(
+                "product:{} model:{} device:{} ",
+                sanitize(product),
+                sanitize(model),
+                sanitize(device)
+            ));
+        }
+    }
+    description.push_str(&format!("transport_id:{transport_id}\n"));
+
+    description
+}
+
+impl std::fmt::Debug for HostDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostDevice")
+            .field("serial", &self.serial)
+            .field("transport_id", &self.transport_id)
+            .field("location", &self.location)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Replace characters that cannot be part of a `devices-l` field, as done by `adb`.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    devices: Vec<Arc<HostDevice>>,
+    generation: u64,
+}
+
+/// Devices known by the server. Every modification bumps a generation counter, allowing to wait for changes.
+#[derive(Debug, Default)]
+pub(crate) struct DeviceRegistry {
+    state: Mutex<RegistryState>,
+    changed: Condvar,
+    next_transport_id: AtomicU32,
+}
+
+impl DeviceRegistry {
+    /// Register a newly connected device.
+    pub(crate) fn add(
+        &self,
+        serial: String,
+        location: HostDeviceLocation,
+        connection: Box<dyn DeviceConnection>,
+    ) -> Result<Arc<HostDevice>> {
+        let device = Arc::new(HostDevice {
+            serial,
+            transport_id: self.next_transport_id.fetch_add(1, Ordering::SeqCst) + 1,
+            location,
+            connection,
+        });
+        log::info!("device {} connected", device.serial);
+
+        let mut state = self.state.lock()?;
+        state.devices.push(device.clone());
+        state.generation += 1;
+        self.changed.notify_all();
+
+        Ok(device)
+    }
+
+    /// Remove device with given `serial`, returning it if it was known.
+    pub(crate) fn remove(&self, serial: &str) -> Result<Option<Arc<HostDevice>>> {
+        let mut state = self.state.lock()?;
+        let Some(position) = state.devices.iter().position(|d| d.serial == serial) else {
+            return Ok(None);
+        };
+
+        let device = state.devices.remove(position);
+        state.generation += 1;
+        self.changed.notify_all();
+        log::info!("device {} disconnected", device.serial);
+
+        Ok(Some(device))
+    }
+
+    /// List currently connected devices, forgetting about the ones whose connection has been lost.
+    pub(crate) fn devices(&self) -> Result<Vec<Arc<HostDevice>>> {
+        let mut state = self.state.lock()?;
+        let before = state.devices.len();
+        state.devices.retain(|d| {
+            let alive = d.connection.is_alive();
+            if !alive {
+                log::info!("device {} connection lost", d.serial);
+            }
+            alive
+        });
+        if state.devices.len() != before {
+            state.generation += 1;
+            self.changed.notify_all();
+        }
+
+        Ok(state.devices.clone())
+    }
+
+    /// Whether a device is already connected at given `location`.
+    pub(crate) fn contains_location(&self, location: &HostDeviceLocation) -> Result<bool> {
+        Ok(self.devices()?.iter().any(|d| &d.location == location))
+    }
+
+    /// Find device with given `serial`, or the only connected device if not specified.
+    pub(crate) fn find(&self, serial: Option<&str>) -> Result<Arc<HostDevice>> {
+        match serial {
+            Some(serial) => self.select(&TransportSelector::Serial(serial.to_string())),
+            None => self.select(&TransportSelector::Any),
+        }
+    }
+
+    /// Find device selected by `selector`, which has to be the only matching one unless a serial or transport id is given.
+    pub(crate) fn select(&self, selector: &TransportSelector) -> Result<Arc<HostDevice>> {
+        let mut devices = self.devices()?.into_iter();
+        // Messages are the ones of `adb`, when no device or more than one device match
+        let (none, many, matching): (&str, &str, Vec<_>) = match selector {
+            TransportSelector::Serial(serial) => {
+                return devices.find(|d| d.serial == *serial).ok_or_else(|| {
+                    RustADBError::DeviceNotFound(format!("device '{serial}' not found"))
+                });
+            }
+            TransportSelector::Id(id) => {
+                return devices
+                    .find(|d| u64::from(d.transport_id) == *id)
+                    .ok_or_else(|| {
+                        RustADBError::DeviceNotFound(format!("no device with transport id '{id}'"))
+                    });
+            }
+            TransportSelector::Any => (
+                "no devices/emulators found",
+                "more than one device/emulator",
+                devices.collect(),
+            ),
+            TransportSelector::Usb => (
+                "no devices found",
+                "more than one device",
+                devices
+                    .filter(|d| matches!(d.location, HostDeviceLocation::Usb(_)))
+                    .collect(),
+            ),
+            TransportSelector::Local => (
+                "no emulators found",
+                "more than one emulator",
+                devices
+                    .filter(|d| matches!(d.location, HostDeviceLocation::Tcp(_)))
+                    .collect(),
+            ),
+        };
+
+        let mut matching = matching.into_iter();
+        match (matching.next(), matching.next()) {
+            (Some(device), None) => Ok(device),
+            (None, _) => Err(RustADBError::DeviceNotFound(none.into())),
+            (Some(_), Some(_)) => Err(RustADBError::DeviceNotFound(many.into())),
+        }
+    }
+
+    /// Current generation of the registry.
+    pub(crate) fn generation(&self) -> Result<u64> {
+        Ok(self.state.lock()?.generation)
+    }
+
+    /// Wait for registry to change from given `generation`, at most `timeout`.
+    pub(crate) fn wait_for_change(&self, generation: u64, timeout: Duration) -> Result<()> {
+        let state = self.state.lock()?;
+        let _unused = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.generation == generation)?;
+
+        Ok(())
+    }
+}
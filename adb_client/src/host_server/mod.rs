@@ -0,0 +1,10 @@
+mod adb_host_server;
+mod client_handler;
+mod device_registry;
+mod smart_socket;
+
+pub use adb_host_server::ADBHostServer;
+pub(crate) use device_registry::{DeviceConnection, device_description};
+pub(crate) use smart_socket::{
+    is_closed, read_request, write_body, write_fail, write_okay, write_okay_with_body,
+};
@@ -0,0 +1,51 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+use crate::Result;
+use crate::models::{decode_hex_length, encode_hex_framed};
+
+/// Read a request framed as 4 hexadecimal digits giving its length, followed by its content.
+/// Returns `None` if client closed connection.
+pub(crate) fn read_request(client: &mut TcpStream) -> Result<Option<String>> {
+    let mut length = [0; 4];
+    match client.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut request = vec![0; decode_hex_length(length)? as usize];
+    client.read_exact(&mut request)?;
+
+    Ok(Some(String::from_utf8(request)?))
+}
+
+pub(crate) fn write_okay(client: &mut TcpStream) -> Result<()> {
+    Ok(client.write_all(b"OKAY")?)
+}
+
+/// Write `body` prefixed by its length as 4 hexadecimal digits.
+pub(crate) fn write_body(client: &mut TcpStream, body: impl AsRef<[u8]>) -> Result<()> {
+    Ok(client.write_all(&encode_hex_framed(body.as_ref()))?)
+}
+
+pub(crate) fn write_okay_with_body(client: &mut TcpStream, body: impl AsRef<[u8]>) -> Result<()> {
+    write_okay(client)?;
+    write_body(client, body)
+}
+
+/// Whether `client` closed its connection, or lost it. Nothing is expected to be read from it.
+pub(crate) fn is_closed(client: &TcpStream) -> Result<bool> {
+    client.set_nonblocking(true)?;
+    let closed = match client.peek(&mut [0]) {
+        Ok(size) => size == 0,
+        Err(e) => e.kind() != ErrorKind::WouldBlock,
+    };
+    client.set_nonblocking(false)?;
+    Ok(closed)
+}
+
+pub(crate) fn write_fail(client: &mut TcpStream, message: &str) -> Result<()> {
+    client.write_all(b"FAIL")?;
+    write_body(client, message)
+}
@@ -0,0 +1,311 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::utils::{check_package_manager_response, shell_quote};
+use crate::{InstallOptions, Result, RustADBError};
+
+/// Directory of `.apks` archives holding standalone APKs, installed instead of splits on old devices.
+const STANDALONES_DIRECTORY: &str = "standalones/";
+
+/// Devices running commands whose input is streamed from host, as required by install sessions.
+pub(crate) trait ExecDevice {
+    /// Whether device supports `abb_exec:` services, reaching package manager without going through a shell.
+    fn supports_abb_exec(&mut self) -> Result<bool>;
+
+    /// Open `service`, write `input` into it and return everything it outputs until it gets closed.
+    fn exec(&mut self, service: &str, input: &mut dyn Read) -> Result<Vec<u8>>;
+}
+
+/// An APK to write into an install session.
+struct SessionApk {
+    /// Name given to APK in session.
+    name: String,
+    size: u64,
+    /// Local file holding APK, and index of APK if this file is an archive.
+    path: PathBuf,
+    archive_index: Option<usize>,
+}
+
+impl SessionApk {
+    /// Run `f` on a reader of contents of this APK.
+    fn read<T, F: FnOnce(&mut dyn Read) -> Result<T>>(&self, f: F) -> Result<T> {
+        let mut file = File::open(&self.path)?;
+        match self.archive_index {
+            None => f(&mut file),
+            Some(index) => f(&mut ZipArchive::new(file)?.by_index(index)?),
+        }
+    }
+}
+
+/// Install all APKs of `apk_paths` as a single package, in an install session. See [`crate::ADBDeviceExt::install_multiple`].
+pub(crate) fn install_multiple<D: ExecDevice + ?Sized>(
+    device: &mut D,
+    apk_paths: &[&dyn AsRef<Path>],
+    options: &InstallOptions,
+) -> Result<()> {
+    let mut apks = Vec::new();
+    for path in apk_paths {
+        collect_apks(path.as_ref(), &mut apks)?;
+    }
+
+    let total_size = apks.iter().map(|apk| apk.size).sum();
+    let mut session = InstallSession::create(device, options, total_size)?;
+    let result = apks
+        .iter()
+        .try_for_each(|apk| session.write(apk))
+        .and_then(|()| session.commit());
+
+    if result.is_err() {
+        if let Err(e) = session.abandon() {
+            log::warn!("cannot abandon install session {}: {e}", session.id);
+        }
+    }
+    result
+}
+
+/// APKs to install from local file `path`: either an APK, or an `.apks` / `.xapk` archive of split APKs.
+fn collect_apks(path: &Path, apks: &mut Vec<SessionApk>) -> Result<()> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("apk") => {
+            apks.push(SessionApk {
+                name: format!("{}_{}", apks.len(), file_name(&path.to_string_lossy())),
+                size: path.metadata()?.len(),
+                path: path.to_path_buf(),
+                archive_index: None,
+            });
+            Ok(())
+        }
+        Some("apks" | "xapk") => {
+            let mut archive = ZipArchive::new(File::open(path)?)?;
+            let mut entries = Vec::new();
+            for index in 0..archive.len() {
+                let entry = archive.by_index(index)?;
+                if entry.is_file() && entry.name().to_lowercase().ends_with(".apk") {
+                    entries.push((index, entry.name().to_string(), entry.size()));
+                }
+            }
+
+            // Standalone APKs duplicate splits, and are only installed when archive has nothing else
+            if entries
+                .iter()
+                .any(|(_, name, _)| !name.starts_with(STANDALONES_DIRECTORY))
+            {
+                entries.retain(|(_, name, _)| !name.starts_with(STANDALONES_DIRECTORY));
+            }
+            if entries.is_empty() {
+                return Err(RustADBError::WrongFileExtension(format!(
+                    "{} does not contain any APK file",
+                    path.display()
+                )));
+            }
+
+            for (index, name, size) in entries {
+                apks.push(SessionApk {
+                    name: format!("{}_{}", apks.len(), file_name(&name)),
+                    size,
+                    path: path.to_path_buf(),
+                    archive_index: Some(index),
+                });
+            }
+            Ok(())
+        }
+        _ => Err(RustADBError::WrongFileExtension(format!(
+            "{} is not an APK file or archive",
+            path.display()
+        ))),
+    }
+}
+
+/// Last component of `path`, separated by slashes as in archives.
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// A package manager install session, created with `pm install-create`.
+struct InstallSession<'d, D: ?Sized> {
+    device: &'d mut D,
+    id: u32,
+    /// Whether package manager is reached through `abb_exec:` instead of `cmd`.
+    abb_exec: bool,
+}
+
+impl<'d, D: ExecDevice + ?Sized> InstallSession<'d, D> {
+    /// Create a session installing APKs of `total_size` bytes with `options`.
+    fn create(device: &'d mut D, options: &InstallOptions, total_size: u64) -> Result<Self> {
+        let abb_exec = device.supports_abb_exec()?;
+        let mut args = vec!["install-create".to_string()];
+        args.extend(options.args());
+        args.extend(["-S".to_string(), total_size.to_string()]);
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let output = package_command(device, abb_exec, &args, &mut std::io::empty())?;
+
+        // Output looks like "Success: created install session [1234567]"
+        let id = output
+            .strip_prefix("Success")
+            .and_then(|output| output.split_once('['))
+            .and_then(|(_, id)| id.split_once(']'))
+            .and_then(|(id, _)| id.parse().ok())
+            .ok_or_else(|| RustADBError::ADBRequestFailed(output.clone()))?;
+
+        log::debug!("created install session {id}");
+        Ok(Self {
+            device,
+            id,
+            abb_exec,
+        })
+    }
+
+    /// Write `apk` into session.
+    fn write(&mut self, apk: &SessionApk) -> Result<()> {
+        let args = [
+            "install-write",
+            "-S",
+            &apk.size.to_string(),
+            &self.id.to_string(),
+            &apk.name,
+            "-",
+        ];
+        let output = apk.read(|input| package_command(self.device, self.abb_exec, &args, input))?;
+        self.check(output)
+    }
+
+    /// Install everything written into session.
+    fn commit(&mut self) -> Result<()> {
+        let output = package_command(
+            self.device,
+            self.abb_exec,
+            &["install-commit", &self.id.to_string()],
+            &mut std::io::empty(),
+        )?;
+        self.check(output)?;
+
+        log::info!("install session {} successfully committed", self.id);
+        Ok(())
+    }
+
+    /// Drop session and everything written into it.
+    fn abandon(&mut self) -> Result<()> {
+        let output = package_command(
+            self.device,
+            self.abb_exec,
+            &["install-abandon", &self.id.to_string()],
+            &mut std::io::empty(),
+        )?;
+        self.check(output)
+    }
+
+    /// Check `output` of a session command, failures not coming from package manager being reported along with session.
+    fn check(&self, output: String) -> Result<()> {
+        match check_package_manager_response(output.as_bytes()) {
+            Err(RustADBError::ADBRequestFailed(output)) => {
+                Err(RustADBError::InstallSessionFailed(self.id, output))
+            }
+            result => result,
+        }
+    }
+}
+
+/// Run package manager command `args`, writing `input` into it, and return its trimmed output.
+fn package_command<D: ExecDevice + ?Sized>(
+    device: &mut D,
+    abb_exec: bool,
+    args: &[&str],
+    input: &mut dyn Read,
+) -> Result<String> {
+    let service = match abb_exec {
+        // Arguments are separated by NUL bytes, and reach package manager untouched
+        true => format!("abb_exec:package\0{}", args.join("\0")),
+        false => {
+            let args = args.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>();
+            format!("exec:cmd package {}", args.join(" "))
+        }
+    };
+
+    let output = device.exec(&service, input)?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+#[test]
+fn test_install_multiple() {
+    use std::io::Write;
+
+    use crate::{ADBDeviceExt, FakeADBDevice, InstallFailureReason};
+
+    let private_key_path = crate::fake_device::write_test_private_key("install_session");
+    let base_apk = private_key_path.with_file_name("base.apk");
+    std::fs::write(&base_apk, b"PK\x03\x04base").expect("cannot write APK");
+    let invalid_apk = private_key_path.with_file_name("invalid.apk");
+    std::fs::write(&invalid_apk, b"invalid").expect("cannot write APK");
+
+    let archive = private_key_path.with_file_name("app.apks");
+    let mut writer = zip::ZipWriter::new(File::create(&archive).expect("cannot create archive"));
+    for name in [
+        "splits/base-master.apk",
+        "splits/base-arm64_v8a.apk",
+        "standalones/standalone-arm64_v8a.apk",
+        "toc.pb",
+    ] {
+        writer
+            .start_file(name, zip::write::SimpleFileOptions::default())
+            .and_then(|()| Ok(writer.write_all(b"PK\x03\x04split")?))
+            .expect("cannot write archive entry");
+    }
+    writer.finish().expect("cannot write archive");
+
+    let mut fake_device = FakeADBDevice::new();
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    // Splits of archive are installed through `abb_exec:`, without standalone APKs
+    device
+        .install_multiple(&[&archive])
+        .expect("cannot install archive");
+    let installed = ["0_base-master.apk", "1_base-arm64_v8a.apk"];
+    assert_eq!(
+        fake_device.install_sessions().ok(),
+        Some(vec![(
+            installed.iter().map(|s| s.to_string()).collect(),
+            true
+        )])
+    );
+    assert!(
+        fake_device
+            .opened_services()
+            .expect("cannot get services")
+            .contains(&"abb_exec:package\0install-commit\x001".to_string())
+    );
+
+    // Session is abandoned when commit fails
+    let mut banner = fake_device.banner().clone();
+    banner.features.retain(|feature| feature != "abb_exec");
+    fake_device.set_banner(banner);
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+    assert!(matches!(
+        device.install_multiple(&[&base_apk, &invalid_apk]),
+        Err(RustADBError::InstallFailed(
+            InstallFailureReason::InvalidApk,
+            _
+        ))
+    ));
+    assert_eq!(
+        fake_device.install_sessions().map(|s| s.len()).ok(),
+        Some(1)
+    );
+    assert!(
+        fake_device
+            .opened_services()
+            .expect("cannot get services")
+            .contains(&"exec:cmd package 'install-abandon' '2'".to_string())
+    );
+
+    assert!(matches!(
+        device.install_multiple(&[&private_key_path]),
+        Err(RustADBError::WrongFileExtension(_))
+    ));
+}
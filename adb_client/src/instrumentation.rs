@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Instant;
+
+use crate::models::{InstrumentationOptions, InstrumentationReport, TestResult, TestStatus};
+use crate::{ADBDeviceExt, Result};
+
+/// Status code reported when a test starts.
+const STATUS_START: i32 = 1;
+
+/// Run instrumentation with `options`, reporting tests to `on_test` as they finish. See [`ADBDeviceExt::run_instrumentation`].
+pub(crate) fn run_instrumentation<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    options: &InstrumentationOptions,
+    on_test: &mut dyn FnMut(&TestResult),
+) -> Result<InstrumentationReport> {
+    let args = options.args();
+    let mut command = vec!["am", "instrument", "-w", "-r"];
+    command.extend(args.iter().map(String::as_str));
+
+    let mut parser = InstrumentationParser::new(on_test);
+    device.shell_command(&command, &mut parser)?;
+    Ok(parser.finish())
+}
+
+/// Kind of a key-value pair reported by instrumentation.
+enum PairKind {
+    /// `INSTRUMENTATION_STATUS`, about current test
+    Status,
+    /// `INSTRUMENTATION_RESULT`, about whole run
+    Result,
+}
+
+/// Parser of output of `am instrument -r`, made of key-value pairs whose values may span several lines:
+///
+/// ```text
+/// INSTRUMENTATION_STATUS: class=com.example.FooTest
+/// INSTRUMENTATION_STATUS: test=testBar
+/// INSTRUMENTATION_STATUS: stack=java.lang.AssertionError
+///     at com.example.FooTest.testBar(FooTest.java:12)
+/// INSTRUMENTATION_STATUS_CODE: -2
+/// ```
+pub(crate) struct InstrumentationParser<'c> {
+    on_test: &'c mut dyn FnMut(&TestResult),
+    /// Output which does not make a whole line yet
+    buffer: Vec<u8>,
+    /// Last key-value pair, whose value goes on until next report
+    pending: Option<(PairKind, String, String)>,
+    /// Values reported about current test
+    status: BTreeMap<String, String>,
+    /// Test currently running, along with when it started
+    current: Option<(String, String, Instant)>,
+    start: Instant,
+    report: InstrumentationReport,
+}
+
+impl<'c> InstrumentationParser<'c> {
+    pub(crate) fn new(on_test: &'c mut dyn FnMut(&TestResult)) -> Self {
+        Self {
+            on_test,
+            buffer: Vec::new(),
+            pending: None,
+            status: BTreeMap::new(),
+            current: None,
+            start: Instant::now(),
+            report: InstrumentationReport::default(),
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        if let Some(pair) = line.strip_prefix("INSTRUMENTATION_STATUS: ") {
+            self.start_pair(PairKind::Status, pair);
+        } else if let Some(pair) = line.strip_prefix("INSTRUMENTATION_RESULT: ") {
+            self.start_pair(PairKind::Result, pair);
+        } else if let Some(code) = line.strip_prefix("INSTRUMENTATION_STATUS_CODE: ") {
+            self.flush_pending();
+            self.on_status_code(code.trim().parse().unwrap_or_default());
+        } else if let Some(code) = line.strip_prefix("INSTRUMENTATION_CODE: ") {
+            self.flush_pending();
+            self.report.code = code.trim().parse().ok();
+        } else if let Some(error) = line
+            .strip_prefix("INSTRUMENTATION_FAILED: ")
+            .or_else(|| line.strip_prefix("INSTRUMENTATION_ABORTED: "))
+        {
+            self.flush_pending();
+            self.report.error = Some(error.trim().to_string());
+        } else if let Some((_, _, value)) = self.pending.as_mut() {
+            value.push('\n');
+            value.push_str(line);
+        }
+    }
+
+    fn start_pair(&mut self, kind: PairKind, pair: &str) {
+        self.flush_pending();
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        self.pending = Some((kind, key.to_string(), value.to_string()));
+    }
+
+    fn flush_pending(&mut self) {
+        if let Some((kind, key, value)) = self.pending.take() {
+            let value = value.trim_end().to_string();
+            match kind {
+                PairKind::Status => self.status.insert(key, value),
+                PairKind::Result => self.report.results.insert(key, value),
+            };
+        }
+    }
+
+    fn on_status_code(&mut self, code: i32) {
+        let mut status = std::mem::take(&mut self.status);
+        let class = status.remove("class").unwrap_or_default();
+        let name = status.remove("test").unwrap_or_default();
+
+        if code == STATUS_START {
+            self.current = Some((class, name, Instant::now()));
+            return;
+        }
+        let Some(test_status) = TestStatus::from_code(code) else {
+            return;
+        };
+
+        let started = match self.current.take() {
+            Some((current_class, current_name, started))
+                if current_class == class && current_name == name =>
+            {
+                Some(started)
+            }
+            _ => None,
+        };
+        self.add_test(TestResult {
+            class,
+            name,
+            status: test_status,
+            stack: status.remove("stack"),
+            duration: started.map(|started| started.elapsed()).unwrap_or_default(),
+        });
+    }
+
+    fn add_test(&mut self, test: TestResult) {
+        (self.on_test)(&test);
+        self.report.tests.push(test);
+    }
+
+    /// Parse end of output, and return outcome of instrumentation.
+    pub(crate) fn finish(mut self) -> InstrumentationReport {
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+            self.parse_line(line.trim_end_matches('\r'));
+        }
+        self.flush_pending();
+
+        // Process under test crashed, e.g. `INSTRUMENTATION_RESULT: shortMsg=Process crashed.`
+        if let Some(message) = self.report.results.get("shortMsg") {
+            self.report.error.get_or_insert_with(|| message.clone());
+        }
+        if self.report.code.is_none() {
+            self.report
+                .error
+                .get_or_insert_with(|| "instrumentation did not complete".to_string());
+        }
+        // Test running when instrumentation stopped is reported as an error
+        if let Some((class, name, started)) = self.current.take() {
+            let stack = self
+                .report
+                .results
+                .get("longMsg")
+                .or(self.report.error.as_ref())
+                .cloned();
+            self.add_test(TestResult {
+                class,
+                name,
+                status: TestStatus::Error,
+                stack,
+                duration: started.elapsed(),
+            });
+        }
+
+        self.report.duration = self.start.elapsed();
+        self.report
+    }
+}
+
+impl Write for InstrumentationParser<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            self.parse_line(line.trim_end_matches(['\r', '\n']));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_instrumentation() {
+    use crate::FakeADBDevice;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response(
+        "am instrument -w -r -e 'class' 'com.example.FooTest' -e 'shardIndex' '0' -e 'numShards' '2' \
+         'com.example.test/androidx.test.runner.AndroidJUnitRunner'",
+        b"INSTRUMENTATION_STATUS: class=com.example.FooTest\n\
+          INSTRUMENTATION_STATUS: current=1\n\
+          INSTRUMENTATION_STATUS: numtests=3\n\
+          INSTRUMENTATION_STATUS: stream=\n\
+          com.example.FooTest:\n\
+          INSTRUMENTATION_STATUS: test=testPasses\n\
+          INSTRUMENTATION_STATUS_CODE: 1\n\
+          INSTRUMENTATION_STATUS: class=com.example.FooTest\n\
+          INSTRUMENTATION_STATUS: test=testPasses\n\
+          INSTRUMENTATION_STATUS_CODE: 0\n\
+          INSTRUMENTATION_STATUS: class=com.example.FooTest\n\
+          INSTRUMENTATION_STATUS: test=testFails\n\
+          INSTRUMENTATION_STATUS_CODE: 1\n\
+          INSTRUMENTATION_STATUS: class=com.example.FooTest\n\
+          INSTRUMENTATION_STATUS: stack=java.lang.AssertionError: expected:<1> but was:<2>\n\
+          \tat org.junit.Assert.fail(Assert.java:89)\n\
+          \tat com.example.FooTest.testFails(FooTest.java:12)\n\
+          \n\
+          INSTRUMENTATION_STATUS: test=testFails\n\
+          INSTRUMENTATION_STATUS_CODE: -2\n\
+          INSTRUMENTATION_STATUS: class=com.example.FooTest\n\
+          INSTRUMENTATION_STATUS: test=testIgnored\n\
+          INSTRUMENTATION_STATUS_CODE: -3\n\
+          INSTRUMENTATION_RESULT: stream=\n\
+          \n\
+          Time: 0.42\n\
+          \n\
+          FAILURES!!!\n\
+          Tests run: 2,  Failures: 1\n\
+          \n\
+          INSTRUMENTATION_CODE: -1\n",
+    );
+    fake_device.add_shell_response(
+        "am instrument -w -r 'com.example.test/androidx.test.runner.AndroidJUnitRunner'",
+        b"INSTRUMENTATION_STATUS: class=com.example.FooTest\n\
+          INSTRUMENTATION_STATUS: test=testCrashes\n\
+          INSTRUMENTATION_STATUS_CODE: 1\n\
+          INSTRUMENTATION_RESULT: shortMsg=Process crashed.\n\
+          INSTRUMENTATION_CODE: 0\n",
+    );
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let runner = "com.example.test/androidx.test.runner.AndroidJUnitRunner";
+    let options = InstrumentationOptions {
+        classes: vec!["com.example.FooTest".to_string()],
+        shard: Some((0, 2)),
+        ..InstrumentationOptions::new(runner)
+    };
+    let mut finished = Vec::new();
+    let report = device
+        .run_instrumentation(&options, &mut |test| finished.push(test.name.clone()))
+        .expect("cannot run instrumentation");
+    assert_eq!(finished, ["testPasses", "testFails", "testIgnored"]);
+    assert_eq!(
+        report
+            .tests
+            .iter()
+            .map(|test| test.status)
+            .collect::<Vec<_>>(),
+        [TestStatus::Passed, TestStatus::Failed, TestStatus::Ignored]
+    );
+    assert_eq!(
+        report.tests[1].stack.as_deref(),
+        Some(
+            "java.lang.AssertionError: expected:<1> but was:<2>\n\
+             \tat org.junit.Assert.fail(Assert.java:89)\n\
+             \tat com.example.FooTest.testFails(FooTest.java:12)"
+        )
+    );
+    assert!(report.results["stream"].contains("Tests run: 2,  Failures: 1"));
+    assert!(!report.is_successful());
+
+    let mut xml = Vec::new();
+    report
+        .write_junit_xml("FooTest", &mut xml)
+        .expect("cannot write JUnit report");
+    let xml = String::from_utf8(xml).expect("invalid JUnit report");
+    assert!(xml.contains(r#"tests="3" failures="1" errors="0" skipped="1""#));
+    assert!(xml.contains(
+        r#"<failure message="java.lang.AssertionError: expected:&lt;1&gt; but was:&lt;2&gt;">"#
+    ));
+
+    // Test running when process under test crashed is reported as an error
+    let report = device
+        .run_instrumentation(&InstrumentationOptions::new(runner), &mut |_| {})
+        .expect("cannot run instrumentation");
+    assert_eq!(report.error.as_deref(), Some("Process crashed."));
+    assert_eq!(
+        report
+            .tests
+            .iter()
+            .map(|test| (test.name.as_str(), test.status))
+            .collect::<Vec<_>>(),
+        [("testCrashes", TestStatus::Error)]
+    );
+}
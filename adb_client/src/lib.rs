@@ -4,24 +4,61 @@
 #![forbid(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+mod activity_manager;
 mod adb_device_ext;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod boot;
 mod constants;
 mod device;
+mod dir_sync;
+mod dir_transfer;
 mod emulator_device;
 mod error;
+mod fake_device;
+mod fake_server;
+mod host_server;
+mod install_session;
+mod instrumentation;
 mod mdns;
 mod models;
+mod package_manager;
+mod pairing;
+mod permissions;
+mod properties;
+mod pty_shell;
+mod recording;
 mod server;
 mod server_device;
+mod transfer_monitor;
 mod transports;
 mod utils;
 
 pub use adb_device_ext::ADBDeviceExt;
-pub use device::{ADBTcpDevice, ADBUSBDevice, is_adb_device, search_adb_devices};
+#[cfg(feature = "tokio")]
+pub use asynchronous::*;
+pub use device::{
+    ADBDeviceBanner, ADBKeyStore, ADBMessageDevice, ADBMultiplexer, ADBRsaKey, ADBStream,
+    ADBStreamReader, ADBStreamWriter, ADBTcpDevice, ADBUSBDevice, get_default_adb_key_path,
+    is_adb_device, search_adb_devices,
+};
 pub use emulator_device::ADBEmulatorDevice;
 pub use error::{Result, RustADBError};
+pub use fake_device::{FakeADBDevice, FakeADBTransport, FakeAuthMode, FakeFault};
+pub use fake_server::FakeADBServer;
+pub use host_server::ADBHostServer;
 pub use mdns::*;
-pub use models::{AdbStatResponse, RebootType};
+pub use models::{
+    ActivityStartResult, AdbDirEntry, AdbFileType, AdbStatResponse, AppOpMode, BootStage,
+    BroadcastResult, DeviceProperties, DirSyncChange, DirSyncChangeKind, DirSyncOptions,
+    DirSyncReport, FileTransfer, InstallFailureReason, InstallLocation, InstallOptions,
+    InstalledPackage, InstrumentationOptions, InstrumentationReport, Intent, IntentExtra,
+    PackageDetails, PackageFilter, PermissionFailureReason, PushOptions, RebootType,
+    SyncCompression, TerminalSize, TestResult, TestStatus, UninstallOptions,
+};
+pub use pty_shell::{ADBPtyShell, ShellResizer};
+pub use recording::{Session, SessionDirection, SessionEvent, SessionRecord, SessionRecorder};
 pub use server::*;
 pub use server_device::ADBServerDevice;
+pub use transfer_monitor::{CancellationToken, TransferMonitor, TransferProgress};
 pub use transports::*;
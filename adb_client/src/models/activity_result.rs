@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+/// Outcome of an activity start waited for, as reported by `am start -W`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActivityStartResult {
+    /// Status of start, e.g. `ok`, or `timeout` when activity took too long to be drawn.
+    pub status: Option<String>,
+    /// Component of activity which got started, e.g. `com.example.app/.MainActivity`.
+    pub activity: Option<String>,
+    /// How app got started: `COLD`, `WARM` or `HOT`.
+    pub launch_state: Option<String>,
+    /// Time taken to start activity, from its launch until it got drawn.
+    pub this_time: Option<Duration>,
+    /// Time taken to start all activities of launch, including the ones started before this one.
+    pub total_time: Option<Duration>,
+    /// Time taken by activity manager to process the start, including time spent by previous activities to pause.
+    pub wait_time: Option<Duration>,
+}
+
+/// Outcome of a broadcast sent with `am broadcast`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BroadcastResult {
+    /// Result code set by receivers of broadcast, `0` if none set one.
+    pub code: i32,
+    /// Result data set by receivers of broadcast, if any.
+    pub data: Option<String>,
+}
@@ -0,0 +1,220 @@
+use std::fmt::Display;
+use std::io::Read;
+use std::time::{Duration, UNIX_EPOCH};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use chrono::{DateTime, Utc};
+
+use crate::{Result, RustADBError};
+
+use super::AdbFileType;
+
+/// Entry of a directory listed on a device, as returned by [`crate::ADBDeviceExt::list_dir`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdbDirEntry {
+    /// Name of file in its directory
+    pub name: String,
+    /// Type of file
+    pub file_type: AdbFileType,
+    /// File permissions, including file type bits
+    pub file_perm: u32,
+    /// File size, in bytes
+    pub file_size: u64,
+    /// File modification time, in seconds since epoch
+    pub mod_time: u64,
+}
+
+impl AdbDirEntry {
+    /// Read entries sent by device in response to a `LIST` request, or to a `LIS2` request if `list_v2` is set,
+    /// until a `DONE` packet. `.` and `..` entries are skipped.
+    pub(crate) fn read_all(reader: &mut dyn Read, list_v2: bool) -> Result<Vec<Self>> {
+        let mut entries = Vec::new();
+        loop {
+            let mut id = [0; 4];
+            reader.read_exact(&mut id)?;
+            let entry = match &id {
+                b"DENT" => Self::read_v1(reader)?,
+                b"DNT2" => Self::read_v2(reader)?,
+                b"DONE" => {
+                    // Same size as an entry without name
+                    let mut done = vec![0; if list_v2 { 72 } else { 16 }];
+                    reader.read_exact(&mut done)?;
+                    return Ok(entries);
+                }
+                b"FAIL" => {
+                    let mut message = vec![0; reader.read_u32::<LittleEndian>()? as usize];
+                    reader.read_exact(&mut message)?;
+                    return Err(RustADBError::ADBRequestFailed(
+                        String::from_utf8_lossy(&message).to_string(),
+                    ));
+                }
+                _ => {
+                    return Err(RustADBError::UnknownResponseType(
+                        String::from_utf8_lossy(&id).to_string(),
+                    ));
+                }
+            };
+
+            if let Some(entry) = entry.filter(|entry| entry.name != "." && entry.name != "..") {
+                entries.push(entry);
+            }
+        }
+    }
+
+    /// Decode entries listed in `data` like [`AdbDirEntry::read_all`], or `None` if listing is not complete yet.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn decode_all(data: &[u8], list_v2: bool) -> Result<Option<Vec<Self>>> {
+        match Self::read_all(&mut &data[..], list_v2) {
+            Ok(entries) => Ok(Some(entries)),
+            Err(RustADBError::IOError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read a `DENT` entry, following its id.
+    fn read_v1(reader: &mut dyn Read) -> Result<Option<Self>> {
+        let file_perm = reader.read_u32::<LittleEndian>()?;
+        let file_size = reader.read_u32::<LittleEndian>()?;
+        let mod_time = reader.read_u32::<LittleEndian>()?;
+        let name = read_name(reader)?;
+
+        Ok(Some(Self {
+            name,
+            file_type: AdbFileType::from_mode(file_perm),
+            file_perm,
+            file_size: file_size.into(),
+            mod_time: mod_time.into(),
+        }))
+    }
+
+    /// Read a `DNT2` entry, following its id. Entries that device could not stat are skipped.
+    fn read_v2(reader: &mut dyn Read) -> Result<Option<Self>> {
+        let error = reader.read_u32::<LittleEndian>()?;
+        // Device and inode numbers
+        reader.read_u64::<LittleEndian>()?;
+        reader.read_u64::<LittleEndian>()?;
+        let file_perm = reader.read_u32::<LittleEndian>()?;
+        // Link count, user and group ids
+        let mut ids = [0; 12];
+        reader.read_exact(&mut ids)?;
+        let file_size = reader.read_u64::<LittleEndian>()?;
+        // Access, modification and change times
+        reader.read_i64::<LittleEndian>()?;
+        let mod_time = reader.read_i64::<LittleEndian>()?;
+        reader.read_i64::<LittleEndian>()?;
+        let name = read_name(reader)?;
+
+        if error != 0 {
+            log::debug!("device cannot stat {name}, error {error}");
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            name,
+            file_type: AdbFileType::from_mode(file_perm),
+            file_perm,
+            file_size,
+            mod_time: mod_time.max(0) as u64,
+        }))
+    }
+}
+
+impl Display for AdbDirEntry {
+    /// Display entry as a line of `ls -l` output.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mod_time = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(self.mod_time));
+        write!(
+            f,
+            "{}{} {:>10} {} {}",
+            self.file_type.ls_char(),
+            permissions(self.file_perm),
+            self.file_size,
+            mod_time.format("%Y-%m-%d %H:%M"),
+            self.name
+        )
+    }
+}
+
+fn read_name(reader: &mut dyn Read) -> Result<String> {
+    let mut name = vec![0; reader.read_u32::<LittleEndian>()? as usize];
+    reader.read_exact(&mut name)?;
+    Ok(String::from_utf8(name)?)
+}
+
+/// Permissions of `mode` as displayed by `ls -l`, e.g. `rwxr-xr-x`.
+fn permissions(mode: u32) -> String {
+    let mut permissions = b"rwxrwxrwx".to_vec();
+    for (i, permission) in permissions.iter_mut().enumerate() {
+        if mode & (0o400 >> i) == 0 {
+            *permission = b'-';
+        }
+    }
+
+    // Set-user-ID, set-group-ID and sticky bits replace execute permission
+    for (bit, index, set) in [(0o4000, 2, b's'), (0o2000, 5, b's'), (0o1000, 8, b't')] {
+        if mode & bit != 0 {
+            permissions[index] = match permissions[index] {
+                b'-' => set.to_ascii_uppercase(),
+                _ => set,
+            };
+        }
+    }
+
+    String::from_utf8_lossy(&permissions).to_string()
+}
+
+#[test]
+fn test_dir_entries() {
+    let mut listing = Vec::new();
+    listing.extend_from_slice(b"DNT2");
+    listing.extend_from_slice(&0_u32.to_le_bytes());
+    listing.extend_from_slice(&[0; 16]);
+    listing.extend_from_slice(&0o104755_u32.to_le_bytes());
+    listing.extend_from_slice(&[0; 12]);
+    listing.extend_from_slice(&5_000_000_000_u64.to_le_bytes());
+    listing.extend_from_slice(&0_i64.to_le_bytes());
+    listing.extend_from_slice(&1_700_000_000_i64.to_le_bytes());
+    listing.extend_from_slice(&0_i64.to_le_bytes());
+    listing.extend_from_slice(&2_u32.to_le_bytes());
+    listing.extend_from_slice(b"su");
+    listing.extend_from_slice(b"DONE");
+    listing.extend_from_slice(&[0; 72]);
+
+    let entries =
+        AdbDirEntry::read_all(&mut listing.as_slice(), true).expect("cannot read entries");
+    assert_eq!(
+        entries,
+        [AdbDirEntry {
+            name: "su".to_string(),
+            file_type: AdbFileType::File,
+            file_perm: 0o104755,
+            file_size: 5_000_000_000,
+            mod_time: 1_700_000_000,
+        }]
+    );
+    assert_eq!(
+        entries[0].to_string(),
+        "-rwsr-xr-x 5000000000 2023-11-14 22:13 su"
+    );
+
+    // Legacy listing, with 32 bits sizes and modification times
+    let mut listing = Vec::new();
+    for (mode, name) in [(0o040755_u32, &b"."[..]), (0o120777, b"sdcard")] {
+        listing.extend_from_slice(b"DENT");
+        for arg in [mode, 21, 1_700_000_000, name.len() as u32] {
+            listing.extend_from_slice(&arg.to_le_bytes());
+        }
+        listing.extend_from_slice(name);
+    }
+    listing.extend_from_slice(b"DONE");
+    listing.extend_from_slice(&[0; 16]);
+
+    let entries =
+        AdbDirEntry::read_all(&mut listing.as_slice(), false).expect("cannot read entries");
+    assert_eq!(
+        entries.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["lrwxrwxrwx         21 2023-11-14 22:13 sdcard"]
+    );
+}
@@ -0,0 +1,69 @@
+/// Mask of file type bits in a file mode
+const S_IFMT: u32 = 0o170000;
+
+/// Type of a file on a device, as given by its mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdbFileType {
+    /// Regular file
+    File,
+    /// Directory
+    Directory,
+    /// Symbolic link
+    Symlink,
+    /// Character device
+    CharDevice,
+    /// Block device
+    BlockDevice,
+    /// Named pipe
+    Fifo,
+    /// Unix domain socket
+    Socket,
+    /// Unknown type, e.g. for files that do not exist
+    Unknown,
+}
+
+impl AdbFileType {
+    /// File type bits of files of this type.
+    pub fn mode_bits(self) -> u32 {
+        match self {
+            Self::File => 0o100000,
+            Self::Directory => 0o040000,
+            Self::Symlink => 0o120000,
+            Self::CharDevice => 0o020000,
+            Self::BlockDevice => 0o060000,
+            Self::Fifo => 0o010000,
+            Self::Socket => 0o140000,
+            Self::Unknown => 0,
+        }
+    }
+
+    /// Character representing this type in `ls -l` output, e.g. `d` for directories.
+    pub fn ls_char(self) -> char {
+        match self {
+            Self::File => '-',
+            Self::Directory => 'd',
+            Self::Symlink => 'l',
+            Self::CharDevice => 'c',
+            Self::BlockDevice => 'b',
+            Self::Fifo => 'p',
+            Self::Socket => 's',
+            Self::Unknown => '?',
+        }
+    }
+
+    /// Type of a file having given `mode`, as returned by `stat`.
+    pub fn from_mode(mode: u32) -> Self {
+        [
+            Self::File,
+            Self::Directory,
+            Self::Symlink,
+            Self::CharDevice,
+            Self::BlockDevice,
+            Self::Fifo,
+            Self::Socket,
+        ]
+        .into_iter()
+        .find(|file_type| file_type.mode_bits() == mode & S_IFMT)
+        .unwrap_or(Self::Unknown)
+    }
+}
@@ -1,9 +1,48 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
-use crate::{WaitForDeviceState, WaitForDeviceTransport};
+use crate::{RustADBError, WaitForDeviceState, WaitForDeviceTransport};
 
-use super::RebootType;
-use std::net::SocketAddrV4;
+use super::{InstallOptions, RebootType, UninstallOptions};
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+/// Port used by `adbd` when listening over TCP, if not specified otherwise.
+const DEFAULT_DEVICE_PORT: u16 = 5555;
+/// Requests which can follow serial of a `host-serial:<serial>:` request.
+const HOST_SERIAL_REQUESTS: [&str; 5] = [
+    "wait-for-",
+    "forward:",
+    "killforward",
+    "get-state",
+    "features",
+];
+
+/// Device selected by a transport request, e.g. `host:transport-usb` or `host:tport:serial:<serial>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TransportSelector {
+    /// Only connected device
+    Any,
+    /// Only device connected over USB
+    Usb,
+    /// Only device connected over TCP
+    Local,
+    /// Device with given serial
+    Serial(String),
+    /// Device with given transport id
+    Id(u64),
+}
+
+impl Display for TransportSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransportSelector::Any => write!(f, "any"),
+            TransportSelector::Usb => write!(f, "usb"),
+            TransportSelector::Local => write!(f, "local"),
+            TransportSelector::Serial(serial) => write!(f, "serial:{serial}"),
+            TransportSelector::Id(id) => write!(f, "id:{id}"),
+        }
+    }
+}
 
 pub(crate) enum AdbServerCommand {
     // Host commands
@@ -18,20 +57,33 @@ pub(crate) enum AdbServerCommand {
     Pair(SocketAddrV4, String),
     TransportAny,
     TransportSerial(String),
+    TransportUsb,
+    TransportLocal,
+    TransportId(u64),
+    /// Select a device, and get its transport id
+    Tport(TransportSelector),
+    /// Request to a given device, e.g. `host-serial:<serial>:get-state`
+    HostSerial(String, Box<AdbServerCommand>),
+    GetState,
     MDNSCheck,
     MDNSServices,
     ServerStatus,
     ReconnectOffline,
-    Uninstall(String),
-    Install(u64),
-    WaitForDevice(WaitForDeviceState, WaitForDeviceTransport),
+    Uninstall(String, UninstallOptions),
+    Install(u64, InstallOptions),
+    Exec(String),
+    WaitForDevice(WaitForDeviceState, WaitForDeviceTransport, Option<String>),
     // Local commands
     ShellCommand(String),
+    ShellV2Command(String),
     Shell,
+    PtyShell(String),
     FrameBuffer,
     Sync,
     Reboot(RebootType),
     Forward(String, String),
+    ForwardNoRebind(String, String),
+    KillForward(String),
     ForwardRemoveAll,
     Reverse(String, String),
     ReverseRemoveAll,
@@ -51,14 +103,29 @@ impl Display for AdbServerCommand {
             AdbServerCommand::TrackDevices => write!(f, "host:track-devices"),
             AdbServerCommand::TransportAny => write!(f, "host:transport-any"),
             AdbServerCommand::TransportSerial(serial) => write!(f, "host:transport:{serial}"),
+            AdbServerCommand::TransportUsb => write!(f, "host:transport-usb"),
+            AdbServerCommand::TransportLocal => write!(f, "host:transport-local"),
+            AdbServerCommand::TransportId(id) => write!(f, "host:transport-id:{id}"),
+            AdbServerCommand::Tport(selector) => write!(f, "host:tport:{selector}"),
+            AdbServerCommand::HostSerial(serial, command) => {
+                let command = command.to_string();
+                let request = command.strip_prefix("host:").unwrap_or(&command);
+                write!(f, "host-serial:{serial}:{request}")
+            }
+            AdbServerCommand::GetState => write!(f, "host:get-state"),
             AdbServerCommand::ShellCommand(command) => match std::env::var("TERM") {
                 Ok(term) => write!(f, "shell,TERM={term},raw:{command}"),
                 Err(_) => write!(f, "shell,raw:{command}"),
             },
+            AdbServerCommand::ShellV2Command(command) => match std::env::var("TERM") {
+                Ok(term) => write!(f, "shell,v2,TERM={term},raw:{command}"),
+                Err(_) => write!(f, "shell,v2,raw:{command}"),
+            },
             AdbServerCommand::Shell => match std::env::var("TERM") {
                 Ok(term) => write!(f, "shell,TERM={term},raw:"),
                 Err(_) => write!(f, "shell,raw:"),
             },
+            AdbServerCommand::PtyShell(term) => write!(f, "shell,v2,TERM={term},pty:"),
             AdbServerCommand::HostFeatures => write!(f, "host:features"),
             AdbServerCommand::Reboot(reboot_type) => {
                 write!(f, "reboot:{reboot_type}")
@@ -72,6 +139,10 @@ impl Display for AdbServerCommand {
             AdbServerCommand::Forward(remote, local) => {
                 write!(f, "host:forward:{local};{remote}")
             }
+            AdbServerCommand::ForwardNoRebind(remote, local) => {
+                write!(f, "host:forward:norebind:{local};{remote}")
+            }
+            AdbServerCommand::KillForward(local) => write!(f, "host:killforward:{local}"),
             AdbServerCommand::ForwardRemoveAll => write!(f, "host:killforward-all"),
             AdbServerCommand::Reverse(remote, local) => {
                 write!(f, "reverse:forward:{remote};{local}")
@@ -86,20 +157,145 @@ impl Display for AdbServerCommand {
                 write!(f, "tcpip:{port}")
             }
             AdbServerCommand::Usb => write!(f, "usb:"),
-            AdbServerCommand::Install(size) => write!(f, "exec:cmd package 'install' -S {size}"),
-            AdbServerCommand::Uninstall(package) => {
-                write!(f, "exec:cmd package 'uninstall' {package}")
+            AdbServerCommand::Install(size, options) => {
+                write!(f, "{}", options.install_service(*size))
+            }
+            AdbServerCommand::Exec(service) => write!(f, "{service}"),
+            AdbServerCommand::Uninstall(package, options) => {
+                write!(f, "{}", options.uninstall_service(package))
             }
-            AdbServerCommand::WaitForDevice(wait_for_device_state, wait_for_device_transport) => {
-                write!(
+            AdbServerCommand::WaitForDevice(
+                wait_for_device_state,
+                wait_for_device_transport,
+                serial,
+            ) => match serial {
+                Some(serial) => write!(
+                    f,
+                    "host-serial:{serial}:wait-for-{wait_for_device_transport}-{wait_for_device_state}"
+                ),
+                None => write!(
                     f,
                     "host:wait-for-{wait_for_device_transport}-{wait_for_device_state}"
-                )
-            }
+                ),
+            },
         }
     }
 }
 
+fn parse_device_address(address: &str) -> Result<SocketAddrV4, RustADBError> {
+    match address.parse::<SocketAddrV4>() {
+        Ok(address) => Ok(address),
+        Err(_) => Ok(SocketAddrV4::new(
+            address.parse::<Ipv4Addr>()?,
+            DEFAULT_DEVICE_PORT,
+        )),
+    }
+}
+
+/// Parse requests received by an ADB server, as sent by a client.
+///
+/// Only requests handled by the server itself (`host:*` and `host-serial:*`) can be parsed, local services are forwarded untouched to devices.
+impl FromStr for AdbServerCommand {
+    type Err = RustADBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let command = match s {
+            "host:version" => AdbServerCommand::Version,
+            "host:kill" => AdbServerCommand::Kill,
+            "host:devices" => AdbServerCommand::Devices,
+            "host:devices-l" => AdbServerCommand::DevicesLong,
+            "host:track-devices" => AdbServerCommand::TrackDevices,
+            "host:features" => AdbServerCommand::HostFeatures,
+            "host:transport-any" => AdbServerCommand::TransportAny,
+            "host:mdns:check" => AdbServerCommand::MDNSCheck,
+            "host:mdns:services" => AdbServerCommand::MDNSServices,
+            "host:server-status" => AdbServerCommand::ServerStatus,
+            "host:reconnect-offline" => AdbServerCommand::ReconnectOffline,
+            "host:killforward-all" => AdbServerCommand::ForwardRemoveAll,
+            "host:transport-usb" => AdbServerCommand::TransportUsb,
+            "host:transport-local" => AdbServerCommand::TransportLocal,
+            "host:get-state" => AdbServerCommand::GetState,
+            _ => {
+                if let Some(serial) = s.strip_prefix("host:transport:") {
+                    AdbServerCommand::TransportSerial(serial.to_string())
+                } else if let Some(id) = s.strip_prefix("host:transport-id:") {
+                    AdbServerCommand::TransportId(id.parse()?)
+                } else if let Some(selector) = s.strip_prefix("host:tport:") {
+                    AdbServerCommand::Tport(match selector {
+                        "any" => TransportSelector::Any,
+                        "usb" => TransportSelector::Usb,
+                        "local" => TransportSelector::Local,
+                        _ => match selector.strip_prefix("serial:") {
+                            Some(serial) => TransportSelector::Serial(serial.to_string()),
+                            None => return Err(RustADBError::UnsupportedRequest(s.to_string())),
+                        },
+                    })
+                } else if let Some(address) = s.strip_prefix("host:connect:") {
+                    AdbServerCommand::Connect(parse_device_address(address)?)
+                } else if let Some(address) = s.strip_prefix("host:disconnect:") {
+                    AdbServerCommand::Disconnect(parse_device_address(address)?)
+                } else if let Some(pair) = s.strip_prefix("host:pair:") {
+                    let (code, address) = pair
+                        .split_once(':')
+                        .ok_or_else(|| RustADBError::UnsupportedRequest(s.to_string()))?;
+                    AdbServerCommand::Pair(address.parse()?, code.to_string())
+                } else if let Some(forward) = s.strip_prefix("host:forward:") {
+                    let (rebind, forward) = match forward.strip_prefix("norebind:") {
+                        Some(forward) => (false, forward),
+                        None => (true, forward),
+                    };
+                    let (local, remote) = forward
+                        .split_once(';')
+                        .ok_or_else(|| RustADBError::UnsupportedRequest(s.to_string()))?;
+                    let (remote, local) = (remote.to_string(), local.to_string());
+                    match rebind {
+                        true => AdbServerCommand::Forward(remote, local),
+                        false => AdbServerCommand::ForwardNoRebind(remote, local),
+                    }
+                } else if let Some(local) = s.strip_prefix("host:killforward:") {
+                    AdbServerCommand::KillForward(local.to_string())
+                } else if let Some(wait_for) = s.strip_prefix("host:wait-for-") {
+                    let (transport, state) = wait_for
+                        .split_once('-')
+                        .ok_or_else(|| RustADBError::UnsupportedRequest(s.to_string()))?;
+                    AdbServerCommand::WaitForDevice(
+                        WaitForDeviceState::try_from(state)?,
+                        WaitForDeviceTransport::try_from(transport)?,
+                        None,
+                    )
+                } else if let Some(request) = s.strip_prefix("host-serial:") {
+                    // Serial may contain ':' (e.g. `192.168.0.10:5555`), and ends right before the request it is given
+                    let (serial, request) = request
+                        .match_indices(':')
+                        .map(|(end, _)| (&request[..end], &request[end + 1..]))
+                        .find(|(_, request)| {
+                            HOST_SERIAL_REQUESTS
+                                .iter()
+                                .any(|prefix| request.starts_with(prefix))
+                        })
+                        .ok_or_else(|| RustADBError::UnsupportedRequest(s.to_string()))?;
+                    match AdbServerCommand::from_str(&format!("host:{request}"))? {
+                        AdbServerCommand::WaitForDevice(state, transport, None) => {
+                            AdbServerCommand::WaitForDevice(
+                                state,
+                                transport,
+                                Some(serial.to_string()),
+                            )
+                        }
+                        command => {
+                            AdbServerCommand::HostSerial(serial.to_string(), Box::new(command))
+                        }
+                    }
+                } else {
+                    return Err(RustADBError::UnsupportedRequest(s.to_string()));
+                }
+            }
+        };
+
+        Ok(command)
+    }
+}
+
 #[test]
 fn test_pair_command() {
     let host = "192.168.0.197:34783";
@@ -110,3 +306,36 @@ fn test_pair_command() {
     assert_eq!(pair.to_string(), format!("host:pair:{code}:{host}"));
     assert_ne!(pair.to_string(), format!("host:pair:{code_u32}:{host}"))
 }
+
+#[test]
+fn test_parse_host_commands() {
+    for request in [
+        "host:version",
+        "host:devices-l",
+        "host:transport:emulator-5554",
+        "host:connect:192.168.0.10:5555",
+        "host:forward:tcp:8080;tcp:80",
+        "host:forward:norebind:tcp:8080;tcp:80",
+        "host:killforward:tcp:8080",
+        "host:wait-for-usb-device",
+        "host-serial:192.168.0.10:5555:wait-for-any-device",
+        "host-serial:192.168.0.10:5555:forward:tcp:1;tcp:2",
+        "host-serial:emulator-5554:forward:norebind:tcp:1;localabstract:a:b",
+        "host-serial:192.168.0.10:5555:killforward-all",
+        "host-serial:emulator-5554:get-state",
+        "host-serial:emulator-5554:features",
+        "host:transport-usb",
+        "host:transport-local",
+        "host:transport-id:3",
+        "host:tport:serial:192.168.0.10:5555",
+        "host:tport:usb",
+        "host:tport:any",
+    ] {
+        let command = AdbServerCommand::from_str(request).expect("cannot parse request");
+        assert_eq!(command.to_string(), request);
+    }
+
+    let command = AdbServerCommand::from_str("host:connect:192.168.0.10").expect("cannot parse");
+    assert_eq!(command.to_string(), "host:connect:192.168.0.10:5555");
+    assert!(AdbServerCommand::from_str("shell:ls").is_err());
+}
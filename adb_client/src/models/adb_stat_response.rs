@@ -8,6 +8,12 @@ use std::{
 use byteorder::LittleEndian;
 use serde::{Deserialize, Serialize};
 
+use super::AdbFileType;
+use crate::{Result, RustADBError};
+
+/// Size of a `STAT` response: its id, followed by file permissions, size and modification time.
+pub(crate) const STAT_RESPONSE_SIZE: usize = 16;
+
 /// Represents a `stat` response
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AdbStatResponse {
@@ -19,6 +25,27 @@ pub struct AdbStatResponse {
     pub mod_time: u32,
 }
 
+impl AdbStatResponse {
+    /// Type of file, [`AdbFileType::Unknown`] if it does not exist
+    pub fn file_type(&self) -> AdbFileType {
+        AdbFileType::from_mode(self.file_perm)
+    }
+
+    /// Decode `response` sent by device to a `STAT` sync request.
+    pub(crate) fn decode(response: &[u8]) -> Result<Self> {
+        match response.split_first_chunk::<4>() {
+            Some((b"STAT", data)) => {
+                let data: [u8; 12] = data.try_into().map_err(|_| RustADBError::ConversionError)?;
+                Ok(data.into())
+            }
+            _ => Err(RustADBError::UnknownResponseType(format!(
+                "Unknown response {}",
+                String::from_utf8_lossy(response.get(..4).unwrap_or(response))
+            ))),
+        }
+    }
+}
+
 impl From<[u8; 12]> for AdbStatResponse {
     fn from(value: [u8; 12]) -> Self {
         Self {
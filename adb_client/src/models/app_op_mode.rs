@@ -0,0 +1,47 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::RustADBError;
+
+/// Mode of an app operation, as read and set by `appops`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppOpMode {
+    /// Operation is allowed
+    Allow,
+    /// Operation is silently ignored, e.g. returning empty data
+    Ignore,
+    /// Operation fails with a security error
+    Deny,
+    /// Operation follows default policy, usually the one of its runtime permission
+    Default,
+    /// Operation is only allowed while app is in foreground
+    Foreground,
+}
+
+impl Display for AppOpMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppOpMode::Allow => write!(f, "allow"),
+            AppOpMode::Ignore => write!(f, "ignore"),
+            AppOpMode::Deny => write!(f, "deny"),
+            AppOpMode::Default => write!(f, "default"),
+            AppOpMode::Foreground => write!(f, "foreground"),
+        }
+    }
+}
+
+impl FromStr for AppOpMode {
+    type Err = RustADBError;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "allow" => Ok(AppOpMode::Allow),
+            "ignore" => Ok(AppOpMode::Ignore),
+            // Devices display `deny` as `errored`
+            "deny" | "errored" => Ok(AppOpMode::Deny),
+            "default" => Ok(AppOpMode::Default),
+            "foreground" => Ok(AppOpMode::Foreground),
+            mode => Err(RustADBError::UnknownResponseType(mode.to_string())),
+        }
+    }
+}
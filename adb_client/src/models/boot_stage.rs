@@ -0,0 +1,28 @@
+use std::fmt::Display;
+
+/// Stages a booting device goes through, as waited for by [`crate::ADBDeviceExt::wait_for_boot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BootStage {
+    /// Device cannot be reached yet
+    Disconnected,
+    /// Device can be reached, but system did not finish booting
+    Connected,
+    /// System finished booting, as told by `sys.boot_completed`
+    SystemBooted,
+    /// Device finished booting, as told by `dev.bootcomplete`
+    DeviceBooted,
+    /// Package manager is up and answers requests, so that apps can be installed and started
+    Ready,
+}
+
+impl Display for BootStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BootStage::Disconnected => write!(f, "disconnected"),
+            BootStage::Connected => write!(f, "connected"),
+            BootStage::SystemBooted => write!(f, "system booted"),
+            BootStage::DeviceBooted => write!(f, "device booted"),
+            BootStage::Ready => write!(f, "ready"),
+        }
+    }
+}
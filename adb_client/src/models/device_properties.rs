@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+/// System properties of a device, as listed by `getprop`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceProperties {
+    properties: BTreeMap<String, String>,
+}
+
+impl DeviceProperties {
+    /// Parse output of `getprop`, made of `[name]: [value]` lines. Values may span several lines.
+    pub(crate) fn parse(output: &str) -> Self {
+        let mut properties = BTreeMap::new();
+        let mut lines = output.lines().peekable();
+        while let Some(line) = lines.next() {
+            let Some((name, value)) = line
+                .trim_end_matches('\r')
+                .strip_prefix('[')
+                .and_then(|line| line.split_once("]: ["))
+            else {
+                continue;
+            };
+
+            // Value ends with a `]`, at the end of a line followed by next property
+            let mut value = value.to_string();
+            while !(value.ends_with(']') && lines.peek().map_or(true, |line| is_property(line))) {
+                match lines.next() {
+                    Some(line) => {
+                        value.push('\n');
+                        value.push_str(line.trim_end_matches('\r'));
+                    }
+                    None => break,
+                }
+            }
+            let value = value.strip_suffix(']').unwrap_or(&value);
+            properties.insert(name.to_string(), value.to_string());
+        }
+        Self { properties }
+    }
+
+    /// Value of property `name`, if it is set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    /// Iterate over names and values of all properties, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// SDK level of Android running on device (`ro.build.version.sdk`), e.g. `34`.
+    pub fn sdk_version(&self) -> Option<u32> {
+        self.get("ro.build.version.sdk")?.parse().ok()
+    }
+
+    /// Version of Android running on device (`ro.build.version.release`), e.g. `14`.
+    pub fn release(&self) -> Option<&str> {
+        self.get("ro.build.version.release")
+    }
+
+    /// ABIs supported by device, preferred one first (`ro.product.cpu.abilist`), e.g. `arm64-v8a`.
+    pub fn abis(&self) -> Vec<&str> {
+        self.get("ro.product.cpu.abilist")
+            .or_else(|| self.get("ro.product.cpu.abi"))
+            .map(|abis| abis.split(',').filter(|abi| !abi.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Fingerprint identifying build running on device (`ro.build.fingerprint`).
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.get("ro.build.fingerprint")
+    }
+
+    /// Manufacturer of device (`ro.product.manufacturer`).
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.get("ro.product.manufacturer")
+    }
+
+    /// Model of device (`ro.product.model`).
+    pub fn model(&self) -> Option<&str> {
+        self.get("ro.product.model")
+    }
+
+    /// Whether system finished booting (`sys.boot_completed`).
+    pub fn boot_completed(&self) -> bool {
+        self.get("sys.boot_completed") == Some("1")
+    }
+}
+
+/// Whether `line` of `getprop` output starts a new property.
+fn is_property(line: &str) -> bool {
+    line.starts_with('[') && line.contains("]: [")
+}
+
+#[test]
+fn test_parse_properties() {
+    let properties = DeviceProperties::parse(
+        "[ro.build.version.sdk]: [34]\n\
+         [ro.product.cpu.abilist]: [arm64-v8a,armeabi-v7a,armeabi]\n\
+         [persist.sys.motd]: [first line\n\
+         [second] line\n\
+         ]\n\
+         [sys.boot_completed]: [1]\n\
+         [ro.boot.empty]: []\n",
+    );
+
+    assert_eq!(properties.sdk_version(), Some(34));
+    assert_eq!(properties.abis(), ["arm64-v8a", "armeabi-v7a", "armeabi"]);
+    assert_eq!(
+        properties.get("persist.sys.motd"),
+        Some("first line\n[second] line\n")
+    );
+    assert!(properties.boot_completed());
+    assert_eq!(properties.get("ro.boot.empty"), Some(""));
+    assert_eq!(properties.iter().count(), 5);
+}
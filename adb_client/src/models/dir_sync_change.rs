@@ -0,0 +1,60 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use crate::Result;
+
+use super::AdbFileType;
+
+/// Reason why an entry of a remote tree is changed by [`crate::ADBDeviceExt::sync_dir`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirSyncChangeKind {
+    /// Entry only exists in local tree, and is pushed
+    Added,
+    /// Entry differs between both trees, and is pushed again
+    Modified,
+    /// Entry only exists in remote tree, and is deleted
+    Extraneous,
+}
+
+impl Display for DirSyncChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DirSyncChangeKind::Added => write!(f, "added"),
+            DirSyncChangeKind::Modified => write!(f, "modified"),
+            DirSyncChangeKind::Extraneous => write!(f, "extraneous"),
+        }
+    }
+}
+
+/// Change made to one entry of a remote tree by [`crate::ADBDeviceExt::sync_dir`], or that would be made in dry-run mode.
+#[derive(Debug)]
+pub struct DirSyncChange {
+    /// Kind of change
+    pub kind: DirSyncChangeKind,
+    /// Path of entry on host, unless it is extraneous
+    pub local_path: Option<PathBuf>,
+    /// Path of entry on device
+    pub remote_path: String,
+    /// Type of entry
+    pub file_type: AdbFileType,
+    /// Size of entry, in bytes
+    pub size: u64,
+    /// Whether change has been made, always successful in dry-run mode
+    pub result: Result<()>,
+}
+
+/// Outcome of [`crate::ADBDeviceExt::sync_dir`].
+#[derive(Debug, Default)]
+pub struct DirSyncReport {
+    /// Changes of remote tree, in the order they were made
+    pub changes: Vec<DirSyncChange>,
+    /// Amount of files which were already up to date
+    pub unchanged: usize,
+}
+
+impl DirSyncReport {
+    /// Changes which could not be made.
+    pub fn failures(&self) -> impl Iterator<Item = &DirSyncChange> {
+        self.changes.iter().filter(|change| change.result.is_err())
+    }
+}
@@ -0,0 +1,12 @@
+/// Options of [`crate::ADBDeviceExt::sync_dir`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirSyncOptions {
+    /// Compare contents of files having the same size with MD5 hashes, ignoring their modification times.
+    ///
+    /// Useful when files are rebuilt identically: they are not pushed again. Requires `md5sum` on device.
+    pub compare_hash: bool,
+    /// Delete files and directories of remote tree which do not exist in local tree.
+    pub delete_extraneous: bool,
+    /// Only report changes, without modifying anything on device.
+    pub dry_run: bool,
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use crate::Result;
+
+use super::AdbFileType;
+
+/// Outcome of transferring one entry of a directory, as reported by [`crate::ADBDeviceExt::push_dir`] and [`crate::ADBDeviceExt::pull_dir`].
+#[derive(Debug)]
+pub struct FileTransfer {
+    /// Path of entry on host
+    pub local_path: PathBuf,
+    /// Path of entry on device
+    pub remote_path: String,
+    /// Type of entry
+    pub file_type: AdbFileType,
+    /// Size of entry, in bytes
+    pub size: u64,
+    /// Whether entry has been transferred
+    pub result: Result<()>,
+}
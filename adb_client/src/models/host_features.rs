@@ -4,6 +4,13 @@ use std::fmt::Display;
 pub enum HostFeatures {
     ShellV2,
     Cmd,
+    SendRecvV2,
+    SendRecvV2Brotli,
+    SendRecvV2Lz4,
+    SendRecvV2Zstd,
+    LsV2,
+    FixedPushMkdir,
+    AbbExec,
 }
 
 impl Display for HostFeatures {
@@ -11,6 +18,13 @@ impl Display for HostFeatures {
         match self {
             HostFeatures::ShellV2 => write!(f, "ShellV2"),
             HostFeatures::Cmd => write!(f, "Cmd"),
+            HostFeatures::SendRecvV2 => write!(f, "SendRecvV2"),
+            HostFeatures::SendRecvV2Brotli => write!(f, "SendRecvV2Brotli"),
+            HostFeatures::SendRecvV2Lz4 => write!(f, "SendRecvV2Lz4"),
+            HostFeatures::SendRecvV2Zstd => write!(f, "SendRecvV2Zstd"),
+            HostFeatures::LsV2 => write!(f, "LsV2"),
+            HostFeatures::FixedPushMkdir => write!(f, "FixedPushMkdir"),
+            HostFeatures::AbbExec => write!(f, "AbbExec"),
         }
     }
 }
@@ -22,6 +36,13 @@ impl TryFrom<&[u8]> for HostFeatures {
         match value {
             b"shell_v2" => Ok(Self::ShellV2),
             b"cmd" => Ok(Self::Cmd),
+            b"sendrecv_v2" => Ok(Self::SendRecvV2),
+            b"sendrecv_v2_brotli" => Ok(Self::SendRecvV2Brotli),
+            b"sendrecv_v2_lz4" => Ok(Self::SendRecvV2Lz4),
+            b"sendrecv_v2_zstd" => Ok(Self::SendRecvV2Zstd),
+            b"ls_v2" => Ok(Self::LsV2),
+            b"fixed_push_mkdir" => Ok(Self::FixedPushMkdir),
+            b"abb_exec" => Ok(Self::AbbExec),
             _ => Err(format!("Unknown value {value:?}")),
         }
     }
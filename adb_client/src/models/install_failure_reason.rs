@@ -0,0 +1,76 @@
+use std::fmt::Display;
+
+/// Reason of a failed installation, as reported by package manager with an `INSTALL_FAILED_*` code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstallFailureReason {
+    /// Package is already installed, and replacing it was not allowed
+    AlreadyExists,
+    /// APK file is invalid
+    InvalidApk,
+    /// Device does not have enough storage left
+    InsufficientStorage,
+    /// Installed package is signed with another certificate
+    UpdateIncompatible,
+    /// Version code is older than the one of installed package
+    VersionDowngrade,
+    /// Package is marked as `android:testOnly`, and test packages were not allowed
+    TestOnly,
+    /// Package has no native code for any ABI supported by device
+    NoMatchingAbis,
+    /// Package requires a newer SDK than the one of device
+    OlderSdk,
+    /// Package requires an older SDK than the one of device
+    NewerSdk,
+    /// A split required by package is missing
+    MissingSplit,
+    /// User is not allowed to install apps
+    UserRestricted,
+    /// Installation was aborted, e.g. refused on device
+    Aborted,
+    /// Package verifier rejected package
+    VerificationFailure,
+    /// Any other code, such as `INSTALL_PARSE_FAILED_*` ones
+    Other(String),
+}
+
+impl From<&str> for InstallFailureReason {
+    fn from(code: &str) -> Self {
+        match code {
+            "INSTALL_FAILED_ALREADY_EXISTS" => Self::AlreadyExists,
+            "INSTALL_FAILED_INVALID_APK" => Self::InvalidApk,
+            "INSTALL_FAILED_INSUFFICIENT_STORAGE" => Self::InsufficientStorage,
+            "INSTALL_FAILED_UPDATE_INCOMPATIBLE" => Self::UpdateIncompatible,
+            "INSTALL_FAILED_VERSION_DOWNGRADE" => Self::VersionDowngrade,
+            "INSTALL_FAILED_TEST_ONLY" => Self::TestOnly,
+            "INSTALL_FAILED_NO_MATCHING_ABIS" => Self::NoMatchingAbis,
+            "INSTALL_FAILED_OLDER_SDK" => Self::OlderSdk,
+            "INSTALL_FAILED_NEWER_SDK" => Self::NewerSdk,
+            "INSTALL_FAILED_MISSING_SPLIT" => Self::MissingSplit,
+            "INSTALL_FAILED_USER_RESTRICTED" => Self::UserRestricted,
+            "INSTALL_FAILED_ABORTED" => Self::Aborted,
+            "INSTALL_FAILED_VERIFICATION_FAILURE" => Self::VerificationFailure,
+            code => Self::Other(code.to_string()),
+        }
+    }
+}
+
+impl Display for InstallFailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlreadyExists => write!(f, "INSTALL_FAILED_ALREADY_EXISTS"),
+            Self::InvalidApk => write!(f, "INSTALL_FAILED_INVALID_APK"),
+            Self::InsufficientStorage => write!(f, "INSTALL_FAILED_INSUFFICIENT_STORAGE"),
+            Self::UpdateIncompatible => write!(f, "INSTALL_FAILED_UPDATE_INCOMPATIBLE"),
+            Self::VersionDowngrade => write!(f, "INSTALL_FAILED_VERSION_DOWNGRADE"),
+            Self::TestOnly => write!(f, "INSTALL_FAILED_TEST_ONLY"),
+            Self::NoMatchingAbis => write!(f, "INSTALL_FAILED_NO_MATCHING_ABIS"),
+            Self::OlderSdk => write!(f, "INSTALL_FAILED_OLDER_SDK"),
+            Self::NewerSdk => write!(f, "INSTALL_FAILED_NEWER_SDK"),
+            Self::MissingSplit => write!(f, "INSTALL_FAILED_MISSING_SPLIT"),
+            Self::UserRestricted => write!(f, "INSTALL_FAILED_USER_RESTRICTED"),
+            Self::Aborted => write!(f, "INSTALL_FAILED_ABORTED"),
+            Self::VerificationFailure => write!(f, "INSTALL_FAILED_VERIFICATION_FAILURE"),
+            Self::Other(code) => write!(f, "{code}"),
+        }
+    }
+}
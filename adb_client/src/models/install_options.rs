@@ -0,0 +1,153 @@
+use std::fmt::Display;
+
+/// Location where an app gets installed, given to `pm install --install-location`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstallLocation {
+    /// Let system decide, as requested by app manifest
+    Auto,
+    /// Install on internal storage only
+    InternalOnly,
+    /// Prefer external storage when available
+    PreferExternal,
+}
+
+impl Display for InstallLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallLocation::Auto => write!(f, "0"),
+            InstallLocation::InternalOnly => write!(f, "1"),
+            InstallLocation::PreferExternal => write!(f, "2"),
+        }
+    }
+}
+
+/// Options of an APK installation, mapped to `pm install` flags.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstallOptions {
+    /// Replace an already installed app, keeping its data (`-r`).
+    pub replace: bool,
+    /// Allow installing an older version code than the installed one (`-d`).
+    pub allow_downgrade: bool,
+    /// Grant all runtime permissions requested by app (`-g`).
+    pub grant_permissions: bool,
+    /// Allow installing apps marked as `android:testOnly` (`-t`).
+    pub allow_test: bool,
+    /// Only install app for this user id instead of all users (`--user`).
+    pub user: Option<u32>,
+    /// Install app as an instant app (`--instant`).
+    pub instant: bool,
+    /// Where app gets installed (`--install-location`), as decided by its manifest if `None`.
+    pub install_location: Option<InstallLocation>,
+}
+
+impl InstallOptions {
+    /// Arguments of `pm install` and `pm install-create` selecting these options.
+    pub(crate) fn args(&self) -> Vec<String> {
+        let flags = [
+            (self.replace, "-r"),
+            (self.allow_downgrade, "-d"),
+            (self.grant_permissions, "-g"),
+            (self.allow_test, "-t"),
+            (self.instant, "--instant"),
+        ];
+        let mut args = flags
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, flag)| flag.to_string())
+            .collect::<Vec<_>>();
+
+        if let Some(user) = self.user {
+            args.extend(["--user".to_string(), user.to_string()]);
+        }
+        if let Some(location) = self.install_location {
+            args.extend(["--install-location".to_string(), location.to_string()]);
+        }
+        args
+    }
+
+    /// Service streaming an APK of `size` bytes to `pm install`.
+    pub(crate) fn install_service(&self, size: u64) -> String {
+        let mut args = self.args();
+        args.extend(["-S".to_string(), size.to_string()]);
+        format!("exec:cmd package 'install' {}", args.join(" "))
+    }
+}
+
+#[test]
+fn test_install_options() {
+    use crate::{
+        ADBDeviceExt, FakeADBDevice, InstallFailureReason, RustADBError, TransferMonitor,
+        UninstallOptions,
+    };
+
+    let private_key_path = crate::fake_device::write_test_private_key("install_options");
+    let test_apk = private_key_path.with_file_name("test.apk");
+    std::fs::write(&test_apk, b"PK\x03\x04testOnly").expect("cannot write APK");
+
+    let fake_device = FakeADBDevice::new();
+    fake_device
+        .add_package("com.example.app")
+        .expect("cannot add package");
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    assert!(matches!(
+        device.install(&test_apk),
+        Err(RustADBError::InstallFailed(InstallFailureReason::TestOnly, message)) if message == "installPackageLI"
+    ));
+    let options = InstallOptions {
+        replace: true,
+        allow_test: true,
+        user: Some(10),
+        install_location: Some(InstallLocation::InternalOnly),
+        ..Default::default()
+    };
+    device
+        .install_with_options(&test_apk, &options, &mut TransferMonitor::new())
+        .expect("cannot install APK");
+
+    // APKs can be streamed from memory, as long as they look like one
+    let mut monitor = TransferMonitor::new();
+    let apk = b"PK\x03\x04app";
+    device
+        .install_from_reader(&mut &apk[..], 7, &options, &mut monitor)
+        .expect("cannot install APK");
+    assert!(matches!(
+        device.install_from_reader(&mut &b"<html>"[..], 6, &options, &mut monitor),
+        Err(RustADBError::InvalidApk(_))
+    ));
+    // Readers must hold as many bytes as declared, package manager would wait for missing ones
+    assert!(matches!(
+        device.install_from_reader(&mut &apk[..], 10, &options, &mut monitor),
+        Err(RustADBError::InvalidApk(message)) if message == "expected 10 bytes, got 7"
+    ));
+    device
+        .install_from_reader(&mut &apk[..], 7, &options, &mut monitor)
+        .expect("cannot install APK after a truncated one");
+
+    let options = UninstallOptions {
+        keep_data: true,
+        user: None,
+    };
+    device
+        .uninstall_with_options("com.example.app", &options)
+        .expect("cannot uninstall package");
+    // Failures which are not about installing are reported as is
+    assert!(matches!(
+        device.uninstall("com.example.app"),
+        Err(RustADBError::ADBRequestFailed(message)) if message == "Failure [DELETE_FAILED_INTERNAL_ERROR]"
+    ));
+
+    let services = fake_device.opened_services().expect("cannot get services");
+    assert_eq!(
+        services[services.len() - 7..],
+        [
+            "exec:cmd package 'install' -S 12",
+            "exec:cmd package 'install' -r -t --user 10 --install-location 1 -S 12",
+            "exec:cmd package 'install' -r -t --user 10 --install-location 1 -S 7",
+            "exec:cmd package 'install' -r -t --user 10 --install-location 1 -S 10",
+            "exec:cmd package 'install' -r -t --user 10 --install-location 1 -S 7",
+            "exec:cmd package 'uninstall' -k 'com.example.app'",
+            "exec:cmd package 'uninstall' 'com.example.app'",
+        ]
+    );
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::Result;
+use crate::utils::shell_quote;
+
+/// Options of an instrumentation run, see [`crate::ADBDeviceExt::run_instrumentation`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstrumentationOptions {
+    /// Instrumentation to run, e.g. `com.example.app.test/androidx.test.runner.AndroidJUnitRunner`.
+    pub runner: String,
+    /// Only run these test classes, or test methods given as `com.example.FooTest#testBar` (`-e class`).
+    pub classes: Vec<String>,
+    /// Only run tests of this Java package (`-e package`).
+    pub package: Option<String>,
+    /// Only run tests having this annotation (`-e annotation`).
+    pub annotation: Option<String>,
+    /// Skip tests having this annotation (`-e notAnnotation`).
+    pub not_annotation: Option<String>,
+    /// Index of shard to run, and amount of shards tests are split into (`-e shardIndex`, `-e numShards`).
+    pub shard: Option<(u32, u32)>,
+    /// Other arguments given to runner (`-e key value`).
+    pub args: Vec<(String, String)>,
+    /// Run instrumentation as this user (`--user`).
+    pub user: Option<u32>,
+}
+
+impl InstrumentationOptions {
+    /// Options running all tests of instrumentation `runner`.
+    pub fn new(runner: &str) -> Self {
+        Self {
+            runner: runner.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Arguments of `am instrument` applying these options, quoted for device shell.
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut runner_args = Vec::new();
+        if !self.classes.is_empty() {
+            runner_args.push(("class", self.classes.join(",")));
+        }
+        let filters = [
+            ("package", &self.package),
+            ("annotation", &self.annotation),
+            ("notAnnotation", &self.not_annotation),
+        ];
+        for (key, value) in filters {
+            if let Some(value) = value {
+                runner_args.push((key, value.clone()));
+            }
+        }
+        if let Some((index, count)) = self.shard {
+            runner_args.push(("shardIndex", index.to_string()));
+            runner_args.push(("numShards", count.to_string()));
+        }
+        let others = self
+            .args
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone()));
+
+        let mut args = Vec::new();
+        if let Some(user) = self.user {
+            args.extend(["--user".to_string(), user.to_string()]);
+        }
+        for (key, value) in runner_args.into_iter().chain(others) {
+            args.extend(["-e".to_string(), shell_quote(key), shell_quote(&value)]);
+        }
+        args.push(shell_quote(&self.runner));
+        args
+    }
+}
+
+/// Outcome of a test run by an instrumentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStatus {
+    /// Test passed
+    Passed,
+    /// An assertion of test failed
+    Failed,
+    /// Test threw an unexpected exception
+    Error,
+    /// Test was not run, e.g. because of an `@Ignore` annotation
+    Ignored,
+    /// An assumption of test did not hold, so that test was skipped
+    AssumptionFailure,
+}
+
+impl TestStatus {
+    /// Status reported with `INSTRUMENTATION_STATUS_CODE` `code` once a test finished, if any.
+    pub(crate) fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Passed),
+            -1 => Some(Self::Error),
+            -2 => Some(Self::Failed),
+            -3 => Some(Self::Ignored),
+            -4 => Some(Self::AssumptionFailure),
+            _ => None,
+        }
+    }
+}
+
+/// A test run by an instrumentation, as reported to [`crate::ADBDeviceExt::run_instrumentation`] callbacks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestResult {
+    /// Name of class of test, e.g. `com.example.FooTest`.
+    pub class: String,
+    /// Name of test method.
+    pub name: String,
+    /// Outcome of test.
+    pub status: TestStatus,
+    /// Stack trace of failure or error, if any.
+    pub stack: Option<String>,
+    /// Time taken by test, as measured by host between its start and end reports.
+    pub duration: Duration,
+}
+
+/// Outcome of an instrumentation run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstrumentationReport {
+    /// Tests which were run, in order.
+    pub tests: Vec<TestResult>,
+    /// Values reported by instrumentation once it finished, e.g. `stream` holding its summary.
+    pub results: BTreeMap<String, String>,
+    /// Result code of instrumentation, `-1` when it completed. `None` if it did not report one.
+    pub code: Option<i32>,
+    /// Error which stopped instrumentation before it completed, e.g. a crash of process under test.
+    pub error: Option<String>,
+    /// Time taken by whole run.
+    pub duration: Duration,
+}
+
+impl InstrumentationReport {
+    /// Whether instrumentation completed and all tests which were run passed.
+    pub fn is_successful(&self) -> bool {
+        self.error.is_none()
+            && self.code == Some(-1)
+            && self
+                .tests
+                .iter()
+                .all(|test| !matches!(test.status, TestStatus::Failed | TestStatus::Error))
+    }
+
+    /// Write this report as a JUnit XML test suite named `name` into `writer`, e.g. for CI systems.
+    pub fn write_junit_xml(&self, name: &str, writer: &mut dyn Write) -> Result<()> {
+        let count = |status: &[TestStatus]| {
+            self.tests
+                .iter()
+                .filter(|test| status.contains(&test.status))
+                .count()
+        };
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+            escape_xml(name),
+            self.tests.len(),
+            count(&[TestStatus::Failed]),
+            count(&[TestStatus::Error]),
+            count(&[TestStatus::Ignored, TestStatus::AssumptionFailure]),
+            self.duration.as_secs_f64()
+        )?;
+
+        for test in &self.tests {
+            write!(
+                writer,
+                r#"  <testcase classname="{}" name="{}" time="{:.3}""#,
+                escape_xml(&test.class),
+                escape_xml(&test.name),
+                test.duration.as_secs_f64()
+            )?;
+            let element = match test.status {
+                TestStatus::Passed => {
+                    writeln!(writer, "/>")?;
+                    continue;
+                }
+                TestStatus::Failed => "failure",
+                TestStatus::Error => "error",
+                TestStatus::Ignored | TestStatus::AssumptionFailure => "skipped",
+            };
+
+            // Message of failure is the first line of its stack trace, such as `java.lang.AssertionError: expected:<1> but was:<2>`
+            let stack = test.stack.as_deref().unwrap_or_default();
+            let message = stack.lines().next().unwrap_or_default();
+            writeln!(writer, ">")?;
+            writeln!(
+                writer,
+                r#"    <{element} message="{}">{}</{element}>"#,
+                escape_xml(message),
+                escape_xml(stack)
+            )?;
+            writeln!(writer, "  </testcase>")?;
+        }
+
+        if let Some(error) = &self.error {
+            writeln!(writer, "  <system-err>{}</system-err>", escape_xml(error))?;
+        }
+        writeln!(writer, "</testsuite>")?;
+        Ok(())
+    }
+}
+
+/// Escape `text` to use it in XML attributes and contents.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
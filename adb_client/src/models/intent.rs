@@ -0,0 +1,251 @@
+use crate::utils::shell_quote;
+
+/// Typed value of an [`Intent`] extra.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntentExtra {
+    /// A string, given with `--es`
+    String(String),
+    /// An `int`, given with `--ei`
+    Int(i32),
+    /// A `boolean`, given with `--ez`
+    Bool(bool),
+    /// A `long`, given with `--el`
+    Long(i64),
+    /// A `float`, given with `--ef`
+    Float(f32),
+    /// A URI, given with `--eu`
+    Uri(String),
+    /// An array of strings, given with `--esa`
+    StringArray(Vec<String>),
+    /// An array of `int`, given with `--eia`
+    IntArray(Vec<i32>),
+    /// An array of `long`, given with `--ela`
+    LongArray(Vec<i64>),
+    /// An array of `float`, given with `--efa`
+    FloatArray(Vec<f32>),
+    /// A `null` string, given with `--esn`
+    Null,
+}
+
+impl IntentExtra {
+    /// Option of `am` giving this extra, and its value if any.
+    fn arg(&self) -> (&'static str, Option<String>) {
+        match self {
+            IntentExtra::String(value) => ("--es", Some(value.clone())),
+            IntentExtra::Int(value) => ("--ei", Some(value.to_string())),
+            IntentExtra::Bool(value) => ("--ez", Some(value.to_string())),
+            IntentExtra::Long(value) => ("--el", Some(value.to_string())),
+            IntentExtra::Float(value) => ("--ef", Some(value.to_string())),
+            IntentExtra::Uri(value) => ("--eu", Some(value.clone())),
+            // Commas separate values, and have to be escaped in strings
+            IntentExtra::StringArray(values) => (
+                "--esa",
+                Some(
+                    values
+                        .iter()
+                        .map(|value| value.replace(',', "\\,"))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+            ),
+            IntentExtra::IntArray(values) => ("--eia", Some(join(values))),
+            IntentExtra::LongArray(values) => ("--ela", Some(join(values))),
+            IntentExtra::FloatArray(values) => ("--efa", Some(join(values))),
+            IntentExtra::Null => ("--esn", None),
+        }
+    }
+}
+
+/// Join `values` with commas.
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl From<&str> for IntentExtra {
+    fn from(value: &str) -> Self {
+        IntentExtra::String(value.to_string())
+    }
+}
+
+impl From<String> for IntentExtra {
+    fn from(value: String) -> Self {
+        IntentExtra::String(value)
+    }
+}
+
+impl From<i32> for IntentExtra {
+    fn from(value: i32) -> Self {
+        IntentExtra::Int(value)
+    }
+}
+
+impl From<bool> for IntentExtra {
+    fn from(value: bool) -> Self {
+        IntentExtra::Bool(value)
+    }
+}
+
+impl From<i64> for IntentExtra {
+    fn from(value: i64) -> Self {
+        IntentExtra::Long(value)
+    }
+}
+
+impl From<f32> for IntentExtra {
+    fn from(value: f32) -> Self {
+        IntentExtra::Float(value)
+    }
+}
+
+/// An intent given to activity manager, to start an activity or a service, or to send a broadcast.
+///
+/// ```rust
+/// use adb_client::{Intent, IntentExtra};
+///
+/// let intent = Intent::new()
+///     .with_component("com.example.app", ".MainActivity")
+///     .with_action("android.intent.action.VIEW")
+///     .with_data("https://example.com")
+///     .with_extra("retries", 3)
+///     .with_extra("tags", IntentExtra::StringArray(vec!["a".to_string(), "b".to_string()]))
+///     .with_flags(Intent::FLAG_ACTIVITY_CLEAR_TOP);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Intent {
+    component: Option<String>,
+    package: Option<String>,
+    action: Option<String>,
+    data: Option<String>,
+    mime_type: Option<String>,
+    categories: Vec<String>,
+    extras: Vec<(String, IntentExtra)>,
+    flags: u32,
+}
+
+impl Intent {
+    /// Start activity in a new task.
+    pub const FLAG_ACTIVITY_NEW_TASK: u32 = 0x1000_0000;
+    /// Finish activities on top of started activity if it is already running.
+    pub const FLAG_ACTIVITY_CLEAR_TOP: u32 = 0x0400_0000;
+    /// Do not start activity if it is already on top of its task.
+    pub const FLAG_ACTIVITY_SINGLE_TOP: u32 = 0x2000_0000;
+    /// Finish all activities of task before starting activity, along with [`Intent::FLAG_ACTIVITY_NEW_TASK`].
+    pub const FLAG_ACTIVITY_CLEAR_TASK: u32 = 0x0000_8000;
+    /// Do not keep activity in history once user leaves it.
+    pub const FLAG_ACTIVITY_NO_HISTORY: u32 = 0x4000_0000;
+    /// Deliver broadcast to packages which were force-stopped or never started.
+    pub const FLAG_INCLUDE_STOPPED_PACKAGES: u32 = 0x0000_0020;
+    /// Deliver broadcast with foreground priority.
+    pub const FLAG_RECEIVER_FOREGROUND: u32 = 0x1000_0000;
+
+    /// Instantiate an empty intent.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Target component `class` of `package`. Class names starting with `.` are relative to package.
+    pub fn with_component(mut self, package: &str, class: &str) -> Self {
+        self.component = Some(format!("{package}/{class}"));
+        self
+    }
+
+    /// Limit intent to components of `package`.
+    pub fn with_package(mut self, package: &str) -> Self {
+        self.package = Some(package.to_string());
+        self
+    }
+
+    /// Set action of intent, e.g. `android.intent.action.VIEW`.
+    pub fn with_action(mut self, action: &str) -> Self {
+        self.action = Some(action.to_string());
+        self
+    }
+
+    /// Set data URI of intent.
+    pub fn with_data(mut self, uri: &str) -> Self {
+        self.data = Some(uri.to_string());
+        self
+    }
+
+    /// Set MIME type of intent data.
+    pub fn with_mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_type = Some(mime_type.to_string());
+        self
+    }
+
+    /// Add a category to intent, e.g. `android.intent.category.LAUNCHER`.
+    pub fn with_category(mut self, category: &str) -> Self {
+        self.categories.push(category.to_string());
+        self
+    }
+
+    /// Add extra `key` to intent.
+    pub fn with_extra<V: Into<IntentExtra>>(mut self, key: &str, value: V) -> Self {
+        self.extras.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Add `flags` to flags of intent, e.g. [`Intent::FLAG_ACTIVITY_NEW_TASK`].
+    pub fn with_flags(mut self, flags: u32) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /// Arguments of `am` commands describing this intent, quoted for device shell.
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let options = [
+            ("-n", &self.component),
+            ("-p", &self.package),
+            ("-a", &self.action),
+            ("-d", &self.data),
+            ("-t", &self.mime_type),
+        ];
+        for (option, value) in options {
+            if let Some(value) = value {
+                args.extend([option.to_string(), shell_quote(value)]);
+            }
+        }
+        for category in &self.categories {
+            args.extend(["-c".to_string(), shell_quote(category)]);
+        }
+        if self.flags != 0 {
+            args.extend(["-f".to_string(), format!("{:#x}", self.flags)]);
+        }
+        for (key, value) in &self.extras {
+            let (option, value) = value.arg();
+            args.extend([option.to_string(), shell_quote(key)]);
+            args.extend(value.as_deref().map(shell_quote));
+        }
+        args
+    }
+}
+
+#[test]
+fn test_intent_args() {
+    let intent = Intent::new()
+        .with_component("com.example.app", ".MainActivity")
+        .with_action("android.intent.action.VIEW")
+        .with_data("https://example.com/?a=1&b=2")
+        .with_category("android.intent.category.BROWSABLE")
+        .with_extra("name", "it's me")
+        .with_extra("count", 3)
+        .with_extra("enabled", true)
+        .with_extra(
+            "tags",
+            IntentExtra::StringArray(vec!["a,b".to_string(), "c".to_string()]),
+        )
+        .with_extra("none", IntentExtra::Null)
+        .with_flags(Intent::FLAG_ACTIVITY_NEW_TASK | Intent::FLAG_ACTIVITY_CLEAR_TOP);
+
+    assert_eq!(
+        intent.args().join(" "),
+        "-n 'com.example.app/.MainActivity' -a 'android.intent.action.VIEW' -d 'https://example.com/?a=1&b=2' \
+         -c 'android.intent.category.BROWSABLE' -f 0x14000000 --es 'name' 'it'\\''s me' --ei 'count' '3' \
+         --ez 'enabled' 'true' --esa 'tags' 'a\\,b,c' --esn 'none'"
+    );
+}
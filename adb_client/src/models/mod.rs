@@ -1,15 +1,66 @@
+mod activity_result;
+mod adb_dir_entry;
+mod adb_file_type;
 mod adb_request_status;
 mod adb_server_command;
 mod adb_stat_response;
+mod app_op_mode;
+mod boot_stage;
+mod device_properties;
+mod dir_sync_change;
+mod dir_sync_options;
+mod file_transfer;
 mod framebuffer_info;
 mod host_features;
+mod install_failure_reason;
+mod install_options;
+mod instrumentation;
+mod intent;
+mod package_filter;
+mod package_info;
+mod permission_failure_reason;
+mod push_options;
 mod reboot_type;
+mod shell_protocol;
+mod smart_socket;
 mod sync_command;
+mod sync_compression;
+mod sync_data;
+mod terminal_size;
+mod uninstall_options;
 
+pub use activity_result::{ActivityStartResult, BroadcastResult};
+pub use adb_dir_entry::AdbDirEntry;
+pub use adb_file_type::AdbFileType;
 pub use adb_request_status::AdbRequestStatus;
-pub(crate) use adb_server_command::AdbServerCommand;
+pub(crate) use adb_server_command::{AdbServerCommand, TransportSelector};
 pub use adb_stat_response::AdbStatResponse;
+pub(crate) use adb_stat_response::STAT_RESPONSE_SIZE;
+pub use app_op_mode::AppOpMode;
+pub use boot_stage::BootStage;
+pub use device_properties::DeviceProperties;
+pub use dir_sync_change::{DirSyncChange, DirSyncChangeKind, DirSyncReport};
+pub use dir_sync_options::DirSyncOptions;
+pub use file_transfer::FileTransfer;
 pub(crate) use framebuffer_info::{FrameBufferInfoV1, FrameBufferInfoV2};
 pub use host_features::HostFeatures;
+pub use install_failure_reason::InstallFailureReason;
+pub use install_options::{InstallLocation, InstallOptions};
+pub use instrumentation::{InstrumentationOptions, InstrumentationReport, TestResult, TestStatus};
+pub use intent::{Intent, IntentExtra};
+pub use package_filter::PackageFilter;
+pub use package_info::{InstalledPackage, PackageDetails};
+pub use permission_failure_reason::PermissionFailureReason;
+pub use push_options::PushOptions;
 pub use reboot_type::RebootType;
+pub(crate) use shell_protocol::{
+    ShellPacketDecoder, ShellPacketId, ShellV2Output, encode_shell_packet,
+};
+#[cfg(feature = "tokio")]
+pub(crate) use shell_protocol::ShellOutput;
+pub(crate) use smart_socket::{decode_hex_length, encode_hex_framed};
 pub use sync_command::SyncCommand;
+pub use sync_compression::SyncCompression;
+pub(crate) use sync_data::{SyncDataDecoder, SyncDataReader, SyncDataWriter};
+pub use terminal_size::TerminalSize;
+pub use uninstall_options::UninstallOptions;
@@ -0,0 +1,31 @@
+/// Filters of [`crate::ADBDeviceExt::list_packages`]. Default filter lists all packages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PackageFilter {
+    /// Only list system packages when `true`, or third-party ones when `false`.
+    pub system: Option<bool>,
+    /// Only list enabled packages when `true`, or disabled ones when `false`.
+    pub enabled: Option<bool>,
+    /// Only list packages installed for this user.
+    pub user: Option<u32>,
+}
+
+impl PackageFilter {
+    /// Arguments of `pm list packages` applying this filter.
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        match self.system {
+            Some(true) => args.push("-s".to_string()),
+            Some(false) => args.push("-3".to_string()),
+            None => {}
+        }
+        match self.enabled {
+            Some(true) => args.push("-e".to_string()),
+            Some(false) => args.push("-d".to_string()),
+            None => {}
+        }
+        if let Some(user) = self.user {
+            args.extend(["--user".to_string(), user.to_string()]);
+        }
+        args
+    }
+}
@@ -0,0 +1,44 @@
+/// A package listed by [`crate::ADBDeviceExt::list_packages`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstalledPackage {
+    /// Package name, e.g. `com.example.app`.
+    pub name: String,
+    /// Path of base APK of package on device.
+    pub path: String,
+    /// Version code of package, if reported by device.
+    pub version_code: Option<u64>,
+    /// Package which installed this package, if any.
+    pub installer: Option<String>,
+    /// Linux user id under which package runs, if reported by device.
+    pub uid: Option<u32>,
+}
+
+/// Details of an installed package, as returned by [`crate::ADBDeviceExt::package_details`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackageDetails {
+    /// Package name, e.g. `com.example.app`.
+    pub name: String,
+    /// Version code of package.
+    pub version_code: Option<u64>,
+    /// Human readable version of package, e.g. `1.2.3`.
+    pub version_name: Option<String>,
+    /// Paths of all APKs of package on device, base APK first.
+    pub paths: Vec<String>,
+    /// Package which installed this package, if any.
+    pub installer: Option<String>,
+    /// Linux user id under which package runs.
+    pub uid: Option<u32>,
+    /// Package manager flags of package, e.g. `HAS_CODE` or `DEBUGGABLE`.
+    pub flags: Vec<String>,
+    /// Permissions requested in manifest of package.
+    pub requested_permissions: Vec<String>,
+    /// Install and runtime permissions currently granted to package, for any user.
+    pub granted_permissions: Vec<String>,
+}
+
+impl PackageDetails {
+    /// Whether package manager flags of package contain `flag`.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+/// Reason of a failed permission or app op change, as reported by device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PermissionFailureReason {
+    /// Package does not request permission in its manifest
+    NotRequested,
+    /// Permission is not a runtime permission, and cannot be granted or revoked
+    NotChangeable,
+    /// Permission does not exist on device
+    UnknownPermission,
+    /// Any other failure
+    Other,
+}
+
+impl PermissionFailureReason {
+    /// Reason of failure reported by device with `message`.
+    pub(crate) fn from_message(message: &str) -> Self {
+        if message.contains("has not requested permission") {
+            Self::NotRequested
+        } else if message.contains("not a changeable permission type") {
+            Self::NotChangeable
+        } else if message.contains("Unknown permission") {
+            Self::UnknownPermission
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl Display for PermissionFailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotRequested => write!(f, "permission not requested"),
+            Self::NotChangeable => write!(f, "permission not changeable"),
+            Self::UnknownPermission => write!(f, "unknown permission"),
+            Self::Other => write!(f, "device error"),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::fs::Metadata;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Metadata given to files pushed on a device with [`crate::ADBDeviceExt::push_with_options`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PushOptions {
+    /// Mode of remote file, `0o644` by default. Setting file type bits of symbolic links pushes a link, pointing to pushed contents.
+    pub mode: u32,
+    /// Modification time of remote file, in seconds since epoch. Time of transfer is used when `None`, by default.
+    pub mtime: Option<u32>,
+}
+
+impl Default for PushOptions {
+    fn default() -> Self {
+        Self {
+            mode: 0o644,
+            mtime: None,
+        }
+    }
+}
+
+impl PushOptions {
+    /// Options copying mode and modification time of a local file, as given by its `metadata`.
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.permissions().mode()
+        };
+        #[cfg(not(unix))]
+        let mode = match metadata.permissions().readonly() {
+            true => 0o444,
+            false => 0o644,
+        };
+
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+            .map(|mtime| mtime.as_secs() as u32);
+
+        Self { mode, mtime }
+    }
+
+    /// Modification time to send, time of transfer if none was set.
+    pub(crate) fn mtime_or_now(&self) -> u32 {
+        self.mtime.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs() as u32)
+        })
+    }
+}
+
+#[test]
+fn test_push_with_options() {
+    use crate::{ADBDeviceExt, FakeADBDevice};
+
+    let private_key_path = crate::fake_device::write_test_private_key("push_options");
+    let mut fake_device = FakeADBDevice::new();
+    let mut banner = fake_device.banner().clone();
+    banner
+        .features
+        .retain(|feature| feature != "fixed_push_mkdir");
+    fake_device.set_banner(banner);
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let options = PushOptions {
+        mode: 0o600,
+        mtime: Some(1_700_000_000),
+    };
+    device
+        .push_with_options(&mut &b"data"[..], &"/sdcard/new dir/data.txt", options)
+        .expect("cannot push file");
+    let stat = device
+        .stat("/sdcard/new dir/data.txt")
+        .expect("cannot stat file");
+    assert_eq!((stat.file_perm, stat.mod_time), (0o100600, 1_700_000_000));
+
+    // Local metadata is kept
+    let local_path = private_key_path.with_file_name("local.txt");
+    std::fs::write(&local_path, b"local").expect("cannot write file");
+    let metadata = std::fs::metadata(&local_path).expect("cannot stat file");
+    device
+        .push_file(&local_path, &"/sdcard/local.txt")
+        .expect("cannot push file");
+    let stat = device.stat("/sdcard/local.txt").expect("cannot stat file");
+    assert_eq!(
+        stat.mod_time,
+        PushOptions::from_metadata(&metadata)
+            .mtime
+            .expect("no mtime")
+    );
+
+    // Parent directory is created as device lacks `fixed_push_mkdir`
+    assert!(
+        fake_device
+            .opened_services()
+            .expect("cannot get services")
+            .contains(&"shell,v2,raw:mkdir -p '/sdcard/new dir'".to_string())
+    );
+}
@@ -0,0 +1,197 @@
+use std::io::Write;
+
+/// Size of packet headers: one byte of packet id, followed by data length as a little endian `u32`.
+const SHELL_PACKET_HEADER_SIZE: usize = 5;
+/// Largest data accepted in a packet, which is the largest payload `adbd` sends in a message.
+const MAX_SHELL_PACKET_DATA_SIZE: usize = 1024 * 1024;
+
+/// Packets exchanged by shell protocol v2, as defined by `adbd` in `shell_protocol.h`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum ShellPacketId {
+    Stdin = 0,
+    Stdout = 1,
+    Stderr = 2,
+    Exit = 3,
+    CloseStdin = 4,
+    WindowSizeChange = 5,
+}
+
+impl TryFrom<u8> for ShellPacketId {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Stdin,
+            1 => Self::Stdout,
+            2 => Self::Stderr,
+            3 => Self::Exit,
+            4 => Self::CloseStdin,
+            5 => Self::WindowSizeChange,
+            v => return Err(v),
+        })
+    }
+}
+
+/// Frame `data` in a shell protocol v2 packet.
+pub(crate) fn encode_shell_packet(id: ShellPacketId, data: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(SHELL_PACKET_HEADER_SIZE + data.len());
+    packet.push(id as u8);
+    packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+/// Id of a shell protocol v2 packet, or raw id if unknown, along with its data.
+pub(crate) type ShellPacket = (Result<ShellPacketId, u8>, Vec<u8>);
+
+/// Output of a command, carried by a shell protocol v2 packet.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ShellOutput {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    Exit(Option<u8>),
+}
+
+/// Reassemble shell protocol v2 packets from data received in arbitrary chunks.
+#[derive(Debug, Default)]
+pub(crate) struct ShellPacketDecoder {
+    buffer: Vec<u8>,
+}
+
+impl ShellPacketDecoder {
+    /// Append received `data`.
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Next complete packet, if any. Packets with an unknown id are returned with their raw id as error.
+    ///
+    /// Fails if a packet claims to carry more than [`MAX_SHELL_PACKET_DATA_SIZE`] bytes, instead of buffering them.
+    pub(crate) fn next_packet(&mut self) -> std::io::Result<Option<ShellPacket>> {
+        if self.buffer.len() < SHELL_PACKET_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let mut length = [0; 4];
+        length.copy_from_slice(&self.buffer[1..SHELL_PACKET_HEADER_SIZE]);
+        let data_size = u32::from_le_bytes(length) as usize;
+        if data_size > MAX_SHELL_PACKET_DATA_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "shell packet of {data_size} bytes exceeds {MAX_SHELL_PACKET_DATA_SIZE} bytes"
+                ),
+            ));
+        }
+        let packet_size = SHELL_PACKET_HEADER_SIZE + data_size;
+        if self.buffer.len() < packet_size {
+            return Ok(None);
+        }
+
+        let packet = self.buffer.drain(..packet_size).collect::<Vec<u8>>();
+        Ok(Some((
+            ShellPacketId::try_from(packet[0]),
+            packet[SHELL_PACKET_HEADER_SIZE..].to_vec(),
+        )))
+    }
+
+    /// Next complete packet carrying output of command, if any. Other packets are skipped.
+    pub(crate) fn next_output(&mut self) -> std::io::Result<Option<ShellOutput>> {
+        while let Some((id, data)) = self.next_packet()? {
+            match id {
+                Ok(ShellPacketId::Stdout) => return Ok(Some(ShellOutput::Stdout(data))),
+                Ok(ShellPacketId::Stderr) => return Ok(Some(ShellOutput::Stderr(data))),
+                Ok(ShellPacketId::Exit) => {
+                    return Ok(Some(ShellOutput::Exit(data.first().copied())));
+                }
+                id => log::debug!("ignoring unexpected shell packet {id:?}"),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Writer splitting shell protocol v2 packets written to it into `stdout` and `stderr`, and keeping exit code of command.
+pub(crate) struct ShellV2Output<'a> {
+    decoder: ShellPacketDecoder,
+    stdout: &'a mut dyn Write,
+    /// Error stream is written into `stdout` if none is given
+    stderr: Option<&'a mut dyn Write>,
+    exit_code: Option<u8>,
+}
+
+impl<'a> ShellV2Output<'a> {
+    pub(crate) fn new(stdout: &'a mut dyn Write, stderr: &'a mut dyn Write) -> Self {
+        Self {
+            decoder: ShellPacketDecoder::default(),
+            stdout,
+            stderr: Some(stderr),
+            exit_code: None,
+        }
+    }
+
+    /// Write both output and error streams into `output`.
+    pub(crate) fn merged(output: &'a mut dyn Write) -> Self {
+        Self {
+            decoder: ShellPacketDecoder::default(),
+            stdout: output,
+            stderr: None,
+            exit_code: None,
+        }
+    }
+
+    /// Exit code of command, once received.
+    pub(crate) fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+}
+
+impl Write for ShellV2Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.decoder.push(buf);
+        while let Some(output) = self.decoder.next_output()? {
+            match output {
+                ShellOutput::Stdout(data) => self.stdout.write_all(&data)?,
+                ShellOutput::Stderr(data) => match self.stderr.as_mut() {
+                    Some(stderr) => stderr.write_all(&data)?,
+                    None => self.stdout.write_all(&data)?,
+                },
+                ShellOutput::Exit(code) => self.exit_code = code,
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(stderr) = self.stderr.as_mut() {
+            stderr.flush()?;
+        }
+        self.stdout.flush()
+    }
+}
+
+#[test]
+fn test_shell_v2_output() {
+    let mut session = encode_shell_packet(ShellPacketId::Stdout, b"hello\n");
+    session.extend(encode_shell_packet(ShellPacketId::Stderr, b"oops\n"));
+    session.extend(encode_shell_packet(ShellPacketId::Exit, &[3]));
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let mut output = ShellV2Output::new(&mut stdout, &mut stderr);
+    // Packets may be split across messages
+    for chunk in session.chunks(3) {
+        output.write_all(chunk).expect("cannot write packets");
+    }
+    assert_eq!(output.exit_code(), Some(3));
+    assert_eq!(stdout, b"hello\n");
+    assert_eq!(stderr, b"oops\n");
+
+    // Oversized packets are rejected before their data is received
+    let mut header = vec![ShellPacketId::Stdout as u8];
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    let mut output = ShellV2Output::new(&mut stdout, &mut stderr);
+    assert!(output.write_all(&header).is_err());
+}
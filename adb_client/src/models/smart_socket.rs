@@ -0,0 +1,13 @@
+use crate::Result;
+
+/// Prefix `body` by its length as 4 hexadecimal digits, as requests and responses of the smart-socket protocol are framed.
+pub(crate) fn encode_hex_framed(body: &[u8]) -> Vec<u8> {
+    let mut frame = format!("{:04x}", body.len()).into_bytes();
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// Length of a smart-socket request or response body, given as 4 hexadecimal digits.
+pub(crate) fn decode_hex_length(length: [u8; 4]) -> Result<u32> {
+    Ok(u32::from_str_radix(std::str::from_utf8(&length)?, 16)?)
+}
@@ -3,10 +3,16 @@ use std::fmt::Display;
 pub enum SyncCommand {
     /// List files in a folder
     List,
+    /// List files in a folder, using sync protocol v2
+    List2,
     /// Receive a file from the device
     Recv,
     /// Send a file to the device
     Send,
+    /// Receive a file from the device, using sync protocol v2
+    Recv2,
+    /// Send a file to the device, using sync protocol v2
+    Send2,
     // Stat a file
     Stat,
 }
@@ -15,8 +21,11 @@ impl Display for SyncCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SyncCommand::List => write!(f, "LIST"),
+            SyncCommand::List2 => write!(f, "LIS2"),
             SyncCommand::Recv => write!(f, "RECV"),
             SyncCommand::Send => write!(f, "SEND"),
+            SyncCommand::Recv2 => write!(f, "RCV2"),
+            SyncCommand::Send2 => write!(f, "SND2"),
             SyncCommand::Stat => write!(f, "STAT"),
         }
     }
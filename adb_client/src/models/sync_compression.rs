@@ -0,0 +1,188 @@
+use std::io::{Read, Write};
+
+use crate::constants::BUFFER_SIZE;
+
+/// Feature advertised by devices supporting sync protocol v2 (`SND2` and `RCV2` requests).
+const SYNC_V2_FEATURE: &str = "sendrecv_v2";
+
+/// Brotli quality used by `adb`, favoring speed over compression ratio.
+const BROTLI_QUALITY: u32 = 1;
+/// Brotli window size, as a power of two.
+const BROTLI_WINDOW_BITS: u32 = 22;
+
+/// Compression applied to file contents transferred with sync protocol v2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncCompression {
+    /// Contents are sent as is
+    None,
+    /// Brotli, supported since Android 11
+    Brotli,
+    /// LZ4, supported since Android 12
+    Lz4,
+    /// Zstandard, supported since Android 12, only used when explicitly requested
+    Zstd,
+}
+
+impl SyncCompression {
+    /// Compression to use with a device, given a predicate telling whether it advertises a feature.
+    ///
+    /// Returns `None` if device does not support sync protocol v2, and legacy `SEND` and `RECV` requests must be used.
+    /// Otherwise, `preferred` compression is used if device supports it.
+    /// Failing that, LZ4 is preferred over Brotli if device supports both, as `adb` does.
+    /// Like `adb`, Zstd is only picked when explicitly requested.
+    pub(crate) fn negotiate<F: Fn(&str) -> bool>(
+        preferred: Option<Self>,
+        has_feature: F,
+    ) -> Option<Self> {
+        if !has_feature(SYNC_V2_FEATURE) {
+            return None;
+        }
+
+        preferred
+            .into_iter()
+            .chain([Self::Lz4, Self::Brotli])
+            .find(|compression| has_feature(&compression.feature()))
+            .or(Some(Self::None))
+    }
+
+    /// Feature advertised by devices supporting this compression.
+    pub(crate) fn feature(self) -> String {
+        match self {
+            Self::None => SYNC_V2_FEATURE.to_string(),
+            Self::Brotli => format!("{SYNC_V2_FEATURE}_brotli"),
+            Self::Lz4 => format!("{SYNC_V2_FEATURE}_lz4"),
+            Self::Zstd => format!("{SYNC_V2_FEATURE}_zstd"),
+        }
+    }
+
+    /// Flags of `SND2` and `RCV2` requests selecting this compression.
+    pub(crate) fn flags(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Brotli => 1,
+            Self::Lz4 => 2,
+            Self::Zstd => 4,
+        }
+    }
+
+    /// Compression selected by flags of a `SND2` or `RCV2` request, ignoring other flags.
+    pub(crate) fn from_flags(flags: u32) -> Self {
+        [Self::Brotli, Self::Lz4, Self::Zstd]
+            .into_iter()
+            .find(|compression| flags & compression.flags() != 0)
+            .unwrap_or(Self::None)
+    }
+
+    /// Compress everything read from `input` into `output`, returning `output` once compressed stream is complete.
+    pub(crate) fn compress<W: Write>(
+        self,
+        input: &mut dyn Read,
+        mut output: W,
+    ) -> std::io::Result<W> {
+        match self {
+            Self::None => {
+                std::io::copy(input, &mut output)?;
+                Ok(output)
+            }
+            Self::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(
+                    output,
+                    BUFFER_SIZE,
+                    BROTLI_QUALITY,
+                    BROTLI_WINDOW_BITS,
+                );
+                std::io::copy(input, &mut encoder)?;
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+            Self::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(output);
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish().map_err(std::io::Error::other)
+            }
+            Self::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(output, 0)?;
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    /// Decompress stream read from `input` into `output`, returning amount of bytes written.
+    pub(crate) fn decompress(
+        self,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> std::io::Result<u64> {
+        match self {
+            Self::None => std::io::copy(input, output),
+            Self::Brotli => {
+                std::io::copy(&mut brotli::Decompressor::new(input, BUFFER_SIZE), output)
+            }
+            Self::Lz4 => std::io::copy(&mut lz4_flex::frame::FrameDecoder::new(input), output),
+            Self::Zstd => std::io::copy(&mut zstd::stream::read::Decoder::new(input)?, output),
+        }
+    }
+}
+
+#[test]
+fn test_sync_compression() {
+    let contents = b"sync protocol v2 ".repeat(10_000);
+    for compression in [
+        SyncCompression::None,
+        SyncCompression::Brotli,
+        SyncCompression::Lz4,
+        SyncCompression::Zstd,
+    ] {
+        let compressed = compression
+            .compress(&mut contents.as_slice(), Vec::new())
+            .expect("cannot compress");
+        let mut decompressed = Vec::new();
+        compression
+            .decompress(&mut compressed.as_slice(), &mut decompressed)
+            .expect("cannot decompress");
+        assert_eq!(decompressed, contents, "{compression:?}");
+        assert_eq!(
+            SyncCompression::from_flags(compression.flags()),
+            compression
+        );
+    }
+
+    // LZ4 is preferred over Brotli, and only picked if device supports sync protocol v2
+    let features = [
+        "sendrecv_v2",
+        "sendrecv_v2_brotli",
+        "sendrecv_v2_lz4",
+        "sendrecv_v2_zstd",
+    ];
+    let negotiate = |features: &[&str]| SyncCompression::negotiate(None, |f| features.contains(&f));
+    assert_eq!(negotiate(&features), Some(SyncCompression::Lz4));
+    assert_eq!(
+        negotiate(&["sendrecv_v2", "sendrecv_v2_brotli", "sendrecv_v2_zstd"]),
+        Some(SyncCompression::Brotli)
+    );
+    assert_eq!(
+        negotiate(&["sendrecv_v2", "sendrecv_v2_zstd"]),
+        Some(SyncCompression::None)
+    );
+    assert_eq!(negotiate(&features[..1]), Some(SyncCompression::None));
+    assert_eq!(negotiate(&features[1..]), None);
+
+    // Requested compression is used when device supports it
+    let negotiate = |preferred, features: &[&str]| {
+        SyncCompression::negotiate(Some(preferred), |f| features.contains(&f))
+    };
+    assert_eq!(
+        negotiate(SyncCompression::Zstd, &features),
+        Some(SyncCompression::Zstd)
+    );
+    assert_eq!(
+        negotiate(SyncCompression::None, &features),
+        Some(SyncCompression::None)
+    );
+    assert_eq!(
+        negotiate(SyncCompression::Zstd, &features[..3]),
+        Some(SyncCompression::Lz4)
+    );
+    assert_eq!(negotiate(SyncCompression::Zstd, &features[1..]), None);
+}
@@ -0,0 +1,186 @@
+use std::io::{Read, Write};
+
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+
+use crate::constants::BUFFER_SIZE;
+use crate::{Result, RustADBError};
+
+/// Size of sync packet headers: 4 bytes of id, followed by a little endian `u32`.
+const SYNC_PACKET_HEADER_SIZE: usize = 8;
+
+/// [`Write`] implementation framing everything written to it in sync `DATA` packets, one packet per call of at most [`BUFFER_SIZE`] bytes.
+pub(crate) struct SyncDataWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> SyncDataWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner }
+    }
+}
+
+impl<W: Write> Write for SyncDataWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // Devices refuse packets bigger than this
+        let buf = &buf[..buf.len().min(BUFFER_SIZE)];
+        let chunk_len = buf.len() as u32;
+
+        // 8 = "DATA".len() + sizeof(u32)
+        let mut buffer = Vec::with_capacity(8 + buf.len());
+        buffer.extend_from_slice(b"DATA");
+        buffer.extend_from_slice(&chunk_len.to_le_bytes());
+        buffer.extend_from_slice(buf);
+
+        self.inner.write_all(&buffer)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// [`Read`] implementation returning contents of sync `DATA` packets read from `inner`, until a `DONE` packet.
+pub(crate) struct SyncDataReader<R: Read> {
+    inner: R,
+    remaining_data_bytes_to_read: usize,
+    done: bool,
+}
+
+impl<R: Read> SyncDataReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining_data_bytes_to_read: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Read for SyncDataReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // In case of a "DATA" header, we may not have enough space in `buf` to fill it with "length" bytes coming from device.
+        // `remaining_data_bytes_to_read` represents how many bytes are still left to read before receiving another header.
+        while self.remaining_data_bytes_to_read == 0 {
+            if self.done {
+                return Ok(0);
+            }
+
+            let mut header = [0_u8; 4];
+            self.inner.read_exact(&mut header)?;
+            let length = self.inner.read_u32::<LittleEndian>()? as usize;
+
+            match &header[..] {
+                b"DATA" => self.remaining_data_bytes_to_read = length,
+                // Length is the modification time of file here
+                b"DONE" => self.done = true,
+                b"FAIL" => {
+                    let mut error_msg = vec![0; length];
+                    self.inner.read_exact(&mut error_msg)?;
+
+                    return Err(std::io::Error::other(format!(
+                        "ADB request failed: {}",
+                        String::from_utf8_lossy(&error_msg)
+                    )));
+                }
+                _ => {
+                    return Err(std::io::Error::other(format!(
+                        "Unknown response from device {header:#?}"
+                    )));
+                }
+            }
+        }
+
+        // Computing minimum to ensure to stop reading before next header...
+        let data_to_read = std::cmp::min(self.remaining_data_bytes_to_read, buf.len());
+        let effective_read = self.inner.read(&mut buf[..data_to_read])?;
+        if effective_read == 0 && data_to_read > 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining_data_bytes_to_read -= effective_read;
+
+        Ok(effective_read)
+    }
+}
+
+/// Reassemble file contents sent by device in sync `DATA` packets received in arbitrary chunks, until a `DONE` packet.
+#[derive(Debug, Default)]
+pub(crate) struct SyncDataDecoder {
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl SyncDataDecoder {
+    /// Append received `data`.
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Whether `DONE` packet was received, no more data being sent then.
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Contents of next complete `DATA` packet, if any. Fails with message of `FAIL` packets.
+    pub(crate) fn next_data(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.done || self.buffer.len() < SYNC_PACKET_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let length = LittleEndian::read_u32(&self.buffer[4..SYNC_PACKET_HEADER_SIZE]) as usize;
+        let packet_size = SYNC_PACKET_HEADER_SIZE + length;
+        match &self.buffer[..4] {
+            // Devices never send bigger packets
+            b"DATA" if length > BUFFER_SIZE => Err(RustADBError::UnknownResponseType(format!(
+                "DATA packet of {length} bytes exceeds {BUFFER_SIZE} bytes"
+            ))),
+            b"DATA" | b"FAIL" if self.buffer.len() < packet_size => Ok(None),
+            b"DATA" => {
+                let data = self.buffer[SYNC_PACKET_HEADER_SIZE..packet_size].to_vec();
+                self.buffer.drain(..packet_size);
+                Ok(Some(data))
+            }
+            // Length is the modification time of file here
+            b"DONE" => {
+                self.buffer.drain(..SYNC_PACKET_HEADER_SIZE);
+                self.done = true;
+                Ok(None)
+            }
+            b"FAIL" => Err(RustADBError::ADBRequestFailed(
+                String::from_utf8_lossy(&self.buffer[SYNC_PACKET_HEADER_SIZE..packet_size])
+                    .to_string(),
+            )),
+            id => Err(RustADBError::UnknownResponseType(format!(
+                "Unknown response from device {id:#?}"
+            ))),
+        }
+    }
+}
+
+#[test]
+fn test_sync_data_decoder() {
+    let mut packets = Vec::new();
+    packets.extend_from_slice(b"DATA\x05\0\0\0hello");
+    packets.extend_from_slice(b"DATA\x06\0\0\0 world");
+    packets.extend_from_slice(b"DONE\0\0\0\0");
+
+    // Contents come back whole, whatever chunks packets are received in
+    let mut decoder = SyncDataDecoder::default();
+    let mut contents = Vec::new();
+    for chunk in packets.chunks(3) {
+        decoder.push(chunk);
+        while let Some(data) = decoder.next_data().expect("invalid packet") {
+            contents.extend_from_slice(&data);
+        }
+    }
+    assert_eq!(contents, b"hello world");
+    assert!(decoder.is_done());
+
+    let mut decoder = SyncDataDecoder::default();
+    decoder.push(b"FAIL\x0e\0\0\0file not found");
+    assert!(matches!(
+        decoder.next_data(),
+        Err(RustADBError::ADBRequestFailed(message)) if message == "file not found"
+    ));
+}
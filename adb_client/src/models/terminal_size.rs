@@ -0,0 +1,24 @@
+use std::fmt::Display;
+
+/// Size of a terminal, in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TerminalSize {
+    /// Number of rows
+    pub rows: u16,
+    /// Number of columns
+    pub cols: u16,
+}
+
+impl TerminalSize {
+    /// Instantiate a new [`TerminalSize`].
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self { rows, cols }
+    }
+}
+
+/// Format size as expected by `adbd` in window size change packets: `{rows}x{cols},{x_pixels}x{y_pixels}`.
+impl Display for TerminalSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{},0x0", self.rows, self.cols)
+    }
+}
@@ -0,0 +1,25 @@
+use crate::utils::shell_quote;
+
+/// Options of a package removal, mapped to `pm uninstall` flags.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UninstallOptions {
+    /// Keep data and cache directories of package (`-k`).
+    pub keep_data: bool,
+    /// Only remove package for this user id instead of all users (`--user`).
+    pub user: Option<u32>,
+}
+
+impl UninstallOptions {
+    /// Service removing `package` with `pm uninstall`.
+    pub(crate) fn uninstall_service(&self, package: &str) -> String {
+        let mut args = Vec::new();
+        if self.keep_data {
+            args.push("-k".to_string());
+        }
+        if let Some(user) = self.user {
+            args.extend(["--user".to_string(), user.to_string()]);
+        }
+        args.push(shell_quote(package));
+        format!("exec:cmd package 'uninstall' {}", args.join(" "))
+    }
+}
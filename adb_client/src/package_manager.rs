@@ -0,0 +1,345 @@
+use crate::models::{InstalledPackage, PackageDetails, PackageFilter};
+use crate::utils::{check_package_manager_response, shell_quote};
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// List packages installed on device matching `filter`. See [`ADBDeviceExt::list_packages`].
+pub(crate) fn list_packages<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    filter: &PackageFilter,
+) -> Result<Vec<InstalledPackage>> {
+    let args = filter.args();
+    let mut command = vec![
+        "pm",
+        "list",
+        "packages",
+        "-f",
+        "-U",
+        "-i",
+        "--show-versioncode",
+    ];
+    command.extend(args.iter().map(String::as_str));
+    let output = run_package_command(device, &command)?;
+
+    Ok(output.lines().filter_map(parse_package_line).collect())
+}
+
+/// Parse a line of `pm list packages -f -U -i --show-versioncode`, such as
+/// `package:/data/app/com.example.app-1/base.apk=com.example.app versionCode:42 installer=com.android.vending uid:10123`.
+fn parse_package_line(line: &str) -> Option<InstalledPackage> {
+    let mut tokens = line.trim().strip_prefix("package:")?.split_whitespace();
+    // Paths may contain `=` themselves, name of package comes after the last one
+    let (path, name) = tokens.next()?.rsplit_once('=')?;
+
+    let mut package = InstalledPackage {
+        name: name.to_string(),
+        path: path.to_string(),
+        ..Default::default()
+    };
+    for token in tokens {
+        if let Some(version_code) = token.strip_prefix("versionCode:") {
+            package.version_code = version_code.parse().ok();
+        } else if let Some(installer) = token.strip_prefix("installer=") {
+            package.installer = (installer != "null").then(|| installer.to_string());
+        } else if let Some(uid) = token.strip_prefix("uid:") {
+            package.uid = uid.parse().ok();
+        }
+    }
+    Some(package)
+}
+
+/// Details of installed package `package`. See [`ADBDeviceExt::package_details`].
+pub(crate) fn package_details<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+) -> Result<PackageDetails> {
+    let output = run_package_command(device, &["dumpsys", "package", &shell_quote(package)])?;
+    let mut details = parse_dumpsys_package(&output, package)
+        .ok_or_else(|| RustADBError::PackageNotFound(package.to_string()))?;
+
+    let output = run_package_command(device, &["pm", "path", &shell_quote(package)])?;
+    details.paths = output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("package:"))
+        .map(str::to_string)
+        .collect();
+    Ok(details)
+}
+
+/// Section of a `Package [name]` block of `dumpsys package` listing permissions.
+#[derive(Clone, Copy)]
+enum PermissionSection {
+    Requested,
+    /// Install or runtime permissions, along with whether they are granted.
+    Granted,
+}
+
+/// Parse the `Package [name]` block of `dumpsys package` output, if `package` is installed.
+fn parse_dumpsys_package(output: &str, package: &str) -> Option<PackageDetails> {
+    let header = format!("Package [{package}]");
+    let mut lines = output
+        .lines()
+        .skip_while(|line| !line.trim().starts_with(&header));
+    let block_indent = indentation(lines.next()?);
+
+    let mut details = PackageDetails {
+        name: package.to_string(),
+        ..Default::default()
+    };
+    // Current permission section, along with indentation of its header
+    let mut section = None;
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let indent = indentation(line);
+        if indent <= block_indent {
+            break;
+        }
+        let line = line.trim();
+
+        match section {
+            Some((_, section_indent)) if indent <= section_indent => section = None,
+            Some((PermissionSection::Requested, _)) => {
+                // Permissions may be followed by attributes, e.g. `android.permission.CAMERA, restricted=true`
+                let permission = line.split([',', ' ', ':']).next().unwrap_or(line);
+                details.requested_permissions.push(permission.to_string());
+                continue;
+            }
+            Some((PermissionSection::Granted, _)) => {
+                // Permissions look like `android.permission.CAMERA: granted=true, flags=[ USER_SET ]`
+                if let Some((permission, state)) = line.split_once(": ") {
+                    if state.contains("granted=true")
+                        && !details.granted_permissions.iter().any(|p| p == permission)
+                    {
+                        details.granted_permissions.push(permission.to_string());
+                    }
+                }
+                continue;
+            }
+            None => {}
+        }
+
+        match line {
+            "requested permissions:" => section = Some((PermissionSection::Requested, indent)),
+            "install permissions:" | "runtime permissions:" => {
+                section = Some((PermissionSection::Granted, indent));
+            }
+            _ if line.starts_with("flags=[") => {
+                details.flags = line
+                    .trim_start_matches("flags=[")
+                    .trim_end_matches(']')
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect();
+            }
+            // Several values may share a line, e.g. `versionCode=42 minSdk=24 targetSdk=34`
+            _ => {
+                for (key, value) in line
+                    .split_whitespace()
+                    .filter_map(|token| token.split_once('='))
+                {
+                    match key {
+                        "userId" => details.uid = value.parse().ok(),
+                        "versionCode" => details.version_code = value.parse().ok(),
+                        "versionName" => details.version_name = Some(value.to_string()),
+                        "installerPackageName" if value != "null" => {
+                            details.installer = Some(value.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    Some(details)
+}
+
+/// Amount of leading whitespace of `line`.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Enable package `package`, or disable it for current user. See [`ADBDeviceExt::enable_package`].
+pub(crate) fn set_package_enabled<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+    enabled: bool,
+) -> Result<()> {
+    let command = match enabled {
+        true => "enable",
+        false => "disable-user",
+    };
+    let output = run_package_command(device, &["pm", command, &shell_quote(package)])?;
+
+    // Output looks like "Package com.example.app new state: enabled"
+    match output.contains("new state:") {
+        true => Ok(()),
+        false => Err(RustADBError::ADBRequestFailed(output.trim().to_string())),
+    }
+}
+
+/// Delete all data of package `package`. See [`ADBDeviceExt::clear_package_data`].
+pub(crate) fn clear_package_data<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+) -> Result<()> {
+    let output = run_package_command(device, &["pm", "clear", &shell_quote(package)])?;
+    check_package_manager_response(output.as_bytes())
+}
+
+/// Stop everything running for package `package`. See [`ADBDeviceExt::force_stop_package`].
+pub(crate) fn force_stop_package<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+) -> Result<()> {
+    run_package_command(device, &["am", "force-stop", &shell_quote(package)])?;
+    Ok(())
+}
+
+/// Run shell `command` on device and return its output, failing if it does not succeed or does not know package.
+pub(crate) fn run_package_command<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    command: &[&str],
+) -> Result<String> {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let status = device.shell_command_with_status(command, &mut stdout, &mut stderr)?;
+    check_package_command(status, &stdout, &stderr)
+}
+
+/// Check outcome of a shell command which exited with `status`, returning its output if it succeeded and knew package.
+pub(crate) fn check_package_command(
+    status: Option<u8>,
+    stdout: &[u8],
+    stderr: &[u8],
+) -> Result<String> {
+    let stdout = String::from_utf8_lossy(stdout).into_owned();
+    let stderr = String::from_utf8_lossy(stderr);
+
+    // Devices without shell protocol v2 mix both streams and do not report exit code
+    let error = match status {
+        None | Some(0) => &stdout,
+        Some(_) => stderr.as_ref(),
+    };
+    if let Some((_, package)) = error.split_once("Unknown package: ") {
+        let package = package.split_whitespace().next().unwrap_or_default();
+        return Err(RustADBError::PackageNotFound(package.to_string()));
+    }
+    match status {
+        None | Some(0) => Ok(stdout),
+        Some(_) => Err(RustADBError::ADBRequestFailed(error.trim().to_string())),
+    }
+}
+
+#[test]
+fn test_package_manager() {
+    use crate::FakeADBDevice;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response(
+        "pm list packages -f -U -i --show-versioncode -3 --user 0",
+        b"package:/data/app/~~a1b2==/com.example.app-c3d4==/base.apk=com.example.app versionCode:42 installer=com.android.vending uid:10123\n\
+          package:/data/app/com.example.tests-1/base.apk=com.example.tests versionCode:1 installer=null uid:10124\n",
+    );
+    fake_device.add_shell_response(
+        "dumpsys package 'com.example.app'",
+        b"Activity Resolver Table:\n\
+          \x20 Non-Data Actions:\n\
+          Packages:\n\
+          \x20 Package [com.example.app] (5e1f2a7):\n\
+          \x20   userId=10123\n\
+          \x20   flags=[ HAS_CODE ALLOW_CLEAR_USER_DATA DEBUGGABLE ]\n\
+          \x20   versionCode=42 minSdk=24 targetSdk=34\n\
+          \x20   versionName=1.2.3\n\
+          \x20   installerPackageName=com.android.vending\n\
+          \x20   requested permissions:\n\
+          \x20     android.permission.INTERNET\n\
+          \x20     android.permission.CAMERA, restricted=true\n\
+          \x20   install permissions:\n\
+          \x20     android.permission.INTERNET: granted=true\n\
+          \x20   User 0: ceDataInode=1234 installed=true hidden=false\n\
+          \x20     runtime permissions:\n\
+          \x20       android.permission.CAMERA: granted=false, flags=[ USER_SET ]\n\
+          \x20 Package [com.example.other] (1a2b3c4):\n\
+          \x20   userId=10200\n",
+    );
+    fake_device.add_shell_response(
+        "pm path 'com.example.app'",
+        b"package:/data/app/com.example.app-1/base.apk\npackage:/data/app/com.example.app-1/split_config.arm64_v8a.apk\n",
+    );
+    fake_device.add_shell_response("dumpsys package 'com.example.missing'", b"Packages:\n");
+    fake_device.add_shell_response(
+        "pm disable-user 'com.example.app'",
+        b"Package com.example.app new state: disabled-user\n",
+    );
+    fake_device.add_shell_response_with_status(
+        "pm enable 'com.example.missing'",
+        b"",
+        b"Exception occurred while executing 'enable':\njava.lang.IllegalArgumentException: Unknown package: com.example.missing\n",
+        255,
+    );
+    fake_device.add_shell_response("pm clear 'com.example.app'", b"Failed\n");
+    fake_device.add_shell_response("am force-stop 'com.example.app'", b"");
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let filter = PackageFilter {
+        system: Some(false),
+        user: Some(0),
+        ..Default::default()
+    };
+    let packages = device.list_packages(&filter).expect("cannot list packages");
+    assert_eq!(
+        packages,
+        [
+            InstalledPackage {
+                name: "com.example.app".to_string(),
+                path: "/data/app/~~a1b2==/com.example.app-c3d4==/base.apk".to_string(),
+                version_code: Some(42),
+                installer: Some("com.android.vending".to_string()),
+                uid: Some(10123),
+            },
+            InstalledPackage {
+                name: "com.example.tests".to_string(),
+                path: "/data/app/com.example.tests-1/base.apk".to_string(),
+                version_code: Some(1),
+                installer: None,
+                uid: Some(10124),
+            },
+        ]
+    );
+
+    let details = device
+        .package_details("com.example.app")
+        .expect("cannot get package details");
+    assert_eq!(
+        (
+            details.uid,
+            details.version_code,
+            details.version_name.as_deref()
+        ),
+        (Some(10123), Some(42), Some("1.2.3"))
+    );
+    assert_eq!(details.installer.as_deref(), Some("com.android.vending"));
+    assert_eq!(details.paths.len(), 2);
+    assert!(details.has_flag("DEBUGGABLE"));
+    assert_eq!(
+        details.requested_permissions,
+        ["android.permission.INTERNET", "android.permission.CAMERA"]
+    );
+    assert_eq!(details.granted_permissions, ["android.permission.INTERNET"]);
+    assert!(matches!(
+        device.package_details("com.example.missing"),
+        Err(RustADBError::PackageNotFound(package)) if package == "com.example.missing"
+    ));
+
+    device
+        .disable_package("com.example.app")
+        .expect("cannot disable package");
+    assert!(matches!(
+        device.enable_package("com.example.missing"),
+        Err(RustADBError::PackageNotFound(_))
+    ));
+    assert!(matches!(
+        device.clear_package_data("com.example.app"),
+        Err(RustADBError::ADBRequestFailed(_))
+    ));
+    device
+        .force_stop_package("com.example.app")
+        .expect("cannot force stop package");
+}
@@ -0,0 +1,6 @@
+mod pairing_cipher;
+mod pairing_connection;
+mod spake2;
+
+pub(crate) use pairing_connection::{PeerInfo, exchange_peer_info, pairing_password};
+pub(crate) use spake2::Spake2Role;
@@ -0,0 +1,81 @@
+use ring::aead::{AES_128_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce};
+use ring::hkdf::{HKDF_SHA256, Salt};
+
+use crate::{Result, RustADBError};
+
+/// Information used to derive encryption key from key material.
+const HKDF_INFO: &[u8] = b"adb pairing_auth aes-128-gcm key";
+
+/// AES-128-GCM cipher encrypting messages exchanged once SPAKE2 succeeded, as `adbd` does.
+pub(crate) struct PairingCipher {
+    key: LessSafeKey,
+    encrypt_sequence: u64,
+    decrypt_sequence: u64,
+}
+
+impl std::fmt::Debug for PairingCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PairingCipher")
+            .field("encrypt_sequence", &self.encrypt_sequence)
+            .field("decrypt_sequence", &self.decrypt_sequence)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PairingCipher {
+    /// Instantiate a new [`PairingCipher`], deriving its key from `key_material` with HKDF-SHA256.
+    pub(crate) fn new(key_material: &[u8]) -> Result<Self> {
+        let pseudo_random_key = Salt::new(HKDF_SHA256, &[]).extract(key_material);
+        let key = pseudo_random_key
+            .expand(&[HKDF_INFO], &AES_128_GCM)
+            .map_err(|_| RustADBError::PairingError("cannot derive encryption key".into()))?;
+
+        Ok(Self {
+            key: LessSafeKey::new(key.into()),
+            encrypt_sequence: 0,
+            decrypt_sequence: 0,
+        })
+    }
+
+    pub(crate) fn encrypt(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encrypted = data.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                sequence_nonce(self.encrypt_sequence),
+                Aad::empty(),
+                &mut encrypted,
+            )
+            .map_err(|_| RustADBError::PairingError("cannot encrypt message".into()))?;
+        self.encrypt_sequence += 1;
+
+        Ok(encrypted)
+    }
+
+    pub(crate) fn decrypt(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut decrypted = data.to_vec();
+        let size = self
+            .key
+            .open_in_place(
+                sequence_nonce(self.decrypt_sequence),
+                Aad::empty(),
+                &mut decrypted,
+            )
+            .map_err(|_| {
+                RustADBError::PairingError(
+                    "cannot decrypt message, pairing code may be wrong".into(),
+                )
+            })?
+            .len();
+        decrypted.truncate(size);
+        self.decrypt_sequence += 1;
+
+        Ok(decrypted)
+    }
+}
+
+/// Nonce of message number `sequence`, as little endian bytes padded with zeros.
+fn sequence_nonce(sequence: u64) -> Nonce {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[..8].copy_from_slice(&sequence.to_le_bytes());
+    Nonce::assume_unique_for_key(nonce)
+}
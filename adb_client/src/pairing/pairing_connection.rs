@@ -0,0 +1,160 @@
+use std::io::{Read, Write};
+
+use byteorder::{BigEndian, ByteOrder};
+use rustls::ConnectionCommon;
+
+use super::pairing_cipher::PairingCipher;
+use super::spake2::{Spake2, Spake2Role};
+use crate::{Result, RustADBError};
+
+/// Version of pairing packets.
+const PAIRING_PACKET_VERSION: u8 = 1;
+/// Size of pairing packets headers: version, type and payload size.
+const PAIRING_PACKET_HEADER_SIZE: usize = 6;
+/// Size of peer information, as sent before encryption.
+const MAX_PEER_INFO_SIZE: usize = 8192;
+/// Maximum size of pairing packets payloads.
+const MAX_PAYLOAD_SIZE: usize = 2 * MAX_PEER_INFO_SIZE;
+
+/// Label of TLS keying material appended to pairing code.
+const EXPORTED_KEY_LABEL: &[u8] = b"adb-label\0";
+/// Size of TLS keying material appended to pairing code.
+const EXPORTED_KEY_SIZE: usize = 64;
+
+const CLIENT_NAME: &[u8] = b"adb pair client\0";
+const SERVER_NAME: &[u8] = b"adb pair server\0";
+
+const PACKET_TYPE_SPAKE2_MESSAGE: u8 = 0;
+const PACKET_TYPE_PEER_INFO: u8 = 1;
+
+const PEER_INFO_RSA_PUBLIC_KEY: u8 = 0;
+const PEER_INFO_DEVICE_GUID: u8 = 1;
+
+/// Information exchanged by both parties once pairing code has been checked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PeerInfo {
+    /// Public key of host, in ADB format
+    RsaPublicKey(String),
+    /// Unique identifier of device, also used as its mDNS instance name
+    DeviceGuid(String),
+}
+
+impl PeerInfo {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let (kind, data) = match self {
+            PeerInfo::RsaPublicKey(data) => (PEER_INFO_RSA_PUBLIC_KEY, data),
+            PeerInfo::DeviceGuid(data) => (PEER_INFO_DEVICE_GUID, data),
+        };
+        // Data must be followed by at least one NUL byte
+        if data.len() >= MAX_PEER_INFO_SIZE - 1 {
+            return Err(RustADBError::PairingError("peer info is too long".into()));
+        }
+
+        let mut bytes = vec![0; MAX_PEER_INFO_SIZE];
+        bytes[0] = kind;
+        bytes[1..=data.len()].copy_from_slice(data.as_bytes());
+        Ok(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some((&kind, data)) = bytes.split_first() else {
+            return Err(RustADBError::PairingError("empty peer info".into()));
+        };
+        let data = data.split(|&b| b == 0).next().unwrap_or_default();
+        let data = String::from_utf8(data.to_vec())?;
+
+        match kind {
+            PEER_INFO_RSA_PUBLIC_KEY => Ok(PeerInfo::RsaPublicKey(data)),
+            PEER_INFO_DEVICE_GUID => Ok(PeerInfo::DeviceGuid(data)),
+            kind => Err(RustADBError::PairingError(format!(
+                "unknown peer info type {kind}"
+            ))),
+        }
+    }
+}
+
+/// Password authenticating a pairing over `connection`: pairing `code` followed by keying material exported from TLS session.
+///
+/// Binding password to TLS session prevents anyone from relaying pairing messages.
+pub(crate) fn pairing_password<Data>(
+    code: &str,
+    connection: &ConnectionCommon<Data>,
+) -> Result<Vec<u8>> {
+    let keying_material =
+        connection.export_keying_material([0; EXPORTED_KEY_SIZE], EXPORTED_KEY_LABEL, None)?;
+
+    let mut password = code.as_bytes().to_vec();
+    password.extend_from_slice(&keying_material);
+    Ok(password)
+}
+
+/// Run pairing protocol over an established TLS `stream`, sending `peer_info` and returning the one of other party.
+///
+/// Both parties first exchange SPAKE2 messages to agree on a key derived from `password`, then exchange their
+/// information encrypted with this key. Decryption fails if passwords do not match.
+pub(crate) fn exchange_peer_info<S: Read + Write>(
+    stream: &mut S,
+    role: Spake2Role,
+    password: &[u8],
+    peer_info: &PeerInfo,
+) -> Result<PeerInfo> {
+    let (my_name, their_name) = match role {
+        Spake2Role::Alice => (CLIENT_NAME, SERVER_NAME),
+        Spake2Role::Bob => (SERVER_NAME, CLIENT_NAME),
+    };
+    let spake2 = Spake2::new(role, my_name, their_name, password);
+
+    write_packet(stream, PACKET_TYPE_SPAKE2_MESSAGE, spake2.message())?;
+    let their_message = read_packet(stream, PACKET_TYPE_SPAKE2_MESSAGE)?;
+    let key = spake2.process_message(&their_message)?;
+    let mut cipher = PairingCipher::new(&key)?;
+
+    write_packet(
+        stream,
+        PACKET_TYPE_PEER_INFO,
+        &cipher.encrypt(&peer_info.to_bytes()?)?,
+    )?;
+    let their_peer_info = read_packet(stream, PACKET_TYPE_PEER_INFO)?;
+
+    PeerInfo::from_bytes(&cipher.decrypt(&their_peer_info)?)
+}
+
+fn write_packet<W: Write>(writer: &mut W, packet_type: u8, payload: &[u8]) -> Result<()> {
+    let mut header = [0; PAIRING_PACKET_HEADER_SIZE];
+    header[0] = PAIRING_PACKET_VERSION;
+    header[1] = packet_type;
+    BigEndian::write_u32(&mut header[2..], payload.len() as u32);
+
+    writer.write_all(&header)?;
+    writer.write_all(payload)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_packet<R: Read>(reader: &mut R, expected_type: u8) -> Result<Vec<u8>> {
+    let mut header = [0; PAIRING_PACKET_HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+
+    if header[0] != PAIRING_PACKET_VERSION {
+        return Err(RustADBError::PairingError(format!(
+            "unsupported pairing packet version {}",
+            header[0]
+        )));
+    }
+    if header[1] != expected_type {
+        return Err(RustADBError::PairingError(format!(
+            "unexpected pairing packet type {}, expected {expected_type}",
+            header[1]
+        )));
+    }
+    let size = BigEndian::read_u32(&header[2..]) as usize;
+    if size > MAX_PAYLOAD_SIZE {
+        return Err(RustADBError::PairingError(format!(
+            "pairing packet too big ({size} bytes)"
+        )));
+    }
+
+    let mut payload = vec![0; size];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
@@ -0,0 +1,226 @@
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+
+use crate::{Result, RustADBError};
+
+/// Size of messages exchanged by both parties.
+const SPAKE2_MESSAGE_SIZE: usize = 32;
+/// Size of the shared key computed by both parties.
+const SPAKE2_KEY_SIZE: usize = 64;
+
+/// Point `M` masking messages sent by Alice, encoded.
+const SPAKE_M: [u8; 32] = [
+    0x5a, 0xda, 0x7e, 0x4b, 0xf6, 0xdd, 0xd9, 0xad, 0xb6, 0x62, 0x6d, 0x32, 0x13, 0x1c, 0x6b, 0x5c,
+    0x51, 0xa1, 0xe3, 0x47, 0xa3, 0x47, 0x8f, 0x53, 0xcf, 0xcf, 0x44, 0x1b, 0x88, 0xee, 0xd1, 0x2e,
+];
+/// Point `N` masking messages sent by Bob, encoded.
+const SPAKE_N: [u8; 32] = [
+    0x10, 0xe3, 0xdf, 0x0a, 0xe3, 0x7d, 0x8e, 0x7a, 0x99, 0xb5, 0xfe, 0x74, 0xb4, 0x46, 0x72, 0x10,
+    0x3d, 0xbd, 0xdc, 0xbd, 0x06, 0xaf, 0x68, 0x0d, 0x71, 0x32, 0x9a, 0x11, 0x69, 0x3b, 0xc7, 0x78,
+];
+
+/// Role of a party in a SPAKE2 exchange.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Spake2Role {
+    Alice,
+    Bob,
+}
+
+/// One side of a SPAKE2 key exchange over Ed25519, compatible with BoringSSL's `SPAKE2_*` functions used by `adbd`.
+pub(crate) struct Spake2 {
+    role: Spake2Role,
+    my_name: Vec<u8>,
+    their_name: Vec<u8>,
+    private_key: Scalar,
+    password_scalar: Scalar,
+    password_hash: [u8; 64],
+    my_message: [u8; SPAKE2_MESSAGE_SIZE],
+}
+
+impl std::fmt::Debug for Spake2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Spake2")
+            .field("role", &self.role)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Spake2 {
+    /// Start an exchange authenticated by `password`, generating message to send to other party.
+    pub(crate) fn new(
+        role: Spake2Role,
+        my_name: &[u8],
+        their_name: &[u8],
+        password: &[u8],
+    ) -> Self {
+        let mut private_bytes = [0; 64];
+        rand::rng().fill_bytes(&mut private_bytes);
+        Self::new_with_private_bytes(role, my_name, their_name, password, &private_bytes)
+    }
+
+    /// Start an exchange like [`Spake2::new`], deriving private key from `private_bytes` instead of random ones.
+    fn new_with_private_bytes(
+        role: Spake2Role,
+        my_name: &[u8],
+        their_name: &[u8],
+        password: &[u8],
+        private_bytes: &[u8; 64],
+    ) -> Self {
+        // Reduced the same way as BoringSSL does with its 64 random bytes
+        let private_key = Scalar::from_bytes_mod_order_wide(private_bytes);
+
+        let password_hash: [u8; 64] = Sha512::digest(password).into();
+        // BoringSSL adds multiples of the group order to make password scalar a multiple of the cofactor.
+        // Multiplying masks by this scalar is the same as multiplying them by cofactor, then by scalar divided by cofactor.
+        let password_scalar =
+            Scalar::from_bytes_mod_order_wide(&password_hash) * Scalar::from(8u8).invert();
+
+        // Private key is multiplied by cofactor too, to clear it from points received
+        let public_point = EdwardsPoint::mul_base(&(private_key * Scalar::from(8u8)));
+        let mask = mask_point(role) * password_scalar;
+        let my_message = (public_point + mask).compress().to_bytes();
+
+        Self {
+            role,
+            my_name: my_name.to_vec(),
+            their_name: their_name.to_vec(),
+            private_key,
+            password_scalar,
+            password_hash,
+            my_message,
+        }
+    }
+
+    /// Message to send to other party.
+    pub(crate) fn message(&self) -> &[u8; SPAKE2_MESSAGE_SIZE] {
+        &self.my_message
+    }
+
+    /// Compute key shared with other party from its message. Both keys only match if passwords were the same.
+    pub(crate) fn process_message(&self, their_message: &[u8]) -> Result<[u8; SPAKE2_KEY_SIZE]> {
+        let their_message: [u8; SPAKE2_MESSAGE_SIZE] = their_message
+            .try_into()
+            .map_err(|_| RustADBError::PairingError("invalid SPAKE2 message size".into()))?;
+        let their_point = CompressedEdwardsY(their_message)
+            .decompress()
+            .ok_or_else(|| RustADBError::PairingError("invalid SPAKE2 point".into()))?;
+
+        let their_role = match self.role {
+            Spake2Role::Alice => Spake2Role::Bob,
+            Spake2Role::Bob => Spake2Role::Alice,
+        };
+        let their_mask = mask_point(their_role) * self.password_scalar;
+        let shared_point = ((their_point - their_mask) * self.private_key).mul_by_cofactor();
+
+        let (first_name, second_name, first_message, second_message) = match self.role {
+            Spake2Role::Alice => (
+                &self.my_name,
+                &self.their_name,
+                &self.my_message,
+                &their_message,
+            ),
+            Spake2Role::Bob => (
+                &self.their_name,
+                &self.my_name,
+                &their_message,
+                &self.my_message,
+            ),
+        };
+
+        let mut transcript = Sha512::new();
+        for value in [
+            first_name.as_slice(),
+            second_name,
+            first_message,
+            second_message,
+            &shared_point.compress().to_bytes(),
+            &self.password_hash,
+        ] {
+            transcript.update((value.len() as u64).to_le_bytes());
+            transcript.update(value);
+        }
+
+        Ok(transcript.finalize().into())
+    }
+}
+
+/// Point masking messages sent by `role`, multiplied by cofactor.
+fn mask_point(role: Spake2Role) -> EdwardsPoint {
+    let encoded = match role {
+        Spake2Role::Alice => SPAKE_M,
+        Spake2Role::Bob => SPAKE_N,
+    };
+
+    CompressedEdwardsY(encoded)
+        .decompress()
+        .map(|point| point.mul_by_cofactor())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_spake2_key_agreement() {
+    let alice = Spake2::new(Spake2Role::Alice, b"alice", b"bob", b"123456");
+    let bob = Spake2::new(Spake2Role::Bob, b"bob", b"alice", b"123456");
+    let alice_key = alice
+        .process_message(bob.message())
+        .expect("cannot process bob message");
+    let bob_key = bob
+        .process_message(alice.message())
+        .expect("cannot process alice message");
+    assert_eq!(alice_key, bob_key);
+
+    // A wrong password leads to different keys
+    let mallory = Spake2::new(Spake2Role::Bob, b"bob", b"alice", b"654321");
+    let mallory_key = mallory
+        .process_message(alice.message())
+        .expect("cannot process alice message");
+    let alice_key = alice
+        .process_message(mallory.message())
+        .expect("cannot process mallory message");
+    assert_ne!(alice_key, mallory_key);
+}
+
+#[test]
+fn test_spake2_known_answer() {
+    // Expected values were produced by BoringSSL's `SPAKE2_*` functions (from AWS-LC, as vendored by
+    // `aws-lc-sys` 0.39.1) with `RAND_bytes` returning same private bytes, using names sent by `adb pair`.
+    let client_bytes: [u8; 64] = std::array::from_fn(|i| i as u8 + 1);
+    let server_bytes: [u8; 64] = std::array::from_fn(|i| 0xff - i as u8);
+    let client = Spake2::new_with_private_bytes(
+        Spake2Role::Alice,
+        b"adb pair client\0",
+        b"adb pair server\0",
+        b"123456",
+        &client_bytes,
+    );
+    let server = Spake2::new_with_private_bytes(
+        Spake2Role::Bob,
+        b"adb pair server\0",
+        b"adb pair client\0",
+        b"123456",
+        &server_bytes,
+    );
+
+    let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    assert_eq!(
+        hex(client.message()),
+        "950ec4a2329addab36f0254719a24be86919b1cf68df68d910a3ea590ff599d4"
+    );
+    assert_eq!(
+        hex(server.message()),
+        "003422cb79b405d600f27915ee3c8fb36b4dee3ffa067b11b2eac11a0381560a"
+    );
+
+    let expected_key = "645505e4a171fbec55fd6ef43923841ddda757a6c6091edfc0b80e85c80222df\
+                        2c94ac53cd4e172a7650520a1d6f0eb3fc40c5cf5d70c252649da0b5f8bfedcc";
+    let client_key = client
+        .process_message(server.message())
+        .expect("cannot process server message");
+    let server_key = server
+        .process_message(client.message())
+        .expect("cannot process client message");
+    assert_eq!(hex(&client_key), expected_key);
+    assert_eq!(hex(&server_key), expected_key);
+}
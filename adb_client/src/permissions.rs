@@ -0,0 +1,262 @@
+use crate::models::{AppOpMode, PermissionFailureReason};
+use crate::package_manager::{package_details, run_package_command};
+use crate::utils::shell_quote;
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// Grant runtime permission `permission` to package `package`. See [`ADBDeviceExt::grant_permission`].
+pub(crate) fn grant_permission<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+    permission: &str,
+) -> Result<()> {
+    run_permission_command(
+        device,
+        &[
+            "pm",
+            "grant",
+            &shell_quote(package),
+            &shell_quote(permission),
+        ],
+    )
+}
+
+/// Revoke runtime permission `permission` from package `package`. See [`ADBDeviceExt::revoke_permission`].
+pub(crate) fn revoke_permission<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+    permission: &str,
+) -> Result<()> {
+    run_permission_command(
+        device,
+        &[
+            "pm",
+            "revoke",
+            &shell_quote(package),
+            &shell_quote(permission),
+        ],
+    )
+}
+
+/// Reset runtime permissions of package `package`. See [`ADBDeviceExt::reset_permissions`].
+pub(crate) fn reset_permissions<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+) -> Result<()> {
+    run_permission_command(
+        device,
+        &["pm", "reset-permissions", "-p", &shell_quote(package)],
+    )
+}
+
+/// Grant all runtime permissions requested by package `package`. See [`ADBDeviceExt::grant_requested_permissions`].
+pub(crate) fn grant_requested_permissions<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+) -> Result<Vec<String>> {
+    let details = package_details(device, package)?;
+
+    let mut granted = Vec::new();
+    for permission in details.requested_permissions {
+        if details.granted_permissions.contains(&permission) {
+            continue;
+        }
+
+        match grant_permission(device, package, &permission) {
+            Ok(()) => granted.push(permission),
+            // Install-time permissions are granted by system, or never
+            Err(RustADBError::PermissionChangeFailed(
+                PermissionFailureReason::NotChangeable,
+                _,
+            )) => {
+                log::debug!("skipping permission {permission}, which is not a runtime one");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(granted)
+}
+
+/// Mode of app op `op` for package `package`. See [`ADBDeviceExt::app_op`].
+pub(crate) fn app_op<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+    op: &str,
+) -> Result<AppOpMode> {
+    let output = run_package_command(
+        device,
+        &["appops", "get", &shell_quote(package), &shell_quote(op)],
+    )?;
+    if let Some(message) = output.trim().strip_prefix("Error: ") {
+        return Err(RustADBError::PermissionChangeFailed(
+            PermissionFailureReason::from_message(message),
+            message.to_string(),
+        ));
+    }
+
+    // Output looks like `CAMERA: allow; time=+5m ago`, possibly along with a `Uid mode: CAMERA: ignore` line.
+    // Android 12+ follows them with access history lines, e.g. `Access: [fg-s] 2024-01-01 (-5m ago)`, which are skipped.
+    // Mode of package takes precedence over the one of its uid, which applies when package has none.
+    let op_prefix = format!("{op}: ");
+    let mut package_mode = None;
+    let mut uid_mode = None;
+    for line in output.lines().map(str::trim) {
+        let (mode, is_uid_mode) = match line.strip_prefix("Uid mode: ") {
+            Some(uid_line) => (uid_line.strip_prefix(&op_prefix), true),
+            None => (line.strip_prefix(&op_prefix), false),
+        };
+        let Some(mode) = mode else {
+            continue;
+        };
+        let mode = mode.split(';').next().unwrap_or(mode).trim().parse()?;
+        match is_uid_mode {
+            true => uid_mode = Some(mode),
+            false => package_mode = Some(mode),
+        }
+    }
+    // Operations never set are reported as `No operations.`
+    Ok(package_mode.or(uid_mode).unwrap_or(AppOpMode::Default))
+}
+
+/// Set mode of app op `op` for package `package`. See [`ADBDeviceExt::set_app_op`].
+pub(crate) fn set_app_op<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    package: &str,
+    op: &str,
+    mode: AppOpMode,
+) -> Result<()> {
+    run_permission_command(
+        device,
+        &[
+            "appops",
+            "set",
+            &shell_quote(package),
+            &shell_quote(op),
+            &mode.to_string(),
+        ],
+    )
+}
+
+/// Run shell `command` changing a permission or an app op, which outputs nothing when it succeeds.
+fn run_permission_command<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    command: &[&str],
+) -> Result<()> {
+    let output = match run_package_command(device, command) {
+        Ok(output) => output,
+        Err(RustADBError::ADBRequestFailed(output)) => output,
+        Err(e) => return Err(e),
+    };
+    let output = output.trim();
+    if output.is_empty() {
+        return Ok(());
+    }
+
+    // Failures are reported as exceptions, e.g. `java.lang.SecurityException: Package com.example.app has not requested permission ...`
+    let message = output
+        .lines()
+        .find_map(|line| {
+            line.split_once("Exception: ")
+                .or_else(|| line.split_once("Error: "))
+        })
+        .map_or(output, |(_, message)| message.trim());
+    Err(RustADBError::PermissionChangeFailed(
+        PermissionFailureReason::from_message(message),
+        message.to_string(),
+    ))
+}
+
+#[test]
+fn test_permissions() {
+    use crate::FakeADBDevice;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response(
+        "dumpsys package 'com.example.app'",
+        b"Packages:\n\
+          \x20 Package [com.example.app] (5e1f2a7):\n\
+          \x20   userId=10123\n\
+          \x20   requested permissions:\n\
+          \x20     android.permission.INTERNET\n\
+          \x20     android.permission.CAMERA\n\
+          \x20     android.permission.RECORD_AUDIO\n\
+          \x20   install permissions:\n\
+          \x20     android.permission.INTERNET: granted=true\n\
+          \x20   User 0: installed=true\n\
+          \x20     runtime permissions:\n\
+          \x20       android.permission.CAMERA: granted=false\n\
+          \x20       android.permission.RECORD_AUDIO: granted=true\n",
+    );
+    fake_device.add_shell_response("pm path 'com.example.app'", b"package:/data/app/base.apk\n");
+    fake_device.add_shell_response(
+        "pm grant 'com.example.app' 'android.permission.CAMERA'",
+        b"",
+    );
+    fake_device.add_shell_response_with_status(
+        "pm grant 'com.example.app' 'android.permission.READ_CONTACTS'",
+        b"",
+        b"Exception occurred while executing 'grant':\n\
+          java.lang.SecurityException: Package com.example.app has not requested permission android.permission.READ_CONTACTS\n\
+          \tat com.android.server.pm.permission.PermissionManagerServiceImpl.grantRuntimePermissionInternal\n",
+        255,
+    );
+    // Devices without shell protocol v2 mix both streams, and report no exit code
+    fake_device.add_shell_response(
+        "pm revoke 'com.example.app' 'android.permission.INTERNET'",
+        b"Exception occurred while executing 'revoke':\n\
+          java.lang.SecurityException: Permission android.permission.INTERNET requested by com.example.app is not a changeable permission type\n",
+    );
+    fake_device.add_shell_response(
+        "appops get 'com.example.app' 'CAMERA'",
+        b"Uid mode: CAMERA: ignore\nCAMERA: allow; time=+5m12s ago\n",
+    );
+    // Android 12+ reports access history of each op
+    fake_device.add_shell_response(
+        "appops get 'com.example.app' 'COARSE_LOCATION'",
+        b"Uid mode: COARSE_LOCATION: allow\n\
+          COARSE_LOCATION: ignore\n\
+          \x20         Access: [fg-s] 2024-01-01 10:00:00.000 (-5m12s ago)\n\
+          \x20         Reject: [bg-s] 2024-01-01 09:00:00.000 (-1h5m ago)\n",
+    );
+    fake_device.add_shell_response(
+        "appops get 'com.example.app' 'RUN_IN_BACKGROUND'",
+        b"No operations.\n",
+    );
+    fake_device.add_shell_response("appops set 'com.example.app' 'CAMERA' deny", b"");
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    // Only runtime permissions which are not granted yet get granted
+    assert_eq!(
+        device
+            .grant_requested_permissions("com.example.app")
+            .expect("cannot grant permissions"),
+        ["android.permission.CAMERA"]
+    );
+    assert!(matches!(
+        device.grant_permission("com.example.app", "android.permission.READ_CONTACTS"),
+        Err(RustADBError::PermissionChangeFailed(PermissionFailureReason::NotRequested, message))
+            if message.starts_with("Package com.example.app has not requested")
+    ));
+    assert!(matches!(
+        device.revoke_permission("com.example.app", "android.permission.INTERNET"),
+        Err(RustADBError::PermissionChangeFailed(
+            PermissionFailureReason::NotChangeable,
+            _
+        ))
+    ));
+
+    assert_eq!(
+        device.app_op("com.example.app", "CAMERA").ok(),
+        Some(AppOpMode::Allow)
+    );
+    assert_eq!(
+        device.app_op("com.example.app", "COARSE_LOCATION").ok(),
+        Some(AppOpMode::Ignore)
+    );
+    assert_eq!(
+        device.app_op("com.example.app", "RUN_IN_BACKGROUND").ok(),
+        Some(AppOpMode::Default)
+    );
+    device
+        .set_app_op("com.example.app", "CAMERA", AppOpMode::Deny)
+        .expect("cannot set app op");
+}
@@ -0,0 +1,122 @@
+use crate::models::DeviceProperties;
+use crate::utils::shell_quote;
+use crate::{ADBDeviceExt, Result, RustADBError};
+
+/// All system properties of device. See [`ADBDeviceExt::properties`].
+pub(crate) fn properties<D: ADBDeviceExt + ?Sized>(device: &mut D) -> Result<DeviceProperties> {
+    let output = run_property_command(device, &["getprop"])?;
+    Ok(DeviceProperties::parse(&output))
+}
+
+/// Value of system property `name`. See [`ADBDeviceExt::property`].
+pub(crate) fn property<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    name: &str,
+) -> Result<Option<String>> {
+    let output = run_property_command(device, &["getprop", &shell_quote(name)])?;
+    Ok(parse_property_value(&output))
+}
+
+/// Value of a system property printed by `getprop`, `None` if it is not set.
+pub(crate) fn parse_property_value(output: &str) -> Option<String> {
+    // Properties which are not set are reported as empty
+    let value = output.strip_suffix('\n').unwrap_or(output);
+    let value = value.strip_suffix('\r').unwrap_or(value);
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Set system property `name` to `value`. See [`ADBDeviceExt::set_property`].
+pub(crate) fn set_property<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    name: &str,
+    value: &str,
+) -> Result<()> {
+    let output = run_property_command(
+        device,
+        &["setprop", &shell_quote(name), &shell_quote(value)],
+    )?;
+    // Control properties trigger init actions, they cannot be read back
+    if name.starts_with("ctl.") {
+        return Ok(());
+    }
+
+    // Devices without shell protocol v2 do not report exit code, and some `setprop` fail silently, e.g. on read-only properties
+    let current = property(device, name)?;
+    if current.as_deref().unwrap_or_default() == value {
+        return Ok(());
+    }
+    Err(RustADBError::ADBRequestFailed(match output.trim() {
+        "" => format!(
+            "property {name} is set to '{}' instead of '{value}'",
+            current.unwrap_or_default()
+        ),
+        error => error.to_string(),
+    }))
+}
+
+/// Run shell `command` on device and return its output, failing with its error output if it does not succeed.
+fn run_property_command<D: ADBDeviceExt + ?Sized>(
+    device: &mut D,
+    command: &[&str],
+) -> Result<String> {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    match device.shell_command_with_status(command, &mut stdout, &mut stderr)? {
+        None | Some(0) => Ok(String::from_utf8_lossy(&stdout).into_owned()),
+        Some(_) => Err(RustADBError::ADBRequestFailed(
+            String::from_utf8_lossy(&stderr).trim().to_string(),
+        )),
+    }
+}
+
+#[test]
+fn test_properties() {
+    use crate::FakeADBDevice;
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response(
+        "getprop",
+        b"[ro.build.version.release]: [14]\n\
+          [ro.build.fingerprint]: [google/husky/husky:14/UQ1A.240205.004/11269751:user/release-keys]\n\
+          [ro.product.model]: [Pixel 8 Pro]\n",
+    );
+    fake_device.add_shell_response("getprop 'ro.product.model'", b"Pixel 8 Pro\n");
+    fake_device.add_shell_response("getprop 'persist.unset'", b"\n");
+    fake_device.add_shell_response("setprop 'debug.example' 'it'\\''s on'", b"");
+    fake_device.add_shell_response("getprop 'debug.example'", b"it's on\n");
+    fake_device.add_shell_response("setprop 'ro.secure' '0'", b"");
+    fake_device.add_shell_response("getprop 'ro.secure'", b"1\n");
+    fake_device.add_shell_response("setprop 'ctl.start' 'bootanim'", b"");
+    fake_device.add_shell_response_with_status(
+        "setprop 'ro.product.model' 'Pixel'",
+        b"",
+        b"Failed to set property 'ro.product.model' to 'Pixel'.\n",
+        1,
+    );
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let properties = device.properties().expect("cannot get properties");
+    assert_eq!(properties.release(), Some("14"));
+    assert_eq!(properties.model(), Some("Pixel 8 Pro"));
+    assert!(properties.fingerprint().is_some());
+
+    assert_eq!(
+        device.property("ro.product.model").ok(),
+        Some(Some("Pixel 8 Pro".to_string()))
+    );
+    assert_eq!(device.property("persist.unset").ok(), Some(None));
+    device
+        .set_property("debug.example", "it's on")
+        .expect("cannot set property");
+    assert!(matches!(
+        device.set_property("ro.product.model", "Pixel"),
+        Err(RustADBError::ADBRequestFailed(error)) if error.starts_with("Failed to set property")
+    ));
+    // Values are read back, as some devices ignore requests without reporting any error
+    assert!(matches!(
+        device.set_property("ro.secure", "0"),
+        Err(RustADBError::ADBRequestFailed(error)) if error == "property ro.secure is set to '1' instead of '0'"
+    ));
+    device
+        .set_property("ctl.start", "bootanim")
+        .expect("cannot set control property");
+}
@@ -0,0 +1,266 @@
+use std::io::{ErrorKind, Read, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+
+use crate::constants::BUFFER_SIZE;
+use crate::models::{ShellPacketId, ShellV2Output, TerminalSize, encode_shell_packet};
+use crate::{Result, RustADBError};
+
+/// Writing side of a shell session, shared by input forwarding and resize events.
+type SessionWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Handle sending terminal size changes to an interactive shell. It can be cloned and sent to other threads.
+#[derive(Clone)]
+pub struct ShellResizer {
+    writer: SessionWriter,
+    shell_v2: bool,
+}
+
+impl std::fmt::Debug for ShellResizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShellResizer")
+            .field("shell_v2", &self.shell_v2)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ShellResizer {
+    /// Tell device that terminal now has given `size`.
+    ///
+    /// Without shell protocol v2, devices cannot be told about size changes and this does nothing.
+    pub fn resize(&self, size: TerminalSize) -> Result<()> {
+        if !self.shell_v2 {
+            log::debug!("device does not support shell v2, ignoring new terminal size {size}");
+            return Ok(());
+        }
+
+        // Size is sent as a NUL-terminated string
+        let packet = encode_shell_packet(
+            ShellPacketId::WindowSizeChange,
+            format!("{size}\0").as_bytes(),
+        );
+        Ok(self.writer.lock()?.write_all(&packet)?)
+    }
+}
+
+/// Interactive shell running in a pseudo-terminal on a device, as started by [`crate::ADBDeviceExt::pty_shell`].
+///
+/// Input and output of shell are forwarded by background threads until shell exits.
+/// Input thread outlives the session: it only stops once `reader` returns from the read it is blocked in, whose data is then dropped.
+/// Failing to read input closes it, and is reported by [`ADBPtyShell::wait`].
+#[derive(Debug)]
+pub struct ADBPtyShell {
+    resizer: ShellResizer,
+    exit: Receiver<Result<Option<u8>>>,
+    input_error: Receiver<RustADBError>,
+}
+
+impl ADBPtyShell {
+    /// Start forwarding `reader` to `input` of a freshly opened shell session, and its `output` to `writer`.
+    pub(crate) fn start(
+        input: Box<dyn Write + Send>,
+        mut output: Box<dyn Read + Send>,
+        shell_v2: bool,
+        size: TerminalSize,
+        mut reader: Box<dyn Read + Send>,
+        mut writer: Box<dyn Write + Send>,
+    ) -> Result<Self> {
+        let resizer = ShellResizer {
+            writer: Arc::new(Mutex::new(input)),
+            shell_v2,
+        };
+        resizer.resize(size)?;
+
+        // Input thread, forwarding data read from reader (that could be stdin e.g.)
+        let input = resizer.writer.clone();
+        let (input_error_sender, input_error) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(e) = forward_input(&mut reader, &input, shell_v2) {
+                // Reported before closing input, so that it is known once shell exits
+                let _ = input_error_sender.send(e);
+            }
+            if shell_v2 {
+                let close = encode_shell_packet(ShellPacketId::CloseStdin, &[]);
+                if let Err(e) = input.lock().map(|mut input| input.write_all(&close)) {
+                    log::debug!("cannot close shell input: {e}");
+                }
+            }
+        });
+
+        // Output thread, reporting exit code once device closes session
+        let (exit_sender, exit) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut forward = || -> Result<Option<u8>> {
+                // Without shell protocol v2, output is received as is
+                if !shell_v2 {
+                    forward_output(&mut output, &mut writer)?;
+                    return Ok(None);
+                }
+
+                let mut shell_output = ShellV2Output::merged(&mut writer);
+                forward_output(&mut output, &mut shell_output)?;
+                Ok(shell_output.exit_code())
+            };
+            let _ = exit_sender.send(forward());
+        });
+
+        Ok(Self {
+            resizer,
+            exit,
+            input_error,
+        })
+    }
+
+    /// Tell device that terminal now has given `size`.
+    pub fn resize(&self, size: TerminalSize) -> Result<()> {
+        self.resizer.resize(size)
+    }
+
+    /// Get a handle to resize terminal from another thread, e.g. one watching for `SIGWINCH` signals.
+    pub fn resizer(&self) -> ShellResizer {
+        self.resizer.clone()
+    }
+
+    /// Wait for shell to exit, returning its exit code if device supports shell protocol v2.
+    ///
+    /// Fails if input could not be read, even though shell exited.
+    pub fn wait(self) -> Result<Option<u8>> {
+        let exit_code = self.exit.recv().map_err(|_| {
+            RustADBError::IOError(std::io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "shell output stopped unexpectedly",
+            ))
+        })??;
+
+        match self.input_error.try_recv() {
+            Ok(e) => Err(e),
+            Err(_) => Ok(exit_code),
+        }
+    }
+}
+
+/// Forward data read from `reader` to shell `input` until end of stream.
+///
+/// Only failures to read `reader` are returned: failing to write means session is over, which output thread reports.
+fn forward_input(reader: &mut dyn Read, input: &SessionWriter, shell_v2: bool) -> Result<()> {
+    let mut buffer = [0; BUFFER_SIZE];
+    loop {
+        let size = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(size) => size,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let written = match shell_v2 {
+            true => input
+                .lock()?
+                .write_all(&encode_shell_packet(ShellPacketId::Stdin, &buffer[..size])),
+            false => input.lock()?.write_all(&buffer[..size]),
+        };
+        if let Err(e) = written {
+            log::debug!("shell session stopped accepting input: {e}");
+            return Ok(());
+        }
+    }
+}
+
+/// Copy `output` into `writer` until end of stream, flushing after each read so that interactive output is displayed right away.
+fn forward_output(output: &mut dyn Read, writer: &mut dyn Write) -> Result<()> {
+    let mut buffer = [0; BUFFER_SIZE];
+    loop {
+        match output.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(size) => {
+                writer.write_all(&buffer[..size])?;
+                writer.flush()?;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[test]
+fn test_pty_shell() {
+    use crate::{ADBDeviceExt, FakeADBDevice};
+    use std::io::Cursor;
+    use std::sync::mpsc::Sender;
+
+    /// Input of shell, ending once sender is dropped
+    struct ChannelReader(Receiver<Vec<u8>>, Cursor<Vec<u8>>);
+
+    impl Read for ChannelReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.1.position() as usize == self.1.get_ref().len() {
+                match self.0.recv() {
+                    Ok(data) => self.1 = Cursor::new(data),
+                    Err(_) => return Ok(0),
+                }
+            }
+            self.1.read(buf)
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("poisoned output").write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let fake_device = FakeADBDevice::new();
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let (input, receiver): (Sender<Vec<u8>>, _) = mpsc::channel();
+    let output = SharedOutput::default();
+    let shell = device
+        .pty_shell(
+            "xterm-256color",
+            TerminalSize::new(24, 80),
+            Box::new(ChannelReader(receiver, Cursor::new(Vec::new()))),
+            Box::new(output.clone()),
+        )
+        .expect("cannot start shell");
+
+    shell
+        .resize(TerminalSize::new(50, 132))
+        .expect("cannot resize terminal");
+    input.send(b"ls\n".to_vec()).expect("cannot send input");
+    drop(input);
+
+    assert_eq!(shell.wait().expect("shell failed"), Some(0));
+    assert_eq!(*output.0.lock().expect("poisoned output"), b"ls\n");
+    assert_eq!(
+        fake_device.opened_services().expect("cannot get services"),
+        ["shell,v2,TERM=xterm-256color,pty:"]
+    );
+    assert_eq!(
+        fake_device.window_sizes().expect("cannot get window sizes"),
+        ["24x80,0x0", "50x132,0x0"]
+    );
+
+    // Input failing to be read ends shell, and gets reported
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("terminal went away"))
+        }
+    }
+
+    let shell = device
+        .pty_shell(
+            "xterm-256color",
+            TerminalSize::new(24, 80),
+            Box::new(FailingReader),
+            Box::new(std::io::sink()),
+        )
+        .expect("cannot start shell");
+    assert!(shell.wait().is_err());
+}
@@ -0,0 +1,8 @@
+mod session;
+mod session_printer;
+mod session_record;
+mod session_recorder;
+
+pub use session::Session;
+pub use session_record::{SessionDirection, SessionEvent, SessionRecord};
+pub use session_recorder::SessionRecorder;
@@ -0,0 +1,47 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use super::SessionRecord;
+use super::session_recorder::SESSION_MAGIC;
+use crate::{Result, RustADBError};
+
+/// A session recorded by a [`crate::SessionRecorder`].
+///
+/// It can be played back with a [`crate::ReplayTransport`] or [`crate::TCPServerTransport::new_replay`], and displayed as
+/// a human-readable transcript decoding ADB messages, server requests and sync subcommands.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Session {
+    records: Vec<SessionRecord>,
+}
+
+impl Session {
+    /// Instantiate a new [`Session`] from its records.
+    pub fn new(records: Vec<SessionRecord>) -> Self {
+        Self { records }
+    }
+
+    /// Load session written at `path` by a [`crate::SessionRecorder`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read(path)?;
+        let Some(records) = content.strip_prefix(SESSION_MAGIC) else {
+            return Err(RustADBError::InvalidSessionFile(
+                "missing session header".into(),
+            ));
+        };
+
+        let mut reader = Cursor::new(records);
+        let mut session = Self::default();
+        while (reader.position() as usize) < records.len() {
+            let record = bincode::deserialize_from(&mut reader)
+                .map_err(|e| RustADBError::InvalidSessionFile(e.to_string()))?;
+            session.records.push(record);
+        }
+
+        Ok(session)
+    }
+
+    /// Records of this session, in order.
+    pub fn records(&self) -> &[SessionRecord] {
+        &self.records
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::{Session, SessionDirection, SessionEvent, SessionRecord};
+use crate::device::MessageCommand;
+use crate::device::adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN};
+
+/// Maximum number of bytes displayed for a raw payload.
+const MAX_DISPLAYED_BYTES: usize = 64;
+
+/// State needed to decode a session, as the meaning of data depends on what was exchanged before.
+#[derive(Default)]
+struct SessionPrinter {
+    /// Host ids of streams opened on `sync:` service
+    sync_streams: HashSet<u32>,
+    /// Whether current server connection has been switched to sync mode
+    server_sync: bool,
+}
+
+impl SessionPrinter {
+    fn describe_message(&mut self, record: &SessionRecord) -> Vec<String> {
+        let message = match record.message() {
+            Some(Ok(message)) => message,
+            Some(Err(e)) => return vec![format!("invalid message: {e}")],
+            None => return Vec::new(),
+        };
+        let header = message.header();
+        let (arg0, arg1) = (header.arg0(), header.arg1());
+        let payload = message.payload();
+        let sent = record.direction == SessionDirection::Sent;
+        // Host side id of stream, first argument being always the id of sender
+        let host_id = if sent { arg0 } else { arg1 };
+
+        let mut lines = match header.command() {
+            MessageCommand::Cnxn => vec![format!(
+                "CNXN(version={arg0:#x}, max_payload={arg1}) {}",
+                quote(payload)
+            )],
+            MessageCommand::Stls => vec![format!("STLS(version={arg0:#x})")],
+            MessageCommand::Auth => {
+                let kind = match arg0 {
+                    AUTH_TOKEN => "token",
+                    AUTH_SIGNATURE => "signature",
+                    AUTH_RSAPUBLICKEY => "public key",
+                    _ => "unknown",
+                };
+                vec![format!("AUTH({kind}) {} bytes", payload.len())]
+            }
+            MessageCommand::Open => {
+                if sent && payload.starts_with(b"sync:") {
+                    self.sync_streams.insert(host_id);
+                }
+                vec![format!(
+                    "OPEN(local={arg0}, remote={arg1}) {}",
+                    quote(payload)
+                )]
+            }
+            MessageCommand::Okay => vec![format!("OKAY(local={arg0}, remote={arg1})")],
+            MessageCommand::Clse => {
+                self.sync_streams.remove(&host_id);
+                vec![format!("CLSE(local={arg0}, remote={arg1})")]
+            }
+            MessageCommand::Write => {
+                let mut lines = vec![format!(
+                    "WRTE(local={arg0}, remote={arg1}) {} bytes",
+                    payload.len()
+                )];
+                let content = if self.sync_streams.contains(&host_id) {
+                    describe_sync(payload, sent)
+                } else {
+                    vec![quote(payload)]
+                };
+                lines.extend(content.into_iter().map(|line| format!("  {line}")));
+                lines
+            }
+        };
+
+        if !message.check_message_integrity() {
+            lines[0].push_str(" (invalid checksum)");
+        }
+
+        lines
+    }
+
+    fn describe_bytes(&mut self, direction: SessionDirection, bytes: &[u8]) -> Vec<String> {
+        let from_host = direction == SessionDirection::Sent;
+        if self.server_sync {
+            return describe_sync(bytes, from_host);
+        }
+        if !from_host {
+            return vec![quote(bytes)];
+        }
+
+        // Requests are prefixed by their length, as 4 hexadecimal digits
+        let mut lines = Vec::new();
+        let mut rest = bytes;
+        while let Some(length) = rest
+            .get(..4)
+            .and_then(|length| std::str::from_utf8(length).ok())
+            .and_then(|length| usize::from_str_radix(length, 16).ok())
+        {
+            let Some(request) = rest.get(4..4 + length) else {
+                break;
+            };
+            lines.push(format!("request {}", quote(request)));
+            rest = &rest[4 + length..];
+
+            if request == b"sync:" {
+                self.server_sync = true;
+                lines.extend(describe_sync(rest, from_host));
+                return lines;
+            }
+        }
+        if !rest.is_empty() {
+            lines.push(quote(rest));
+        }
+
+        lines
+    }
+}
+
+impl Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = SessionPrinter::default();
+        let mut records = self.records().iter().peekable();
+
+        while let Some(record) = records.next() {
+            let lines = match &record.event {
+                SessionEvent::Connected => {
+                    printer.server_sync = false;
+                    vec!["connected".to_string()]
+                }
+                SessionEvent::Message { .. } => printer.describe_message(record),
+                SessionEvent::Bytes(bytes) => {
+                    // Data is split by reads and writes sizes, merging it gives whole requests and responses
+                    let mut bytes = bytes.clone();
+                    while let Some(next) = records.next_if(|next| {
+                        next.direction == record.direction
+                            && matches!(next.event, SessionEvent::Bytes(_))
+                    }) {
+                        if let SessionEvent::Bytes(next_bytes) = &next.event {
+                            bytes.extend_from_slice(next_bytes);
+                        }
+                    }
+                    printer.describe_bytes(record.direction, &bytes)
+                }
+            };
+
+            let arrow = match record.direction {
+                SessionDirection::Sent => '>',
+                SessionDirection::Received => '<',
+            };
+            for line in lines {
+                writeln!(f, "[{:>12.6}] {arrow} {line}", record.elapsed.as_secs_f64())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode sync subcommands found in `data`, as sent by host if `from_host` is set, or by device otherwise.
+fn describe_sync(data: &[u8], from_host: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut rest = data;
+
+    while rest.len() >= 8 {
+        let id = String::from_utf8_lossy(&rest[..4]).to_string();
+        let arg = LittleEndian::read_u32(&rest[4..8]);
+        let arg_length = arg as usize;
+
+        let (line, size) = match (&rest[..4], from_host) {
+            // Sync protocol v2 requests are followed by another one with the same id, holding transfer options
+            (b"SND2" | b"RCV2", true) if !starts_with_path(rest, arg_length) => {
+                match (&rest[..4], rest.get(8..12)) {
+                    (b"SND2", Some(flags)) => (
+                        format!(
+                            "sync SND2 mode={arg:o} flags={}",
+                            LittleEndian::read_u32(flags)
+                        ),
+                        12,
+                    ),
+                    _ => (format!("sync {id} flags={arg}"), 8),
+                }
+            }
+            (
+                b"STAT" | b"STA2" | b"LSTA" | b"LIST" | b"LIS2" | b"RECV" | b"SEND" | b"RCV2"
+                | b"SND2",
+                true,
+            ) => {
+                match rest.get(8..8 + arg_length) {
+                    Some(path) => (format!("sync {id} {}", quote(path)), 8 + arg_length),
+                    // Path may be sent separately
+                    None => (format!("sync {id}, path of {arg} bytes follows"), 8),
+                }
+            }
+            (b"DATA", _) => (
+                format!("sync DATA {arg} bytes"),
+                // Data may continue in next payloads
+                rest.len().min(8 + arg_length),
+            ),
+            (b"DONE", true) => (format!("sync DONE mtime={arg}"), 8),
+            // Listings end with a `DONE` as big as an entry without name
+            (b"DONE", false) if rest.len() == 20 => ("sync DONE".to_string(), 20),
+            (b"DONE", false) if rest.len() == 76 => ("sync DONE".to_string(), 76),
+            (b"DONE", false) => ("sync DONE".to_string(), 8),
+            (b"OKAY", false) => ("sync OKAY".to_string(), 8),
+            (b"QUIT", true) => ("sync QUIT".to_string(), 8),
+            (b"FAIL", _) => {
+                let Some(message) = rest.get(8..8 + arg_length) else {
+                    break;
+                };
+                (format!("sync FAIL {}", quote(message)), 8 + arg_length)
+            }
+            (b"STAT", false) if rest.len() >= 16 => (
+                format!(
+                    "sync STAT mode={arg:o} size={} mtime={}",
+                    LittleEndian::read_u32(&rest[8..12]),
+                    LittleEndian::read_u32(&rest[12..16])
+                ),
+                16,
+            ),
+            (b"DENT", false) if rest.len() >= 20 => {
+                let name_length = LittleEndian::read_u32(&rest[16..20]) as usize;
+                let Some(name) = rest.get(20..20 + name_length) else {
+                    break;
+                };
+                (
+                    format!(
+                        "sync DENT mode={arg:o} size={} mtime={} {}",
+                        LittleEndian::read_u32(&rest[8..12]),
+                        LittleEndian::read_u32(&rest[12..16]),
+                        quote(name)
+                    ),
+                    20 + name_length,
+                )
+            }
+            (b"DNT2", false) if rest.len() >= 76 => {
+                let name_length = LittleEndian::read_u32(&rest[72..76]) as usize;
+                let Some(name) = rest.get(76..76 + name_length) else {
+                    break;
+                };
+                (
+                    format!(
+                        "sync DNT2 mode={:o} size={} mtime={} {}",
+                        LittleEndian::read_u32(&rest[24..28]),
+                        LittleEndian::read_u64(&rest[40..48]),
+                        LittleEndian::read_i64(&rest[56..64]),
+                        quote(name)
+                    ),
+                    76 + name_length,
+                )
+            }
+            _ => break,
+        };
+
+        lines.push(line);
+        rest = &rest[size..];
+    }
+
+    if !rest.is_empty() {
+        lines.push(quote(rest));
+    }
+
+    lines
+}
+
+/// Whether sync request `request` is followed by a path of `length` bytes, rather than by options.
+fn starts_with_path(request: &[u8], length: usize) -> bool {
+    request
+        .get(8..8 + length)
+        .is_some_and(|path| path.first().is_some_and(u8::is_ascii_graphic))
+}
+
+/// Display `bytes` as an escaped string, truncated if too long.
+fn quote(bytes: &[u8]) -> String {
+    if bytes.len() <= MAX_DISPLAYED_BYTES {
+        return format!("\"{}\"", bytes.escape_ascii());
+    }
+
+    format!(
+        "\"{}\"... ({} bytes)",
+        bytes[..MAX_DISPLAYED_BYTES].escape_ascii(),
+        bytes.len()
+    )
+}
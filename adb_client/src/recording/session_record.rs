@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::device::{ADBTransportMessage, ADBTransportMessageHeader};
+
+/// Direction of data exchanged during a recorded session, from host point of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionDirection {
+    /// Sent by host to device or server
+    Sent,
+    /// Received by host
+    Received,
+}
+
+/// Something that happened during a recorded session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionEvent {
+    /// Host opened a new connection
+    Connected,
+    /// An ADB message exchanged with a device
+    Message {
+        /// Raw message header, as sent on the wire
+        header: [u8; 24],
+        /// Message payload
+        payload: Vec<u8>,
+    },
+    /// Raw bytes exchanged with server
+    Bytes(Vec<u8>),
+}
+
+/// A single timestamped entry of a recorded session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Time elapsed since recording started
+    pub elapsed: Duration,
+    /// Whether data was sent or received by host
+    pub direction: SessionDirection,
+    /// What happened
+    pub event: SessionEvent,
+}
+
+impl SessionRecord {
+    /// Decode message recorded in this entry, if any.
+    pub(crate) fn message(&self) -> Option<Result<ADBTransportMessage>> {
+        match &self.event {
+            SessionEvent::Message { header, payload } => {
+                Some(ADBTransportMessageHeader::try_from(*header).map(|header| {
+                    ADBTransportMessage::from_header_and_payload(header, payload.clone())
+                }))
+            }
+            _ => None,
+        }
+    }
+}
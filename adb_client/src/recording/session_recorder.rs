@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::{SessionDirection, SessionEvent, SessionRecord};
+use crate::device::ADBTransportMessage;
+use crate::{Result, RustADBError};
+
+/// Bytes starting every session file.
+pub(crate) const SESSION_MAGIC: &[u8; 8] = b"ADBSESS1";
+
+/// Writes every record of a session to a file, as soon as it happens.
+///
+/// Recorders can be cloned to record several transports in the same file. Load written sessions with [`crate::Session::load`].
+#[derive(Clone, Debug)]
+pub struct SessionRecorder {
+    output: Arc<Mutex<File>>,
+    start: Instant,
+}
+
+impl SessionRecorder {
+    /// Create session file at `path`, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut output = File::create(path)?;
+        output.write_all(SESSION_MAGIC)?;
+
+        Ok(Self {
+            output: Arc::new(Mutex::new(output)),
+            start: Instant::now(),
+        })
+    }
+
+    /// Append `event` to session. Failures are only logged, as recording must not disturb the session itself.
+    pub(crate) fn record(&self, direction: SessionDirection, event: SessionEvent) {
+        let record = SessionRecord {
+            elapsed: self.start.elapsed(),
+            direction,
+            event,
+        };
+
+        if let Err(e) = self.write_record(&record) {
+            log::warn!("cannot record session: {e}");
+        }
+    }
+
+    pub(crate) fn record_message(
+        &self,
+        direction: SessionDirection,
+        message: &ADBTransportMessage,
+    ) {
+        let header = match message.header().as_bytes() {
+            Ok(header) => header,
+            Err(e) => {
+                log::warn!("cannot record session: {e}");
+                return;
+            }
+        };
+
+        match <[u8; 24]>::try_from(header.as_slice()) {
+            Ok(header) => self.record(
+                direction,
+                SessionEvent::Message {
+                    header,
+                    payload: message.payload().clone(),
+                },
+            ),
+            Err(e) => log::warn!("cannot record session: {e}"),
+        }
+    }
+
+    fn write_record(&self, record: &SessionRecord) -> Result<()> {
+        let bytes = bincode::serialize(record).map_err(|_e| RustADBError::ConversionError)?;
+        self.output.lock()?.write_all(&bytes)?;
+        Ok(())
+    }
+}
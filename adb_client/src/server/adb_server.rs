@@ -92,11 +92,13 @@ impl ADBServer {
             TCPServerTransport::default()
         };
 
-        if is_local_ip {
-            Self::start(&self.envs, &self.adb_path);
+        // A server may already be listening (e.g. an `ADBHostServer`), in which case `adb` binary is not needed
+        if transport.connect().is_err() {
+            if is_local_ip {
+                Self::start(&self.envs, &self.adb_path);
+            }
+            transport.connect()?;
         }
-
-        transport.connect()?;
         self.transport = Some(transport);
 
         self.get_transport()
@@ -1,4 +1,5 @@
-use quick_protobuf::{BytesReader, MessageRead};
+use quick_protobuf::sizeofs::{sizeof_len, sizeof_varint};
+use quick_protobuf::{BytesReader, MessageRead, MessageWrite, Writer, WriterBackend};
 
 use std::fmt::Display;
 
@@ -135,6 +136,38 @@ impl<'a> MessageRead<'a> for ServerStatus {
     }
 }
 
+impl MessageWrite for ServerStatus {
+    fn get_size(&self) -> usize {
+        let strings = [
+            &self.version,
+            &self.build,
+            &self.executable_absolute_path,
+            &self.log_absolute_path,
+            &self.os,
+        ];
+
+        4 + sizeof_varint(self.usb_backend as u64)
+            + sizeof_varint(self.mdns_backend.clone() as u64)
+            + 2
+            + strings
+                .iter()
+                .map(|s| 1 + sizeof_len(s.len()))
+                .sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> quick_protobuf::Result<()> {
+        w.write_with_tag(8, |w| w.write_enum(self.usb_backend as i32))?;
+        w.write_with_tag(16, |w| w.write_bool(self.usb_backend_forced))?;
+        w.write_with_tag(24, |w| w.write_enum(self.mdns_backend.clone() as i32))?;
+        w.write_with_tag(32, |w| w.write_bool(self.mdns_backend_forced))?;
+        w.write_with_tag(42, |w| w.write_string(&self.version))?;
+        w.write_with_tag(50, |w| w.write_string(&self.build))?;
+        w.write_with_tag(58, |w| w.write_string(&self.executable_absolute_path))?;
+        w.write_with_tag(66, |w| w.write_string(&self.log_absolute_path))?;
+        w.write_with_tag(74, |w| w.write_string(&self.os))
+    }
+}
+
 impl Display for ServerStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "usb_backend: {}", self.usb_backend)?;
@@ -165,3 +198,16 @@ impl TryFrom<Vec<u8>> for ServerStatus {
         ServerStatus::from_reader(&mut reader, &value).map_err(|_| RustADBError::ConversionError)
     }
 }
+
+impl TryFrom<&ServerStatus> for Vec<u8> {
+    type Error = RustADBError;
+
+    fn try_from(value: &ServerStatus) -> Result<Self, Self::Error> {
+        let mut bytes = Vec::with_capacity(value.get_size());
+        value
+            .write_message(&mut Writer::new(&mut bytes))
+            .map_err(|_| RustADBError::ConversionError)?;
+        Ok(bytes)
+    }
+}
+
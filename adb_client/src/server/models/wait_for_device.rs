@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use crate::RustADBError;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 /// List of available transports to wait for.
 pub enum WaitForDeviceTransport {
     /// USB transport
@@ -10,15 +10,10 @@ pub enum WaitForDeviceTransport {
     /// Local transport
     Local,
     /// Any transport (default value)
+    #[default]
     Any,
 }
 
-impl Default for WaitForDeviceTransport {
-    fn default() -> Self {
-        Self::Any
-    }
-}
-
 impl Display for WaitForDeviceTransport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -65,3 +60,17 @@ impl Display for WaitForDeviceState {
         }
     }
 }
+
+impl TryFrom<&str> for WaitForDeviceState {
+    type Error = RustADBError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "device" => Ok(Self::Device),
+            "recovery" => Ok(Self::Recovery),
+            "sideload" => Ok(Self::Sideload),
+            "bootloader" => Ok(Self::Bootloader),
+            s => Err(RustADBError::UnknownDeviceState(s.to_string())),
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{ADBTransport, Result, TCPServerTransport, models::AdbServerCommand};
+use crate::{
+    ADBTransport, Result, TCPServerTransport,
+    models::{AdbServerCommand, SyncCompression},
+};
 use std::net::SocketAddrV4;
 
 /// Represents a device connected to the ADB server.
@@ -8,6 +11,8 @@ pub struct ADBServerDevice {
     pub identifier: Option<String>,
     /// Internal [TCPServerTransport]
     pub(crate) transport: TCPServerTransport,
+    /// Compression requested for file transfers, negotiated with device if `None`
+    pub(crate) sync_compression: Option<SyncCompression>,
 }
 
 impl ADBServerDevice {
@@ -18,6 +23,7 @@ impl ADBServerDevice {
         Self {
             identifier: Some(identifier),
             transport,
+            sync_compression: None,
         }
     }
 
@@ -28,9 +34,28 @@ impl ADBServerDevice {
         Self {
             identifier: None,
             transport,
+            sync_compression: None,
         }
     }
 
+    /// Instantiates a new [ADBServerDevice] from a [TCPServerTransport], e.g. one recording or playing back a session.
+    /// Device is autodetected if no identifier is given.
+    pub fn new_from_transport(identifier: Option<String>, transport: TCPServerTransport) -> Self {
+        Self {
+            identifier,
+            transport,
+            sync_compression: None,
+        }
+    }
+
+    /// Compress contents of pushed and pulled files with `compression` when device supports it, e.g. [`SyncCompression::Zstd`]
+    /// which is never picked otherwise.
+    ///
+    /// With `None`, by default, compression is negotiated with device as `adb` does.
+    pub fn set_sync_compression(&mut self, compression: Option<SyncCompression>) {
+        self.sync_compression = compression;
+    }
+
     /// Connect to underlying transport
     pub(crate) fn connect(&mut self) -> Result<&mut TCPServerTransport> {
         self.transport.connect()?;
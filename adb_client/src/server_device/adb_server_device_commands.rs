@@ -1,12 +1,18 @@
 use std::{
     io::{ErrorKind, Read, Write},
     path::Path,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    ADBDeviceExt, Result, RustADBError,
+    ADBDeviceExt, ADBPtyShell, InstallOptions, Result, RustADBError, TransferMonitor,
+    UninstallOptions, WaitForDeviceState, WaitForDeviceTransport,
+    boot::{self, BootTarget},
     constants::BUFFER_SIZE,
-    models::{AdbServerCommand, AdbStatResponse, HostFeatures},
+    models::{
+        AdbDirEntry, AdbServerCommand, AdbStatResponse, HostFeatures, PushOptions, ShellPacketId,
+        ShellV2Output, TerminalSize, encode_shell_packet,
+    },
 };
 
 use super::ADBServerDevice;
@@ -42,14 +48,44 @@ impl ADBDeviceExt for ADBServerDevice {
         }
     }
 
+    fn shell_command_with_status(
+        &mut self,
+        command: &[&str],
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<Option<u8>> {
+        if !self.host_features()?.contains(&HostFeatures::ShellV2) {
+            log::debug!("device does not support shell v2, exit code will not be available");
+            self.shell_command(command, stdout)?;
+            return Ok(None);
+        }
+
+        self.set_serial_transport()?;
+        self.transport
+            .send_adb_request(AdbServerCommand::ShellV2Command(command.join(" ")))?;
+
+        let mut connection = self.transport.get_raw_connection()?;
+        // Command does not get any input
+        connection.write_all(&encode_shell_packet(ShellPacketId::CloseStdin, &[]))?;
+
+        let mut output = ShellV2Output::new(stdout, stderr);
+        std::io::copy(&mut connection, &mut output)?;
+
+        Ok(output.exit_code())
+    }
+
     fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
         self.stat(remote_path)
     }
 
+    fn list_dir(&mut self, path: &str) -> Result<Vec<AdbDirEntry>> {
+        self.list_dir(path)
+    }
+
     fn shell(
         &mut self,
         mut reader: &mut dyn Read,
-        mut writer: Box<(dyn Write + Send)>,
+        mut writer: Box<dyn Write + Send>,
     ) -> Result<()> {
         let supported_features = self.host_features()?;
         if !supported_features.contains(&HostFeatures::ShellV2)
@@ -96,6 +132,43 @@ impl ADBDeviceExt for ADBServerDevice {
         Ok(())
     }
 
+    fn pty_shell(
+        &mut self,
+        term: &str,
+        size: TerminalSize,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<ADBPtyShell> {
+        let supported_features = self.host_features()?;
+        let shell_v2 = supported_features.contains(&HostFeatures::ShellV2);
+        if !shell_v2 && !supported_features.contains(&HostFeatures::Cmd) {
+            return Err(RustADBError::ADBShellNotSupported);
+        }
+
+        self.set_serial_transport()?;
+        match shell_v2 {
+            true => self
+                .transport
+                .send_adb_request(AdbServerCommand::PtyShell(term.to_string()))?,
+            false => {
+                log::debug!("device does not support shell v2, terminal size will not be updated");
+                self.transport.send_adb_request(AdbServerCommand::Shell)?
+            }
+        }
+
+        let output = self.transport.get_raw_connection()?.try_clone()?;
+        let input = output.try_clone()?;
+
+        ADBPtyShell::start(
+            Box::new(input),
+            Box::new(output),
+            shell_v2,
+            size,
+            reader,
+            writer,
+        )
+    }
+
     fn pull(&mut self, source: &dyn AsRef<str>, mut output: &mut dyn Write) -> Result<()> {
         self.pull(source, &mut output)
     }
@@ -104,19 +177,72 @@ impl ADBDeviceExt for ADBServerDevice {
         self.reboot(reboot_type)
     }
 
-    fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()> {
-        self.push(stream, path)
+    fn wait_for_boot_since(
+        &mut self,
+        previous_boot: Option<&str>,
+        timeout: Duration,
+    ) -> Result<()> {
+        boot::wait_for_boot(self, previous_boot, timeout)
+    }
+
+    fn push_with_options(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        options: PushOptions,
+    ) -> Result<()> {
+        self.push_with_options(stream, path, options)
+    }
+
+    fn install_with_options(
+        &mut self,
+        apk_path: &dyn AsRef<Path>,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        self.install_with_options(apk_path, options, monitor)
+    }
+
+    fn install_from_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        self.install_from_reader(reader, size, options, monitor)
     }
 
-    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
-        self.install(apk_path)
+    fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.install_multiple_with_options(apk_paths, options)
     }
 
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.uninstall(package)
+    fn uninstall_with_options(&mut self, package: &str, options: &UninstallOptions) -> Result<()> {
+        self.uninstall_with_options(package, options)
     }
 
     fn framebuffer_inner(&mut self) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
         self.framebuffer_inner()
     }
 }
+
+impl BootTarget for ADBServerDevice {
+    fn reconnect(&mut self) -> Result<()> {
+        // Server answers once device is back
+        let command = AdbServerCommand::WaitForDevice(
+            WaitForDeviceState::Device,
+            WaitForDeviceTransport::Any,
+            self.identifier.clone(),
+        );
+        self.connect()?.send_adb_request(command)?;
+        self.transport.read_adb_response()
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) {
+        self.transport.set_read_deadline(deadline);
+    }
+}
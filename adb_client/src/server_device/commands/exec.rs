@@ -0,0 +1,28 @@
+use std::io::Read;
+
+use crate::{
+    Result,
+    install_session::ExecDevice,
+    models::{AdbServerCommand, HostFeatures},
+    server_device::ADBServerDevice,
+};
+
+impl ExecDevice for ADBServerDevice {
+    fn supports_abb_exec(&mut self) -> Result<bool> {
+        Ok(self.host_features()?.contains(&HostFeatures::AbbExec))
+    }
+
+    fn exec(&mut self, service: &str, input: &mut dyn Read) -> Result<Vec<u8>> {
+        self.set_serial_transport()?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::Exec(service.to_string()))?;
+
+        let mut raw_connection = self.transport.get_raw_connection()?;
+        std::io::copy(input, &mut raw_connection)?;
+
+        let mut output = Vec::new();
+        raw_connection.read_to_end(&mut output)?;
+        Ok(output)
+    }
+}
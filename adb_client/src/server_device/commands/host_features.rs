@@ -1,6 +1,6 @@
 use crate::{
     ADBServerDevice, Result,
-    models::{AdbServerCommand, HostFeatures},
+    models::{AdbServerCommand, HostFeatures, SyncCompression},
 };
 
 impl ADBServerDevice {
@@ -17,4 +17,16 @@ impl ADBServerDevice {
             .filter_map(|v| HostFeatures::try_from(v).ok())
             .collect())
     }
+
+    /// Compression to use for file transfers, or `None` if device only supports legacy sync requests.
+    pub(crate) fn sync_compression(&mut self) -> Result<Option<SyncCompression>> {
+        let features = self.host_features()?;
+        Ok(SyncCompression::negotiate(
+            self.sync_compression,
+            |feature| {
+                HostFeatures::try_from(feature.as_bytes())
+                    .is_ok_and(|feature| features.contains(&feature))
+            },
+        ))
+    }
 }
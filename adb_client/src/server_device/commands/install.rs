@@ -1,41 +1,83 @@
 use std::{fs::File, io::Read, path::Path};
 
 use crate::{
-    Result, models::AdbServerCommand, server_device::ADBServerDevice, utils::check_extension_is_apk,
+    InstallOptions, Result, TransferMonitor, install_session,
+    models::AdbServerCommand,
+    server_device::ADBServerDevice,
+    utils::{check_apk_magic, check_extension_is_apk, check_package_manager_response, copy_apk},
 };
 
 impl ADBServerDevice {
     /// Install an APK on device
     pub fn install<P: AsRef<Path>>(&mut self, apk_path: P) -> Result<()> {
-        let mut apk_file = File::open(&apk_path)?;
+        self.install_with_monitor(apk_path, &mut TransferMonitor::new())
+    }
+
+    /// Install APKs on device as a single package. See [`crate::ADBDeviceExt::install_multiple`].
+    pub fn install_multiple(&mut self, apk_paths: &[&dyn AsRef<Path>]) -> Result<()> {
+        self.install_multiple_with_options(apk_paths, &InstallOptions::default())
+    }
+
+    /// Install APKs on device as a single package, with given `options`.
+    pub fn install_multiple_with_options(
+        &mut self,
+        apk_paths: &[&dyn AsRef<Path>],
+        options: &InstallOptions,
+    ) -> Result<()> {
+        install_session::install_multiple(self, apk_paths, options)
+    }
 
+    /// Install an APK on device, reporting upload progress to `monitor` which may cancel it.
+    pub fn install_with_monitor<P: AsRef<Path>>(
+        &mut self,
+        apk_path: P,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        self.install_with_options(apk_path, &InstallOptions::default(), monitor)
+    }
+
+    /// Install an APK on device with given `options`, reporting upload progress to `monitor` which may cancel it.
+    pub fn install_with_options<P: AsRef<Path>>(
+        &mut self,
+        apk_path: P,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
         check_extension_is_apk(&apk_path)?;
 
+        let mut apk_file = File::open(&apk_path)?;
         let file_size = apk_file.metadata()?.len();
+        self.install_from_reader(&mut apk_file, file_size, options, monitor)?;
+
+        log::info!(
+            "APK file {} successfully installed",
+            apk_path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Install an APK of `size` bytes read from `reader` on device with given `options`, reporting upload progress to `monitor` which may cancel it.
+    pub fn install_from_reader<R: Read>(
+        &mut self,
+        reader: R,
+        size: u64,
+        options: &InstallOptions,
+        monitor: &mut TransferMonitor,
+    ) -> Result<()> {
+        let apk = check_apk_magic(reader)?;
 
         self.set_serial_transport()?;
 
         self.transport
-            .send_adb_request(AdbServerCommand::Install(file_size))?;
+            .send_adb_request(AdbServerCommand::Install(size, options.clone()))?;
 
         let mut raw_connection = self.transport.get_raw_connection()?;
 
-        std::io::copy(&mut apk_file, &mut raw_connection)?;
+        copy_apk(apk, size, &mut raw_connection, monitor)?;
 
         let mut data = [0; 1024];
         let read_amount = self.transport.get_raw_connection()?.read(&mut data)?;
 
-        match &data[0..read_amount] {
-            b"Success\n" => {
-                log::info!(
-                    "APK file {} successfully installed",
-                    apk_path.as_ref().display()
-                );
-                Ok(())
-            }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
-        }
+        check_package_manager_response(&data[0..read_amount])
     }
 }
@@ -1,3 +1,4 @@
+mod exec;
 mod forward;
 mod framebuffer;
 mod host_features;
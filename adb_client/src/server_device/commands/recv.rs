@@ -1,86 +1,32 @@
 use crate::{
     ADBServerDevice, Result, constants,
-    models::{AdbServerCommand, SyncCommand},
+    models::{AdbServerCommand, SyncCommand, SyncCompression, SyncDataReader},
 };
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::{BufReader, BufWriter, Read, Write};
-
-/// Internal structure wrapping a [std::io::Read] and hiding underlying protocol logic.
-struct ADBRecvCommandReader<R: Read> {
-    inner: R,
-    remaining_data_bytes_to_read: usize,
-}
-
-impl<R: Read> ADBRecvCommandReader<R> {
-    pub fn new(inner: R) -> Self {
-        Self {
-            inner,
-            remaining_data_bytes_to_read: 0,
-        }
-    }
-}
-
-impl<R: Read> Read for ADBRecvCommandReader<R> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        // In case of a "DATA" header, we may not have enough space in `buf` to fill it with "length" bytes coming from device.
-        // `remaining_data_bytes_to_read` represents how many bytes are still left to read before receiving another header.
-        if self.remaining_data_bytes_to_read == 0 {
-            let mut header = [0_u8; 4];
-            self.inner.read_exact(&mut header)?;
-
-            match &header[..] {
-                b"DATA" => {
-                    let length = self.inner.read_u32::<LittleEndian>()? as usize;
-                    let effective_read = self.inner.read(&mut buf[0..length])?;
-                    self.remaining_data_bytes_to_read = length - effective_read;
-
-                    Ok(effective_read)
-                }
-                b"DONE" => Ok(0),
-                b"FAIL" => {
-                    let length = self.inner.read_u32::<LittleEndian>()? as usize;
-                    let mut error_msg = vec![0; length];
-                    self.inner.read_exact(&mut error_msg)?;
-
-                    Err(std::io::Error::other(format!(
-                        "ADB request failed: {}",
-                        String::from_utf8_lossy(&error_msg)
-                    )))
-                }
-                _ => Err(std::io::Error::other(format!(
-                    "Unknown response from device {header:#?}"
-                ))),
-            }
-        } else {
-            // Computing minimum to ensure to stop reading before next header...
-            let data_to_read = std::cmp::min(self.remaining_data_bytes_to_read, buf.len());
-            self.inner.read_exact(&mut buf[..data_to_read])?;
-
-            self.remaining_data_bytes_to_read -= self.remaining_data_bytes_to_read;
-
-            Ok(data_to_read)
-        }
-    }
-}
+use std::io::{BufReader, BufWriter, Write};
 
 impl ADBServerDevice {
     /// Receives path to stream from the device.
     pub fn pull(&mut self, path: &dyn AsRef<str>, stream: &mut dyn Write) -> Result<()> {
+        let compression = self.sync_compression()?;
         self.set_serial_transport()?;
 
         // Set device in SYNC mode
         self.transport.send_adb_request(AdbServerCommand::Sync)?;
 
-        // Send a recv command
-        self.transport.send_sync_request(SyncCommand::Recv)?;
+        // Send a recv command, using sync protocol v2 when device supports it
+        match compression {
+            Some(_) => self.transport.send_sync_request(SyncCommand::Recv2)?,
+            None => self.transport.send_sync_request(SyncCommand::Recv)?,
+        }
 
-        self.handle_recv_command(path, stream)
+        self.handle_recv_command(path, stream, compression)
     }
 
     fn handle_recv_command<S: AsRef<str>>(
         &mut self,
         from: S,
         output: &mut dyn Write,
+        compression: Option<SyncCompression>,
     ) -> Result<()> {
         let mut raw_connection = self.transport.get_raw_connection()?;
 
@@ -90,11 +36,22 @@ impl ADBServerDevice {
         buffer.extend_from_slice(from_as_bytes);
         raw_connection.write_all(&buffer)?;
 
-        let reader = ADBRecvCommandReader::new(raw_connection);
-        std::io::copy(
-            &mut BufReader::with_capacity(constants::BUFFER_SIZE, reader),
-            &mut BufWriter::with_capacity(constants::BUFFER_SIZE, output),
-        )?;
+        if let Some(compression) = compression {
+            let mut buffer = Vec::with_capacity(8);
+            buffer.extend_from_slice(SyncCommand::Recv2.to_string().as_bytes());
+            buffer.extend_from_slice(&compression.flags().to_le_bytes());
+            raw_connection.write_all(&buffer)?;
+        }
+
+        let mut reader =
+            BufReader::with_capacity(constants::BUFFER_SIZE, SyncDataReader::new(raw_connection));
+        let mut writer = BufWriter::with_capacity(constants::BUFFER_SIZE, output);
+        compression
+            .unwrap_or(SyncCompression::None)
+            .decompress(&mut reader, &mut writer)?;
+        writer.flush()?;
+        // Compressed stream may end before "DONE" is read
+        std::io::copy(&mut reader, &mut std::io::sink())?;
 
         // Connection should've been left in SYNC mode by now
         Ok(())
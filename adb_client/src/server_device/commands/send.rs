@@ -1,63 +1,69 @@
 use crate::{
-    ADBServerDevice, Result, RustADBError, constants,
-    models::{AdbRequestStatus, AdbServerCommand, SyncCommand},
+    ADBServerDevice, Result, RustADBError, constants, dir_transfer,
+    models::{
+        AdbRequestStatus, AdbServerCommand, HostFeatures, PushOptions, SyncCommand,
+        SyncCompression, SyncDataWriter,
+    },
 };
 use std::{
     convert::TryInto,
     io::{BufReader, BufWriter, Read, Write},
     str::{self, FromStr},
-    time::SystemTime,
 };
 
-/// Internal structure wrapping a [std::io::Write] and hiding underlying protocol logic.
-struct ADBSendCommandWriter<W: Write> {
-    inner: W,
-}
-
-impl<W: Write> ADBSendCommandWriter<W> {
-    pub fn new(inner: W) -> Self {
-        ADBSendCommandWriter { inner }
-    }
-}
-
-impl<W: Write> Write for ADBSendCommandWriter<W> {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let chunk_len = buf.len() as u32;
-
-        // 8 = "DATA".len() + sizeof(u32)
-        let mut buffer = Vec::with_capacity(8 + buf.len());
-        buffer.extend_from_slice(b"DATA");
-        buffer.extend_from_slice(&chunk_len.to_le_bytes());
-        buffer.extend_from_slice(buf);
-
-        self.inner.write_all(&buffer)?;
-
-        Ok(buf.len())
+impl ADBServerDevice {
+    /// Send stream to path on the device, with default [`PushOptions`].
+    pub fn push<R: Read, A: AsRef<str>>(&mut self, stream: R, path: A) -> Result<()> {
+        self.push_with_options(stream, path, PushOptions::default())
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        self.inner.flush()
-    }
-}
+    /// Send stream to path on the device, creating it with mode and modification time from `options`.
+    pub fn push_with_options<R: Read, A: AsRef<str>>(
+        &mut self,
+        stream: R,
+        path: A,
+        options: PushOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let (mode, mtime) = (options.mode, options.mtime_or_now());
+        log::info!("Sending data to {path}");
+
+        // Older devices fail to push files into missing directories
+        if !self
+            .host_features()?
+            .contains(&HostFeatures::FixedPushMkdir)
+        {
+            dir_transfer::make_remote_parent_dir(self, path)?;
+        }
 
-impl ADBServerDevice {
-    /// Send stream to path on the device.
-    pub fn push<R: Read, A: AsRef<str>>(&mut self, stream: R, path: A) -> Result<()> {
-        log::info!("Sending data to {}", path.as_ref());
+        let compression = self.sync_compression()?;
         self.set_serial_transport()?;
 
         // Set device in SYNC mode
         self.transport.send_adb_request(AdbServerCommand::Sync)?;
 
-        // Send a send command
-        self.transport.send_sync_request(SyncCommand::Send)?;
+        // Send a send command, using sync protocol v2 when device supports it
+        match compression {
+            Some(_) => self.transport.send_sync_request(SyncCommand::Send2)?,
+            None => self.transport.send_sync_request(SyncCommand::Send)?,
+        }
 
-        self.handle_send_command(stream, path)
+        self.handle_send_command(stream, path, mode, mtime, compression)
     }
 
-    fn handle_send_command<R: Read, S: AsRef<str>>(&mut self, input: R, to: S) -> Result<()> {
-        // Append the permission flags to the filename
-        let to = to.as_ref().to_string() + ",0777";
+    fn handle_send_command<R: Read>(
+        &mut self,
+        input: R,
+        to: &str,
+        mode: u32,
+        mtime: u32,
+        compression: Option<SyncCompression>,
+    ) -> Result<()> {
+        // Append the permission flags to the filename, sync protocol v2 sends them in a separate request
+        let to = match compression {
+            Some(_) => to.to_string(),
+            None => format!("{to},{mode}"),
+        };
 
         let mut raw_connection = self.transport.get_raw_connection()?;
 
@@ -68,23 +74,29 @@ impl ADBServerDevice {
         buffer.extend_from_slice(to_as_bytes);
         raw_connection.write_all(&buffer)?;
 
-        let writer = ADBSendCommandWriter::new(raw_connection);
+        if let Some(compression) = compression {
+            let mut buffer = Vec::with_capacity(12);
+            buffer.extend_from_slice(SyncCommand::Send2.to_string().as_bytes());
+            buffer.extend_from_slice(&mode.to_le_bytes());
+            buffer.extend_from_slice(&compression.flags().to_le_bytes());
+            raw_connection.write_all(&buffer)?;
+        }
 
-        std::io::copy(
-            &mut BufReader::with_capacity(constants::BUFFER_SIZE, input),
-            &mut BufWriter::with_capacity(constants::BUFFER_SIZE, writer),
-        )?;
+        let writer =
+            BufWriter::with_capacity(constants::BUFFER_SIZE, SyncDataWriter::new(raw_connection));
+        compression
+            .unwrap_or(SyncCompression::None)
+            .compress(
+                &mut BufReader::with_capacity(constants::BUFFER_SIZE, input),
+                writer,
+            )?
+            .flush()?;
 
         // Copy is finished, we can now notify as finished
         // Have to send DONE + file mtime
-        let last_modified = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(n) => n,
-            Err(_) => panic!("SystemTime before UNIX EPOCH!"),
-        };
-
         let mut done_buffer = Vec::with_capacity(8);
         done_buffer.extend_from_slice(b"DONE");
-        done_buffer.extend_from_slice(&last_modified.as_secs().to_le_bytes());
+        done_buffer.extend_from_slice(&mtime.to_le_bytes());
         raw_connection.write_all(&done_buffer)?;
 
         // We expect 'OKAY' response from this
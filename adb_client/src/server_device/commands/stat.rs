@@ -3,8 +3,8 @@ use std::io::{Read, Write};
 use byteorder::{ByteOrder, LittleEndian};
 
 use crate::{
-    ADBServerDevice, Result, RustADBError,
-    models::{AdbServerCommand, AdbStatResponse, SyncCommand},
+    ADBServerDevice, Result,
+    models::{AdbServerCommand, AdbStatResponse, STAT_RESPONSE_SIZE, SyncCommand},
 };
 
 impl ADBServerDevice {
@@ -19,21 +19,18 @@ impl ADBServerDevice {
             .write_all(path.as_ref().to_string().as_bytes())?;
 
         // Reads returned status code from ADB server
-        let mut response = [0_u8; 4];
+        let mut response = [0_u8; STAT_RESPONSE_SIZE];
         self.transport
             .get_raw_connection()?
-            .read_exact(&mut response)?;
-        match std::str::from_utf8(response.as_ref())? {
-            "STAT" => {
-                let mut data = [0_u8; 12];
-                self.transport.get_raw_connection()?.read_exact(&mut data)?;
-
-                Ok(data.into())
-            }
-            x => Err(RustADBError::UnknownResponseType(format!(
-                "Unknown response {x}"
-            ))),
+            .read_exact(&mut response[..4])?;
+        // Any other response is reported without waiting for more data
+        if response.starts_with(b"STAT") {
+            self.transport
+                .get_raw_connection()?
+                .read_exact(&mut response[4..])?;
         }
+
+        AdbStatResponse::decode(&response)
     }
 
     /// Stat file given as path on the device.
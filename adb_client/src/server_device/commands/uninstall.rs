@@ -1,26 +1,35 @@
 use std::io::Read;
 
-use crate::{Result, models::AdbServerCommand, server_device::ADBServerDevice};
+use crate::{
+    Result, UninstallOptions, models::AdbServerCommand, server_device::ADBServerDevice,
+    utils::check_package_manager_response,
+};
 
 impl ADBServerDevice {
     /// Uninstall a package from device
     pub fn uninstall(&mut self, package_name: &str) -> Result<()> {
+        self.uninstall_with_options(package_name, &UninstallOptions::default())
+    }
+
+    /// Uninstall a package from device with given `options`, e.g. keeping its data.
+    pub fn uninstall_with_options(
+        &mut self,
+        package_name: &str,
+        options: &UninstallOptions,
+    ) -> Result<()> {
         self.set_serial_transport()?;
 
         self.transport
-            .send_adb_request(AdbServerCommand::Uninstall(package_name.to_string()))?;
+            .send_adb_request(AdbServerCommand::Uninstall(
+                package_name.to_string(),
+                options.clone(),
+            ))?;
 
         let mut data = [0; 1024];
         let read_amount = self.transport.get_raw_connection()?.read(&mut data)?;
 
-        match &data[0..read_amount] {
-            b"Success\n" => {
-                log::info!("Package {package_name} successfully uninstalled");
-                Ok(())
-            }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
-        }
+        check_package_manager_response(&data[0..read_amount])?;
+        log::info!("Package {package_name} successfully uninstalled");
+        Ok(())
     }
 }
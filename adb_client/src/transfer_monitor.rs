@@ -0,0 +1,261 @@
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{Result, RustADBError};
+
+/// Callback receiving progress of a transfer.
+type ProgressCallback<'a> = Box<dyn FnMut(&TransferProgress) + 'a>;
+
+/// Progress of a transfer, as reported to [`TransferMonitor`] callbacks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransferProgress {
+    /// Amount of bytes transferred so far.
+    pub bytes_done: u64,
+    /// Total amount of bytes to transfer, if known.
+    pub total: Option<u64>,
+    /// Time elapsed since transfer started.
+    pub elapsed: Duration,
+}
+
+impl TransferProgress {
+    /// Average throughput since transfer started, in bytes per second.
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.is_zero() {
+            true => 0.0,
+            false => self.bytes_done as f64 / self.elapsed.as_secs_f64(),
+        }
+    }
+}
+
+/// Token cancelling transfers it is given to. It can be cloned and sent to other threads.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Instantiate a new token, not cancelled yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel transfers using this token. They fail with [`RustADBError::TransferCancelled`] at their next read or write.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether this token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Follows progress of `push`, `pull` and `install` transfers, and cancels them on request.
+///
+/// ```rust
+/// use adb_client::{CancellationToken, TransferMonitor};
+///
+/// let token = CancellationToken::new();
+/// let mut monitor = TransferMonitor::new()
+///     .on_progress(|progress| println!("{} bytes sent", progress.bytes_done))
+///     .with_cancellation(token.clone());
+/// ```
+#[derive(Default)]
+pub struct TransferMonitor<'a> {
+    callback: Option<ProgressCallback<'a>>,
+    cancellation: CancellationToken,
+}
+
+impl std::fmt::Debug for TransferMonitor<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransferMonitor")
+            .field("cancellation", &self.cancellation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> TransferMonitor<'a> {
+    /// Instantiate a monitor ignoring progress, which can only be cancelled through [`TransferMonitor::cancellation_token`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `callback` each time some data has been transferred.
+    pub fn on_progress<F: FnMut(&TransferProgress) + 'a>(mut self, callback: F) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Cancel transfer once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Token cancelling transfers monitored by this monitor.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Wrap `inner`, reporting bytes read from it out of `total`.
+    pub(crate) fn reader<'m, R: Read>(
+        &'m mut self,
+        inner: R,
+        total: Option<u64>,
+    ) -> MonitoredStream<'m, 'a, R> {
+        MonitoredStream::new(self, inner, total)
+    }
+
+    /// Wrap `inner`, reporting bytes written into it out of `total`.
+    pub(crate) fn writer<'m, W: Write>(
+        &'m mut self,
+        inner: W,
+        total: Option<u64>,
+    ) -> MonitoredStream<'m, 'a, W> {
+        MonitoredStream::new(self, inner, total)
+    }
+
+    /// Outcome of a monitored transfer, reporting cancellation instead of the I/O error it caused.
+    pub(crate) fn finish<T, E: Into<RustADBError>>(
+        &self,
+        result: std::result::Result<T, E>,
+    ) -> Result<T> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(_) if self.cancellation.is_cancelled() => Err(RustADBError::TransferCancelled),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Stream counting bytes going through it for a [`TransferMonitor`], and failing once transfer is cancelled.
+pub(crate) struct MonitoredStream<'m, 'a, S> {
+    monitor: &'m mut TransferMonitor<'a>,
+    inner: S,
+    progress: TransferProgress,
+    start: Instant,
+}
+
+impl<'m, 'a, S> MonitoredStream<'m, 'a, S> {
+    fn new(monitor: &'m mut TransferMonitor<'a>, inner: S, total: Option<u64>) -> Self {
+        Self {
+            monitor,
+            inner,
+            progress: TransferProgress {
+                bytes_done: 0,
+                total,
+                elapsed: Duration::ZERO,
+            },
+            start: Instant::now(),
+        }
+    }
+
+    fn check_cancelled(&self) -> std::io::Result<()> {
+        match self.monitor.cancellation.is_cancelled() {
+            true => Err(std::io::Error::other("transfer cancelled")),
+            false => Ok(()),
+        }
+    }
+
+    fn advance(&mut self, amount: usize) {
+        if amount == 0 {
+            return;
+        }
+
+        self.progress.bytes_done += amount as u64;
+        self.progress.elapsed = self.start.elapsed();
+        if let Some(callback) = self.monitor.callback.as_mut() {
+            callback(&self.progress);
+        }
+    }
+}
+
+impl<R: Read> Read for MonitoredStream<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.check_cancelled()?;
+        let amount = self.inner.read(buf)?;
+        self.advance(amount);
+        Ok(amount)
+    }
+}
+
+impl<W: Write> Write for MonitoredStream<'_, '_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.check_cancelled()?;
+        let amount = self.inner.write(buf)?;
+        self.advance(amount);
+        Ok(amount)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn test_transfer_monitor() {
+    use crate::{ADBDeviceExt, FakeADBDevice, PushOptions};
+
+    let fake_device = FakeADBDevice::new();
+    let mut device = crate::fake_device::connected_tcp_device(&fake_device);
+
+    let contents = vec![42; 300_000];
+    let mut reports = Vec::new();
+    let mut monitor = TransferMonitor::new().on_progress(|progress| reports.push(*progress));
+    device
+        .push_with_monitor(
+            &mut contents.as_slice(),
+            &"/sdcard/data.bin",
+            PushOptions::default(),
+            &mut monitor,
+        )
+        .expect("cannot push file");
+    let mut pulled = Vec::new();
+    device
+        .pull_with_monitor(&"/sdcard/data.bin", &mut pulled, &mut monitor)
+        .expect("cannot pull file");
+    drop(monitor);
+    assert_eq!(pulled, contents);
+
+    // Pushed stream has no known size, pulled file has one
+    let last_push = reports.iter().rev().find(|p| p.total.is_none());
+    assert_eq!(last_push.map(|p| p.bytes_done), Some(300_000));
+    let last_pull = reports.last().expect("no progress reported");
+    assert_eq!(
+        (last_pull.bytes_done, last_pull.total),
+        (300_000, Some(300_000))
+    );
+
+    let token = CancellationToken::new();
+    let mut monitor = TransferMonitor::new().with_cancellation(token.clone());
+    token.cancel();
+    assert!(matches!(
+        device.pull_with_monitor(&"/sdcard/data.bin", &mut Vec::new(), &mut monitor),
+        Err(RustADBError::TransferCancelled)
+    ));
+
+    // Transfers cancelled midway close their stream, leaving device usable
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    let mut monitor = TransferMonitor::new()
+        .on_progress(move |_| canceller.cancel())
+        .with_cancellation(token);
+    assert!(matches!(
+        device.pull_with_monitor(&"/sdcard/data.bin", &mut Vec::new(), &mut monitor),
+        Err(RustADBError::TransferCancelled)
+    ));
+    assert!(matches!(
+        device.push_with_monitor(
+            &mut contents.as_slice(),
+            &"/sdcard/other.bin",
+            PushOptions::default(),
+            &mut monitor,
+        ),
+        Err(RustADBError::TransferCancelled)
+    ));
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/data.bin", &mut pulled)
+        .expect("cannot pull file after cancelled transfers");
+    assert_eq!(pulled, contents);
+}
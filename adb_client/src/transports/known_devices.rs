@@ -0,0 +1,170 @@
+use std::fs::{read_to_string, write};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use rustls::pki_types::CertificateDer;
+use sha2::{Digest, Sha256};
+
+use crate::{Result, RustADBError};
+
+/// Name of store file created next to private key by default.
+const KNOWN_DEVICES_FILE_NAME: &str = "adb_known_devices";
+
+/// Store of TLS certificates of devices, each one being pinned the first time it is seen (trust on first use).
+///
+/// Store is a text file holding one device per line: its identifier (its adb GUID, or its address), followed by the
+/// SHA-256 fingerprint of its certificate. Connections to a device whose certificate changed fail with
+/// [`RustADBError::DeviceCertificateChanged`], until new certificate gets approved with [`KnownDevices::approve`].
+#[derive(Clone, Debug)]
+pub struct KnownDevices {
+    path: PathBuf,
+}
+
+impl KnownDevices {
+    /// Instantiate a new [`KnownDevices`], stored at `path`. File is created when first device is pinned.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Store located in same directory as `private_key_path`, used by default by TCP transports.
+    pub fn next_to_private_key(private_key_path: &Path) -> Self {
+        Self::new(private_key_path.with_file_name(KNOWN_DEVICES_FILE_NAME))
+    }
+
+    /// Fingerprint of certificate pinned for device `identifier`, if any.
+    pub fn fingerprint(&self, identifier: &str) -> Result<Option<String>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .find(|(known_identifier, _)| known_identifier == identifier)
+            .map(|(_, fingerprint)| fingerprint))
+    }
+
+    /// Pin certificate with given `fingerprint` for device `identifier`, replacing the previous one if any.
+    pub fn approve(&self, identifier: &str, fingerprint: &str) -> Result<()> {
+        let mut entries = self.entries()?;
+        entries.retain(|(known_identifier, _)| known_identifier != identifier);
+        entries.push((identifier.to_string(), fingerprint.to_string()));
+        self.save(&entries)
+    }
+
+    /// Remove certificate pinned for device `identifier`. Next certificate seen will be trusted.
+    pub fn forget(&self, identifier: &str) -> Result<()> {
+        let mut entries = self.entries()?;
+        entries.retain(|(known_identifier, _)| known_identifier != identifier);
+        self.save(&entries)
+    }
+
+    /// Check `certificates` presented by device `identifier` against pinned one, pinning it if device is new.
+    pub(crate) fn verify(
+        &self,
+        identifier: &str,
+        certificates: Option<&[CertificateDer<'_>]>,
+    ) -> Result<()> {
+        let Some(certificate) = certificates.and_then(|certificates| certificates.first()) else {
+            return Err(RustADBError::UpgradeError(
+                "device did not present any certificate".into(),
+            ));
+        };
+        let fingerprint = certificate_fingerprint(certificate);
+
+        match self.fingerprint(identifier)? {
+            Some(known_fingerprint) if known_fingerprint == fingerprint => Ok(()),
+            Some(_) => Err(RustADBError::DeviceCertificateChanged(
+                identifier.to_string(),
+                fingerprint,
+            )),
+            None => {
+                log::info!("pinning certificate {fingerprint} of new device {identifier}");
+                self.approve(identifier, &fingerprint)
+            }
+        }
+    }
+
+    fn entries(&self) -> Result<Vec<(String, String)>> {
+        let content = match read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(content
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(identifier, fingerprint)| {
+                (identifier.to_string(), fingerprint.trim().to_string())
+            })
+            .collect())
+    }
+
+    fn save(&self, entries: &[(String, String)]) -> Result<()> {
+        let content: String = entries
+            .iter()
+            .map(|(identifier, fingerprint)| format!("{identifier} {fingerprint}\n"))
+            .collect();
+        Ok(write(&self.path, content)?)
+    }
+}
+
+/// SHA-256 fingerprint of a DER encoded certificate, as hexadecimal.
+fn certificate_fingerprint(certificate: &[u8]) -> String {
+    Sha256::digest(certificate)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[test]
+fn test_known_devices_pinning() {
+    use crate::{ADBTcpDevice, FakeADBDevice, TcpTransport};
+
+    let private_key_path = crate::fake_device::write_test_private_key("known_devices");
+    let known_devices = KnownDevices::next_to_private_key(&private_key_path);
+
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.set_tls(true);
+    let address = fake_device.listen().expect("cannot listen");
+    let identifier = address.to_string();
+
+    // First connection pins certificate, next ones check it
+    ADBTcpDevice::new_with_custom_private_key(address, private_key_path.clone())
+        .expect("cannot connect to new device");
+    let fingerprint = known_devices
+        .fingerprint(&identifier)
+        .expect("cannot read known devices")
+        .expect("certificate not pinned");
+    ADBTcpDevice::new_with_custom_private_key(address, private_key_path.clone())
+        .expect("cannot connect to known device");
+
+    fake_device
+        .renew_tls_certificate()
+        .expect("cannot renew certificate");
+    let new_fingerprint =
+        match ADBTcpDevice::new_with_custom_private_key(address, private_key_path.clone()) {
+            Err(RustADBError::DeviceCertificateChanged(changed, new_fingerprint)) => {
+                assert_eq!(changed, identifier);
+                new_fingerprint
+            }
+            other => panic!("changed certificate not detected: {other:?}"),
+        };
+    assert_ne!(new_fingerprint, fingerprint);
+
+    known_devices
+        .approve(&identifier, &new_fingerprint)
+        .expect("cannot approve certificate");
+    ADBTcpDevice::new_with_custom_private_key(address, private_key_path.clone())
+        .expect("cannot connect to approved device");
+
+    // Devices can be pinned under another identifier, such as their GUID
+    let transport = TcpTransport::new_with_custom_private_key(address, private_key_path.clone())
+        .expect("cannot create transport")
+        .with_device_identifier("adb-FAKE0001-fake");
+    ADBTcpDevice::new_from_transport(transport, Some(private_key_path))
+        .expect("cannot connect to device");
+    assert_eq!(
+        known_devices
+            .fingerprint("adb-FAKE0001-fake")
+            .expect("cannot read known devices"),
+        Some(new_fingerprint)
+    );
+}
@@ -1,11 +1,19 @@
+mod known_devices;
+mod recording_transport;
+mod replay_transport;
+mod server_connection;
 mod tcp_emulator_transport;
 mod tcp_server_transport;
 mod tcp_transport;
 mod traits;
 mod usb_transport;
 
+pub use known_devices::KnownDevices;
+pub use recording_transport::RecordingTransport;
+pub use replay_transport::ReplayTransport;
 pub use tcp_emulator_transport::TCPEmulatorTransport;
 pub use tcp_server_transport::TCPServerTransport;
+pub(crate) use tcp_transport::tls_client_config;
 pub use tcp_transport::TcpTransport;
 pub use traits::{ADBMessageTransport, ADBTransport};
 pub use usb_transport::USBTransport;
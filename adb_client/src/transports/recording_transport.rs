@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::device::ADBTransportMessage;
+use crate::{
+    ADBMessageTransport, ADBTransport, Result, SessionDirection, SessionEvent, SessionRecorder,
+};
+
+/// An [`ADBMessageTransport`] recording every message going through the transport it wraps.
+///
+/// Recorded sessions can then be played back with a [`crate::ReplayTransport`].
+#[derive(Clone, Debug)]
+pub struct RecordingTransport<T: ADBMessageTransport> {
+    inner: T,
+    recorder: SessionRecorder,
+}
+
+impl<T: ADBMessageTransport> RecordingTransport<T> {
+    /// Instantiate a new [`RecordingTransport`], recording messages of `inner` with `recorder`.
+    pub fn new(inner: T, recorder: SessionRecorder) -> Self {
+        Self { inner, recorder }
+    }
+
+    /// Get back wrapped transport.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ADBMessageTransport> ADBTransport for RecordingTransport<T> {
+    fn connect(&mut self) -> Result<()> {
+        self.inner.connect()?;
+        self.recorder
+            .record(SessionDirection::Sent, SessionEvent::Connected);
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<()> {
+        self.inner.disconnect()
+    }
+}
+
+impl<T: ADBMessageTransport> ADBMessageTransport for RecordingTransport<T> {
+    fn read_message_with_timeout(&mut self, read_timeout: Duration) -> Result<ADBTransportMessage> {
+        let message = self.inner.read_message_with_timeout(read_timeout)?;
+        self.recorder
+            .record_message(SessionDirection::Received, &message);
+        Ok(message)
+    }
+
+    fn write_message_with_timeout(
+        &mut self,
+        message: ADBTransportMessage,
+        write_timeout: Duration,
+    ) -> Result<()> {
+        self.recorder
+            .record_message(SessionDirection::Sent, &message);
+        self.inner
+            .write_message_with_timeout(message, write_timeout)
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::device::{ADBTransportMessage, MessageCommand};
+use crate::{
+    ADBMessageTransport, ADBTransport, Result, RustADBError, Session, SessionDirection,
+    SessionRecord,
+};
+
+#[derive(Debug)]
+struct ReplayState {
+    /// Recorded messages, handshake excluded
+    records: Vec<SessionRecord>,
+    /// Index from which next message sent by host is expected
+    next_sent: usize,
+    /// Index from which next message received by host is looked for
+    next_received: usize,
+    /// Stream ids used by host while recording, mapped to the ones used while replaying
+    host_ids: HashMap<u32, u32>,
+}
+
+impl ReplayState {
+    /// Index of first message going in `direction`, starting at `from`.
+    fn find(&self, direction: SessionDirection, from: usize) -> Option<usize> {
+        (from..self.records.len()).find(|&i| self.records[i].direction == direction)
+    }
+
+    /// Message recorded at `index`.
+    fn message(&self, index: usize) -> Result<ADBTransportMessage> {
+        // Only records holding messages are kept
+        self.records[index]
+            .message()
+            .unwrap_or(Err(RustADBError::ConversionError))
+    }
+
+    /// Whether host sent every message recorded before `index`.
+    fn sent_before(&self, index: usize) -> bool {
+        self.find(SessionDirection::Sent, self.next_sent)
+            .map_or(true, |sent| sent > index)
+    }
+}
+
+/// An [`ADBMessageTransport`] playing back the device side of a recorded [`Session`], to turn a recorded bug into a regression test.
+///
+/// Messages sent by host are checked against recorded ones, and device messages are delivered once every message recorded
+/// before them has been sent. Recorded connection handshake is skipped, as transport behaves as an already connected one:
+/// it can be used with [`crate::ADBMessageDevice`] or [`crate::ADBMultiplexer`].
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    state: Arc<Mutex<ReplayState>>,
+    progress: Arc<Condvar>,
+}
+
+impl ReplayTransport {
+    /// Instantiate a new [`ReplayTransport`], playing back messages of `session`.
+    pub fn new(session: &Session) -> Result<Self> {
+        let mut records = Vec::new();
+        for record in session.records() {
+            let Some(message) = record.message() else {
+                continue;
+            };
+            // Everything before first opened stream belongs to connection handshake
+            let is_open = message?.header().command() == MessageCommand::Open;
+            if !records.is_empty() || (is_open && record.direction == SessionDirection::Sent) {
+                records.push(record.clone());
+            }
+        }
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(ReplayState {
+                records,
+                next_sent: 0,
+                next_received: 0,
+                host_ids: HashMap::new(),
+            })),
+            progress: Arc::new(Condvar::new()),
+        })
+    }
+
+    /// Whether every recorded message has been sent and received.
+    pub fn is_finished(&self) -> Result<bool> {
+        let state = self.state.lock()?;
+        Ok(state
+            .find(SessionDirection::Sent, state.next_sent)
+            .is_none()
+            && state
+                .find(SessionDirection::Received, state.next_received)
+                .is_none())
+    }
+}
+
+impl ADBTransport for ReplayTransport {
+    fn connect(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ADBMessageTransport for ReplayTransport {
+    fn read_message_with_timeout(&mut self, read_timeout: Duration) -> Result<ADBTransportMessage> {
+        let state = self.state.lock()?;
+        let Some(index) = state.find(SessionDirection::Received, state.next_received) else {
+            return Err(std::io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "end of recorded session",
+            )
+            .into());
+        };
+
+        let (mut state, _) = self
+            .progress
+            .wait_timeout_while(state, read_timeout, |state| !state.sent_before(index))?;
+        if !state.sent_before(index) {
+            return Err(std::io::Error::new(
+                ErrorKind::TimedOut,
+                "host did not send messages expected by recorded device",
+            )
+            .into());
+        }
+        state.next_received = index + 1;
+
+        let message = state.message(index)?;
+        let header = message.header();
+        match state.host_ids.get(&header.arg1()) {
+            Some(&host_id) => Ok(ADBTransportMessage::new(
+                header.command(),
+                header.arg0(),
+                host_id,
+                message.payload(),
+            )),
+            None => Ok(message),
+        }
+    }
+
+    fn write_message_with_timeout(
+        &mut self,
+        message: ADBTransportMessage,
+        _write_timeout: Duration,
+    ) -> Result<()> {
+        let mut state = self.state.lock()?;
+        let command = message.header().command();
+        let Some(index) = state.find(SessionDirection::Sent, state.next_sent) else {
+            return Err(RustADBError::ReplayMismatch(format!(
+                "{command} sent after end of recorded session"
+            )));
+        };
+
+        let expected = state.message(index)?;
+        let expected_command = expected.header().command();
+        // Only services are compared, as data sent may legitimately vary (e.g. timestamps, authentication)
+        if expected_command != command
+            || (command == MessageCommand::Open && expected.payload() != message.payload())
+        {
+            return Err(RustADBError::ReplayMismatch(format!(
+                "expected {expected_command} {:?}, got {command} {:?}",
+                String::from_utf8_lossy(expected.payload()),
+                String::from_utf8_lossy(message.payload())
+            )));
+        }
+
+        if command == MessageCommand::Open {
+            let recorded_id = expected.header().arg0();
+            state.host_ids.insert(recorded_id, message.header().arg0());
+        }
+        state.next_sent = index + 1;
+        self.progress.notify_all();
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_replay_transport() {
+    use crate::{ADBMessageDevice, FakeADBDevice, RecordingTransport, SessionRecorder};
+
+    let path = std::env::temp_dir().join(format!(
+        "adb_client_replay_transport_{}",
+        std::process::id()
+    ));
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop ro.product.model", b"Pixel 9\n");
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+
+    let recorder = SessionRecorder::create(&path).expect("cannot create session file");
+    let mut device =
+        ADBMessageDevice::new(RecordingTransport::new(fake_device.transport(), recorder));
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut output)
+        .expect("cannot run shell command");
+    let mut pulled = Vec::new();
+    device
+        .pull("/sdcard/hello.txt", &mut pulled)
+        .expect("cannot pull file");
+
+    let session = Session::load(&path).expect("cannot load session");
+    let transcript = session.to_string();
+    assert!(transcript.contains("OPEN(local="));
+    assert!(transcript.contains("\"shell:getprop ro.product.model\\x00\""));
+    assert!(transcript.contains("sync RECV, path of 17 bytes follows"));
+    assert!(transcript.contains("sync DATA 5 bytes"));
+
+    // Recorded device answers as the real one did
+    let replay = ReplayTransport::new(&session).expect("cannot replay session");
+    let mut device = ADBMessageDevice::new(replay.clone());
+    let mut replayed_output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut replayed_output)
+        .expect("cannot replay shell command");
+    let mut replayed_pull = Vec::new();
+    device
+        .pull("/sdcard/hello.txt", &mut replayed_pull)
+        .expect("cannot replay pull");
+    assert_eq!(replayed_output, output);
+    assert_eq!(replayed_pull, pulled);
+    assert!(replay.is_finished().expect("cannot get replay state"));
+
+    // Host behaving differently is detected
+    let mut device =
+        ADBMessageDevice::new(ReplayTransport::new(&session).expect("cannot replay session"));
+    assert!(matches!(
+        device.shell_command(&["ls"], &mut Vec::new()),
+        Err(RustADBError::ReplayMismatch(_))
+    ));
+}
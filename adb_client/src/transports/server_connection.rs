@@ -0,0 +1,239 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{Result, Session, SessionDirection, SessionEvent, SessionRecorder};
+
+#[derive(Debug)]
+struct ServerReplayState {
+    session: Session,
+    /// Index of next record to play back
+    position: usize,
+    /// Recorded bytes not read yet by host
+    pending: Vec<u8>,
+}
+
+/// Server side of a recorded session, played back connection after connection.
+#[derive(Clone, Debug)]
+pub(crate) struct ServerReplay {
+    state: Arc<Mutex<ServerReplayState>>,
+}
+
+impl ServerReplay {
+    pub(crate) fn new(session: Session) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ServerReplayState {
+                session,
+                position: 0,
+                pending: Vec::new(),
+            })),
+        }
+    }
+
+    /// Move to next recorded connection.
+    fn connect(&self) -> Result<()> {
+        let mut state = self.state.lock()?;
+        let records = state.session.records();
+        let Some(index) =
+            (state.position..records.len()).find(|&i| records[i].event == SessionEvent::Connected)
+        else {
+            return Err(std::io::Error::new(
+                ErrorKind::ConnectionRefused,
+                "no more connection in recorded session",
+            )
+            .into());
+        };
+
+        state.position = index + 1;
+        state.pending.clear();
+        Ok(())
+    }
+
+    /// Play back bytes received by host on current connection. Bytes sent are skipped, as they may legitimately vary.
+    fn read(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| std::io::Error::other("error while locking data"))?;
+
+        while state.pending.is_empty() {
+            let Some(record) = state.session.records().get(state.position) else {
+                // End of session, as if server closed connection
+                return Ok(0);
+            };
+            let pending = match (&record.event, record.direction) {
+                (SessionEvent::Connected, _) => return Ok(0),
+                (SessionEvent::Bytes(bytes), SessionDirection::Received) => bytes.clone(),
+                _ => Vec::new(),
+            };
+            state.pending = pending;
+            state.position += 1;
+        }
+
+        let size = buf.len().min(state.pending.len());
+        buf[..size].copy_from_slice(&state.pending[..size]);
+        state.pending.drain(..size);
+
+        Ok(size)
+    }
+}
+
+/// Connection of a [`crate::TCPServerTransport`] to its server, which may be recorded or played back.
+#[derive(Debug)]
+pub(crate) enum ServerConnection {
+    Tcp(TcpStream),
+    Recording {
+        stream: TcpStream,
+        recorder: SessionRecorder,
+    },
+    Replay(ServerReplay),
+}
+
+impl ServerConnection {
+    pub(crate) fn new_recording(stream: TcpStream, recorder: SessionRecorder) -> Self {
+        recorder.record(SessionDirection::Sent, SessionEvent::Connected);
+        Self::Recording { stream, recorder }
+    }
+
+    pub(crate) fn new_replay(replay: ServerReplay) -> Result<Self> {
+        replay.connect()?;
+        Ok(Self::Replay(replay))
+    }
+
+    pub(crate) fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(match self {
+            Self::Tcp(stream) => Self::Tcp(stream.try_clone()?),
+            Self::Recording { stream, recorder } => Self::Recording {
+                stream: stream.try_clone()?,
+                recorder: recorder.clone(),
+            },
+            Self::Replay(replay) => Self::Replay(replay.clone()),
+        })
+    }
+
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(stream) | Self::Recording { stream, .. } => stream.set_read_timeout(timeout),
+            Self::Replay(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn shutdown(&self, how: Shutdown) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(stream) | Self::Recording { stream, .. } => stream.shutdown(how),
+            Self::Replay(_) => Ok(()),
+        }
+    }
+}
+
+impl Read for &ServerConnection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ServerConnection::Tcp(stream) => (&*stream).read(buf),
+            ServerConnection::Recording { stream, recorder } => {
+                let size = (&*stream).read(buf)?;
+                if size > 0 {
+                    let bytes = buf[..size].to_vec();
+                    recorder.record(SessionDirection::Received, SessionEvent::Bytes(bytes));
+                }
+                Ok(size)
+            }
+            ServerConnection::Replay(replay) => replay.read(buf),
+        }
+    }
+}
+
+impl Write for &ServerConnection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ServerConnection::Tcp(stream) => (&*stream).write(buf),
+            ServerConnection::Recording { stream, recorder } => {
+                let size = (&*stream).write(buf)?;
+                let bytes = buf[..size].to_vec();
+                recorder.record(SessionDirection::Sent, SessionEvent::Bytes(bytes));
+                Ok(size)
+            }
+            ServerConnection::Replay(_) => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ServerConnection::Tcp(stream) | ServerConnection::Recording { stream, .. } => {
+                (&*stream).flush()
+            }
+            ServerConnection::Replay(_) => Ok(()),
+        }
+    }
+}
+
+impl Read for ServerConnection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for ServerConnection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+#[test]
+fn test_server_session_replay() {
+    use crate::{ADBDeviceExt, ADBServerDevice, FakeADBDevice, FakeADBServer, TCPServerTransport};
+
+    let path = std::env::temp_dir().join(format!(
+        "adb_client_server_session_replay_{}",
+        std::process::id()
+    ));
+    let mut fake_device = FakeADBDevice::new();
+    fake_device.add_shell_response("getprop ro.product.model", b"Pixel 9\n");
+    fake_device
+        .add_file("/sdcard/hello.txt", b"hello")
+        .expect("cannot add file");
+    let fake_server = FakeADBServer::new();
+    fake_server
+        .add_device("emulator-5554", &fake_device)
+        .expect("cannot add device");
+    let address = fake_server.listen().expect("cannot listen");
+
+    let recorder = SessionRecorder::create(&path).expect("cannot create session file");
+    let transport = TCPServerTransport::new(address).with_recorder(recorder);
+    let mut device = ADBServerDevice::new_from_transport(Some("emulator-5554".into()), transport);
+    let mut output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut output)
+        .expect("cannot run shell command");
+    let mut pulled = Vec::new();
+    device
+        .pull(&"/sdcard/hello.txt", &mut pulled)
+        .expect("cannot pull file");
+    assert_eq!(output, b"Pixel 9\n");
+    drop(device);
+
+    let session = Session::load(&path).expect("cannot load session");
+    let transcript = session.to_string();
+    assert!(transcript.contains("request \"host:transport:emulator-5554\""));
+    assert!(transcript.contains("sync RCV2 \"/sdcard/hello.txt\""));
+    assert!(transcript.contains("sync RCV2 flags=2"));
+
+    // No server is needed anymore
+    let transport = TCPServerTransport::new_replay(session);
+    let mut device = ADBServerDevice::new_from_transport(Some("emulator-5554".into()), transport);
+    let mut replayed_output = Vec::new();
+    device
+        .shell_command(&["getprop", "ro.product.model"], &mut replayed_output)
+        .expect("cannot replay shell command");
+    let mut replayed_pull = Vec::new();
+    device
+        .pull(&"/sdcard/hello.txt", &mut replayed_pull)
+        .expect("cannot replay pull");
+    assert_eq!(replayed_output, output);
+    assert_eq!(replayed_pull, pulled);
+}
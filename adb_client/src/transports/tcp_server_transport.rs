@@ -1,21 +1,35 @@
 use std::io::{Error, ErrorKind, Read, Write};
-use std::net::{Ipv4Addr, SocketAddrV4, TcpStream};
+use std::net::{Ipv4Addr, Shutdown, SocketAddrV4, TcpStream};
 use std::str::FromStr;
+use std::time::Instant;
 
 use byteorder::{ByteOrder, LittleEndian};
 
-use crate::models::{AdbRequestStatus, SyncCommand};
+use super::server_connection::{ServerConnection, ServerReplay};
+use crate::models::{AdbRequestStatus, SyncCommand, decode_hex_length, encode_hex_framed};
+use crate::utils::time_left;
 use crate::{ADBTransport, models::AdbServerCommand};
-use crate::{Result, RustADBError};
+use crate::{Result, RustADBError, Session, SessionRecorder};
 
 const DEFAULT_SERVER_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
 const DEFAULT_SERVER_PORT: u16 = 5037;
 
+/// How [`TCPServerTransport`] reaches its server.
+#[derive(Clone, Debug)]
+enum ConnectionMode {
+    Tcp,
+    Recording(SessionRecorder),
+    Replay(ServerReplay),
+}
+
 /// Server transport running on top on TCP
 #[derive(Debug)]
 pub struct TCPServerTransport {
     socket_addr: SocketAddrV4,
-    tcp_stream: Option<TcpStream>,
+    mode: ConnectionMode,
+    connection: Option<ServerConnection>,
+    /// Time after which reading from server fails, if any
+    read_deadline: Option<Instant>,
 }
 
 impl Default for TCPServerTransport {
@@ -29,10 +43,28 @@ impl TCPServerTransport {
     pub fn new(socket_addr: SocketAddrV4) -> Self {
         Self {
             socket_addr,
-            tcp_stream: None,
+            mode: ConnectionMode::Tcp,
+            connection: None,
+            read_deadline: None,
+        }
+    }
+
+    /// Instantiate a new instance of [TCPServerTransport] playing back the server side of a recorded [Session].
+    ///
+    /// No connection is made: each connection plays back the next one of `session`, ignoring data sent by host.
+    pub fn new_replay(session: Session) -> Self {
+        Self {
+            mode: ConnectionMode::Replay(ServerReplay::new(session)),
+            ..Self::default()
         }
     }
 
+    /// Record every byte exchanged with server using `recorder`.
+    pub fn with_recorder(mut self, recorder: SessionRecorder) -> Self {
+        self.mode = ConnectionMode::Recording(recorder);
+        self
+    }
+
     /// Instantiate a new instance of [TCPServerTransport] using given address, or default if not specified.
     pub fn new_or_default(socket_addr: Option<SocketAddrV4>) -> Self {
         match socket_addr {
@@ -71,22 +103,30 @@ impl TCPServerTransport {
         }
     }
 
-    pub(crate) fn get_raw_connection(&self) -> Result<&TcpStream> {
-        self.tcp_stream
+    /// Bound reads from server by `deadline`, or wait for data as long as needed if it is `None`.
+    pub(crate) fn set_read_deadline(&mut self, deadline: Option<Instant>) {
+        self.read_deadline = deadline;
+    }
+
+    pub(crate) fn get_raw_connection(&self) -> Result<&ServerConnection> {
+        let connection = self
+            .connection
             .as_ref()
             .ok_or(RustADBError::IOError(Error::new(
                 ErrorKind::NotConnected,
                 "not connected",
-            )))
+            )))?;
+
+        // Next reads get time left before deadline
+        let timeout = self.read_deadline.map(time_left).transpose()?;
+        connection.set_read_timeout(timeout)?;
+
+        Ok(connection)
     }
 
     /// Gets the body length from hexadecimal value
     pub(crate) fn get_hex_body_length(&mut self) -> Result<u32> {
-        let length_buffer = self.read_body_length()?;
-        Ok(u32::from_str_radix(
-            std::str::from_utf8(&length_buffer)?,
-            16,
-        )?)
+        decode_hex_length(self.read_body_length()?)
     }
 
     /// Send the given [SyncCommand] to ADB server, and checks that the request has been taken in consideration.
@@ -104,6 +144,12 @@ impl TCPServerTransport {
         Ok(LittleEndian::read_u32(&length_buffer))
     }
 
+    fn connect_stream(&self) -> Result<TcpStream> {
+        let tcp_stream = TcpStream::connect(self.socket_addr)?;
+        tcp_stream.set_nodelay(true)?;
+        Ok(tcp_stream)
+    }
+
     /// Read 4 bytes representing body length
     fn read_body_length(&self) -> Result<[u8; 4]> {
         let mut length_buffer = [0; 4];
@@ -115,11 +161,9 @@ impl TCPServerTransport {
     /// Send the given [AdbCommand] to ADB server, and checks that the request has been taken in consideration.
     /// If an error occurred, a [RustADBError] is returned with the response error string.
     pub(crate) fn send_adb_request(&mut self, command: AdbServerCommand) -> Result<()> {
-        let adb_command_string = command.to_string();
-        let adb_request = format!("{:04x}{}", adb_command_string.len(), adb_command_string);
+        let adb_request = encode_hex_framed(command.to_string().as_bytes());
 
-        self.get_raw_connection()?
-            .write_all(adb_request.as_bytes())?;
+        self.get_raw_connection()?.write_all(&adb_request)?;
 
         self.read_adb_response()
     }
@@ -154,22 +198,28 @@ impl TCPServerTransport {
 
 impl ADBTransport for TCPServerTransport {
     fn disconnect(&mut self) -> Result<()> {
-        if let Some(conn) = &mut self.tcp_stream {
-            conn.shutdown(std::net::Shutdown::Both)?;
-            log::trace!("Disconnected from {}", conn.peer_addr()?);
+        if let Some(conn) = &mut self.connection {
+            conn.shutdown(Shutdown::Both)?;
+            log::trace!("Disconnected from {}", self.socket_addr);
         }
 
         Ok(())
     }
 
     fn connect(&mut self) -> Result<()> {
-        if let Some(previous) = &self.tcp_stream {
+        if let Some(previous) = &self.connection {
             // Ignoring underlying error, we will recreate a new connection
-            let _ = previous.shutdown(std::net::Shutdown::Both);
+            let _ = previous.shutdown(Shutdown::Both);
         }
-        let tcp_stream = TcpStream::connect(self.socket_addr)?;
-        tcp_stream.set_nodelay(true)?;
-        self.tcp_stream = Some(tcp_stream);
+
+        let connection = match &self.mode {
+            ConnectionMode::Tcp => ServerConnection::Tcp(self.connect_stream()?),
+            ConnectionMode::Recording(recorder) => {
+                ServerConnection::new_recording(self.connect_stream()?, recorder.clone())
+            }
+            ConnectionMode::Replay(replay) => ServerConnection::new_replay(replay.clone())?,
+        };
+        self.connection = Some(connection);
         log::trace!("Successfully connected to {}", self.socket_addr);
 
         Ok(())
@@ -1,11 +1,12 @@
 use rcgen::{CertificateParams, KeyPair, PKCS_RSA_SHA256};
 use rustls::{
-    ClientConfig, ClientConnection, KeyLogFile, SignatureScheme, StreamOwned,
-    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    ClientConfig, ClientConnection, KeyLogFile, SignatureScheme,
+    client::danger::{ServerCertVerified, ServerCertVerifier},
+    crypto::{WebPkiSupportedAlgorithms, verify_tls12_signature, verify_tls13_signature},
     pki_types::{CertificateDer, PrivatePkcs8KeyDer, pem::PemObject},
 };
 
-use super::{ADBMessageTransport, ADBTransport};
+use super::{ADBMessageTransport, ADBTransport, KnownDevices};
 use crate::{
     Result, RustADBError,
     device::{
@@ -14,75 +15,135 @@ use crate::{
 };
 use std::{
     fs::read_to_string,
-    io::{Read, Write},
+    io::{ErrorKind, Read, Write},
     net::{Shutdown, SocketAddr, TcpStream},
-    ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::Duration,
 };
 
+const TLS_READ_BUFFER_SIZE: usize = 16 * 1024;
+
 #[derive(Debug)]
-enum CurrentConnection {
-    Tcp(TcpStream),
-    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+enum ConnectionKind {
+    Tcp,
+    Tls(Box<Mutex<ClientConnection>>),
+}
+
+/// Connection shared between all clones of a [`TcpTransport`].
+///
+/// Reading and writing are guarded by two distinct locks, allowing a thread to wait for incoming messages while another one is writing.
+#[derive(Debug)]
+struct CurrentConnection {
+    socket: TcpStream,
+    kind: ConnectionKind,
+    /// Ciphertext received from socket but not yet handed to TLS layer
+    read_lock: Mutex<Vec<u8>>,
+    write_lock: Mutex<()>,
 }
 
 impl CurrentConnection {
-    fn set_read_timeout(&self, read_timeout: Duration) -> Result<()> {
-        match self {
-            CurrentConnection::Tcp(tcp_stream) => {
-                Ok(tcp_stream.set_read_timeout(Some(read_timeout))?)
-            }
-            CurrentConnection::Tls(stream_owned) => {
-                Ok(stream_owned.sock.set_read_timeout(Some(read_timeout))?)
-            }
+    fn new(socket: TcpStream, kind: ConnectionKind) -> Self {
+        Self {
+            socket,
+            kind,
+            read_lock: Mutex::new(Vec::new()),
+            write_lock: Mutex::new(()),
         }
     }
 
-    fn set_write_timeout(&self, write_timeout: Duration) -> Result<()> {
-        match self {
-            CurrentConnection::Tcp(tcp_stream) => {
-                Ok(tcp_stream.set_write_timeout(Some(write_timeout))?)
-            }
-            CurrentConnection::Tls(stream_owned) => {
-                Ok(stream_owned.sock.set_write_timeout(Some(write_timeout))?)
+    /// Read exactly `buf.len()` bytes. Caller must hold `read_lock`, given as `pending`.
+    fn read_exact(&self, pending: &mut Vec<u8>, mut buf: &mut [u8]) -> std::io::Result<()> {
+        while !buf.is_empty() {
+            let amount_read = self.read(pending, buf)?;
+            if amount_read == 0 {
+                return Err(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "connection closed",
+                ));
             }
+            buf = &mut buf[amount_read..];
         }
+        Ok(())
     }
-}
 
-impl Read for CurrentConnection {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        match self {
-            CurrentConnection::Tcp(tcp_stream) => tcp_stream.read(buf),
-            CurrentConnection::Tls(tls_conn) => tls_conn.read(buf),
+    fn read(&self, pending: &mut Vec<u8>, buf: &mut [u8]) -> std::io::Result<usize> {
+        let conn = match &self.kind {
+            ConnectionKind::Tcp => return (&self.socket).read(buf),
+            ConnectionKind::Tls(conn) => conn,
+        };
+
+        loop {
+            {
+                let mut conn = conn.lock().map_err(|_| std::io::Error::other("poisoned"))?;
+
+                // Serve already decrypted data first
+                match conn.reader().read(buf) {
+                    Ok(amount_read) => return Ok(amount_read),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(e),
+                }
+
+                if !pending.is_empty() {
+                    let consumed = conn.read_tls(&mut pending.as_slice())?;
+                    pending.drain(..consumed);
+                    conn.process_new_packets().map_err(std::io::Error::other)?;
+                    // TLS layer may need to answer (e.g. key updates)
+                    while conn.wants_write() {
+                        conn.write_tls(&mut &self.socket)?;
+                    }
+                    continue;
+                }
+            }
+
+            // Socket is read without holding the TLS lock, so writers are not blocked meanwhile
+            let mut ciphertext = [0; TLS_READ_BUFFER_SIZE];
+            let amount_read = (&self.socket).read(&mut ciphertext)?;
+            if amount_read == 0 {
+                return Ok(0);
+            }
+            pending.extend_from_slice(&ciphertext[..amount_read]);
         }
     }
-}
 
-impl Write for CurrentConnection {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        match self {
-            CurrentConnection::Tcp(tcp_stream) => tcp_stream.write(buf),
-            CurrentConnection::Tls(tls_conn) => tls_conn.write(buf),
+    /// Write whole `buf`. Caller must hold `write_lock`.
+    fn write_all(&self, mut buf: &[u8]) -> std::io::Result<()> {
+        let conn = match &self.kind {
+            ConnectionKind::Tcp => return (&self.socket).write_all(buf),
+            ConnectionKind::Tls(conn) => conn,
+        };
+
+        let mut conn = conn.lock().map_err(|_| std::io::Error::other("poisoned"))?;
+        while !buf.is_empty() {
+            let amount_written = conn.writer().write(buf)?;
+            buf = &buf[amount_written..];
+            while conn.wants_write() {
+                conn.write_tls(&mut &self.socket)?;
+            }
         }
+        Ok(())
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        match self {
-            CurrentConnection::Tcp(tcp_stream) => tcp_stream.flush(),
-            CurrentConnection::Tls(tls_conn) => tls_conn.flush(),
+    fn shutdown(&self) {
+        if let ConnectionKind::Tls(conn) = &self.kind {
+            if let Ok(mut conn) = conn.lock() {
+                conn.send_close_notify();
+                let _ = conn.write_tls(&mut &self.socket);
+            }
         }
+        let _ = self.socket.shutdown(Shutdown::Both);
     }
 }
 
-/// Transport running on USB
+/// Transport running on TCP
 #[derive(Clone, Debug)]
 pub struct TcpTransport {
     address: SocketAddr,
-    current_connection: Option<Arc<Mutex<CurrentConnection>>>,
+    current_connection: Option<Arc<CurrentConnection>>,
     private_key_path: PathBuf,
+    known_devices: Option<KnownDevices>,
+    device_identifier: Option<String>,
+    key_log: bool,
 }
 
 fn certificate_from_pk(key_pair: &KeyPair) -> Result<Vec<CertificateDer<'static>>> {
@@ -91,6 +152,30 @@ fn certificate_from_pk(key_pair: &KeyPair) -> Result<Vec<CertificateDer<'static>
     Ok(vec![certificate.der().to_owned()])
 }
 
+/// Build the TLS client configuration used to upgrade a connection, authenticating with the private key stored at `private_key_path`.
+///
+/// Session secrets are written to file named by `SSLKEYLOGFILE` environment variable if `key_log` is set.
+pub(crate) fn tls_client_config(private_key_path: &Path, key_log: bool) -> Result<ClientConfig> {
+    // TODO: Check if we cannot be more precise
+    let pk_content = read_to_string(private_key_path)?;
+
+    let key_pair = KeyPair::from_pkcs8_pem_and_sign_algo(&pk_content, &PKCS_RSA_SHA256)?;
+
+    let certificate = certificate_from_pk(&key_pair)?;
+    let private_key = PrivatePkcs8KeyDer::from_pem_file(private_key_path)?;
+
+    let mut client_config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(DeviceCertificateVerification::new()))
+        .with_client_auth_cert(certificate, private_key.into())?;
+
+    if key_log {
+        client_config.key_log = Arc::new(KeyLogFile::new());
+    }
+
+    Ok(client_config)
+}
+
 impl TcpTransport {
     /// Instantiate a new [`TcpTransport`]
     pub fn new(address: SocketAddr) -> Result<Self> {
@@ -98,6 +183,8 @@ impl TcpTransport {
     }
 
     /// Instantiate a new [`TcpTransport`] using a given private key
+    ///
+    /// TLS certificates of devices are pinned in a [`KnownDevices`] store located next to private key.
     pub fn new_with_custom_private_key(
         address: SocketAddr,
         private_key_path: PathBuf,
@@ -105,11 +192,39 @@ impl TcpTransport {
         Ok(Self {
             address,
             current_connection: None,
+            known_devices: Some(KnownDevices::next_to_private_key(&private_key_path)),
             private_key_path,
+            device_identifier: None,
+            key_log: false,
         })
     }
 
-    fn get_current_connection(&self) -> Result<Arc<Mutex<CurrentConnection>>> {
+    /// Pin TLS certificates of devices in given store, or accept any certificate if `None`.
+    pub fn with_known_devices(mut self, known_devices: Option<KnownDevices>) -> Self {
+        self.known_devices = known_devices;
+        self
+    }
+
+    /// Identify device by `identifier` (e.g. its adb GUID) in [`KnownDevices`] store, instead of its address.
+    pub fn with_device_identifier<S: Into<String>>(mut self, identifier: S) -> Self {
+        self.device_identifier = Some(identifier.into());
+        self
+    }
+
+    /// Write TLS session secrets to file named by `SSLKEYLOGFILE` environment variable, to inspect traffic. Disabled by default.
+    pub fn with_key_log(mut self, enabled: bool) -> Self {
+        self.key_log = enabled;
+        self
+    }
+
+    /// Identifier of device in [`KnownDevices`] store.
+    fn device_identifier(&self) -> String {
+        self.device_identifier
+            .clone()
+            .unwrap_or_else(|| self.address.to_string())
+    }
+
+    fn get_current_connection(&self) -> Result<Arc<CurrentConnection>> {
         self.current_connection
             .as_ref()
             .ok_or(RustADBError::IOError(std::io::Error::new(
@@ -119,8 +234,20 @@ impl TcpTransport {
             .cloned()
     }
 
+    /// Move current connection into a new transport, leaving this one disconnected.
+    pub(crate) fn detach(&mut self) -> Self {
+        Self {
+            address: self.address,
+            current_connection: self.current_connection.take(),
+            private_key_path: self.private_key_path.clone(),
+            known_devices: self.known_devices.clone(),
+            device_identifier: self.device_identifier.clone(),
+            key_log: self.key_log,
+        }
+    }
+
     pub(crate) fn upgrade_connection(&mut self) -> Result<()> {
-        let current_connection = match self.current_connection.clone() {
+        let current_connection = match self.current_connection.take() {
             Some(current_connection) => current_connection,
             None => {
                 return Err(RustADBError::UpgradeError(
@@ -129,44 +256,31 @@ impl TcpTransport {
             }
         };
 
-        {
-            let mut current_conn_locked = current_connection.lock()?;
-            match current_conn_locked.deref() {
-                CurrentConnection::Tcp(tcp_stream) => {
-                    // TODO: Check if we cannot be more precise
-
-                    let pk_content = read_to_string(&self.private_key_path)?;
-
-                    let key_pair =
-                        KeyPair::from_pkcs8_pem_and_sign_algo(&pk_content, &PKCS_RSA_SHA256)?;
-
-                    let certificate = certificate_from_pk(&key_pair)?;
-                    let private_key = PrivatePkcs8KeyDer::from_pem_file(&self.private_key_path)?;
-
-                    let mut client_config = ClientConfig::builder()
-                        .dangerous()
-                        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification {}))
-                        .with_client_auth_cert(certificate, private_key.into())?;
-
-                    client_config.key_log = Arc::new(KeyLogFile::new());
+        if let ConnectionKind::Tls(_) = current_connection.kind {
+            self.current_connection = Some(current_connection);
+            return Err(RustADBError::UpgradeError(
+                "cannot upgrade a TLS connection...".into(),
+            ));
+        }
 
-                    let rc_config = Arc::new(client_config);
-                    let server_name = self.address.ip().into();
-                    let conn = ClientConnection::new(rc_config, server_name)?;
-                    let owned = tcp_stream.try_clone()?;
-                    let client = StreamOwned::new(conn, owned);
+        let rc_config = Arc::new(tls_client_config(&self.private_key_path, self.key_log)?);
+        let server_name = self.address.ip().into();
+        let mut conn = ClientConnection::new(rc_config, server_name)?;
+        let mut socket = current_connection.socket.try_clone()?;
+        while conn.is_handshaking() {
+            conn.complete_io(&mut socket)?;
+        }
 
-                    // Update current connection state to now use TLS protocol
-                    *current_conn_locked = CurrentConnection::Tls(Box::new(client));
-                }
-                CurrentConnection::Tls(_) => {
-                    return Err(RustADBError::UpgradeError(
-                        "cannot upgrade a TLS connection...".into(),
-                    ));
-                }
-            }
+        if let Some(known_devices) = &self.known_devices {
+            known_devices.verify(&self.device_identifier(), conn.peer_certificates())?;
         }
 
+        // Update current connection state to now use TLS protocol
+        self.current_connection = Some(Arc::new(CurrentConnection::new(
+            socket,
+            ConnectionKind::Tls(Box::new(Mutex::new(conn))),
+        )));
+
         let message = self.read_message()?;
         match message.header().command() {
             MessageCommand::Cnxn => {
@@ -184,23 +298,17 @@ impl TcpTransport {
 impl ADBTransport for TcpTransport {
     fn connect(&mut self) -> Result<()> {
         let stream = TcpStream::connect(self.address)?;
-        self.current_connection = Some(Arc::new(Mutex::new(CurrentConnection::Tcp(stream))));
+        self.current_connection = Some(Arc::new(CurrentConnection::new(
+            stream,
+            ConnectionKind::Tcp,
+        )));
         Ok(())
     }
 
     fn disconnect(&mut self) -> Result<()> {
         log::debug!("disconnecting...");
         if let Some(current_connection) = &self.current_connection {
-            let mut lock = current_connection.lock()?;
-            match lock.deref_mut() {
-                CurrentConnection::Tcp(tcp_stream) => {
-                    let _ = tcp_stream.shutdown(Shutdown::Both);
-                }
-                CurrentConnection::Tls(tls_conn) => {
-                    tls_conn.conn.send_close_notify();
-                    let _ = tls_conn.sock.shutdown(Shutdown::Both);
-                }
-            }
+            current_connection.shutdown();
         }
 
         Ok(())
@@ -208,50 +316,21 @@ impl ADBTransport for TcpTransport {
 }
 
 impl ADBMessageTransport for TcpTransport {
-    fn read_message_with_timeout(
-        &mut self,
-        read_timeout: std::time::Duration,
-    ) -> Result<crate::device::ADBTransportMessage> {
-        let raw_connection_lock = self.get_current_connection()?;
-        let mut raw_connection = raw_connection_lock.lock()?;
+    fn read_message_with_timeout(&mut self, read_timeout: Duration) -> Result<ADBTransportMessage> {
+        let raw_connection = self.get_current_connection()?;
+        let mut pending = raw_connection.read_lock.lock()?;
 
-        raw_connection.set_read_timeout(read_timeout)?;
+        raw_connection.socket.set_read_timeout(Some(read_timeout))?;
 
         let mut data = [0; 24];
-        let mut total_read = 0;
-        loop {
-            total_read += raw_connection.read(&mut data[total_read..])?;
-            if total_read == data.len() {
-                break;
-            }
-        }
+        raw_connection.read_exact(&mut pending, &mut data)?;
 
         let header = ADBTransportMessageHeader::try_from(data)?;
 
-        if header.data_length() != 0 {
-            let mut msg_data = vec![0_u8; header.data_length() as usize];
-            let mut total_read = 0;
-            loop {
-                total_read += raw_connection.read(&mut msg_data[total_read..])?;
-                if total_read == msg_data.capacity() {
-                    break;
-                }
-            }
-
-            let message = ADBTransportMessage::from_header_and_payload(header, msg_data);
-
-            // Check message integrity
-            if !message.check_message_integrity() {
-                return Err(RustADBError::InvalidIntegrity(
-                    ADBTransportMessageHeader::compute_crc32(message.payload()),
-                    message.header().data_crc32(),
-                ));
-            }
-
-            return Ok(message);
-        }
+        let mut msg_data = vec![0_u8; header.data_length() as usize];
+        raw_connection.read_exact(&mut pending, &mut msg_data)?;
 
-        Ok(ADBTransportMessage::from_header_and_payload(header, vec![]))
+        ADBTransportMessage::decode(header, msg_data)
     }
 
     fn write_message_with_timeout(
@@ -260,40 +339,41 @@ impl ADBMessageTransport for TcpTransport {
         write_timeout: Duration,
     ) -> Result<()> {
         let message_bytes = message.header().as_bytes()?;
-        let raw_connection_lock = self.get_current_connection()?;
-        let mut raw_connection = raw_connection_lock.lock()?;
+        let raw_connection = self.get_current_connection()?;
+        let _write_guard = raw_connection.write_lock.lock()?;
 
-        raw_connection.set_write_timeout(write_timeout)?;
+        raw_connection
+            .socket
+            .set_write_timeout(Some(write_timeout))?;
 
-        let mut total_written = 0;
-        loop {
-            total_written += raw_connection.write(&message_bytes[total_written..])?;
-            if total_written == message_bytes.len() {
-                raw_connection.flush()?;
-                break;
-            }
-        }
+        raw_connection.write_all(&message_bytes)?;
 
         let payload = message.into_payload();
         if !payload.is_empty() {
-            let mut total_written = 0;
-            loop {
-                total_written += raw_connection.write(&payload[total_written..])?;
-                if total_written == payload.len() {
-                    raw_connection.flush()?;
-                    break;
-                }
-            }
+            raw_connection.write_all(&payload)?;
         }
 
         Ok(())
     }
 }
 
+/// Accept self-signed certificates of devices, as long as they hold the key used to sign handshake.
+///
+/// Certificates themselves are checked against a [`KnownDevices`] store once handshake completed.
 #[derive(Debug)]
-struct NoCertificateVerification;
+struct DeviceCertificateVerification {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl DeviceCertificateVerification {
+    fn new() -> Self {
+        Self {
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
 
-impl ServerCertVerifier for NoCertificateVerification {
+impl ServerCertVerifier for DeviceCertificateVerification {
     fn verify_server_cert(
         &self,
         _end_entity: &rustls::pki_types::CertificateDer<'_>,
@@ -307,37 +387,23 @@ impl ServerCertVerifier for NoCertificateVerification {
 
     fn verify_tls12_signature(
         &self,
-        _message: &[u8],
-        _cert: &rustls::pki_types::CertificateDer<'_>,
-        _dss: &rustls::DigitallySignedStruct,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
     ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
-        Ok(HandshakeSignatureValid::assertion())
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
     }
 
     fn verify_tls13_signature(
         &self,
-        _message: &[u8],
-        _cert: &rustls::pki_types::CertificateDer<'_>,
-        _dss: &rustls::DigitallySignedStruct,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
     ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
-        Ok(HandshakeSignatureValid::assertion())
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
     }
 
-    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
-        vec![
-            SignatureScheme::RSA_PKCS1_SHA1,
-            SignatureScheme::ECDSA_SHA1_Legacy,
-            SignatureScheme::RSA_PKCS1_SHA256,
-            SignatureScheme::ECDSA_NISTP256_SHA256,
-            SignatureScheme::RSA_PKCS1_SHA384,
-            SignatureScheme::ECDSA_NISTP384_SHA384,
-            SignatureScheme::RSA_PKCS1_SHA512,
-            SignatureScheme::ECDSA_NISTP521_SHA512,
-            SignatureScheme::RSA_PSS_SHA256,
-            SignatureScheme::RSA_PSS_SHA384,
-            SignatureScheme::RSA_PSS_SHA512,
-            SignatureScheme::ED25519,
-            SignatureScheme::ED448,
-        ]
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
     }
 }
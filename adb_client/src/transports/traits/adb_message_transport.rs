@@ -7,6 +7,8 @@ const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(u64::MAX);
 const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Trait representing a transport able to read and write messages.
+///
+/// Transports must be `Send + 'static`, as [`crate::ADBMultiplexer`] reads their messages from a background thread.
 pub trait ADBMessageTransport: ADBTransport + Clone + Send + 'static {
     /// Read a message using given timeout on the underlying transport
     fn read_message_with_timeout(&mut self, read_timeout: Duration) -> Result<ADBTransportMessage>;
@@ -66,6 +66,16 @@ impl USBTransport {
             .cloned()
     }
 
+    /// Move current connection into a new transport, leaving this one disconnected.
+    pub(crate) fn detach(&mut self) -> Self {
+        Self {
+            device: self.device.clone(),
+            handle: self.handle.take(),
+            read_endpoint: self.read_endpoint.take(),
+            write_endpoint: self.write_endpoint.take(),
+        }
+    }
+
     fn get_read_endpoint(&self) -> Result<Endpoint> {
         self.read_endpoint
             .as_ref()
@@ -85,6 +95,30 @@ impl USBTransport {
             )))
     }
 
+    /// Find device plugged on the same port as this one, with the same vendor and product ids.
+    fn find_same_device(&self) -> Result<Device<GlobalContext>> {
+        let descriptor = self.device.device_descriptor()?;
+        let port_numbers = self.device.port_numbers()?;
+        for device in rusb::devices()?.iter() {
+            let Ok(other) = device.device_descriptor() else {
+                continue;
+            };
+            if other.vendor_id() == descriptor.vendor_id()
+                && other.product_id() == descriptor.product_id()
+                && device.bus_number() == self.device.bus_number()
+                && device.port_numbers().is_ok_and(|ports| ports == port_numbers)
+            {
+                return Ok(device);
+            }
+        }
+
+        Err(RustADBError::DeviceNotFound(format!(
+            "cannot find USB device with vendor_id={} and product_id={} anymore",
+            descriptor.vendor_id(),
+            descriptor.product_id()
+        )))
+    }
+
     fn configure_endpoint(handle: &DeviceHandle<GlobalContext>, endpoint: &Endpoint) -> Result<()> {
         handle.claim_interface(endpoint.iface)?;
         Ok(())
@@ -164,7 +198,15 @@ impl USBTransport {
 
 impl ADBTransport for USBTransport {
     fn connect(&mut self) -> crate::Result<()> {
-        let device = self.device.open()?;
+        let device = match self.device.open() {
+            Ok(device) => device,
+            // Device gets a new address when it comes back, e.g. after a reboot
+            Err(rusb::Error::NoDevice | rusb::Error::NotFound) => {
+                self.device = self.find_same_device()?;
+                self.device.open()?
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         let (read_endpoint, write_endpoint) = self.find_endpoints(&device)?;
 
@@ -182,6 +224,10 @@ impl ADBTransport for USBTransport {
     }
 
     fn disconnect(&mut self) -> crate::Result<()> {
+        if self.handle.is_none() {
+            return Ok(());
+        }
+
         let message = ADBTransportMessage::new(MessageCommand::Clse, 0, 0, &[]);
         if let Err(e) = self.write_message(message) {
             log::error!("error while sending CLSE message: {e}");
@@ -237,28 +283,14 @@ impl ADBMessageTransport for USBTransport {
         let header = ADBTransportMessageHeader::try_from(data)?;
         log::trace!("received header {header:?}");
 
-        if header.data_length() != 0 {
-            let mut msg_data = vec![0_u8; header.data_length() as usize];
-            let mut offset = 0;
-            while offset < msg_data.len() {
-                let end = (offset + max_packet_size).min(msg_data.len());
-                let chunk = &mut msg_data[offset..end];
-                offset += handle.read_bulk(endpoint.address, chunk, timeout)?;
-            }
-
-            let message = ADBTransportMessage::from_header_and_payload(header, msg_data);
-
-            // Check message integrity
-            if !message.check_message_integrity() {
-                return Err(RustADBError::InvalidIntegrity(
-                    ADBTransportMessageHeader::compute_crc32(message.payload()),
-                    message.header().data_crc32(),
-                ));
-            }
-
-            return Ok(message);
+        let mut msg_data = vec![0_u8; header.data_length() as usize];
+        let mut offset = 0;
+        while offset < msg_data.len() {
+            let end = (offset + max_packet_size).min(msg_data.len());
+            let chunk = &mut msg_data[offset..end];
+            offset += handle.read_bulk(endpoint.address, chunk, timeout)?;
         }
 
-        Ok(ADBTransportMessage::from_header_and_payload(header, vec![]))
+        ADBTransportMessage::decode(header, msg_data)
     }
 }
@@ -1,6 +1,14 @@
-use std::{ffi::OsStr, path::Path};
+use std::{
+    ffi::OsStr,
+    io::{Cursor, Read, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use crate::{Result, RustADBError};
+use crate::{InstallFailureReason, Result, RustADBError, TransferMonitor};
+
+/// First bytes of APK files, which are ZIP files.
+const APK_MAGIC: [u8; 4] = *b"PK\x03\x04";
 
 pub fn check_extension_is_apk<P: AsRef<Path>>(path: P) -> Result<()> {
     if let Some(extension) = path.as_ref().extension() {
@@ -16,3 +24,91 @@ pub fn check_extension_is_apk<P: AsRef<Path>>(path: P) -> Result<()> {
 
     Ok(())
 }
+
+/// Quote `arg` so that device shell passes it as a single argument to commands.
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Check `response` of a package manager command, reporting `Failure [INSTALL_FAILED_*: message]` as [`RustADBError::InstallFailed`].
+pub fn check_package_manager_response(response: &[u8]) -> Result<()> {
+    let response = String::from_utf8_lossy(response);
+    // Some commands log progress before their outcome
+    let outcome = response
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or_default()
+        .trim();
+    if outcome.starts_with("Success") {
+        return Ok(());
+    }
+
+    let failure = outcome
+        .strip_prefix("Failure [")
+        .and_then(|failure| failure.strip_suffix(']'))
+        .map(|failure| failure.split_once(": ").unwrap_or((failure, "")));
+    match failure {
+        Some((code, message)) if code.starts_with("INSTALL_") => Err(RustADBError::InstallFailed(
+            InstallFailureReason::from(code),
+            message.to_string(),
+        )),
+        _ => Err(RustADBError::ADBRequestFailed(response.trim().to_string())),
+    }
+}
+
+/// Check that `reader` starts like an APK file, returning a reader of its whole contents.
+pub fn check_apk_magic<R: Read>(mut reader: R) -> Result<impl Read> {
+    let mut magic = [0; APK_MAGIC.len()];
+    let magic = check_apk_signature(reader.read_exact(&mut magic).map(|()| magic))?;
+    Ok(Cursor::new(magic).chain(reader))
+}
+
+/// Check outcome of reading first bytes of a file, which have to be the ones of APK files.
+pub(crate) fn check_apk_signature(
+    magic: std::io::Result<[u8; APK_MAGIC.len()]>,
+) -> Result<[u8; APK_MAGIC.len()]> {
+    match magic {
+        Ok(magic) if magic == APK_MAGIC => Ok(magic),
+        Ok(_) => Err(RustADBError::InvalidApk(
+            "missing ZIP signature".to_string(),
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Err(RustADBError::InvalidApk("file is too short".to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Copy an APK declared to be `size` bytes long from `apk` into `output`, reporting progress to `monitor`.
+///
+/// Package manager expects exactly `size` bytes: no more is read, and a shorter `apk` is an error.
+pub(crate) fn copy_apk<R: Read, W: Write>(
+    apk: R,
+    size: u64,
+    mut output: W,
+    monitor: &mut TransferMonitor,
+) -> Result<()> {
+    let copied = std::io::copy(&mut monitor.reader(apk.take(size), Some(size)), &mut output);
+    check_apk_size(size, monitor.finish(copied)?)
+}
+
+/// Check that an APK declared to be `size` bytes long had all of them `copied`.
+pub(crate) fn check_apk_size(size: u64, copied: u64) -> Result<()> {
+    match copied == size {
+        true => Ok(()),
+        false => Err(RustADBError::InvalidApk(format!(
+            "expected {size} bytes, got {copied}"
+        ))),
+    }
+}
+
+/// Time left until `deadline`, failing with [`std::io::ErrorKind::TimedOut`] once it passed.
+pub(crate) fn time_left(deadline: Instant) -> std::io::Result<Duration> {
+    match deadline.saturating_duration_since(Instant::now()) {
+        Duration::ZERO => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "deadline exceeded",
+        )),
+        left => Ok(left),
+    }
+}
@@ -28,8 +28,7 @@ impl PyADBServerDevice {
 
     /// Push a local file from input to dest
     pub fn push(&mut self, input: PathBuf, dest: PathBuf) -> Result<()> {
-        let mut reader = File::open(input)?;
-        Ok(self.0.push(&mut reader, dest.to_string_lossy())?)
+        Ok(self.0.push_file(&input, &dest.to_string_lossy())?)
     }
 
     /// Pull a file from device located at input, and drop it to dest